@@ -0,0 +1,441 @@
+//! JSON-RPC API used to inspect and control a running bridge.
+
+use std::net::SocketAddr;
+use std::sync::{Arc, RwLock};
+use std::sync::atomic::{AtomicBool, Ordering};
+use futures::{future, Async, Future, Stream};
+use futures::task::AtomicTask;
+use hyper::{self, Method, StatusCode};
+use hyper::header::{ContentLength, ContentType};
+use hyper::server::{Http, Request, Response, Service};
+use rpc::{self, IoHandler, Params, Value};
+use serde_json;
+use tokio_core::net::TcpListener;
+use tokio_core::reactor::Handle;
+use web3::types::{Address, U256};
+use config::Node;
+use bridge::GasPrice;
+use database::{Database, PendingTransaction};
+use error::Error;
+
+/// Allows to pause and resume a bridge component.
+#[derive(Default)]
+pub struct Switch {
+	paused: AtomicBool,
+	task: AtomicTask,
+}
+
+impl Switch {
+	pub fn is_paused(&self) -> bool {
+		self.paused.load(Ordering::SeqCst)
+	}
+
+	pub fn pause(&self) {
+		self.paused.store(true, Ordering::SeqCst);
+	}
+
+	pub fn resume(&self) {
+		self.paused.store(false, Ordering::SeqCst);
+		self.task.notify();
+	}
+
+	/// Returns `NotReady` and schedules the current task to be notified on resume if the component is paused.
+	pub fn poll_resumed(&self) -> Async<()> {
+		if !self.is_paused() {
+			return Async::Ready(());
+		}
+		self.task.register();
+		// the component might have been resumed before the task has been registered
+		if self.is_paused() {
+			Async::NotReady
+		} else {
+			Async::Ready(())
+		}
+	}
+}
+
+/// Switches of all bridge components which can be paused.
+#[derive(Default)]
+pub struct Switches {
+	pub deposit_relay: Switch,
+	pub withdraw_relay: Switch,
+	pub withdraw_confirm: Switch,
+}
+
+impl Switches {
+	fn get(&self, component: &str) -> Option<&Switch> {
+		match component {
+			"deposit_relay" => Some(&self.deposit_relay),
+			"withdraw_relay" => Some(&self.withdraw_relay),
+			"withdraw_confirm" => Some(&self.withdraw_confirm),
+			_ => None,
+		}
+	}
+
+	fn all(&self) -> Vec<&Switch> {
+		vec![&self.deposit_relay, &self.withdraw_relay, &self.withdraw_confirm]
+	}
+}
+
+/// State of a running bridge reported by the admin API.
+#[derive(Default)]
+pub struct State {
+	pub home_chain_id: u64,
+	pub foreign_chain_id: u64,
+	/// Latest version of the database.
+	pub database: RwLock<Database>,
+	pub home_balance: Arc<RwLock<Option<U256>>>,
+	pub foreign_balance: Arc<RwLock<Option<U256>>>,
+	pub home_gas_price: GasPrice,
+	pub foreign_gas_price: GasPrice,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ChainStatus {
+	chain_id: u64,
+	contract_address: Address,
+	account: Address,
+	nonce: U256,
+	balance: Option<U256>,
+	gas_price: u64,
+	/// Seconds since the gas price has been retrieved, `None` if it has not been.
+	gas_price_age: Option<u64>,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Paused {
+	deposit_relay: bool,
+	withdraw_relay: bool,
+	withdraw_confirm: bool,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Status {
+	home: ChainStatus,
+	foreign: ChainStatus,
+	checked_deposit_relay: u64,
+	checked_withdraw_relay: u64,
+	checked_withdraw_confirm: u64,
+	paused: Paused,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+struct PendingTransactions {
+	home: Vec<PendingTransaction>,
+	foreign: Vec<PendingTransaction>,
+}
+
+/// Handler of admin JSON-RPC requests.
+///
+/// Supported methods:
+/// - `bridge_status` - chain ids, contract addresses, checked blocks, balances, gas prices with their age and nonces
+/// - `bridge_pendingTransactions` - transactions which have not been mined yet
+/// - `bridge_pause`, `bridge_resume` - pause or resume given components (`deposit_relay`,
+///   `withdraw_relay`, `withdraw_confirm`) or all of them if no component is given
+pub struct AdminApi {
+	handler: IoHandler,
+}
+
+impl AdminApi {
+	pub fn new(home: &Node, foreign: &Node, switches: Arc<Switches>, state: Arc<State>) -> Self {
+		let mut handler = IoHandler::new();
+
+		{
+			let home = home.clone();
+			let foreign = foreign.clone();
+			let switches = switches.clone();
+			let state = state.clone();
+			handler.add_method("bridge_status", move |_| {
+				let database = state.database.read().unwrap();
+				let status = Status {
+					home: ChainStatus {
+						chain_id: state.home_chain_id,
+						contract_address: database.home_contract_address,
+						account: home.account,
+						nonce: *home.info.nonce.read().unwrap(),
+						balance: *state.home_balance.read().unwrap(),
+						gas_price: state.home_gas_price.last(),
+						gas_price_age: state.home_gas_price.age().map(|age| age.as_secs()),
+					},
+					foreign: ChainStatus {
+						chain_id: state.foreign_chain_id,
+						contract_address: database.foreign_contract_address,
+						account: foreign.account,
+						nonce: *foreign.info.nonce.read().unwrap(),
+						balance: *state.foreign_balance.read().unwrap(),
+						gas_price: state.foreign_gas_price.last(),
+						gas_price_age: state.foreign_gas_price.age().map(|age| age.as_secs()),
+					},
+					checked_deposit_relay: database.checked_deposit_relay,
+					checked_withdraw_relay: database.checked_withdraw_relay,
+					checked_withdraw_confirm: database.checked_withdraw_confirm,
+					paused: Paused {
+						deposit_relay: switches.deposit_relay.is_paused(),
+						withdraw_relay: switches.withdraw_relay.is_paused(),
+						withdraw_confirm: switches.withdraw_confirm.is_paused(),
+					},
+				};
+				to_value(&status)
+			});
+		}
+
+		{
+			let home = home.clone();
+			let foreign = foreign.clone();
+			handler.add_method("bridge_pendingTransactions", move |_| {
+				to_value(&PendingTransactions {
+					home: home.info.pending_transactions.read().unwrap().clone(),
+					foreign: foreign.info.pending_transactions.read().unwrap().clone(),
+				})
+			});
+		}
+
+		{
+			let switches = switches.clone();
+			handler.add_method("bridge_pause", move |params| {
+				for switch in selected_switches(&switches, params)? {
+					switch.pause();
+				}
+				Ok(Value::Bool(true))
+			});
+		}
+
+		handler.add_method("bridge_resume", move |params| {
+			for switch in selected_switches(&switches, params)? {
+				switch.resume();
+			}
+			Ok(Value::Bool(true))
+		});
+
+		AdminApi {
+			handler,
+		}
+	}
+
+	/// Handles serialized JSON-RPC request, returns serialized response unless the request was a notification.
+	pub fn handle_request(&self, request: &str) -> Option<String> {
+		self.handler.handle_request_sync(request)
+	}
+
+	/// Calls `method` in-process.
+	pub fn call(&self, method: &str, params: Vec<Value>) -> Result<Value, rpc::Error> {
+		let request = rpc::Request::Single(rpc::Call::MethodCall(rpc::MethodCall {
+			jsonrpc: Some(rpc::Version::V2),
+			method: method.into(),
+			params: Some(Params::Array(params)),
+			id: rpc::Id::Num(1),
+		}));
+		let request = serde_json::to_string(&request).expect("serialization of a request can't fail; qed");
+		let response = self.handle_request(&request).expect("method calls always have a response; qed");
+		match serde_json::from_str(&response).map_err(|_| rpc::Error::internal_error())? {
+			rpc::Response::Single(rpc::Output::Success(success)) => Ok(success.result),
+			rpc::Response::Single(rpc::Output::Failure(failure)) => Err(failure.error),
+			rpc::Response::Batch(_) => Err(rpc::Error::internal_error()),
+		}
+	}
+}
+
+fn to_value<S: ::serde::Serialize>(value: &S) -> Result<Value, rpc::Error> {
+	serde_json::to_value(value).map_err(|_| rpc::Error::internal_error())
+}
+
+/// Returns switches of components given in `params`, or all of them if there are none.
+fn selected_switches(switches: &Switches, params: Params) -> Result<Vec<&Switch>, rpc::Error> {
+	let components: Vec<String> = match params {
+		Params::None => vec![],
+		params => params.parse()?,
+	};
+	if components.is_empty() {
+		return Ok(switches.all());
+	}
+	components.iter()
+		.map(|component| switches.get(component)
+			.ok_or_else(|| rpc::Error::invalid_params(format!("unknown component {}", component))))
+		.collect()
+}
+
+/// Returns true if the request body is declared as JSON.
+///
+/// Browsers send cross-origin POSTs of other content types without asking for permission,
+/// so a web page could otherwise pause the bridge through the local listener.
+fn is_json(request: &Request) -> bool {
+	match request.headers().get::<ContentType>() {
+		Some(&ContentType(ref mime)) => mime.type_() == "application" && mime.subtype() == "json",
+		None => false,
+	}
+}
+
+/// Serves admin API requests POSTed to `/`.
+struct AdminService(Arc<AdminApi>);
+
+impl Service for AdminService {
+	type Request = Request;
+	type Response = Response;
+	type Error = hyper::Error;
+	type Future = Box<Future<Item = Response, Error = hyper::Error>>;
+
+	fn call(&self, request: Request) -> Self::Future {
+		if *request.method() != Method::Post {
+			return Box::new(future::ok(Response::new().with_status(StatusCode::MethodNotAllowed)));
+		}
+		if !is_json(&request) {
+			return Box::new(future::ok(Response::new().with_status(StatusCode::UnsupportedMediaType)));
+		}
+
+		let api = self.0.clone();
+		Box::new(request.body().concat2().map(move |body| {
+			let response = String::from_utf8(body.to_vec()).ok()
+				.and_then(|request| api.handle_request(&request))
+				.unwrap_or_default();
+			Response::new()
+				.with_header(ContentType::json())
+				.with_header(ContentLength(response.len() as u64))
+				.with_body(response)
+		}))
+	}
+}
+
+/// Starts HTTP listener serving admin API at `address` on the event loop of `handle`.
+pub fn serve(address: &SocketAddr, handle: &Handle, api: AdminApi) -> Result<(), Error> {
+	let listener = TcpListener::bind(address, handle)?;
+	let http = Http::new();
+	let api = Arc::new(api);
+	let connection_handle = handle.clone();
+	let server = listener.incoming().for_each(move |(socket, _)| {
+		connection_handle.spawn(http.serve_connection(socket, AdminService(api.clone()))
+			.map(|_| ())
+			.map_err(|err| warn!("admin API connection failed: {}", err)));
+		Ok(())
+	});
+	handle.spawn(server.map_err(|err| error!("admin API listener failed: {}", err)));
+	info!("serving admin API at http://{}", address);
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use std::sync::{Arc, RwLock};
+	use rpc::{ErrorCode, Value};
+	use serde_json;
+	use config::Node;
+	use database::Database;
+	use bridge::GasPrice;
+	use hyper::Method;
+	use hyper::header::ContentType;
+	use hyper::server::Request;
+	use super::{AdminApi, Switches, State, is_json};
+
+	fn node(account: &str) -> Node {
+		Node {
+			account: account.into(),
+			..Node::default_for_tests()
+		}
+	}
+
+	#[test]
+	fn test_bridge_status() {
+		let home = node("0000000000000000000000000000000000000001");
+		let foreign = node("0000000000000000000000000000000000000002");
+		*foreign.info.nonce.write().unwrap() = 5.into();
+		let home_gas_price = GasPrice::new(&home);
+		home_gas_price.set(1);
+		let state = State {
+			home_chain_id: 77,
+			foreign_chain_id: 99,
+			database: RwLock::new(Database {
+				home_contract_address: "0000000000000000000000000000000000000003".into(),
+				foreign_contract_address: "0000000000000000000000000000000000000004".into(),
+				checked_deposit_relay: 10,
+				checked_withdraw_relay: 20,
+				checked_withdraw_confirm: 30,
+				..Default::default()
+			}),
+			home_balance: Arc::new(RwLock::new(Some(16.into()))),
+			home_gas_price,
+			foreign_gas_price: GasPrice::new(&foreign),
+			..Default::default()
+		};
+		let api = AdminApi::new(&home, &foreign, Default::default(), Arc::new(state));
+
+		let expected: Value = serde_json::from_str(r#"{
+			"home": {
+				"chainId": 77,
+				"contractAddress": "0x0000000000000000000000000000000000000003",
+				"account": "0x0000000000000000000000000000000000000001",
+				"nonce": "0x0",
+				"balance": "0x10",
+				"gasPrice": 1,
+				"gasPriceAge": 0
+			},
+			"foreign": {
+				"chainId": 99,
+				"contractAddress": "0x0000000000000000000000000000000000000004",
+				"account": "0x0000000000000000000000000000000000000002",
+				"nonce": "0x5",
+				"balance": null,
+				"gasPrice": 15000000000,
+				"gasPriceAge": null
+			},
+			"checkedDepositRelay": 10,
+			"checkedWithdrawRelay": 20,
+			"checkedWithdrawConfirm": 30,
+			"paused": {
+				"depositRelay": false,
+				"withdrawRelay": false,
+				"withdrawConfirm": false
+			}
+		}"#).unwrap();
+		assert_eq!(expected, api.call("bridge_status", vec![]).unwrap());
+	}
+
+	#[test]
+	fn test_bridge_pause_resume() {
+		let home = node("0000000000000000000000000000000000000001");
+		let foreign = node("0000000000000000000000000000000000000002");
+		let switches = Arc::new(Switches::default());
+		let api = AdminApi::new(&home, &foreign, switches.clone(), Default::default());
+
+		assert_eq!(Value::Bool(true), api.call("bridge_pause", vec!["withdraw_relay".into()]).unwrap());
+		assert!(!switches.deposit_relay.is_paused());
+		assert!(switches.withdraw_relay.is_paused());
+		assert!(!switches.withdraw_confirm.is_paused());
+
+		assert_eq!(Value::Bool(true), api.call("bridge_pause", vec![]).unwrap());
+		assert!(switches.deposit_relay.is_paused());
+		assert!(switches.withdraw_confirm.is_paused());
+
+		assert_eq!(Value::Bool(true), api.call("bridge_resume", vec!["deposit_relay".into(), "withdraw_confirm".into()]).unwrap());
+		assert!(!switches.deposit_relay.is_paused());
+		assert!(switches.withdraw_relay.is_paused());
+		assert!(!switches.withdraw_confirm.is_paused());
+
+		let err = api.call("bridge_resume", vec!["unknown".into()]).unwrap_err();
+		assert_eq!(ErrorCode::InvalidParams, err.code);
+		assert!(switches.withdraw_relay.is_paused());
+	}
+
+	#[test]
+	fn test_bridge_pending_transactions() {
+		let home = node("0000000000000000000000000000000000000001");
+		let foreign = node("0000000000000000000000000000000000000002");
+		let api = AdminApi::new(&home, &foreign, Default::default(), Default::default());
+
+		let expected: Value = serde_json::from_str(r#"{"home": [], "foreign": []}"#).unwrap();
+		assert_eq!(expected, api.call("bridge_pendingTransactions", vec![]).unwrap());
+	}
+
+	#[test]
+	fn test_is_json() {
+		let mut request = Request::new(Method::Post, "/".parse().unwrap());
+		assert!(!is_json(&request));
+		request.headers_mut().set(ContentType::plaintext());
+		assert!(!is_json(&request));
+		request.headers_mut().set(ContentType::json());
+		assert!(is_json(&request));
+		request.headers_mut().set(ContentType("application/json; charset=utf-8".parse().unwrap()));
+		assert!(is_json(&request));
+	}
+}
@@ -1,13 +1,19 @@
-use std::time::Duration;
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 use serde::de::DeserializeOwned;
 use serde_json::Value;
-use futures::{Future, Stream, Poll};
+use futures::{Async, Future, Stream, Poll};
+use futures::future::{join_all, JoinAll};
 use tokio_timer::{Timer, Interval, Timeout};
-use web3::{self, api, Transport};
+use web3::{self, api, Transport, DuplexTransport};
 use web3::api::Namespace;
 use web3::types::{Log, Filter, H256, U256, FilterBuilder, Bytes, Address, CallRequest, BlockNumber};
 use web3::helpers::{self, CallResult};
+use config::RetryConfig;
 use error::{Error, ErrorKind};
+use metrics::METRICS;
+use retry::Backoff;
 
 /// Imperative alias for web3 function.
 pub use web3::confirm::send_raw_transaction_with_confirmation;
@@ -24,7 +30,13 @@ impl<T: DeserializeOwned, F: Future<Item = Value, Error = web3::Error>>Future fo
 
 	fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
 		trace!(target: "bridge", "{}", self.message);
-		self.future.poll().map_err(ErrorKind::Web3).map_err(Into::into)
+		let message = self.message;
+		self.future.poll()
+			.map_err(|err| {
+				METRICS.rpc_error(message);
+				ErrorKind::Web3(err)
+			})
+			.map_err(Into::into)
 	}
 }
 
@@ -36,6 +48,14 @@ pub fn net_version<T: Transport>(transport: T) -> ApiCall<String, T::Out> {
 	}
 }
 
+/// Imperative wrapper for web3 function.
+pub fn chain_id<T: Transport>(transport: T) -> ApiCall<U256, T::Out> {
+	ApiCall {
+		future: CallResult::new(transport.execute("eth_chainId", vec![])),
+		message: "eth_chainId",
+	}
+}
+
 /// Imperative wrapper for web3 function.
 pub fn eth_get_transaction_count<T: Transport>(transport: T, address: Address, block: Option<BlockNumber>) -> ApiCall<U256, T::Out> {
 	// we are not using Eth.balance() because it converts None block into `latest`
@@ -45,7 +65,7 @@ pub fn eth_get_transaction_count<T: Transport>(transport: T, address: Address, b
 	let block = helpers::serialize(&block.unwrap_or(BlockNumber::Pending));
 	ApiCall {
 		future: CallResult::new(transport.execute("eth_getTransactionCount", vec![address, block])),
-		message: "net_version",
+		message: "eth_getTransactionCount",
 	}
 }
 
@@ -102,6 +122,82 @@ pub fn block_number<T: Transport>(transport: T) -> ApiCall<U256, T::Out> {
 	}
 }
 
+/// Block header fields required to follow the canonical chain.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct BlockHeader {
+	pub number: Option<U256>,
+	pub hash: Option<H256>,
+	#[serde(rename = "parentHash")]
+	pub parent_hash: H256,
+}
+
+/// Imperative wrapper for web3 function.
+pub fn block_header<T: Transport>(transport: T, number: u64) -> ApiCall<Option<BlockHeader>, T::Out> {
+	// we are not using Eth.block() because we only need a few header fields
+	// and do not want to depend on the full block representation.
+	let number = helpers::serialize(&BlockNumber::Number(number));
+	let include_transactions = helpers::serialize(&false);
+	ApiCall {
+		future: CallResult::new(transport.execute("eth_getBlockByNumber", vec![number, include_transactions])),
+		message: "eth_getBlockByNumber",
+	}
+}
+
+/// Base fee fields of an EIP-1559 block.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct BlockBaseFee {
+	/// `None` if the chain does not support EIP-1559.
+	#[serde(rename = "baseFeePerGas")]
+	pub base_fee_per_gas: Option<U256>,
+}
+
+/// Imperative wrapper for web3 function.
+pub fn latest_block_base_fee<T: Transport>(transport: T) -> ApiCall<Option<BlockBaseFee>, T::Out> {
+	let number = helpers::serialize(&BlockNumber::Latest);
+	let include_transactions = helpers::serialize(&false);
+	ApiCall {
+		future: CallResult::new(transport.execute("eth_getBlockByNumber", vec![number, include_transactions])),
+		message: "eth_getBlockByNumber",
+	}
+}
+
+/// Fields of `eth_feeHistory` response required to price transactions.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct FeeHistory {
+	/// Base fees of requested blocks followed by the base fee of the next block.
+	#[serde(rename = "baseFeePerGas")]
+	pub base_fee_per_gas: Vec<U256>,
+	/// Requested percentiles of tips paid in each block.
+	#[serde(default)]
+	pub reward: Vec<Vec<U256>>,
+}
+
+impl FeeHistory {
+	/// Returns base fee of the next block.
+	pub fn next_base_fee(&self) -> Option<U256> {
+		self.base_fee_per_gas.last().cloned()
+	}
+}
+
+/// Imperative wrapper for web3 function.
+pub fn fee_history<T: Transport>(transport: T, block_count: u64, percentiles: Vec<f64>) -> ApiCall<FeeHistory, T::Out> {
+	let block_count = helpers::serialize(&U256::from(block_count));
+	let newest_block = helpers::serialize(&BlockNumber::Latest);
+	let percentiles = helpers::serialize(&percentiles);
+	ApiCall {
+		future: CallResult::new(transport.execute("eth_feeHistory", vec![block_count, newest_block, percentiles])),
+		message: "eth_feeHistory",
+	}
+}
+
+/// Imperative wrapper for web3 function.
+pub fn gas_price<T: Transport>(transport: T) -> ApiCall<U256, T::Out> {
+	ApiCall {
+		future: CallResult::new(transport.execute("eth_gasPrice", vec![])),
+		message: "eth_gasPrice",
+	}
+}
+
 /// Imperative wrapper for web3 function.
 pub fn balance<T: Transport>(transport: T, address: Address, block: Option<BlockNumber>) -> ApiCall<U256, T::Out> {
 	// we are not using Eth.balance() because it converts None block into `latest`
@@ -123,8 +219,98 @@ pub fn send_raw_transaction<T: Transport>(transport: T, tx: Bytes) -> ApiCall<H2
 	}
 }
 
+/// Transaction receipt fields required to tell whether a transaction has been mined and succeeded.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct TransactionReceipt {
+	#[serde(rename = "transactionHash")]
+	pub transaction_hash: H256,
+	#[serde(rename = "blockNumber")]
+	pub block_number: Option<U256>,
+	/// `1` for success, `0` for failure. Missing for pre-Byzantium transactions.
+	pub status: Option<U256>,
+}
+
+impl TransactionReceipt {
+	/// Returns true if the receipt reports an execution failure.
+	pub fn is_reverted(&self) -> bool {
+		self.status.map_or(false, |status| status.is_zero())
+	}
+}
+
+/// Imperative wrapper for web3 function.
+pub fn transaction_receipt<T: Transport>(transport: T, hash: H256) -> ApiCall<Option<TransactionReceipt>, T::Out> {
+	// we are not using Eth.transaction_receipt() because it does not expose receipt status.
+	let hash = helpers::serialize(&hash);
+	ApiCall {
+		future: CallResult::new(transport.execute("eth_getTransactionReceipt", vec![hash])),
+		message: "eth_getTransactionReceipt",
+	}
+}
+
 pub use bridge::nonce::send_transaction_with_nonce;
 
+/// Transaction to sign with `eth_signTransaction` or `account_signTransaction`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SignTransactionRequest {
+	pub from: Address,
+	/// `None` for contract creation.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub to: Option<Address>,
+	pub gas: U256,
+	/// Set for legacy transactions.
+	#[serde(rename = "gasPrice", skip_serializing_if = "Option::is_none")]
+	pub gas_price: Option<U256>,
+	/// Set for EIP-1559 transactions.
+	#[serde(rename = "maxFeePerGas", skip_serializing_if = "Option::is_none")]
+	pub max_fee_per_gas: Option<U256>,
+	/// Set for EIP-1559 transactions.
+	#[serde(rename = "maxPriorityFeePerGas", skip_serializing_if = "Option::is_none")]
+	pub max_priority_fee_per_gas: Option<U256>,
+	pub value: U256,
+	pub data: Bytes,
+	pub nonce: U256,
+	#[serde(rename = "chainId")]
+	pub chain_id: U256,
+}
+
+/// Signed transaction fields returned by `eth_signTransaction` and `account_signTransaction`.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct SignedTransaction {
+	/// Transaction encoded as sent with `eth_sendRawTransaction`.
+	pub raw: Bytes,
+}
+
+/// Imperative wrapper for a signer function, `method` is either `eth_signTransaction` or `account_signTransaction`.
+pub fn sign_transaction<T: Transport>(transport: T, method: &'static str, request: &SignTransactionRequest) -> ApiCall<SignedTransaction, T::Out> {
+	let request = helpers::serialize(request);
+	ApiCall {
+		future: CallResult::new(transport.execute(method, vec![request])),
+		message: method,
+	}
+}
+
+/// Imperative wrapper for web3 function.
+pub fn eth_sign<T: Transport>(transport: T, address: Address, data: Bytes) -> ApiCall<Bytes, T::Out> {
+	let address = helpers::serialize(&address);
+	let data = helpers::serialize(&data);
+	ApiCall {
+		future: CallResult::new(transport.execute("eth_sign", vec![address, data])),
+		message: "eth_sign",
+	}
+}
+
+/// Imperative wrapper for a Clef function, signs `data` prefixed as in `eth_sign`.
+pub fn account_sign_data<T: Transport>(transport: T, address: Address, data: Bytes) -> ApiCall<Bytes, T::Out> {
+	// `text/plain` is the content type signed with the `eth_sign` prefix
+	let content_type = helpers::serialize(&"text/plain");
+	let address = helpers::serialize(&address);
+	let data = helpers::serialize(&data);
+	ApiCall {
+		future: CallResult::new(transport.execute("account_signData", vec![content_type, address, data])),
+		message: "account_signData",
+	}
+}
+
 /// Imperative wrapper for web3 function.
 pub fn call<T: Transport>(transport: T, address: Address, payload: Bytes) -> ApiCall<Bytes, T::Out> {
 	let future = api::Eth::new(transport).call(CallRequest {
@@ -142,6 +328,23 @@ pub fn call<T: Transport>(transport: T, address: Address, payload: Bytes) -> Api
 	}
 }
 
+/// Imperative wrapper for web3 function.
+pub fn estimate_gas<T: Transport>(transport: T, from: Address, to: Address, payload: Bytes) -> ApiCall<U256, T::Out> {
+	let future = api::Eth::new(transport).estimate_gas(CallRequest {
+		from: Some(from),
+		to,
+		gas: None,
+		gas_price: None,
+		value: None,
+		data: Some(payload),
+	}, None);
+
+	ApiCall {
+		future,
+		message: "eth_estimateGas",
+	}
+}
+
 /// Returns a eth_sign-compatible hash of data to sign.
 /// The data is prepended with special message to prevent
 /// chosen-plaintext attacks.
@@ -154,6 +357,131 @@ pub fn eth_data_hash(mut data: Vec<u8>) -> H256 {
 	keccak(message_data)
 }
 
+/// Number of checkpoints remembered by `LogStream` to detect chain reorganizations.
+///
+/// If all of them have been reorganized away, the stream rewinds as many blocks.
+const CHECKPOINTS_HISTORY: usize = 128;
+
+/// Besides its first and last block, a range is checkpointed at 1, 2, 4... up to this many blocks before its last block,
+/// so that a shallow reorganization rewinds only a few blocks.
+const CHECKPOINTS_SPAN: u64 = 16;
+
+/// Time after which a failed new blocks subscription is attempted again.
+const RESUBSCRIBE_INTERVAL_SECS: u64 = 60;
+
+/// Stream of new block notifications.
+pub type Notifications = Box<Stream<Item = (), Error = Error>>;
+
+/// Source of new block notifications.
+pub trait NewHeads {
+	/// Subscribes to new block headers.
+	fn subscribe(&self) -> Box<Future<Item = Notifications, Error = Error>>;
+}
+
+impl<T> NewHeads for T where T: DuplexTransport + 'static, T::Out: 'static, T::NotificationStream: 'static {
+	fn subscribe(&self) -> Box<Future<Item = Notifications, Error = Error>> {
+		let subscription = api::EthSubscribe::new(self.clone())
+			.subscribe_new_heads()
+			.map(|heads| {
+				let heads = heads
+					.map(|_| ())
+					.map_err(|err| Error::from(ErrorKind::Web3(err)));
+				Box::new(heads) as Notifications
+			})
+			.map_err(|err| Error::from(ErrorKind::Web3(err)));
+		Box::new(subscription)
+	}
+}
+
+/// Block ticks state.
+enum BlockTicksState {
+	/// Ticking every poll interval.
+	Polling,
+	/// Waiting for the node to confirm the subscription.
+	Subscribing(Box<Future<Item = Notifications, Error = Error>>),
+	/// Ticking on every new block. `initial` tick catches up with blocks mined before the subscription.
+	Subscribed {
+		notifications: Notifications,
+		initial: bool,
+	},
+}
+
+/// Tells `LogStream` when to check for new blocks.
+///
+/// Ticks on every new block while subscribed to new block headers, or every poll interval otherwise.
+/// If the subscription fails or ends, falls back to polling and subscribes again later.
+struct BlockTicks {
+	interval: Interval,
+	new_heads: Option<Arc<NewHeads>>,
+	failed_at: Option<Instant>,
+	state: BlockTicksState,
+}
+
+impl BlockTicks {
+	fn should_subscribe(&self) -> bool {
+		self.new_heads.is_some() && self.failed_at.map_or(true, |failed_at| {
+			failed_at.elapsed() >= Duration::from_secs(RESUBSCRIBE_INTERVAL_SECS)
+		})
+	}
+}
+
+impl Stream for BlockTicks {
+	type Item = ();
+	type Error = Error;
+
+	fn poll(&mut self) -> Poll<Option<Self::Item>, Self::Error> {
+		loop {
+			let next_state = match self.state {
+				BlockTicksState::Polling => {
+					if !self.should_subscribe() {
+						return self.interval.poll().map_err(Error::from);
+					}
+					let new_heads = self.new_heads.as_ref().expect("should_subscribe checks that new_heads is some; qed");
+					BlockTicksState::Subscribing(new_heads.subscribe())
+				},
+				BlockTicksState::Subscribing(ref mut future) => match future.poll() {
+					Ok(Async::NotReady) => return Ok(Async::NotReady),
+					Ok(Async::Ready(notifications)) => {
+						info!("subscribed to new blocks");
+						self.failed_at = None;
+						BlockTicksState::Subscribed {
+							notifications,
+							initial: true,
+						}
+					},
+					Err(err) => {
+						warn!("subscribing to new blocks failed: {}, falling back to polling", err);
+						self.failed_at = Some(Instant::now());
+						BlockTicksState::Polling
+					},
+				},
+				BlockTicksState::Subscribed { ref mut notifications, ref mut initial } => {
+					// several blocks might have arrived since the last tick, they are handled together
+					let mut tick = ::std::mem::replace(initial, false);
+					let failure = loop {
+						match notifications.poll() {
+							Ok(Async::Ready(Some(()))) => tick = true,
+							Ok(Async::NotReady) => break None,
+							Ok(Async::Ready(None)) => break Some("subscription to new blocks ended".to_owned()),
+							Err(err) => break Some(format!("subscription to new blocks failed: {}", err)),
+						}
+					};
+					match failure {
+						None if tick => return Ok(Async::Ready(Some(()))),
+						None => return Ok(Async::NotReady),
+						Some(reason) => {
+							warn!("{}, falling back to polling", reason);
+							self.failed_at = Some(Instant::now());
+							BlockTicksState::Polling
+						},
+					}
+				},
+			};
+			self.state = next_state;
+		}
+	}
+}
+
 /// Used for `LogStream` initialization.
 pub struct LogStreamInit {
 	pub after: u64,
@@ -161,6 +489,13 @@ pub struct LogStreamInit {
 	pub request_timeout: Duration,
 	pub poll_interval: Duration,
 	pub confirmations: usize,
+	/// Maximum number of blocks queried by a single `eth_getLogs` request.
+	pub max_block_range: Option<u64>,
+	/// Retries of failed requests.
+	pub retry: RetryConfig,
+	/// Source of new block notifications. If set, logs are fetched as soon as a new block arrives
+	/// and `poll_interval` is only used while the subscription is unavailable.
+	pub new_heads: Option<Arc<NewHeads>>,
 }
 
 /// Contains all logs matching `LogStream` filter in inclusive range `[from, to]`.
@@ -171,90 +506,343 @@ pub struct LogStreamItem {
 	pub logs: Vec<Log>,
 }
 
+/// Event yielded by `LogStream`.
+#[derive(Debug, PartialEq)]
+pub enum LogStreamEvent {
+	/// New confirmed logs.
+	Logs(LogStreamItem),
+	/// Chain has been reorganized. All blocks after given one have to be processed again.
+	Rewind(u64),
+}
+
+/// Block of a processed range together with its hash.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Checkpoint {
+	number: u64,
+	hash: H256,
+}
+
 /// Log Stream state.
 enum LogStreamState<T: Transport> {
 	/// Log Stream is waiting for timer to poll.
 	Wait,
 	/// Fetching best block number.
 	FetchBlockNumber(Timeout<ApiCall<U256, T::Out>>),
-	/// Fetching logs for new best block.
+	/// Fetching first block of the range to check that its parent is the last processed block.
+	VerifyParent {
+		from: u64,
+		to: u64,
+		future: Timeout<ApiCall<Option<BlockHeader>, T::Out>>,
+	},
+	/// Fetching hashes of the blocks of the range which are checkpointed, see `checkpoint_blocks`.
+	FetchCheckpoints {
+		from: u64,
+		to: u64,
+		first: Checkpoint,
+		future: JoinAll<Vec<Timeout<ApiCall<Option<BlockHeader>, T::Out>>>>,
+	},
+	/// Fetching logs for new best block. The last checkpoint is the last block of the range.
 	FetchLogs {
 		from: u64,
 		to: u64,
+		checkpoints: Vec<Checkpoint>,
 		future: Timeout<ApiCall<Vec<Log>, T::Out>>,
 	},
 	/// All logs has been fetched.
-	NextItem(Option<LogStreamItem>),
+	NextItem(Option<LogStreamEvent>),
 }
 
 /// Creates new `LogStream`.
 pub fn log_stream<T: Transport>(transport: T, timer: Timer, init: LogStreamInit) -> LogStream<T> {
 	LogStream {
 		transport,
-		interval: timer.interval(init.poll_interval),
+		ticks: BlockTicks {
+			interval: timer.interval(init.poll_interval),
+			new_heads: init.new_heads,
+			failed_at: None,
+			state: BlockTicksState::Polling,
+		},
+		backoff: Backoff::new(timer.clone(), init.retry),
 		timer,
 		state: LogStreamState::Wait,
 		after: init.after,
+		last_confirmed_block: init.after,
+		head_block: None,
+		checkpoints: VecDeque::new(),
+		rewinding: false,
 		filter: init.filter,
 		confirmations: init.confirmations,
+		max_block_range: init.max_block_range,
 		request_timeout: init.request_timeout,
 	}
 }
 
 /// Stream of confirmed logs.
+///
+/// Remembers hashes of several blocks of every processed range and, before fetching
+/// a new range, verifies that the parent of its first block is the last processed block.
+/// If it is not, the chain has been reorganized: the stream walks back its checkpoints
+/// until it finds one which is still canonical and yields `LogStreamEvent::Rewind`.
+/// If none of them is, the reorganization is deeper than the remembered history and the stream
+/// fails with `ErrorKind::ReorgTooDeep`.
+///
+/// Requests which failed because of a transient error are retried with a backoff.
 pub struct LogStream<T: Transport> {
 	transport: T,
 	timer: Timer,
-	interval: Interval,
+	ticks: BlockTicks,
+	backoff: Backoff,
 	state: LogStreamState<T>,
 	after: u64,
+	last_confirmed_block: u64,
+	head_block: Option<u64>,
+	checkpoints: VecDeque<Checkpoint>,
+	rewinding: bool,
 	filter: FilterBuilder,
 	confirmations: usize,
+	max_block_range: Option<u64>,
 	request_timeout: Duration,
 }
 
+impl<T: Transport> LogStream<T> {
+	/// Returns the number of the latest block seen by the stream.
+	pub fn head_block(&self) -> Option<u64> {
+		self.head_block
+	}
+
+	/// Returns true if logs of all confirmed blocks seen by the stream have been yielded.
+	pub fn is_synced(&self) -> bool {
+		self.head_block.is_some() && self.after >= self.last_confirmed_block
+	}
+
+	/// Makes the stream fetch logs of all blocks after `after` again.
+	///
+	/// `after` must not be greater than the last block of the last yielded range.
+	pub fn rewind(&mut self, after: u64) {
+		while self.checkpoints.back().map_or(false, |checkpoint| checkpoint.number > after) {
+			self.checkpoints.pop_back();
+		}
+		self.after = after;
+		self.state = LogStreamState::Wait;
+	}
+}
+
+/// Returns the next inclusive range of blocks which should be queried for logs.
+fn next_range(after: u64, last_confirmed_block: u64, max_block_range: Option<u64>) -> Option<(u64, u64)> {
+	if last_confirmed_block <= after {
+		return None;
+	}
+
+	let from = after + 1;
+	let to = match max_block_range {
+		Some(range) => last_confirmed_block.min(from.saturating_add(range.max(1) - 1)),
+		None => last_confirmed_block,
+	};
+	Some((from, to))
+}
+
+fn fetch_header<T: Transport>(transport: &T, timer: &Timer, request_timeout: Duration, number: u64) -> Timeout<ApiCall<Option<BlockHeader>, T::Out>> {
+	timer.timeout(block_header(transport, number), request_timeout)
+}
+
+/// Returns numbers of the blocks of range `[from, to]` which are checkpointed besides `from`, in ascending order.
+///
+/// These are `to` and blocks 1, 2, 4... up to `CHECKPOINTS_SPAN` blocks before it.
+fn checkpoint_blocks(from: u64, to: u64) -> Vec<u64> {
+	let mut blocks = vec![to];
+	let mut distance = 1;
+	while distance <= CHECKPOINTS_SPAN && to > from + distance {
+		blocks.push(to - distance);
+		distance *= 2;
+	}
+	blocks.reverse();
+	blocks
+}
+
+fn fetch_logs<T: Transport>(transport: &T, timer: &Timer, request_timeout: Duration, filter: &FilterBuilder, from: u64, to: u64, checkpoints: Vec<Checkpoint>) -> LogStreamState<T> {
+	let filter = filter.clone()
+		.from_block(from.into())
+		.to_block(to.into())
+		.build();
+	LogStreamState::FetchLogs {
+		from,
+		to,
+		checkpoints,
+		future: timer.timeout(logs(transport, &filter), request_timeout),
+	}
+}
+
+fn push_checkpoint(checkpoints: &mut VecDeque<Checkpoint>, checkpoint: Checkpoint) {
+	if checkpoints.back().map_or(false, |last| last.number == checkpoint.number) {
+		return;
+	}
+	checkpoints.push_back(checkpoint);
+	if checkpoints.len() > CHECKPOINTS_HISTORY {
+		checkpoints.pop_front();
+	}
+}
+
 impl<T: Transport> Stream for LogStream<T> {
-	type Item = LogStreamItem;
+	type Item = LogStreamEvent;
 	type Error = Error;
 
 	fn poll(&mut self) -> Poll<Option<Self::Item>, Self::Error> {
+		loop {
+			try_ready!(self.backoff.poll());
+			match self.poll_logs() {
+				Err(err) => {
+					self.backoff.retry(err, "fetching logs")?;
+					// start over from fetching the best block
+					self.state = LogStreamState::Wait;
+				},
+				result => return result,
+			}
+		}
+	}
+}
+
+impl<T: Transport> LogStream<T> {
+	fn poll_logs(&mut self) -> Poll<Option<LogStreamEvent>, Error> {
 		loop {
 			let next_state = match self.state {
 				LogStreamState::Wait => {
-					let _ = try_stream!(self.interval.poll());
+					let _ = try_stream!(self.ticks.poll());
 					LogStreamState::FetchBlockNumber(self.timer.timeout(block_number(&self.transport), self.request_timeout))
 				},
 				LogStreamState::FetchBlockNumber(ref mut future) => {
 					let last_block = try_ready!(future.poll()).low_u64();
-					let last_confirmed_block = last_block.saturating_sub(self.confirmations as u64);
-					if last_confirmed_block > self.after {
-						let from = self.after + 1;
-						let filter = self.filter.clone()
-							.from_block(from.into())
-							.to_block(last_confirmed_block.into())
-							.build();
-						LogStreamState::FetchLogs {
-							from: from,
-							to: last_confirmed_block,
-							future: self.timer.timeout(logs(&self.transport, &filter), self.request_timeout),
-						}
-					} else {
-						LogStreamState::Wait
+					self.backoff.reset();
+					self.head_block = Some(last_block);
+					self.last_confirmed_block = last_block.saturating_sub(self.confirmations as u64);
+					match next_range(self.after, self.last_confirmed_block, self.max_block_range) {
+						Some((from, to)) => LogStreamState::VerifyParent {
+							from,
+							to,
+							future: fetch_header(&self.transport, &self.timer, self.request_timeout, from),
+						},
+						None => LogStreamState::Wait,
 					}
 				},
-				LogStreamState::FetchLogs { ref mut future, from, to } => {
+				LogStreamState::VerifyParent { ref mut future, from, to } => match try_ready!(future.poll()) {
+					// node does not know the block yet, try again later
+					None => LogStreamState::Wait,
+					Some(header) => match self.checkpoints.back().cloned() {
+						Some(checkpoint) if checkpoint.hash != header.parent_hash => {
+							warn!(target: "bridge", "block {} is no longer canonical, chain has been reorganized", checkpoint.number);
+							self.checkpoints.pop_back();
+							self.rewinding = true;
+							match self.checkpoints.back().cloned() {
+								Some(previous) => {
+									self.after = previous.number;
+									LogStreamState::VerifyParent {
+										from: previous.number + 1,
+										to,
+										future: fetch_header(&self.transport, &self.timer, self.request_timeout, previous.number + 1),
+									}
+								},
+								None => {
+									// all remembered blocks have been reorganized away, the common ancestor is unknown
+									// and rewinding by a guess could skip logs of the new chain
+									self.rewinding = false;
+									return Err(ErrorKind::ReorgTooDeep(checkpoint.number).into());
+								},
+							}
+						},
+						_ if self.rewinding => {
+							self.rewinding = false;
+							info!(target: "bridge", "rewinding log stream to block {}", self.after);
+							LogStreamState::NextItem(Some(LogStreamEvent::Rewind(self.after)))
+						},
+						expected => {
+							if expected.is_none() {
+								// first range processed by this stream, its parent is the last processed block
+								push_checkpoint(&mut self.checkpoints, Checkpoint { number: self.after, hash: header.parent_hash });
+							}
+							match header.hash {
+								// node does not know the block yet, try again later
+								None => LogStreamState::Wait,
+								Some(hash) if from == to => fetch_logs(&self.transport, &self.timer, self.request_timeout, &self.filter, from, to, vec![Checkpoint { number: to, hash }]),
+								Some(hash) => {
+									let (transport, timer, request_timeout) = (&self.transport, &self.timer, self.request_timeout);
+									let headers = checkpoint_blocks(from, to).into_iter()
+										.map(|number| fetch_header(transport, timer, request_timeout, number))
+										.collect::<Vec<_>>();
+									LogStreamState::FetchCheckpoints {
+										from,
+										to,
+										first: Checkpoint { number: from, hash },
+										future: join_all(headers),
+									}
+								},
+							}
+						},
+					},
+				},
+				LogStreamState::FetchCheckpoints { ref mut future, from, to, first } => {
+					let hashes = try_ready!(future.poll()).into_iter()
+						.map(|header| header.and_then(|header| header.hash))
+						.collect::<Option<Vec<_>>>();
+					match hashes {
+						Some(hashes) => {
+							let checkpoints = Some(first).into_iter()
+								.chain(checkpoint_blocks(from, to).into_iter()
+									.zip(hashes)
+									.map(|(number, hash)| Checkpoint { number, hash }))
+								.collect();
+							fetch_logs(&self.transport, &self.timer, self.request_timeout, &self.filter, from, to, checkpoints)
+						},
+						// node does not know some of the blocks yet, try again later
+						None => LogStreamState::Wait,
+					}
+				},
+				LogStreamState::FetchLogs { ref mut future, from, to, ref mut checkpoints } => {
 					let logs = try_ready!(future.poll());
-					let item = LogStreamItem {
-						from,
-						to,
-						logs,
-					};
+					let hash = checkpoints.last().expect("checkpoints of a range end with its last block; qed").hash;
+					// the range might have been reorganized after hash of its last block has been fetched.
+					// in that case it is fetched again during the next poll.
+					let reorganized = logs.iter().any(|log| {
+						log.block_number.map_or(false, |number| number.low_u64() == to) &&
+						log.block_hash.map_or(false, |block_hash| block_hash != hash)
+					});
+
+					if reorganized {
+						warn!(target: "bridge", "block {} has been reorganized while fetching logs, retrying", to);
+						LogStreamState::Wait
+					} else {
+						let logs = logs.into_iter()
+							.filter(|log| {
+								if log.removed == Some(true) {
+									warn!(target: "bridge", "ignoring removed log {:?}", log.transaction_hash);
+									false
+								} else {
+									true
+								}
+							})
+							.collect();
+
+						let item = LogStreamItem {
+							from,
+							to,
+							logs,
+						};
 
-					self.after = to;
-					LogStreamState::NextItem(Some(item))
+						self.after = to;
+						for checkpoint in checkpoints.drain(..) {
+							push_checkpoint(&mut self.checkpoints, checkpoint);
+						}
+						LogStreamState::NextItem(Some(LogStreamEvent::Logs(item)))
+					}
 				},
 				LogStreamState::NextItem(ref mut item) => match item.take() {
-					None => LogStreamState::Wait,
+					// continue with the next range right away if the previous one has been capped by `max_block_range`
+					None => match next_range(self.after, self.last_confirmed_block, self.max_block_range) {
+						Some((from, to)) => LogStreamState::VerifyParent {
+							from,
+							to,
+							future: fetch_header(&self.transport, &self.timer, self.request_timeout, from),
+						},
+						None => LogStreamState::Wait,
+					},
 					some => return Ok(some.into()),
 				},
 			};
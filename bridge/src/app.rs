@@ -1,28 +1,56 @@
 use std::path::{Path, PathBuf};
 use tokio_core::reactor::{Handle};
 use tokio_timer::{self, Timer};
-use web3::Transport;
+use web3::{Transport, DuplexTransport};
 use error::{Error, ResultExt, ErrorKind};
-use config::Config;
-use contracts::{home, foreign};
+use admin::Switches;
+use config::{Config, Node, SignerConfig};
+use web3::types::Address;
+use contracts::{home, home_erc20, foreign, erc20};
 use web3::transports::http::Http;
+use web3::transports::ws::WebSocket;
+use web3::transports::ipc::Ipc;
+use api::NewHeads;
+use transport::FailoverTransport;
+use signer::{Signer, Signers, KeystoreSigner, RpcSigner};
 use std::time::Duration;
 
 use std::sync::Arc;
 use std::sync::atomic::AtomicBool;
 
-use ethcore::ethstore::{EthStore,accounts_dir::RootDiskDirectory};
-use ethcore::account_provider::{AccountProvider, AccountProviderSettings};
+use keystore;
+use ethcore::ethstore::EthStore;
 
 pub struct App<T> where T: Transport {
 	pub config: Config,
 	pub database_path: PathBuf,
 	pub connections: Connections<T>,
 	pub home_bridge: home::HomeBridge,
+	pub home_erc20_bridge: home_erc20::HomeBridgeErc20,
+	pub erc20_token: erc20::ERC20,
 	pub foreign_bridge: foreign::ForeignBridge,
 	pub timer: Timer,
 	pub running: Arc<AtomicBool>,
-	pub keystore: AccountProvider,
+	pub switches: Arc<Switches>,
+	pub new_heads: NewHeadsSources,
+	pub signers: Signers,
+}
+
+/// Sources of new block notifications, set when the transport supports subscriptions.
+#[derive(Clone, Default)]
+pub struct NewHeadsSources {
+	pub home: Option<Arc<NewHeads>>,
+	pub foreign: Option<Arc<NewHeads>>,
+}
+
+impl NewHeadsSources {
+	fn subscribe_to<T>(connections: &Connections<T>) -> Self
+		where T: DuplexTransport + 'static, T::Out: 'static, T::NotificationStream: 'static {
+		NewHeadsSources {
+			home: Some(Arc::new(connections.home.clone())),
+			foreign: Some(Arc::new(connections.foreign.clone())),
+		}
+	}
 }
 
 pub struct Connections<T> where T: Transport {
@@ -30,16 +58,28 @@ pub struct Connections<T> where T: Transport {
 	pub foreign: T,
 }
 
-impl Connections<Http>  {
-	pub fn new_http(handle: &Handle, home: &str, home_concurrent_connections: usize, foreign: &str, foreign_concurrent_connections: usize) -> Result<Self, Error> {
+impl Connections<FailoverTransport<Http>> {
+	pub fn new_http(handle: &Handle, timer: &Timer, home: &Node, foreign: &Node) -> Result<Self, Error> {
+		let home = failover_http(handle, timer, home)
+			.chain_err(||"Cannot connect to home node rpc")?;
+		let foreign = failover_http(handle, timer, foreign)
+			.chain_err(||"Cannot connect to foreign node rpc")?;
 
-	    let home = Http::with_event_loop(home, handle,home_concurrent_connections)
+		let result = Connections {
+			home,
+			foreign
+		};
+		Ok(result)
+	}
+}
+
+impl Connections<WebSocket> {
+	pub fn new_ws(handle: &Handle, home: &Node, foreign: &Node) -> Result<Self, Error> {
+		let home = WebSocket::with_event_loop(&home.rpc_url(), handle)
 			.map_err(ErrorKind::Web3)
-			.map_err(Error::from)
 			.chain_err(||"Cannot connect to home node rpc")?;
-		let foreign = Http::with_event_loop(foreign, handle, foreign_concurrent_connections)
+		let foreign = WebSocket::with_event_loop(&foreign.rpc_url(), handle)
 			.map_err(ErrorKind::Web3)
-			.map_err(Error::from)
 			.chain_err(||"Cannot connect to foreign node rpc")?;
 
 		let result = Connections {
@@ -50,6 +90,39 @@ impl Connections<Http>  {
 	}
 }
 
+impl Connections<Ipc> {
+	pub fn new_ipc(handle: &Handle, home: &Node, foreign: &Node) -> Result<Self, Error> {
+		let home = ipc(handle, home)
+			.chain_err(||"Cannot connect to home node ipc")?;
+		let foreign = ipc(handle, foreign)
+			.chain_err(||"Cannot connect to foreign node ipc")?;
+
+		let result = Connections {
+			home,
+			foreign
+		};
+		Ok(result)
+	}
+}
+
+fn ipc(handle: &Handle, node: &Node) -> Result<Ipc, Error> {
+	let path = node.ipc_path.as_ref()
+		.ok_or_else(|| ErrorKind::ConfigError("ipc_path is not set".into()))?;
+	Ok(Ipc::with_event_loop(path, handle).map_err(ErrorKind::Web3)?)
+}
+
+fn failover_http(handle: &Handle, timer: &Timer, node: &Node) -> Result<FailoverTransport<Http>, Error> {
+	let endpoints = node.rpc_urls()
+		.into_iter()
+		.map(|url| {
+			let transport = Http::with_event_loop(&url, handle, node.concurrent_http_requests)
+				.map_err(ErrorKind::Web3)?;
+			Ok((url, transport))
+		})
+		.collect::<Result<Vec<_>, Error>>()?;
+	Ok(FailoverTransport::new(endpoints, node.rpc_quorum, timer.clone(), node.request_timeout))
+}
+
 impl<T: Transport> Connections<T> {
 	pub fn as_ref(&self) -> Connections<&T> {
 		Connections {
@@ -59,40 +132,118 @@ impl<T: Transport> Connections<T> {
 	}
 }
 
-impl App<Http> {
+impl App<FailoverTransport<Http>> {
 	pub fn new_http<P: AsRef<Path>>(config: Config, database_path: P, handle: &Handle, running: Arc<AtomicBool>) -> Result<Self, Error> {
-		let home_url:String = format!("{}:{}", config.home.rpc_host, config.home.rpc_port);
-		let foreign_url:String = format!("{}:{}", config.foreign.rpc_host, config.foreign.rpc_port);
+		let timer = build_timer(&config);
+		let connections = Connections::new_http(handle, &timer, &config.home, &config.foreign)?;
+		App::new(config, database_path, handle, connections, timer, NewHeadsSources::default(), running)
+	}
+}
 
-		let connections = Connections::new_http(handle, home_url.as_ref(), config.home.concurrent_http_requests, foreign_url.as_ref(), config.foreign.concurrent_http_requests)?;
-		let keystore = EthStore::open(Box::new(RootDiskDirectory::at(&config.keystore))).map_err(|e| ErrorKind::KeyStore(e))?;
+impl App<WebSocket> {
+	/// Connects to both nodes over WebSocket and uses new block subscriptions to trigger log fetches.
+	///
+	/// Only the primary `rpc_host` and `rpc_port` of each node are used.
+	pub fn new_ws<P: AsRef<Path>>(config: Config, database_path: P, handle: &Handle, running: Arc<AtomicBool>) -> Result<Self, Error> {
+		let timer = build_timer(&config);
+		let connections = Connections::new_ws(handle, &config.home, &config.foreign)?;
+		let new_heads = NewHeadsSources::subscribe_to(&connections);
+		App::new(config, database_path, handle, connections, timer, new_heads, running)
+	}
+}
 
-		let keystore = AccountProvider::new(Box::new(keystore), AccountProviderSettings {
-			enable_hardware_wallets: false,
-			hardware_wallet_classic_key: false,
-			unlock_keep_secret: true,
-			blacklisted_accounts: vec![],
-		});
-		keystore.unlock_account_permanently(config.home.account, config.home.password()?).map_err(|e| ErrorKind::AccountError(e))?;
-		keystore.unlock_account_permanently(config.foreign.account, config.foreign.password()?).map_err(|e| ErrorKind::AccountError(e))?;
+impl App<Ipc> {
+	/// Connects to nodes running on the same host over their IPC sockets and uses new block subscriptions
+	/// to trigger log fetches.
+	pub fn new_ipc<P: AsRef<Path>>(config: Config, database_path: P, handle: &Handle, running: Arc<AtomicBool>) -> Result<Self, Error> {
+		let timer = build_timer(&config);
+		let connections = Connections::new_ipc(handle, &config.home, &config.foreign)?;
+		let new_heads = NewHeadsSources::subscribe_to(&connections);
+		App::new(config, database_path, handle, connections, timer, new_heads, running)
+	}
+}
 
-		let max_timeout = config.clone().home.request_timeout.max(config.clone().foreign.request_timeout);
+impl<T> App<T> where T: Transport + Clone + 'static, T::Out: 'static {
+	fn new<P: AsRef<Path>>(config: Config, database_path: P, handle: &Handle, connections: Connections<T>, timer: Timer, new_heads: NewHeadsSources, running: Arc<AtomicBool>) -> Result<Self, Error> {
+		let signers = {
+			let mut keystore = LazyKeystore {
+				path: &config.keystore,
+				store: None,
+			};
+			let home = create_signer(config.home.account, &config.home.signer, || config.home.password(), &config.home, &connections.home, handle, &mut keystore)
+				.chain_err(|| "Cannot create home signer")?;
+			let foreign = create_signer(config.foreign.account, &config.foreign.signer, || config.foreign.password(), &config.foreign, &connections.foreign, handle, &mut keystore)
+				.chain_err(|| "Cannot create foreign signer")?;
+			let validator = match config.validator {
+				// messages are signed on foreign, where signatures are submitted
+				Some(ref validator) => create_signer(validator.account, &validator.signer, || validator.password(), &config.foreign, &connections.foreign, handle, &mut keystore)
+					.chain_err(|| "Cannot create validator signer")?,
+				None => foreign.clone(),
+			};
+			Signers {
+				home,
+				foreign,
+				validator,
+			}
+		};
 
 		let result = App {
 			config,
 			database_path: database_path.as_ref().to_path_buf(),
 			connections,
 			home_bridge: home::HomeBridge::default(),
+			home_erc20_bridge: home_erc20::HomeBridgeErc20::default(),
+			erc20_token: erc20::ERC20::default(),
 			foreign_bridge: foreign::ForeignBridge::default(),
-			// it is important to build a timer with a max timeout that can accommodate the longest timeout requested,
-			// otherwise it will result in a bizarrely inadequate behaviour of timing out nearly immediately
-			timer: tokio_timer::wheel().max_timeout(max_timeout)
-				.tick_duration(Duration::from_millis(100))
-				.num_slots((max_timeout.as_secs() as usize * 10).next_power_of_two())
-				.build(),
+			timer,
 			running,
-			keystore,
+			switches: Default::default(),
+			new_heads,
+			signers,
 		};
 		Ok(result)
 	}
 }
+
+/// Keystore opened once an account of it is needed.
+struct LazyKeystore<'a> {
+	path: &'a Path,
+	store: Option<Arc<EthStore>>,
+}
+
+impl<'a> LazyKeystore<'a> {
+	/// Returns a signer with the key of `account` decrypted with `password`.
+	fn signer(&mut self, account: Address, password: String) -> Result<KeystoreSigner, Error> {
+		if self.store.is_none() {
+			self.store = Some(Arc::new(keystore::open(self.path)?));
+		}
+		let store = self.store.clone().expect("keystore has just been opened; qed");
+		KeystoreSigner::unlock(store, account, &password)
+	}
+}
+
+/// Creates `signer` of `account`, remote signers use the transport or the `concurrent_http_requests` of `node`.
+fn create_signer<T, P>(account: Address, signer: &SignerConfig, password: P, node: &Node, transport: &T, handle: &Handle, keystore: &mut LazyKeystore) -> Result<Arc<Signer>, Error>
+	where T: Transport + Clone + 'static, T::Out: 'static, P: FnOnce() -> Result<String, Error> {
+	let signer: Arc<Signer> = match *signer {
+		SignerConfig::Keystore => Arc::new(keystore.signer(account, password()?)?),
+		SignerConfig::External { ref url } => {
+			let transport = Http::with_event_loop(url, handle, node.concurrent_http_requests)
+				.map_err(ErrorKind::Web3)?;
+			Arc::new(RpcSigner::external(transport))
+		},
+		SignerConfig::Node => Arc::new(RpcSigner::node(transport.clone())),
+	};
+	Ok(signer)
+}
+
+pub(crate) fn build_timer(config: &Config) -> Timer {
+	let max_timeout = config.home.request_timeout.max(config.foreign.request_timeout)
+		.max(config.retry.longest_delay());
+	// it is important to build a timer with a max timeout that can accommodate the longest timeout or retry delay requested,
+	// otherwise it will result in a bizarrely inadequate behaviour of timing out nearly immediately
+	tokio_timer::wheel().max_timeout(max_timeout)
+		.tick_duration(Duration::from_millis(100))
+		.num_slots((max_timeout.as_secs() as usize * 10).next_power_of_two())
+		.build()
+}
@@ -0,0 +1,198 @@
+use std::sync::Arc;
+use futures::{Stream, Poll};
+use web3::Transport;
+use web3::types::{Address, FilterBuilder, Log};
+use ethabi::{self, RawLog};
+use api::{self, LogStream, LogStreamEvent, NewHeads};
+use app::App;
+use config::Node;
+use contracts::authorities::BridgeAuthorities;
+use error::{Error, ErrorKind, Result};
+use metrics::Chain;
+use util::web3_events_filter;
+
+/// Authorities of a bridge contract and the number of signatures it requires.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AuthoritySet {
+	pub accounts: Vec<Address>,
+	/// `0` until `RequiredSignaturesChanged` has been observed.
+	pub required_signatures: u32,
+}
+
+impl AuthoritySet {
+	pub fn contains(&self, account: &Address) -> bool {
+		self.accounts.contains(account)
+	}
+
+	/// Applies `AuthorityAdded`, `AuthorityRemoved` or `RequiredSignaturesChanged` log to the set.
+	fn apply(&mut self, contract: &BridgeAuthorities, log: Log) -> Result<()> {
+		let events = contract.events();
+		let is_added = is_event(events.authority_added().create_filter(), &log);
+		let is_removed = is_event(events.authority_removed().create_filter(), &log);
+		let raw_log = RawLog {
+			topics: log.topics,
+			data: log.data.0,
+		};
+		if is_added {
+			let authority = events.authority_added().parse_log(raw_log)?.authority;
+			if !self.contains(&authority) {
+				self.accounts.push(authority);
+			}
+		} else if is_removed {
+			let authority = events.authority_removed().parse_log(raw_log)?.authority;
+			self.accounts.retain(|account| *account != authority);
+		} else {
+			let changed = events.required_signatures_changed().parse_log(raw_log)?;
+			self.required_signatures = changed.required_signatures.low_u32();
+		}
+		Ok(())
+	}
+}
+
+/// Returns false if authorities are known and `account` is not one of them.
+pub fn may_sign(authorities: &Option<AuthoritySet>, account: &Address) -> bool {
+	authorities.as_ref().map_or(true, |set| set.contains(account))
+}
+
+fn is_event(filter: ethabi::TopicFilter, log: &Log) -> bool {
+	let signature: Vec<ethabi::Hash> = filter.topic0.into();
+	log.topics.first().map_or(false, |topic| signature.contains(topic))
+}
+
+/// returns a filter for `AuthorityAdded`, `AuthorityRemoved` and `RequiredSignaturesChanged` events
+fn authorities_filter(contract: &BridgeAuthorities, address: Address) -> FilterBuilder {
+	let filters = vec![
+		contract.events().authority_added().create_filter(),
+		contract.events().authority_removed().create_filter(),
+		contract.events().required_signatures_changed().create_filter(),
+	];
+	web3_events_filter(filters, ::std::iter::once(address))
+}
+
+/// Follows authorities of a bridge contract.
+///
+/// Replays all authority events since the contract has been deployed and yields
+/// the set once all confirmed blocks have been processed, and then after every change.
+/// Fails with `NoRequiredSignaturesChanged` if the contract has never announced
+/// its required signatures, which means that it predates these events.
+pub struct AuthoritiesWatch<T: Transport> {
+	contract: BridgeAuthorities,
+	logs: LogStream<T>,
+	chain: Chain,
+	/// Last block before the contract has been deployed.
+	deployed_after: u64,
+	set: AuthoritySet,
+	/// Last yielded set.
+	yielded: Option<AuthoritySet>,
+}
+
+pub fn create_authorities_watch<T: Transport + Clone>(app: Arc<App<T>>, transport: T, node: Node, new_heads: Option<Arc<NewHeads>>, chain: Chain, contract_address: Address, deployed_at: Option<u64>) -> AuthoritiesWatch<T> {
+	let contract = BridgeAuthorities::default();
+	let deployed_after = deployed_at.map_or(0, |block| block.saturating_sub(1));
+	let logs_init = api::LogStreamInit {
+		after: deployed_after,
+		request_timeout: node.request_timeout,
+		poll_interval: node.poll_interval,
+		confirmations: node.required_confirmations,
+		max_block_range: node.max_block_range,
+		retry: app.config.retry.clone(),
+		new_heads,
+		filter: authorities_filter(&contract, contract_address),
+	};
+
+	AuthoritiesWatch {
+		logs: api::log_stream(transport, app.timer.clone(), logs_init),
+		contract,
+		chain,
+		deployed_after,
+		set: AuthoritySet::default(),
+		yielded: None,
+	}
+}
+
+impl<T: Transport> Stream for AuthoritiesWatch<T> {
+	type Item = AuthoritySet;
+	type Error = Error;
+
+	fn poll(&mut self) -> Poll<Option<Self::Item>, Self::Error> {
+		let context = match self.chain {
+			Chain::Home => "polling home for authority changes",
+			Chain::Foreign => "polling foreign for authority changes",
+		};
+		loop {
+			match try_stream!(self.logs.poll().map_err(|e| ErrorKind::ContextualizedError(Box::new(e), context))) {
+				LogStreamEvent::Logs(item) => for log in item.logs {
+					self.set.apply(&self.contract, log)?;
+				},
+				LogStreamEvent::Rewind(block) => {
+					// applied events can not be reverted, replay all of them
+					warn!("{:?} chain has been reorganized after block {}, replaying authority changes", self.chain, block);
+					self.logs.rewind(self.deployed_after);
+					self.set = AuthoritySet::default();
+				},
+			}
+
+			if !self.logs.is_synced() {
+				continue;
+			}
+
+			if self.set.required_signatures == 0 {
+				return Err(ErrorKind::NoRequiredSignaturesChanged.into());
+			}
+
+			if self.yielded.as_ref() != Some(&self.set) {
+				self.yielded = Some(self.set.clone());
+				return Ok(Some(self.set.clone()).into());
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use rustc_hex::FromHex;
+	use web3::types::{Log, Address};
+	use contracts::authorities::BridgeAuthorities;
+	use super::{AuthoritySet, may_sign};
+
+	const AUTHORITY_ADDED: &str = "550a8ae64ec9d6640b6f168a26d3e6364b90defe8110c92135aa775b279e54ea";
+	const AUTHORITY_REMOVED: &str = "272215cde179041f7a3e8da6f8aabc7c8fc1336ccd73aba698cb825a80d3be48";
+	const REQUIRED_SIGNATURES_CHANGED: &str = "10dbc913050d3180c3b99f7da91fd514af7cbc9c1bb59a0da5d2bc38f0cf395a";
+
+	fn log(topic: &str, data: &str) -> Log {
+		Log {
+			data: data.from_hex().unwrap().into(),
+			topics: vec![topic.into()],
+			transaction_hash: None,
+			address: Address::zero(),
+			block_hash: None,
+			transaction_index: None,
+			log_index: None,
+			transaction_log_index: None,
+			log_type: None,
+			block_number: None,
+			removed: None,
+		}
+	}
+
+	#[test]
+	fn test_apply_authority_events() {
+		let contract = BridgeAuthorities::default();
+		let mut set = AuthoritySet::default();
+		let first: Address = "aff3454fce5edbc8cca8697c15331677e6ebcccc".into();
+		let second: Address = "0000000000000000000000000000000000000002".into();
+
+		set.apply(&contract, log(AUTHORITY_ADDED, "000000000000000000000000aff3454fce5edbc8cca8697c15331677e6ebcccc")).unwrap();
+		set.apply(&contract, log(AUTHORITY_ADDED, "0000000000000000000000000000000000000000000000000000000000000002")).unwrap();
+		set.apply(&contract, log(REQUIRED_SIGNATURES_CHANGED, "0000000000000000000000000000000000000000000000000000000000000002")).unwrap();
+		assert_eq!(AuthoritySet { accounts: vec![first, second], required_signatures: 2 }, set);
+
+		set.apply(&contract, log(REQUIRED_SIGNATURES_CHANGED, "0000000000000000000000000000000000000000000000000000000000000001")).unwrap();
+		set.apply(&contract, log(AUTHORITY_REMOVED, "000000000000000000000000aff3454fce5edbc8cca8697c15331677e6ebcccc")).unwrap();
+		assert_eq!(AuthoritySet { accounts: vec![second], required_signatures: 1 }, set);
+
+		assert!(!may_sign(&Some(set.clone()), &first));
+		assert!(may_sign(&Some(set), &second));
+		assert!(may_sign(&None, &first));
+	}
+}
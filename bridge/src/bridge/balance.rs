@@ -1,4 +1,4 @@
-use futures::{Future, Stream, Poll};
+use futures::{Future, Stream, Poll, Async};
 use tokio_timer::Timeout;
 use web3::Transport;
 use web3::types::U256;
@@ -7,6 +7,7 @@ use error::Error;
 use config::Node;
 use std::sync::Arc;
 use app::App;
+use retry::Backoff;
 
 /// State of balance checking.
 enum BalanceCheckState<T: Transport> {
@@ -16,6 +17,8 @@ enum BalanceCheckState<T: Transport> {
 	BalanceRequest {
 		future: Timeout<ApiCall<U256, T::Out>>,
 	},
+	/// Waiting before the failed balance request is retried.
+	Retry,
 	/// Balance request completed.
 	Yield(Option<U256>),
 }
@@ -25,10 +28,12 @@ pub struct BalanceCheck<T: Transport> {
 	transport: T,
 	state: BalanceCheckState<T>,
 	node: Node,
+	backoff: Backoff,
 }
 
 pub fn create_balance_check<T: Transport + Clone>(app: Arc<App<T>>, transport: T, node: Node) -> BalanceCheck<T> {
 	BalanceCheck {
+		backoff: Backoff::new(app.timer.clone(), app.config.retry.clone()),
 		app,
 		state: BalanceCheckState::Wait,
 		transport,
@@ -49,9 +54,20 @@ impl<T: Transport> Stream for BalanceCheck<T> {
 						                           self.node.request_timeout),
 					}
 				},
-				BalanceCheckState::BalanceRequest { ref mut future } => {
-					let value = try_ready!(future.poll());
-					BalanceCheckState::Yield(Some(value))
+				BalanceCheckState::BalanceRequest { ref mut future } => match future.poll() {
+					Ok(Async::Ready(value)) => {
+						self.backoff.reset();
+						BalanceCheckState::Yield(Some(value))
+					},
+					Ok(Async::NotReady) => return Ok(Async::NotReady),
+					Err(err) => {
+						self.backoff.retry(err, "balance request")?;
+						BalanceCheckState::Retry
+					},
+				},
+				BalanceCheckState::Retry => {
+					try_ready!(self.backoff.poll());
+					BalanceCheckState::Wait
 				},
 				BalanceCheckState::Yield(ref mut balance) => match balance.take() {
 					None => BalanceCheckState::Wait,
@@ -0,0 +1,197 @@
+use std::sync::Arc;
+use futures::{Future, Stream, Poll, Async};
+use tokio_timer::{Interval, Timeout};
+use web3::{self, Transport};
+use web3::types::U256;
+use api::{self, ApiCall, BlockBaseFee, FeeHistory};
+use app::App;
+use config::{Node, TransactionType};
+use error::{Error, ErrorKind};
+use retry::Backoff;
+use rpc;
+
+/// State of base fee polling.
+enum BaseFeeState<T: Transport> {
+	/// Waiting for the next poll.
+	Wait,
+	/// Requesting base fee of the next block.
+	FeeHistory {
+		future: Timeout<ApiCall<FeeHistory, T::Out>>,
+	},
+	/// Requesting base fee of the latest block, for nodes which do not support `eth_feeHistory`.
+	LatestBlock {
+		future: Timeout<ApiCall<Option<BlockBaseFee>, T::Out>>,
+	},
+	/// Waiting before the failed request is retried.
+	Retry,
+	/// Base fee request completed, `max_fee_per_gas` is ready.
+	Yield(Option<U256>),
+}
+
+/// Polls base fee of a chain and yields `max_fee_per_gas` of EIP-1559 transactions sent to it.
+///
+/// The base fee of the next block is read with `eth_feeHistory`, nodes which do not support it
+/// are asked for the base fee of the latest block with `eth_getBlockByNumber` instead.
+/// Fails with `NoBaseFee` if the chain does not support EIP-1559.
+pub struct BaseFeeStream<T: Transport> {
+	app: Arc<App<T>>,
+	transport: T,
+	node: Node,
+	interval: Interval,
+	state: BaseFeeState<T>,
+	/// Set once the node rejected `eth_feeHistory`.
+	fee_history_unsupported: bool,
+	/// Set once the base fee has been read.
+	known: bool,
+	max_priority_fee_per_gas: u64,
+	base_fee_multiplier_percent: u64,
+	backoff: Backoff,
+}
+
+/// Returns `max_fee_per_gas` of transactions paying `max_priority_fee_per_gas` when the base fee is `base_fee`.
+///
+/// The base fee may grow by 12.5% per block, the multiplier keeps transactions includable while it does.
+fn max_fee_per_gas(base_fee: U256, max_priority_fee_per_gas: u64, base_fee_multiplier_percent: u64) -> U256 {
+	base_fee * U256::from(base_fee_multiplier_percent) / U256::from(100) + U256::from(max_priority_fee_per_gas)
+}
+
+/// Returns true if the node rejected a request because it does not implement the method.
+///
+/// Other errors, e.g. rate limiting, don't mean that `eth_feeHistory` is unsupported.
+fn is_method_unsupported(err: &rpc::Error) -> bool {
+	let message = err.message.to_lowercase();
+	err.code == rpc::ErrorCode::MethodNotFound
+		|| message.contains("not supported")
+		|| message.contains("does not exist")
+}
+
+/// Returns `None` if `node` is sent legacy transactions.
+pub fn create_base_fee_stream<T: Transport + Clone>(app: Arc<App<T>>, transport: T, node: Node) -> Option<BaseFeeStream<T>> {
+	let (max_priority_fee_per_gas, base_fee_multiplier_percent) = match node.transaction_type {
+		TransactionType::Legacy => return None,
+		TransactionType::Eip1559 { max_priority_fee_per_gas, base_fee_multiplier_percent } => (max_priority_fee_per_gas, base_fee_multiplier_percent),
+	};
+
+	let stream = BaseFeeStream {
+		interval: app.timer.interval(node.poll_interval),
+		backoff: Backoff::new(app.timer.clone(), app.config.retry.clone()),
+		app,
+		transport,
+		node,
+		state: BaseFeeState::Wait,
+		fee_history_unsupported: false,
+		known: false,
+		max_priority_fee_per_gas,
+		base_fee_multiplier_percent,
+	};
+	Some(stream)
+}
+
+impl<T: Transport> BaseFeeStream<T> {
+	/// Returns true once `max_fee_per_gas` has been yielded.
+	pub fn is_known(&self) -> bool {
+		self.known
+	}
+}
+
+impl<T: Transport> Stream for BaseFeeStream<T> {
+	type Item = U256;
+	type Error = Error;
+
+	fn poll(&mut self) -> Poll<Option<Self::Item>, Self::Error> {
+		loop {
+			let next_state = match self.state {
+				BaseFeeState::Wait => {
+					let _ = try_stream!(self.interval.poll());
+					if self.fee_history_unsupported {
+						BaseFeeState::LatestBlock {
+							future: self.app.timer.timeout(api::latest_block_base_fee(&self.transport), self.node.request_timeout),
+						}
+					} else {
+						BaseFeeState::FeeHistory {
+							future: self.app.timer.timeout(api::fee_history(&self.transport, 1, vec![]), self.node.request_timeout),
+						}
+					}
+				},
+				BaseFeeState::FeeHistory { ref mut future } => match future.poll() {
+					Ok(Async::Ready(history)) => match history.next_base_fee() {
+						Some(base_fee) => {
+							self.backoff.reset();
+							BaseFeeState::Yield(Some(max_fee_per_gas(base_fee, self.max_priority_fee_per_gas, self.base_fee_multiplier_percent)))
+						},
+						None => return Err(ErrorKind::NoBaseFee.into()),
+					},
+					Ok(Async::NotReady) => return Ok(Async::NotReady),
+					Err(Error(ErrorKind::Web3(web3::error::Error(web3::error::ErrorKind::Rpc(ref err), _)), _)) if is_method_unsupported(err) => {
+						warn!("{} does not support eth_feeHistory ({}), using base fee of the latest block", self.node.description(), err.message);
+						self.fee_history_unsupported = true;
+						BaseFeeState::LatestBlock {
+							future: self.app.timer.timeout(api::latest_block_base_fee(&self.transport), self.node.request_timeout),
+						}
+					},
+					Err(err) => {
+						self.backoff.retry(err, "fee history request")?;
+						BaseFeeState::Retry
+					},
+				},
+				BaseFeeState::LatestBlock { ref mut future } => match future.poll() {
+					Ok(Async::Ready(block)) => match block.and_then(|block| block.base_fee_per_gas) {
+						Some(base_fee) => {
+							self.backoff.reset();
+							BaseFeeState::Yield(Some(max_fee_per_gas(base_fee, self.max_priority_fee_per_gas, self.base_fee_multiplier_percent)))
+						},
+						None => return Err(ErrorKind::NoBaseFee.into()),
+					},
+					Ok(Async::NotReady) => return Ok(Async::NotReady),
+					Err(err) => {
+						self.backoff.retry(err, "latest block request")?;
+						BaseFeeState::Retry
+					},
+				},
+				BaseFeeState::Retry => {
+					try_ready!(self.backoff.poll());
+					BaseFeeState::Wait
+				},
+				BaseFeeState::Yield(ref mut max_fee) => match max_fee.take() {
+					None => BaseFeeState::Wait,
+					some => {
+						self.known = true;
+						return Ok(some.into());
+					},
+				},
+			};
+			self.state = next_state;
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use web3::types::U256;
+	use rpc;
+	use super::{max_fee_per_gas, is_method_unsupported};
+
+	#[test]
+	fn test_max_fee_per_gas() {
+		assert_eq!(U256::from(21_000_000_000u64), max_fee_per_gas(10_000_000_000u64.into(), 1_000_000_000, 200));
+		assert_eq!(U256::from(17_000_000_000u64), max_fee_per_gas(10_000_000_000u64.into(), 2_000_000_000, 150));
+		assert_eq!(U256::from(1_000_000_000u64), max_fee_per_gas(0.into(), 1_000_000_000, 200));
+	}
+
+	fn rpc_error(code: rpc::ErrorCode, message: &str) -> rpc::Error {
+		rpc::Error {
+			code,
+			message: message.into(),
+			data: None,
+		}
+	}
+
+	#[test]
+	fn test_is_method_unsupported() {
+		assert!(is_method_unsupported(&rpc_error(rpc::ErrorCode::MethodNotFound, "the method eth_feeHistory does not exist/is not available")));
+		assert!(is_method_unsupported(&rpc_error(rpc::ErrorCode::ServerError(-32000), "Method not supported")));
+		// rate limited requests are retried
+		assert!(!is_method_unsupported(&rpc_error(rpc::ErrorCode::ServerError(-32005), "limit exceeded")));
+		assert!(!is_method_unsupported(&rpc_error(rpc::ErrorCode::ServerError(-32000), "header not found")));
+	}
+}
@@ -6,11 +6,13 @@ use web3::Transport;
 #[cfg(feature = "deploy")]
 use web3::types::U256;
 use app::App;
-use database::Database;
+use database::{self, Database};
 use error::{Error, ErrorKind};
 #[cfg(feature = "deploy")]
 use api;
 #[cfg(feature = "deploy")]
+use config::BridgeMode;
+#[cfg(feature = "deploy")]
 use ethcore_transaction::{Transaction, Action};
 #[cfg(feature = "deploy")]
 use super::nonce::{NonceCheck,TransactionWithConfirmation};
@@ -64,17 +66,28 @@ impl<T: Transport + Clone> Future for Deploy<T> {
 	fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
 		loop {
 			let _next_state = match self.state {
-				DeployState::CheckIfNeeded => match Database::load(&self.app.database_path).map_err(ErrorKind::from) {
+				DeployState::CheckIfNeeded => match database::open(self.app.config.database_backend, &self.app.database_path)
+					.and_then(|backend| backend.load())
+					.map_err(ErrorKind::from) {
 					Ok(database) => return Ok(Deployed::Existing(database).into()),
 					Err(ErrorKind::MissingFile(_e)) => {
 						#[cfg(feature = "deploy")] {
 							println!("deploy");
-							let main_data = self.app.home_bridge.constructor(
-								self.app.config.home.contract.bin.clone().0,
-								self.app.config.authorities.required_signatures,
-								self.app.config.authorities.accounts.clone(),
-								self.app.config.estimated_gas_cost_of_withdraw
-							);
+							let main_data = match self.app.config.bridge_mode {
+								BridgeMode::NativeToErc => self.app.home_bridge.constructor(
+									self.app.config.home.contract.bin.clone().0,
+									self.app.config.authorities.required_signatures,
+									self.app.config.authorities.accounts.clone(),
+									self.app.config.estimated_gas_cost_of_withdraw
+								),
+								BridgeMode::ErcToErc { home_token } => self.app.home_erc20_bridge.constructor(
+									self.app.config.home.contract.bin.clone().0,
+									self.app.config.authorities.required_signatures,
+									self.app.config.authorities.accounts.clone(),
+									home_token,
+									self.app.config.tokens.iter().map(|pair| pair.home).collect::<Vec<_>>()
+								),
+							};
 							let test_data = self.app.foreign_bridge.constructor(
 								self.app.config.foreign.contract.bin.clone().0,
 								self.app.config.authorities.required_signatures,
@@ -101,11 +114,11 @@ impl<T: Transport + Clone> Future for Deploy<T> {
 							};
 
 							let main_future = api::send_transaction_with_nonce(self.app.connections.home.clone(), self.app.clone(),
-																			   self.app.config.home.clone(), main_tx, self.home_chain_id,
+																			   self.app.config.home.clone(), self.app.signers.home.clone(), main_tx, self.home_chain_id,
 																			   TransactionWithConfirmation(self.app.connections.home.clone(), self.app.config.home.poll_interval, self.app.config.home.required_confirmations));
 
 							let test_future = api::send_transaction_with_nonce(self.app.connections.foreign.clone(), self.app.clone(),
-																			   self.app.config.foreign.clone(), test_tx, self.foreign_chain_id,
+																			   self.app.config.foreign.clone(), self.app.signers.foreign.clone(), test_tx, self.foreign_chain_id,
 																			   TransactionWithConfirmation(self.app.connections.foreign.clone(), self.app.config.foreign.poll_interval, self.app.config.foreign.required_confirmations));
 
 							DeployState::Deploying(main_future.join(test_future))
@@ -127,6 +140,7 @@ impl<T: Transport + Clone> Future for Deploy<T> {
 						checked_deposit_relay: main_receipt.block_number.low_u64(),
 						checked_withdraw_relay: test_receipt.block_number.low_u64(),
 						checked_withdraw_confirm: test_receipt.block_number.low_u64(),
+						..Default::default()
 					};
 					return Ok(Deployed::New(database).into())
 				},
@@ -1,64 +1,184 @@
 use std::sync::{Arc, RwLock};
-use futures::{self, Future, Stream, stream::{Collect, FuturesUnordered, futures_unordered}, Poll};
+use futures::{self, Future, Stream, future::{JoinAll, join_all}, Poll};
+use tokio_timer::Timeout;
 use web3::Transport;
-use web3::types::{U256, Address, Bytes, Log, FilterBuilder};
-use ethabi::RawLog;
-use api::{LogStream, self};
+use web3::types::{U256, H256, H520, Address, Bytes, Log, FilterBuilder};
+use ethabi::{RawLog, Topic};
+use keccak_hash::keccak;
+use api::{LogStream, LogStreamEvent, ApiCall, self};
 use error::{Error, ErrorKind, Result};
-use database::Database;
-use contracts::{home, foreign};
+use database::{Database, TransactionKind, TransactionRecord};
+use contracts::{home, foreign, erc20};
+use config::{BridgeMode, TokenPair};
 use util::web3_filter;
 use app::App;
+use retry::Backoff;
+use signer::SignerFuture;
+use metrics::{METRICS, Chain};
 use ethcore_transaction::{Transaction, Action};
 use super::nonce::{NonceCheck, SendRawTransaction};
-use super::BridgeChecked;
+use super::{BridgeChecked, BridgeEvent};
+use super::authorities::{AuthoritySet, may_sign};
+use super::gas_limits::{GasLimit, TransactionsGas, transactions_gas};
+use super::gas_price::GasPrice;
 use itertools::Itertools;
 
-fn deposits_filter(home: &home::HomeBridge, address: Address) -> FilterBuilder {
-	let filter = home.events().deposit().create_filter();
-	web3_filter(filter, ::std::iter::once(address))
+fn deposits_filter(home: &home::HomeBridge, token: &erc20::ERC20, mode: BridgeMode, tokens: &[TokenPair], address: Address) -> FilterBuilder {
+	match mode {
+		BridgeMode::NativeToErc => {
+			let filter = home.events().deposit().create_filter();
+			web3_filter(filter, ::std::iter::once(address))
+		},
+		BridgeMode::ErcToErc { home_token } => {
+			// both plain transfers to the home contract and `approveAndCall` emit `Transfer`
+			let filter = token.events().transfer().create_filter(Topic::Any, address);
+			let home_tokens = ::std::iter::once(home_token).chain(tokens.iter().map(|pair| pair.home));
+			web3_filter(filter, home_tokens)
+		},
+	}
+}
+
+/// Deposit made on home.
+#[derive(Debug, PartialEq)]
+struct Deposit {
+	recipient: Address,
+	value: U256,
+	transaction_hash: H256,
+	/// Foreign token registered in `ForeignBridge.registerToken`, `None` for `ForeignBridge.erc20token`.
+	token: Option<Address>,
+}
+
+impl Deposit {
+	/// Hash signed by the authority relaying the deposit,
+	/// `keccak256(token, recipient, value, transactionHash)` tightly packed as in `ForeignBridge`.
+	fn hash(&self) -> H256 {
+		let mut message = Vec::with_capacity(20 + 20 + 32 + 32);
+		if let Some(token) = self.token {
+			message.extend_from_slice(&token.0[..]);
+		}
+		message.extend_from_slice(&self.recipient.0[..]);
+		message.extend_from_slice(&H256::from(self.value));
+		message.extend_from_slice(&self.transaction_hash.0[..]);
+		keccak(message)
+	}
 }
 
-fn deposit_relay_payload(home: &home::HomeBridge, foreign: &foreign::ForeignBridge, log: Log) -> Result<Bytes> {
+fn parse_deposit(home: &home::HomeBridge, token: &erc20::ERC20, mode: BridgeMode, tokens: &[TokenPair], log: &Log) -> Result<Deposit> {
 	let raw_log = RawLog {
-		topics: log.topics,
-		data: log.data.0,
+		topics: log.topics.clone(),
+		data: log.data.0.clone(),
+	};
+	let (recipient, value, token) = match mode {
+		BridgeMode::NativeToErc => {
+			let deposit_log = home.events().deposit().parse_log(raw_log)?;
+			(deposit_log.recipient, deposit_log.value, None)
+		},
+		BridgeMode::ErcToErc { home_token } => {
+			// tokens are minted on foreign for the sender
+			let transfer_log = token.events().transfer().parse_log(raw_log)?;
+			let token = if log.address == home_token {
+				None
+			} else {
+				let pair = tokens.iter()
+					.find(|pair| pair.home == log.address)
+					.ok_or_else(|| ErrorKind::UnknownToken(log.address))?;
+				Some(pair.foreign)
+			};
+			(transfer_log.from, transfer_log.value, token)
+		},
 	};
-	let deposit_log = home.events().deposit().parse_log(raw_log)?;
-	let hash = log.transaction_hash.expect("log to be mined and contain `transaction_hash`");
-	let payload = foreign.functions().deposit().input(deposit_log.recipient, deposit_log.value, hash.0);
-	Ok(payload.into())
+	Ok(Deposit {
+		recipient,
+		value,
+		transaction_hash: log.transaction_hash.expect("log to be mined and contain `transaction_hash`"),
+		token,
+	})
+}
+
+/// Returns payload of `ForeignBridge.deposit` or, for registered tokens, `ForeignBridge.depositToken` call
+/// relaying the deposit with the `signature` of its hash.
+fn deposit_relay_payload(foreign: &foreign::ForeignBridge, deposit: &Deposit, signature: H520) -> Bytes {
+	match deposit.token {
+		Some(token) => foreign.functions().deposit_token().input(token, deposit.recipient, deposit.value, deposit.transaction_hash.0, signature.0.to_vec()).into(),
+		None => foreign.functions().deposit().input(deposit.recipient, deposit.value, deposit.transaction_hash.0, signature.0.to_vec()).into(),
+	}
+}
+
+/// Returns payload of `ForeignBridge.isDepositSigned` (or `ForeignBridge.isTokenDepositSigned`) call
+/// checking whether a deposit signed by `authority` has already been relayed.
+fn deposit_signed_payload(foreign: &foreign::ForeignBridge, authority: Address, deposit: &Deposit) -> Bytes {
+	match deposit.token {
+		Some(token) => foreign.functions().is_token_deposit_signed().input(authority, token, deposit.recipient, deposit.value, deposit.transaction_hash.0).into(),
+		None => foreign.functions().is_deposit_signed().input(authority, deposit.recipient, deposit.value, deposit.transaction_hash.0).into(),
+	}
 }
 
 /// State of deposits relay.
 enum DepositRelayState<T: Transport> {
 	/// Deposit relay is waiting for logs.
 	Wait,
+	/// Checking which deposits signed by this authority have already been relayed.
+	CheckDeposits {
+		future: JoinAll<Vec<Timeout<ApiCall<Bytes, T::Out>>>>,
+		/// Hashes of home transactions and numbers of blocks containing deposits, with the deposits.
+		deposits: Vec<((H256, u64), Deposit)>,
+		block: u64,
+	},
+	/// Signing deposits which have not been relayed yet.
+	SignDeposits {
+		future: JoinAll<Vec<Timeout<SignerFuture<H520>>>>,
+		/// Hashes of home transactions and numbers of blocks containing deposits to sign.
+		sources: Vec<(H256, u64)>,
+		deposits: Vec<Deposit>,
+		block: u64,
+	},
+	/// Computing gas limits of deposit relays.
+	EstimateGas {
+		future: TransactionsGas<T>,
+		/// Hashes of home transactions and numbers of blocks containing deposits to relay.
+		sources: Vec<(H256, u64)>,
+		payloads: Vec<Bytes>,
+		block: u64,
+	},
 	/// Relaying deposits in progress.
 	RelayDeposits {
-		future: Collect<FuturesUnordered<NonceCheck<T, SendRawTransaction<T>>>>,
+		future: JoinAll<Vec<NonceCheck<T, SendRawTransaction<T>>>>,
+		/// Hashes of home transactions and numbers of blocks containing relayed deposits.
+		sources: Vec<(H256, u64)>,
+		block: u64,
+	},
+	/// Recording sent transactions.
+	Record {
+		records: Option<Vec<TransactionRecord>>,
 		block: u64,
 	},
 	/// All deposits till given block has been relayed.
 	Yield(Option<u64>),
 }
 
-pub fn create_deposit_relay<T: Transport + Clone>(app: Arc<App<T>>, init: &Database, foreign_balance: Arc<RwLock<Option<U256>>>, foreign_chain_id: u64, foreign_gas_price: Arc<RwLock<u64>>) -> DepositRelay<T> {
+pub fn create_deposit_relay<T: Transport + Clone>(app: Arc<App<T>>, init: &Database, foreign_balance: Arc<RwLock<Option<U256>>>, foreign_chain_id: u64, foreign_gas_price: GasPrice, foreign_authorities: Arc<RwLock<Option<AuthoritySet>>>, gas_limit: GasLimit) -> DepositRelay<T> {
 	let logs_init = api::LogStreamInit {
 		after: init.checked_deposit_relay,
 		request_timeout: app.config.home.request_timeout,
 		poll_interval: app.config.home.poll_interval,
 		confirmations: app.config.home.required_confirmations,
-		filter: deposits_filter(&app.home_bridge, init.home_contract_address),
+		max_block_range: app.config.home.max_block_range,
+		retry: app.config.retry.clone(),
+		new_heads: app.new_heads.home.clone(),
+		filter: deposits_filter(&app.home_bridge, &app.erc20_token, app.config.bridge_mode, &app.config.tokens, init.home_contract_address),
 	};
 	DepositRelay {
 		logs: api::log_stream(app.connections.home.clone(), app.timer.clone(), logs_init),
 		foreign_contract: init.foreign_contract_address,
 		state: DepositRelayState::Wait,
+		checked: init.checked_deposit_relay,
+		backoff: Backoff::new(app.timer.clone(), app.config.retry.clone()),
 		app,
 		foreign_balance,
 		foreign_chain_id,
 		foreign_gas_price,
+		foreign_authorities,
+		gas_limit,
 	}
 }
 
@@ -66,42 +186,168 @@ pub struct DepositRelay<T: Transport> {
 	app: Arc<App<T>>,
 	logs: LogStream<T>,
 	state: DepositRelayState<T>,
+	/// Last block which has been completely processed.
+	checked: u64,
+	backoff: Backoff,
 	foreign_contract: Address,
 	foreign_balance: Arc<RwLock<Option<U256>>>,
 	foreign_chain_id: u64,
-	foreign_gas_price: Arc<RwLock<u64>>,
+	foreign_gas_price: GasPrice,
+	/// Authorities of the foreign contract, only deposits signed by authorities are accepted.
+	foreign_authorities: Arc<RwLock<Option<AuthoritySet>>>,
+	/// Gas limit of deposit relays.
+	gas_limit: GasLimit,
 }
 
 impl<T: Transport> Stream for DepositRelay<T> {
-	type Item = BridgeChecked;
+	type Item = BridgeEvent;
 	type Error = Error;
 
 	fn poll(&mut self) -> Poll<Option<Self::Item>, Self::Error> {
+		loop {
+			try_ready!(self.backoff.poll());
+			match self.poll_relay() {
+				Err(err) => {
+					self.backoff.retry(err, "relaying deposits")?;
+					// process all blocks after the last completed one again
+					self.logs.rewind(self.checked);
+					self.state = DepositRelayState::Wait;
+				},
+				result => {
+					if let Ok(futures::Async::Ready(Some(BridgeEvent::Checked(BridgeChecked::DepositRelay(block))))) = result {
+						self.checked = block;
+						self.backoff.reset();
+					}
+					return result;
+				},
+			}
+		}
+	}
+}
+
+impl<T: Transport> DepositRelay<T> {
+	fn poll_relay(&mut self) -> Poll<Option<BridgeEvent>, Error> {
 		loop {
 			let next_state = match self.state {
 				DepositRelayState::Wait => {
+					if let futures::Async::NotReady = self.app.switches.deposit_relay.poll_resumed() {
+						return Ok(futures::Async::NotReady);
+					}
 					let foreign_balance = self.foreign_balance.read().unwrap();
 					if foreign_balance.is_none() {
 						warn!("foreign contract balance is unknown");
 						return Ok(futures::Async::NotReady);
 					}
-					let item = try_stream!(self.logs.poll().map_err(|e| ErrorKind::ContextualizedError(Box::new(e), "polling home for deposits")));
-					let len = item.logs.len();
-					info!("got {} new deposits to relay", len);
-
-					let gas = U256::from(self.app.config.txs.deposit_relay.gas);
-					let gas_price = U256::from(*self.foreign_gas_price.read().unwrap());
-					let balance_required = gas * gas_price * U256::from(item.logs.len());
-					
-					if balance_required > *foreign_balance.as_ref().unwrap() {
-						return Err(ErrorKind::InsufficientFunds.into())
+					if !may_sign(&self.foreign_authorities.read().unwrap(), &self.app.config.validator_account()) {
+						warn!("{:?} is not a foreign authority, waiting until it is added", self.app.config.validator_account());
+						return Ok(futures::Async::NotReady);
 					}
-					let deposits = item.logs
+					if self.foreign_gas_price.is_refused() {
+						warn!("foreign gas price is stale, waiting until it is updated");
+						return Ok(futures::Async::NotReady);
+					}
+					let item = match try_stream!(self.logs.poll().map_err(|e| ErrorKind::ContextualizedError(Box::new(e), "polling home for deposits"))) {
+						LogStreamEvent::Logs(item) => item,
+						LogStreamEvent::Rewind(block) => {
+							warn!("home chain has been reorganized, rescanning deposits after block {}", block);
+							return Ok(Some(BridgeEvent::Checked(BridgeChecked::DepositRelay(block))).into());
+						},
+					};
+					if let Some(head_block) = self.logs.head_block() {
+						METRICS.head_block(Chain::Home, head_block);
+					}
+					info!("got {} new deposits to relay", item.logs.len());
+
+					let block = item.to;
+					let authority = self.app.config.validator_account();
+					let (checks, deposits): (Vec<_>, Vec<_>) = item.logs
 						.into_iter()
-						.map(|log| deposit_relay_payload(&self.app.home_bridge, &self.app.foreign_bridge, log))
+						.map(|log| {
+							let source = (
+								log.transaction_hash.unwrap_or_default(),
+								log.block_number.map_or(block, |number| number.low_u64()),
+							);
+							let deposit = parse_deposit(&self.app.home_bridge, &self.app.erc20_token, self.app.config.bridge_mode, &self.app.config.tokens, &log)?;
+							let check = deposit_signed_payload(&self.app.foreign_bridge, authority, &deposit);
+							Ok((check, (source, deposit)))
+						})
 						.collect::<Result<Vec<_>>>()?
 						.into_iter()
-						.map(|payload| {
+						.unzip();
+
+					let checks = checks.into_iter()
+						.map(|payload| self.app.timer.timeout(
+							api::call(self.app.connections.foreign.clone(), self.foreign_contract, payload),
+							self.app.config.foreign.request_timeout))
+						.collect_vec();
+
+					DepositRelayState::CheckDeposits {
+						future: join_all(checks),
+						deposits,
+						block,
+					}
+				},
+				DepositRelayState::CheckDeposits { ref mut future, ref mut deposits, block } => {
+					let signed = try_ready!(future.poll().map_err(|e| ErrorKind::ContextualizedError(Box::new(e), "checking relayed deposits on foreign")));
+					let is_deposit_signed = self.app.foreign_bridge.functions().is_deposit_signed();
+					let signed = signed.iter()
+						.map(|output| is_deposit_signed.output(output.0.as_slice()))
+						.collect::<::ethabi::Result<Vec<bool>>>()?;
+
+					let (sources, deposits): (Vec<_>, Vec<_>) = deposits.drain(..)
+						.zip(signed.into_iter())
+						.filter_map(|((source, deposit), signed)| if signed {
+							info!("deposit {} has already been relayed, skipping", source.0);
+							None
+						} else {
+							Some((source, deposit))
+						})
+						.unzip();
+
+					let signatures = deposits.iter()
+						.map(|deposit| self.app.timer.timeout(
+							self.app.signers.validator.sign_message(self.app.config.validator_account(), deposit.hash().to_vec()),
+							self.app.config.foreign.request_timeout))
+						.collect_vec();
+
+					DepositRelayState::SignDeposits {
+						future: join_all(signatures),
+						sources,
+						deposits,
+						block,
+					}
+				},
+				DepositRelayState::SignDeposits { ref mut future, ref mut sources, ref mut deposits, block } => {
+					let signatures = try_ready!(future.poll().map_err(|e| ErrorKind::ContextualizedError(Box::new(e), "signing deposits")));
+					let payloads = deposits.drain(..)
+						.zip(signatures.into_iter())
+						.map(|(deposit, signature)| deposit_relay_payload(&self.app.foreign_bridge, &deposit, signature))
+						.collect_vec();
+
+					let future = transactions_gas(&self.app.connections.foreign, &self.app.timer, &self.app.config.foreign, self.foreign_contract,
+												  &self.gas_limit, self.app.config.txs.deposit_relay.estimate_gas, &payloads);
+					DepositRelayState::EstimateGas {
+						future,
+						sources: sources.drain(..).collect(),
+						payloads,
+						block,
+					}
+				},
+				DepositRelayState::EstimateGas { ref mut future, ref mut sources, ref mut payloads, block } => {
+					let gas = try_ready!(future.poll().map_err(|e| ErrorKind::ContextualizedError(Box::new(e), "estimating gas of deposit relays on foreign")));
+					let len = payloads.len();
+
+					let gas_price = U256::from(self.foreign_gas_price.get().ok_or(ErrorKind::StaleGasPrice)?);
+					let balance_required = gas.iter().fold(U256::zero(), |total, gas| total + *gas * gas_price);
+
+					let foreign_balance = *self.foreign_balance.read().unwrap();
+					if balance_required > foreign_balance.unwrap_or_default() {
+						return Err(ErrorKind::InsufficientFunds.into())
+					}
+
+					let deposits = payloads.drain(..)
+						.zip(gas.into_iter())
+						.map(|(payload, gas)| {
 							let tx = Transaction {
 								gas,
 								gas_price,
@@ -111,23 +357,40 @@ impl<T: Transport> Stream for DepositRelay<T> {
 								action: Action::Call(self.foreign_contract.clone()),
 							};
 							api::send_transaction_with_nonce(self.app.connections.foreign.clone(), self.app.clone(), self.app.config.foreign.clone(),
-															 tx, self.foreign_chain_id, SendRawTransaction(self.app.connections.foreign.clone()))
+															 self.app.signers.foreign.clone(), tx, self.foreign_chain_id, SendRawTransaction(self.app.connections.foreign.clone()))
 						}).collect_vec();
 
 					info!("relaying {} deposits", len);
 					DepositRelayState::RelayDeposits {
-						future: futures_unordered(deposits).collect(),
-						block: item.to,
+						future: join_all(deposits),
+						sources: sources.drain(..).collect(),
+						block,
 					}
 				},
-				DepositRelayState::RelayDeposits { ref mut future, block } => {
-					let _ = try_ready!(future.poll().map_err(|e| ErrorKind::ContextualizedError(Box::new(e), "relaying deposit to foreign")));
+				DepositRelayState::RelayDeposits { ref mut future, ref sources, block } => {
+					let hashes = try_ready!(future.poll().map_err(|e| ErrorKind::ContextualizedError(Box::new(e), "relaying deposit to foreign")));
 					info!("deposit relay completed");
-					DepositRelayState::Yield(Some(block))
+					let records = sources.iter()
+						.zip(hashes.into_iter())
+						.map(|(&(source_transaction_hash, source_block), transaction_hash)| TransactionRecord {
+							kind: TransactionKind::DepositRelay,
+							source_transaction_hash,
+							transaction_hash,
+							block: source_block,
+						})
+						.collect();
+					DepositRelayState::Record {
+						records: Some(records),
+						block,
+					}
+				},
+				DepositRelayState::Record { ref mut records, block } => match records.take() {
+					Some(records) => return Ok(Some(BridgeEvent::Sent(records)).into()),
+					None => DepositRelayState::Yield(Some(block)),
 				},
 				DepositRelayState::Yield(ref mut block) => match block.take() {
 					None => DepositRelayState::Wait,
-					Some(v) => return Ok(Some(BridgeChecked::DepositRelay(v)).into()),
+					Some(v) => return Ok(Some(BridgeEvent::Checked(BridgeChecked::DepositRelay(v))).into()),
 				}
 			};
 			self.state = next_state;
@@ -138,19 +401,15 @@ impl<T: Transport> Stream for DepositRelay<T> {
 #[cfg(test)]
 mod tests {
 	use rustc_hex::FromHex;
-	use web3::types::{Log, Bytes, Address};
-	use contracts::{home, foreign};
-	use super::deposit_relay_payload;
-
-	#[test]
-	fn test_deposit_relay_payload() {
-		let home = home::HomeBridge::default();
-		let foreign = foreign::ForeignBridge::default();
+	use web3::types::{Log, Bytes, Address, H256, H520};
+	use contracts::{home, foreign, erc20};
+	use config::{BridgeMode, TokenPair};
+	use super::{Deposit, parse_deposit, deposit_relay_payload, deposit_signed_payload};
 
-		let data = "000000000000000000000000aff3454fce5edbc8cca8697c15331677e6ebcccc00000000000000000000000000000000000000000000000000000000000000f0".from_hex().unwrap();
-		let log = Log {
-			data: data.into(),
-			topics: vec!["e1fffcc4923d04b559f4d29a8bfc6cda04eb5b0d3c460751c2402c5c5cc9109c".into()],
+	fn log(topics: Vec<&str>, data: &str) -> Log {
+		Log {
+			data: data.from_hex().unwrap().into(),
+			topics: topics.into_iter().map(Into::into).collect(),
 			transaction_hash: Some("884edad9ce6fa2440d8a54cc123490eb96d2768479d49ff9c7366125a9424364".into()),
 			address: Address::zero(),
 			block_hash: None,
@@ -160,10 +419,94 @@ mod tests {
 			log_type: None,
 			block_number: None,
 			removed: None,
+		}
+	}
+
+	fn expected_deposit() -> Deposit {
+		Deposit {
+			recipient: "aff3454fce5edbc8cca8697c15331677e6ebcccc".into(),
+			value: 0xf0u64.into(),
+			transaction_hash: "884edad9ce6fa2440d8a54cc123490eb96d2768479d49ff9c7366125a9424364".into(),
+			token: None,
+		}
+	}
+
+	#[test]
+	fn test_parse_deposit() {
+		let log = log(
+			vec!["e1fffcc4923d04b559f4d29a8bfc6cda04eb5b0d3c460751c2402c5c5cc9109c"],
+			"000000000000000000000000aff3454fce5edbc8cca8697c15331677e6ebcccc00000000000000000000000000000000000000000000000000000000000000f0",
+		);
+		let deposit = parse_deposit(&home::HomeBridge::default(), &erc20::ERC20::default(), BridgeMode::NativeToErc, &[], &log).unwrap();
+		assert_eq!(expected_deposit(), deposit);
+	}
+
+	#[test]
+	fn test_parse_token_deposit() {
+		let mut log = log(
+			vec![
+				"ddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef",
+				"000000000000000000000000aff3454fce5edbc8cca8697c15331677e6ebcccc",
+				"0000000000000000000000000000000000000000000000000000000000000003",
+			],
+			"00000000000000000000000000000000000000000000000000000000000000f0",
+		);
+		let mode = BridgeMode::ErcToErc {
+			home_token: "0000000000000000000000000000000000000002".into(),
+		};
+		let tokens = vec![TokenPair {
+			home: "0000000000000000000000000000000000000005".into(),
+			foreign: "0000000000000000000000000000000000000006".into(),
+		}];
+		let home = home::HomeBridge::default();
+		let token = erc20::ERC20::default();
+
+		log.address = "0000000000000000000000000000000000000002".into();
+		let deposit = parse_deposit(&home, &token, mode, &tokens, &log).unwrap();
+		assert_eq!(expected_deposit(), deposit);
+
+		log.address = "0000000000000000000000000000000000000005".into();
+		let deposit = parse_deposit(&home, &token, mode, &tokens, &log).unwrap();
+		assert_eq!(Some("0000000000000000000000000000000000000006".into()), deposit.token);
+
+		log.address = "0000000000000000000000000000000000000007".into();
+		assert!(parse_deposit(&home, &token, mode, &tokens, &log).is_err());
+	}
+
+	#[test]
+	fn test_deposit_hash() {
+		assert_eq!(H256::from("fd706bea0e06791fd64ba3691e99ae1ca029f18e1835ca3bbe1fcdd41cb57568"), expected_deposit().hash());
+
+		let deposit = Deposit {
+			token: Some("0000000000000000000000000000000000000006".into()),
+			..expected_deposit()
 		};
+		assert_eq!(H256::from("ee47bc08178a998a4ff5c3ec53d94f64c950aa00f125eab11476d117f6bd39d8"), deposit.hash());
+	}
 
-		let payload = deposit_relay_payload(&home, &foreign, log).unwrap();
-		let expected: Bytes = "26b3293f000000000000000000000000aff3454fce5edbc8cca8697c15331677e6ebcccc00000000000000000000000000000000000000000000000000000000000000f0884edad9ce6fa2440d8a54cc123490eb96d2768479d49ff9c7366125a9424364".from_hex().unwrap().into();
+	#[test]
+	fn test_deposit_relay_payload() {
+		let foreign = foreign::ForeignBridge::default();
+		let signature = H520::from_slice(&[0x11; 65]);
+		let payload = deposit_relay_payload(&foreign, &expected_deposit(), signature);
+		let expected: Bytes = "17c224e8000000000000000000000000aff3454fce5edbc8cca8697c15331677e6ebcccc00000000000000000000000000000000000000000000000000000000000000f0884edad9ce6fa2440d8a54cc123490eb96d2768479d49ff9c7366125a942436400000000000000000000000000000000000000000000000000000000000000800000000000000000000000000000000000000000000000000000000000000041111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111100000000000000000000000000000000000000000000000000000000000000".from_hex().unwrap().into();
+		assert_eq!(expected, payload);
+
+		let deposit = Deposit {
+			token: Some("0000000000000000000000000000000000000006".into()),
+			..expected_deposit()
+		};
+		let payload = deposit_relay_payload(&foreign, &deposit, signature);
+		let expected: Bytes = "b5d8fe790000000000000000000000000000000000000000000000000000000000000006000000000000000000000000aff3454fce5edbc8cca8697c15331677e6ebcccc00000000000000000000000000000000000000000000000000000000000000f0884edad9ce6fa2440d8a54cc123490eb96d2768479d49ff9c7366125a942436400000000000000000000000000000000000000000000000000000000000000a00000000000000000000000000000000000000000000000000000000000000041111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111100000000000000000000000000000000000000000000000000000000000000".from_hex().unwrap().into();
+		assert_eq!(expected, payload);
+	}
+
+	#[test]
+	fn test_deposit_signed_payload() {
+		let foreign = foreign::ForeignBridge::default();
+		let authority = "0000000000000000000000000000000000000001".into();
+		let payload = deposit_signed_payload(&foreign, authority, &expected_deposit());
+		let expected: Bytes = "1f0570600000000000000000000000000000000000000000000000000000000000000001000000000000000000000000aff3454fce5edbc8cca8697c15331677e6ebcccc00000000000000000000000000000000000000000000000000000000000000f0884edad9ce6fa2440d8a54cc123490eb96d2768479d49ff9c7366125a9424364".from_hex().unwrap().into();
 		assert_eq!(expected, payload);
 	}
 }
@@ -0,0 +1,358 @@
+use std::sync::{Arc, RwLock};
+use futures::{Future, Stream, Poll, Async};
+use futures::future::{JoinAll, join_all};
+use tokio_timer::{Timer, Timeout};
+use web3::Transport;
+use web3::types::{U256, Address, Bytes, FilterBuilder, Log};
+use ethabi::RawLog;
+use api::{self, ApiCall, LogStream, LogStreamEvent, NewHeads};
+use app::App;
+use config::{Node, RetryConfig, GasEstimation};
+use contracts::home_gas_limits::HomeBridgeGasConsumptionLimitsStorage;
+use contracts::foreign_gas_limits::ForeignBridgeGasConsumptionLimitsStorage;
+use error::{Error, ErrorKind, Result};
+use metrics::Chain;
+use retry::Backoff;
+use util::web3_filter;
+
+/// Gas limit of transactions of one kind, shared with the component sending them.
+///
+/// The limit is set in the bridge contract, the configured one is used while the contract does not set it.
+#[derive(Clone, Default)]
+pub struct GasLimit {
+	value: Arc<RwLock<u64>>,
+	/// Configured gas limit.
+	default: u64,
+}
+
+impl GasLimit {
+	pub fn new(default: u64) -> Self {
+		GasLimit {
+			value: Arc::new(RwLock::new(default)),
+			default,
+		}
+	}
+
+	pub fn get(&self) -> u64 {
+		*self.value.read().unwrap()
+	}
+
+	/// Sets the limit read from the contract, `0` means that the contract does not set it.
+	fn set(&self, limit: U256) {
+		*self.value.write().unwrap() = if limit.is_zero() { self.default } else { limit.low_u64() };
+	}
+}
+
+/// Resolves to gas limits of transactions about to be sent.
+pub enum TransactionsGas<T: Transport> {
+	/// All transactions use the same gas limit.
+	Fixed {
+		gas: U256,
+		count: usize,
+	},
+	/// Gas of each transaction is estimated.
+	Estimated {
+		future: JoinAll<Vec<Timeout<ApiCall<U256, T::Out>>>>,
+		estimation: GasEstimation,
+	},
+}
+
+/// Returns gas limits of transactions sent from `node.account` to `contract` with given payloads.
+///
+/// Gas of each transaction is estimated if `estimation` is set, otherwise `gas_limit` is used.
+pub fn transactions_gas<T: Transport + Clone>(transport: &T, timer: &Timer, node: &Node, contract: Address, gas_limit: &GasLimit, estimation: Option<GasEstimation>, payloads: &[Bytes]) -> TransactionsGas<T> {
+	match estimation {
+		Some(estimation) => {
+			let estimates = payloads.iter()
+				.map(|payload| timer.timeout(
+					api::estimate_gas(transport.clone(), node.account, contract, payload.clone()),
+					node.request_timeout))
+				.collect();
+			TransactionsGas::Estimated {
+				future: join_all(estimates),
+				estimation,
+			}
+		},
+		None => TransactionsGas::Fixed {
+			gas: gas_limit.get().into(),
+			count: payloads.len(),
+		},
+	}
+}
+
+impl<T: Transport> Future for TransactionsGas<T> {
+	type Item = Vec<U256>;
+	type Error = Error;
+
+	fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
+		match *self {
+			TransactionsGas::Fixed { gas, count } => Ok(Async::Ready(vec![gas; count])),
+			TransactionsGas::Estimated { ref mut future, estimation } => {
+				let estimates = try_ready!(future.poll());
+				let gas = estimates.into_iter().map(|estimate| estimation.gas(estimate)).collect::<Result<_>>()?;
+				Ok(Async::Ready(gas))
+			},
+		}
+	}
+}
+
+/// Payloads of calls reading the gas limits set in the contract,
+/// in order of `GasConsumptionLimitsUpdated` values.
+fn gas_limits_payloads(chain: Chain) -> Vec<Bytes> {
+	match chain {
+		Chain::Home => {
+			let home = HomeBridgeGasConsumptionLimitsStorage::default();
+			vec![home.functions().gas_limit_withdraw_relay().input().into()]
+		},
+		Chain::Foreign => {
+			let foreign = ForeignBridgeGasConsumptionLimitsStorage::default();
+			vec![
+				foreign.functions().gas_limit_deposit_relay().input().into(),
+				foreign.functions().gas_limit_withdraw_confirm().input().into(),
+			]
+		},
+	}
+}
+
+fn gas_limits_outputs(chain: Chain, outputs: &[Bytes]) -> Result<Vec<U256>> {
+	let limits = match chain {
+		Chain::Home => {
+			let home = HomeBridgeGasConsumptionLimitsStorage::default();
+			vec![home.functions().gas_limit_withdraw_relay().output(outputs[0].0.as_slice())?]
+		},
+		Chain::Foreign => {
+			let foreign = ForeignBridgeGasConsumptionLimitsStorage::default();
+			vec![
+				foreign.functions().gas_limit_deposit_relay().output(outputs[0].0.as_slice())?,
+				foreign.functions().gas_limit_withdraw_confirm().output(outputs[1].0.as_slice())?,
+			]
+		},
+	};
+	Ok(limits)
+}
+
+/// returns a filter for `GasConsumptionLimitsUpdated` events
+fn gas_limits_filter(chain: Chain, address: Address) -> FilterBuilder {
+	let filter = match chain {
+		Chain::Home => HomeBridgeGasConsumptionLimitsStorage::default().events().gas_consumption_limits_updated().create_filter(),
+		Chain::Foreign => ForeignBridgeGasConsumptionLimitsStorage::default().events().gas_consumption_limits_updated().create_filter(),
+	};
+	web3_filter(filter, ::std::iter::once(address))
+}
+
+fn parse_gas_limits_log(chain: Chain, log: Log) -> Result<Vec<U256>> {
+	let raw_log = RawLog {
+		topics: log.topics,
+		data: log.data.0,
+	};
+	let limits = match chain {
+		Chain::Home => {
+			let home = HomeBridgeGasConsumptionLimitsStorage::default();
+			let updated = home.events().gas_consumption_limits_updated().parse_log(raw_log)?;
+			vec![updated.withdraw_relay]
+		},
+		Chain::Foreign => {
+			let foreign = ForeignBridgeGasConsumptionLimitsStorage::default();
+			let updated = foreign.events().gas_consumption_limits_updated().parse_log(raw_log)?;
+			vec![updated.deposit_relay, updated.withdraw_confirm]
+		},
+	};
+	Ok(limits)
+}
+
+fn update_gas_limits(chain: Chain, limits: &[GasLimit], values: Vec<U256>) {
+	for (limit, value) in limits.iter().zip(values.into_iter()) {
+		limit.set(value);
+	}
+	let limits = limits.iter().map(GasLimit::get).collect::<Vec<_>>();
+	info!("{:?} gas limits: {:?}", chain, limits);
+}
+
+/// State of gas limits watch.
+enum GasLimitsWatchState<T: Transport> {
+	/// Fetching the block after which updates are followed.
+	FetchBlockNumber(Timeout<ApiCall<U256, T::Out>>),
+	/// Reading the gas limits set in the contract.
+	FetchGasLimits {
+		future: JoinAll<Vec<Timeout<ApiCall<Bytes, T::Out>>>>,
+		block: u64,
+	},
+	/// Waiting before the failed request is retried.
+	Retry,
+	/// Following `GasConsumptionLimitsUpdated` events.
+	Follow(LogStream<T>),
+}
+
+/// Reads gas limits set in a bridge contract at startup and follows their updates.
+///
+/// Home contract sets the gas limit of withdraw relays, foreign contract those of deposit relays
+/// and withdraw confirmations. Yields after the limits have been read or updated.
+pub struct GasLimitsWatch<T: Transport> {
+	transport: T,
+	timer: Timer,
+	state: GasLimitsWatchState<T>,
+	chain: Chain,
+	contract_address: Address,
+	/// Limits set by the contract, in order of `GasConsumptionLimitsUpdated` values.
+	limits: Vec<GasLimit>,
+	node: Node,
+	new_heads: Option<Arc<NewHeads>>,
+	retry: RetryConfig,
+	backoff: Backoff,
+}
+
+pub fn create_gas_limits_watch<T: Transport + Clone>(app: Arc<App<T>>, transport: T, node: Node, new_heads: Option<Arc<NewHeads>>, chain: Chain, contract_address: Address, limits: Vec<GasLimit>) -> GasLimitsWatch<T> {
+	GasLimitsWatch {
+		state: GasLimitsWatchState::FetchBlockNumber(app.timer.timeout(api::block_number(transport.clone()), node.request_timeout)),
+		transport,
+		timer: app.timer.clone(),
+		chain,
+		contract_address,
+		limits,
+		node,
+		new_heads,
+		retry: app.config.retry.clone(),
+		backoff: Backoff::new(app.timer.clone(), app.config.retry.clone()),
+	}
+}
+
+impl<T: Transport> GasLimitsWatch<T> {
+	/// Returns true once the limits set in the contract have been read.
+	pub fn is_following(&self) -> bool {
+		match self.state {
+			GasLimitsWatchState::Follow(_) => true,
+			_ => false,
+		}
+	}
+}
+
+impl<T: Transport + Clone> Stream for GasLimitsWatch<T> {
+	type Item = ();
+	type Error = Error;
+
+	fn poll(&mut self) -> Poll<Option<Self::Item>, Self::Error> {
+		let context = match self.chain {
+			Chain::Home => "polling home for gas limits updates",
+			Chain::Foreign => "polling foreign for gas limits updates",
+		};
+		loop {
+			let mut updated = false;
+			let next_state = match self.state {
+				GasLimitsWatchState::FetchBlockNumber(ref mut future) => match future.poll() {
+					Ok(Async::Ready(block)) => {
+						let timer = &self.timer;
+						let transport = &self.transport;
+						let contract_address = self.contract_address;
+						let request_timeout = self.node.request_timeout;
+						let calls = gas_limits_payloads(self.chain).into_iter()
+							.map(|payload| timer.timeout(api::call(transport.clone(), contract_address, payload), request_timeout))
+							.collect();
+						GasLimitsWatchState::FetchGasLimits {
+							future: join_all(calls),
+							block: block.low_u64(),
+						}
+					},
+					Ok(Async::NotReady) => return Ok(Async::NotReady),
+					Err(err) => {
+						self.backoff.retry(err, "fetching block number")?;
+						GasLimitsWatchState::Retry
+					},
+				},
+				GasLimitsWatchState::FetchGasLimits { ref mut future, block } => match future.poll() {
+					Ok(Async::Ready(outputs)) => {
+						self.backoff.reset();
+						update_gas_limits(self.chain, &self.limits, gas_limits_outputs(self.chain, &outputs)?);
+						updated = true;
+						// updates in blocks after the one limits have been read at are followed,
+						// those already applied are harmless since events carry all limits
+						let logs_init = api::LogStreamInit {
+							after: block,
+							request_timeout: self.node.request_timeout,
+							poll_interval: self.node.poll_interval,
+							confirmations: self.node.required_confirmations,
+							max_block_range: self.node.max_block_range,
+							retry: self.retry.clone(),
+							new_heads: self.new_heads.clone(),
+							filter: gas_limits_filter(self.chain, self.contract_address),
+						};
+						GasLimitsWatchState::Follow(api::log_stream(self.transport.clone(), self.timer.clone(), logs_init))
+					},
+					Ok(Async::NotReady) => return Ok(Async::NotReady),
+					Err(err) => {
+						self.backoff.retry(err, "reading gas limits")?;
+						GasLimitsWatchState::Retry
+					},
+				},
+				GasLimitsWatchState::Retry => {
+					try_ready!(self.backoff.poll());
+					GasLimitsWatchState::FetchBlockNumber(self.timer.timeout(api::block_number(self.transport.clone()), self.node.request_timeout))
+				},
+				GasLimitsWatchState::Follow(ref mut logs) => {
+					let item = match try_stream!(logs.poll().map_err(|e| ErrorKind::ContextualizedError(Box::new(e), context))) {
+						LogStreamEvent::Logs(item) => item,
+						// limits are applied once confirmed, a reorganization can not revert them
+						LogStreamEvent::Rewind(_) => continue,
+					};
+					if let Some(log) = item.logs.into_iter().last() {
+						update_gas_limits(self.chain, &self.limits, parse_gas_limits_log(self.chain, log)?);
+						return Ok(Async::Ready(Some(())));
+					}
+					continue;
+				},
+			};
+			self.state = next_state;
+			if updated {
+				return Ok(Async::Ready(Some(())));
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use rustc_hex::FromHex;
+	use web3::types::{Log, Address, U256};
+	use metrics::Chain;
+	use super::{GasLimit, parse_gas_limits_log};
+
+	fn log(topic: &str, data: &str) -> Log {
+		Log {
+			data: data.from_hex().unwrap().into(),
+			topics: vec![topic.into()],
+			transaction_hash: None,
+			address: Address::zero(),
+			block_hash: None,
+			transaction_index: None,
+			log_index: None,
+			transaction_log_index: None,
+			log_type: None,
+			block_number: None,
+			removed: None,
+		}
+	}
+
+	#[test]
+	fn test_parse_gas_limits_log() {
+		let home_log = log(
+			"bc1d98201a811bd8a296dd081bdba0072c36c75708b7e66b72db806e1050670c",
+			"00000000000000000000000000000000000000000000000000000000000186a0",
+		);
+		assert_eq!(vec![U256::from(100_000u64)], parse_gas_limits_log(Chain::Home, home_log).unwrap());
+
+		let foreign_log = log(
+			"3b49a33ec45179ab3408f6f29a2b208c909ab1460e94af7558808ea22854c106",
+			"00000000000000000000000000000000000000000000000000000000000186a00000000000000000000000000000000000000000000000000000000000000000",
+		);
+		assert_eq!(vec![U256::from(100_000u64), U256::zero()], parse_gas_limits_log(Chain::Foreign, foreign_log).unwrap());
+	}
+
+	#[test]
+	fn test_gas_limit_falls_back_to_config() {
+		let limit = GasLimit::new(300_000);
+		assert_eq!(300_000, limit.get());
+		limit.set(100_000u64.into());
+		assert_eq!(100_000, limit.get());
+		limit.set(U256::zero());
+		assert_eq!(300_000, limit.get());
+	}
+}
@@ -1,22 +1,106 @@
 use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
 use std::time::{Duration, Instant};
 
 use futures::{Async, Future, Poll, Stream};
+use futures::future::join_all;
 use hyper::{Chunk, client::{HttpConnector, Connect}, Client, Uri, Error as HyperError};
 use hyper_tls::HttpsConnector;
 use serde_json as json;
 use tokio_core::reactor::Handle;
-use tokio_timer::{Interval, Timer, Timeout};
+use tokio_timer::{Interval, Timer};
+use web3::Transport;
+use web3::types::U256;
+
+use api::{self, FeeHistory};
+use config::{GasPriceFormat, GasPriceOracle, GasPriceSpeed, GasPriceUnit, Node, StaleGasPrice};
+use error::{Error, ErrorKind};
+use metrics::{Chain, METRICS};
+
+/// Gas price of transactions sent to a chain, shared with the components sending them.
+///
+/// The price is updated by the gas price task or by the base fee of the chain. A price older than
+/// `gas_price_max_age` is stale, it is either replaced with `default_gas_price` or not used at all.
+#[derive(Clone, Default)]
+pub struct GasPrice {
+	value: Arc<RwLock<GasPriceValue>>,
+	default: u64,
+	max_age: Option<Duration>,
+	stale: StaleGasPrice,
+}
+
+#[derive(Default)]
+struct GasPriceValue {
+	price: u64,
+	/// `None` until the price has been retrieved.
+	updated_at: Option<Instant>,
+}
+
+impl GasPrice {
+	/// Creates a price of transactions sent to `node`, which is `default_gas_price` until updated.
+	pub fn new(node: &Node) -> Self {
+		GasPrice {
+			value: Arc::new(RwLock::new(GasPriceValue {
+				price: node.default_gas_price,
+				updated_at: None,
+			})),
+			default: node.default_gas_price,
+			max_age: node.gas_price_max_age,
+			stale: node.stale_gas_price,
+		}
+	}
 
-use config::{GasPriceSpeed, Node};
-use error::Error;
+	/// Stores a price which has just been retrieved.
+	pub fn set(&self, price: u64) {
+		let mut value = self.value.write().unwrap();
+		value.price = price;
+		value.updated_at = Some(Instant::now());
+	}
 
-const CACHE_TIMEOUT_DURATION: Duration = Duration::from_secs(5 * 60);
+	/// Returns the last price, regardless of its age.
+	pub fn last(&self) -> u64 {
+		self.value.read().unwrap().price
+	}
+
+	/// Returns time since the price has been retrieved, `None` if it has not been yet.
+	pub fn age(&self) -> Option<Duration> {
+		self.value.read().unwrap().updated_at.map(|updated_at| updated_at.elapsed())
+	}
 
-enum State<F> {
-    Initial,
-    WaitingForResponse(Timeout<F>),
-    Yield(Option<u64>),
+	/// Returns true if the price is older than `gas_price_max_age` or has not been retrieved yet.
+	pub fn is_stale(&self) -> bool {
+		match (self.max_age, self.age()) {
+			(None, _) => false,
+			(Some(max_age), Some(age)) => age > max_age,
+			(Some(_), None) => true,
+		}
+	}
+
+	/// Returns true if no transactions should be sent until the price is updated.
+	pub fn is_refused(&self) -> bool {
+		self.stale == StaleGasPrice::Refuse && self.is_stale()
+	}
+
+	/// Returns price which transactions should pay now, `None` if none should be sent.
+	pub fn get(&self) -> Option<u64> {
+		if !self.is_stale() {
+			return Some(self.last());
+		}
+		match self.stale {
+			StaleGasPrice::UseDefault => {
+				warn!("Gas price is stale, using default gas price {} gwei", (self.default as f64) / 1_000_000_000.0);
+				Some(self.default)
+			},
+			StaleGasPrice::Refuse => None,
+		}
+	}
+}
+
+enum State<'a> {
+	Initial,
+	/// Waiting for all sources, failed ones resolve to `None`.
+	WaitingForResponses(Box<Future<Item = Vec<Option<u64>>, Error = Error> + 'a>),
+	Yield(Option<u64>),
 }
 
 pub trait Retriever {
@@ -38,89 +122,271 @@ impl<C, B> Retriever for Client<C, B> where C: Connect, B: Stream<Item = Chunk,
 	}
 }
 
-pub type StandardGasPriceStream = GasPriceStream<Box<Future<Item = Chunk, Error = Error>>, Client<HttpsConnector<HttpConnector>>, Chunk>;
-
-pub struct GasPriceStream<F, R, I> where I: AsRef<[u8]>, F: Future<Item = I, Error = Error>, R: Retriever<Future = F, Item = F::Item> {
-    state: State<F>,
-    retriever: R,
-    uri: Uri,
-    speed: GasPriceSpeed,
-    request_timer: Timer,
-    interval: Interval,
-    last_price: u64,
-    request_timeout: Duration,
+/// Reads gas price (in WEI) from the response of a gas price oracle.
+///
+/// Implement it to support oracles returning other JSON schemas.
+pub trait ResponseFormat {
+	fn gas_price(&self, response: &[u8]) -> Result<u64, Error>;
+}
+
+/// Prices in gwei by speed, e.g. `{"fast": 12.0}`, as returned by https://gasprice.poa.network
+pub struct PoaNetworkFormat {
+	pub speed: GasPriceSpeed,
+}
+
+impl ResponseFormat for PoaNetworkFormat {
+	fn gas_price(&self, response: &[u8]) -> Result<u64, Error> {
+		let json_obj = json::from_slice::<HashMap<String, json::Value>>(response)?;
+		match json_obj.get(self.speed.as_str()) {
+			Some(&json::Value::Number(ref price)) => Ok((price.as_f64().unwrap() * 1_000_000_000.0).trunc() as u64),
+			_ => Err(ErrorKind::OtherError(format!("Invalid or missing gas price ({}) in the gas price oracle response: {}",
+				self.speed.as_str(), String::from_utf8_lossy(response))).into()),
+		}
+	}
+}
+
+/// Price at a JSON pointer, e.g. `/result/FastGasPrice`, given as a number or a numeric string.
+pub struct JsonPointerFormat {
+	pub pointer: String,
+	pub unit: GasPriceUnit,
+}
+
+impl ResponseFormat for JsonPointerFormat {
+	fn gas_price(&self, response: &[u8]) -> Result<u64, Error> {
+		let json_obj = json::from_slice::<json::Value>(response)?;
+		let price = match json_obj.pointer(&self.pointer) {
+			Some(&json::Value::Number(ref price)) => price.as_f64(),
+			Some(&json::Value::String(ref price)) => price.parse().ok(),
+			_ => None,
+		};
+		match price {
+			Some(price) if price >= 0.0 => Ok((price * self.unit.wei() as f64).trunc() as u64),
+			_ => Err(ErrorKind::OtherError(format!("Invalid or missing gas price ({}) in the gas price oracle response: {}",
+				self.pointer, String::from_utf8_lossy(response))).into()),
+		}
+	}
+}
+
+impl GasPriceFormat {
+	fn response_format(&self) -> Arc<ResponseFormat> {
+		match *self {
+			GasPriceFormat::PoaNetwork(speed) => Arc::new(PoaNetworkFormat { speed }),
+			GasPriceFormat::JsonPointer { ref pointer, unit } => Arc::new(JsonPointerFormat { pointer: pointer.clone(), unit }),
+		}
+	}
+}
+
+/// Source of gas prices.
+pub trait GasPriceSource<'a> {
+	/// Returns current gas price in WEI.
+	fn gas_price(&self) -> Box<Future<Item = u64, Error = Error> + 'a>;
+	/// Describes the source in logs.
+	fn name(&self) -> String;
+}
+
+/// Gas price oracle queried over HTTP.
+pub struct HttpSource<R> {
+	retriever: R,
+	uri: Uri,
+	format: Arc<ResponseFormat>,
+}
+
+impl<R: Retriever> HttpSource<R> {
+	pub fn new(retriever: R, url: &str, format: Arc<ResponseFormat>) -> Self {
+		HttpSource {
+			retriever,
+			uri: url.parse().unwrap(),
+			format,
+		}
+	}
 }
 
-impl StandardGasPriceStream {
-	pub fn new(node: &Node, handle: &Handle, timer: &Timer) -> Self {
-		let client = Client::configure()
-			.connector(HttpsConnector::new(4, handle).unwrap())
-			.build(handle);
-		GasPriceStream::new_with_retriever(node, client, timer)
+impl<'a, R> GasPriceSource<'a> for HttpSource<R> where R: Retriever, R::Future: 'a {
+	fn gas_price(&self) -> Box<Future<Item = u64, Error = Error> + 'a> {
+		let format = self.format.clone();
+		Box::new(self.retriever.retrieve(&self.uri).and_then(move |response| format.gas_price(response.as_ref())))
+	}
+
+	fn name(&self) -> String {
+		self.uri.to_string()
 	}
 }
 
-impl<F, R, I> GasPriceStream<F, R, I> where I: AsRef<[u8]>, F: Future<Item = I, Error = Error>, R: Retriever<Future = F, Item = F::Item> {
-    pub fn new_with_retriever(node: &Node, retriever: R, timer: &Timer) -> Self {
-        let uri: Uri = node.gas_price_oracle_url.clone().unwrap().parse().unwrap();
-
-        GasPriceStream {
-            state: State::Initial,
-            retriever,
-            uri,
-            speed: node.gas_price_speed,
-            request_timer: timer.clone(),
-            interval: timer.interval_at(Instant::now(), CACHE_TIMEOUT_DURATION),
-            last_price: node.default_gas_price,
-            request_timeout: node.gas_price_timeout,
-        }
-    }
+/// `eth_gasPrice` of a node.
+pub struct EthGasPriceSource<T> {
+	transport: T,
+}
+
+impl<'a, T> GasPriceSource<'a> for EthGasPriceSource<T> where T: Transport + 'a, T::Out: 'a {
+	fn gas_price(&self) -> Box<Future<Item = u64, Error = Error> + 'a> {
+		Box::new(api::gas_price(self.transport.clone()).map(|price| price.low_u64()))
+	}
+
+	fn name(&self) -> String {
+		"eth_gasPrice".into()
+	}
+}
+
+/// Base fee of the next block plus a percentile of tips paid in recent blocks.
+pub struct FeeHistorySource<T> {
+	transport: T,
+	blocks: u64,
+	percentile: u8,
+}
+
+impl<'a, T> GasPriceSource<'a> for FeeHistorySource<T> where T: Transport + 'a, T::Out: 'a {
+	fn gas_price(&self) -> Box<Future<Item = u64, Error = Error> + 'a> {
+		let future = api::fee_history(self.transport.clone(), self.blocks, vec![self.percentile as f64])
+			.and_then(|history| fee_history_gas_price(&history)
+				.map(|price| price.low_u64())
+				.ok_or_else(|| Error::from(ErrorKind::NoBaseFee)));
+		Box::new(future)
+	}
+
+	fn name(&self) -> String {
+		format!("eth_feeHistory ({} blocks, {} percentile)", self.blocks, self.percentile)
+	}
+}
+
+/// Returns base fee of the next block plus the median of tips in `history`.
+fn fee_history_gas_price(history: &FeeHistory) -> Option<U256> {
+	let base_fee = history.next_base_fee()?;
+	let mut tips: Vec<U256> = history.reward.iter().filter_map(|rewards| rewards.first().cloned()).collect();
+	tips.sort();
+	let tip = match tips.len() {
+		0 => U256::zero(),
+		len if len % 2 == 0 => (tips[len / 2 - 1] + tips[len / 2]) / U256::from(2),
+		len => tips[len / 2],
+	};
+	Some(base_fee + tip)
+}
+
+/// Returns median of `prices`, `None` if there are none.
+fn median(mut prices: Vec<u64>) -> Option<u64> {
+	prices.sort();
+	match prices.len() {
+		0 => None,
+		// the average of the middle prices, without overflowing
+		len if len % 2 == 0 => Some(prices[len / 2 - 1] / 2 + prices[len / 2] / 2 + (prices[len / 2 - 1] % 2 + prices[len / 2] % 2) / 2),
+		len => Some(prices[len / 2]),
+	}
+}
+
+/// Returns `price` limited to `min` and `max`.
+fn clamp(price: u64, min: Option<u64>, max: Option<u64>) -> u64 {
+	let price = min.map_or(price, |min| ::std::cmp::max(price, min));
+	max.map_or(price, |max| ::std::cmp::min(price, max))
+}
+
+fn https_client(handle: &Handle) -> Client<HttpsConnector<HttpConnector>> {
+	Client::configure()
+		.connector(HttpsConnector::new(4, handle).unwrap())
+		.build(handle)
+}
+
+/// Queries all gas price sources of a node every `gas_price_interval` and yields the median of their prices.
+///
+/// Yields nothing if all of them fail, so that the last price gets older.
+pub struct GasPriceStream<'a> {
+	state: State<'a>,
+	sources: Vec<Box<GasPriceSource<'a> + 'a>>,
+	request_timer: Timer,
+	interval: Interval,
+	last_price: u64,
+	request_timeout: Duration,
+	min_gas_price: Option<u64>,
+	max_gas_price: Option<u64>,
+}
+
+impl<'a> GasPriceStream<'a> {
+	/// Creates a stream querying `gas_price_oracle_url` and `gas_price_oracles` of `node`, RPC sources use `transport`.
+	pub fn new<T>(node: &Node, transport: T, handle: &Handle, timer: &Timer) -> Self where T: Transport + 'a, T::Out: 'a {
+		let mut sources: Vec<Box<GasPriceSource<'a> + 'a>> = Vec::new();
+		if let Some(ref url) = node.gas_price_oracle_url {
+			let format = GasPriceFormat::PoaNetwork(node.gas_price_speed).response_format();
+			sources.push(Box::new(HttpSource::new(https_client(handle), url, format)));
+		}
+		for oracle in &node.gas_price_oracles {
+			let source: Box<GasPriceSource<'a> + 'a> = match *oracle {
+				GasPriceOracle::Http { ref url, ref format } => Box::new(HttpSource::new(https_client(handle), url, format.response_format())),
+				GasPriceOracle::EthGasPrice => Box::new(EthGasPriceSource {
+					transport: transport.clone(),
+				}),
+				GasPriceOracle::FeeHistory { blocks, percentile } => Box::new(FeeHistorySource {
+					transport: transport.clone(),
+					blocks,
+					percentile,
+				}),
+			};
+			sources.push(source);
+		}
+		GasPriceStream::new_with_sources(node, sources, timer)
+	}
+
+	/// Creates a stream querying `gas_price_oracle_url` of `node` with `retriever`.
+	pub fn new_with_retriever<R>(node: &Node, retriever: R, timer: &Timer) -> Self where R: Retriever + 'a, R::Future: 'a {
+		let url = node.gas_price_oracle_url.clone().unwrap();
+		let format = GasPriceFormat::PoaNetwork(node.gas_price_speed).response_format();
+		GasPriceStream::new_with_sources(node, vec![Box::new(HttpSource::new(retriever, &url, format))], timer)
+	}
+
+	pub fn new_with_sources(node: &Node, sources: Vec<Box<GasPriceSource<'a> + 'a>>, timer: &Timer) -> Self {
+		GasPriceStream {
+			state: State::Initial,
+			sources,
+			request_timer: timer.clone(),
+			interval: timer.interval_at(Instant::now(), node.gas_price_interval),
+			last_price: node.default_gas_price,
+			request_timeout: node.gas_price_timeout,
+			min_gas_price: node.min_gas_price,
+			max_gas_price: node.max_gas_price,
+		}
+	}
 }
 
-impl<F, R, I> Stream for GasPriceStream<F, R, I> where I: AsRef<[u8]>, F: Future<Item = I, Error = Error>, R: Retriever<Future = F, Item = F::Item> {
-    type Item = u64;
-    type Error = Error;
-
-    fn poll(&mut self) -> Poll<Option<Self::Item>, Self::Error> {
-        loop {
-            let next_state = match self.state {
-                State::Initial => {
-                    let _ = try_stream!(self.interval.poll());
-
-                    let request = self.retriever.retrieve(&self.uri);
-
-                    let request_future = self.request_timer
-                        .timeout(request, self.request_timeout);
-
-                    State::WaitingForResponse(request_future)
-                },
-                State::WaitingForResponse(ref mut request_future) => {
-                    match request_future.poll() {
-                        Ok(Async::NotReady) => return Ok(Async::NotReady),
-                        Ok(Async::Ready(chunk)) => {
-                            match json::from_slice::<HashMap<String, json::Value>>(chunk.as_ref()) {
-								Ok(json_obj) => {
-									match json_obj.get(self.speed.as_str()) {
-										Some(json::Value::Number(price)) => State::Yield(Some((price.as_f64().unwrap() * 1_000_000_000.0).trunc() as u64)),
-										_ => {
-											error!("Invalid or missing gas price ({}) in the gas price oracle response: {}", self.speed.as_str(), String::from_utf8_lossy(&*chunk.as_ref()));
-											State::Yield(Some(self.last_price))
-										},
-									}
-								},
-								Err(e) => {
-									error!("Error while parsing response from gas price oracle: {:?} {}", e, String::from_utf8_lossy(&*chunk.as_ref()));
-									State::Yield(Some(self.last_price))
-								}
+impl<'a> Stream for GasPriceStream<'a> {
+	type Item = u64;
+	type Error = Error;
+
+	fn poll(&mut self) -> Poll<Option<Self::Item>, Self::Error> {
+		loop {
+			let next_state = match self.state {
+				State::Initial => {
+					let _ = try_stream!(self.interval.poll());
+
+					let requests = self.sources.iter()
+						.map(|source| {
+							let name = source.name();
+							self.request_timer.timeout(source.gas_price(), self.request_timeout)
+								.then(move |result| match result {
+									Ok(price) => Ok::<_, Error>(Some(price)),
+									Err(e) => {
+										error!("Error while fetching gas price from {}: {:?}", name, e);
+										Ok(None)
+									},
+								})
+						})
+						.collect::<Vec<_>>();
+
+					State::WaitingForResponses(Box::new(join_all(requests)))
+				},
+				State::WaitingForResponses(ref mut future) => {
+					let prices = try_ready!(future.poll()).into_iter().filter_map(|price| price).collect();
+					match median(prices) {
+						Some(price) => {
+							let clamped = clamp(price, self.min_gas_price, self.max_gas_price);
+							if clamped != price {
+								warn!("Gas price {} gwei is out of bounds, using {} gwei", (price as f64) / 1_000_000_000.0, (clamped as f64) / 1_000_000_000.0);
 							}
-                        },
-                        Err(e) => {
-                            error!("Error while fetching gas price: {:?}", e);
-							State::Yield(Some(self.last_price))
-                        },
-                    }
-                },
-                State::Yield(ref mut opt) => match opt.take() {
+							State::Yield(Some(clamped))
+						},
+						None => {
+							error!("All gas price sources failed, keeping gas price of {} gwei", (self.last_price as f64) / 1_000_000_000.0);
+							State::Yield(None)
+						},
+					}
+				},
+				State::Yield(ref mut opt) => match opt.take() {
 					None => State::Initial,
 					Some(price) => {
 						if price != self.last_price {
@@ -128,13 +394,26 @@ impl<F, R, I> Stream for GasPriceStream<F, R, I> where I: AsRef<[u8]>, F: Future
 							self.last_price = price;
 						}
 						return Ok(Async::Ready(Some(price)))
-                    },
+					},
 				}
-            };
+			};
 
-            self.state = next_state;
-        }
-    }
+			self.state = next_state;
+		}
+	}
+}
+
+/// Returns a task storing prices yielded by `stream` in `gas_price`, spawned on the event loop
+/// so that prices are updated independently of the components using them.
+pub fn gas_price_task<'a>(stream: GasPriceStream<'a>, gas_price: GasPrice, chain: Chain) -> Box<Future<Item = (), Error = ()> + 'a> {
+	let task = stream
+		.for_each(move |price| {
+			gas_price.set(price);
+			METRICS.gas_price(chain, price);
+			Ok(())
+		})
+		.map_err(move |e| error!("{:?} gas price task failed: {:?}", chain, e));
+	Box::new(task)
 }
 
 #[cfg(test)]
@@ -143,12 +422,36 @@ mod tests {
 	use super::*;
 	use error::{Error, ErrorKind};
 	use futures::{Async, future::{err, ok, FutureResult}};
-	use config::{Node, NodeInfo, DEFAULT_CONCURRENCY};
+	use std::cell::Cell;
+	use config::{Node, StaleGasPrice};
 	use tokio_timer::Timer;
 	use std::time::Duration;
-	use std::path::PathBuf;
-	use web3::types::Address;
-	use std::str::FromStr;
+
+	/// Responds like `R` to the first request and with a correct price afterwards,
+	/// failed requests yield nothing so the correct price is yielded first.
+	struct ThenCorrect<R> {
+		first: R,
+		requested: Cell<bool>,
+	}
+
+	impl<R> ThenCorrect<R> {
+		fn new(first: R) -> Self {
+			ThenCorrect { first, requested: Cell::new(false) }
+		}
+	}
+
+	impl<R> Retriever for ThenCorrect<R> where R: Retriever, R::Future: 'static {
+		type Item = Vec<u8>;
+		type Future = Box<Future<Item = Self::Item, Error = Error>>;
+
+		fn retrieve(&self, uri: &Uri) -> <Self as Retriever>::Future {
+			if self.requested.replace(true) {
+				Box::new(ok(br#"{"fast": 12.0}"#.to_vec()))
+			} else {
+				Box::new(self.first.retrieve(uri).map(|response| response.as_ref().to_vec()))
+			}
+		}
+	}
 
 	struct ErroredRequest;
 
@@ -164,26 +467,17 @@ mod tests {
 	#[test]
 	fn errored_request() {
 		let node = Node {
-			account: Address::new(),
-			request_timeout: Duration::from_secs(5),
-			poll_interval: Duration::from_secs(1),
-			required_confirmations: 0,
-			rpc_host: "https://rpc".into(),
-			rpc_port: 443,
-			password: PathBuf::from("password"),
-			info: NodeInfo::default(),
 			gas_price_oracle_url: Some("https://gas.price".into()),
-			gas_price_speed: GasPriceSpeed::from_str("fast").unwrap(),
 			gas_price_timeout: Duration::from_secs(5),
-			default_gas_price: 15_000_000_000,
-			concurrent_http_requests: DEFAULT_CONCURRENCY,
+			gas_price_interval: Duration::from_millis(100),
+			..Node::default_for_tests()
 		};
 		let timer = Timer::default();
-		let mut stream = GasPriceStream::new_with_retriever(&node, ErroredRequest, &timer);
+		let mut stream = GasPriceStream::new_with_retriever(&node, ThenCorrect::new(ErroredRequest), &timer);
 		loop {
 			match stream.poll() {
 				Ok(Async::Ready(Some(v))) => {
-					assert_eq!(v, node.default_gas_price);
+					assert_eq!(v, 12_000_000_000);
 					break;
 				},
 				Err(_) => panic!("should not error out"),
@@ -207,26 +501,17 @@ mod tests {
 	#[test]
 	fn bad_json() {
 		let node = Node {
-			account: Address::new(),
-			request_timeout: Duration::from_secs(5),
-			poll_interval: Duration::from_secs(1),
-			required_confirmations: 0,
-			rpc_host: "https://rpc".into(),
-			rpc_port: 443,
-			password: PathBuf::from("password"),
-			info: NodeInfo::default(),
 			gas_price_oracle_url: Some("https://gas.price".into()),
-			gas_price_speed: GasPriceSpeed::from_str("fast").unwrap(),
 			gas_price_timeout: Duration::from_secs(5),
-			default_gas_price: 15_000_000_000,
-			concurrent_http_requests: DEFAULT_CONCURRENCY,
+			gas_price_interval: Duration::from_millis(100),
+			..Node::default_for_tests()
 		};
 		let timer = Timer::default();
-		let mut stream = GasPriceStream::new_with_retriever(&node, BadJson, &timer);
+		let mut stream = GasPriceStream::new_with_retriever(&node, ThenCorrect::new(BadJson), &timer);
 		loop {
 			match stream.poll() {
 				Ok(Async::Ready(Some(v))) => {
-					assert_eq!(v, node.default_gas_price);
+					assert_eq!(v, 12_000_000_000);
 					break;
 				},
 				Err(_) => panic!("should not error out"),
@@ -250,26 +535,17 @@ mod tests {
 	#[test]
 	fn unexpected_json() {
 		let node = Node {
-			account: Address::new(),
-			request_timeout: Duration::from_secs(5),
-			poll_interval: Duration::from_secs(1),
-			required_confirmations: 0,
-			rpc_host: "https://rpc".into(),
-			rpc_port: 443,
-			password: PathBuf::from("password"),
-			info: NodeInfo::default(),
 			gas_price_oracle_url: Some("https://gas.price".into()),
-			gas_price_speed: GasPriceSpeed::from_str("fast").unwrap(),
 			gas_price_timeout: Duration::from_secs(5),
-			default_gas_price: 15_000_000_000,
-			concurrent_http_requests: DEFAULT_CONCURRENCY,
+			gas_price_interval: Duration::from_millis(100),
+			..Node::default_for_tests()
 		};
 		let timer = Timer::default();
-		let mut stream = GasPriceStream::new_with_retriever(&node, UnexpectedJson, &timer);
+		let mut stream = GasPriceStream::new_with_retriever(&node, ThenCorrect::new(UnexpectedJson), &timer);
 		loop {
 			match stream.poll() {
 				Ok(Async::Ready(Some(v))) => {
-					assert_eq!(v, node.default_gas_price);
+					assert_eq!(v, 12_000_000_000);
 					break;
 				},
 				Err(_) => panic!("should not error out"),
@@ -292,26 +568,17 @@ mod tests {
 	#[test]
 	fn non_object_json() {
 		let node = Node {
-			account: Address::new(),
-			request_timeout: Duration::from_secs(5),
-			poll_interval: Duration::from_secs(1),
-			required_confirmations: 0,
-			rpc_host: "https://rpc".into(),
-			rpc_port: 443,
-			password: PathBuf::from("password"),
-			info: NodeInfo::default(),
 			gas_price_oracle_url: Some("https://gas.price".into()),
-			gas_price_speed: GasPriceSpeed::from_str("fast").unwrap(),
 			gas_price_timeout: Duration::from_secs(5),
-			default_gas_price: 15_000_000_000,
-			concurrent_http_requests: DEFAULT_CONCURRENCY,
+			gas_price_interval: Duration::from_millis(100),
+			..Node::default_for_tests()
 		};
 		let timer = Timer::default();
-		let mut stream = GasPriceStream::new_with_retriever(&node, NonObjectJson, &timer);
+		let mut stream = GasPriceStream::new_with_retriever(&node, ThenCorrect::new(NonObjectJson), &timer);
 		loop {
 			match stream.poll() {
 				Ok(Async::Ready(Some(v))) => {
-					assert_eq!(v, node.default_gas_price);
+					assert_eq!(v, 12_000_000_000);
 					break;
 				},
 				Err(_) => panic!("should not error out"),
@@ -334,19 +601,9 @@ mod tests {
 	#[test]
 	fn correct_json() {
 		let node = Node {
-			account: Address::new(),
-			request_timeout: Duration::from_secs(5),
-			poll_interval: Duration::from_secs(1),
-			required_confirmations: 0,
-			rpc_host: "https://rpc".into(),
-			rpc_port: 443,
-			password: PathBuf::from("password"),
-			info: NodeInfo::default(),
 			gas_price_oracle_url: Some("https://gas.price".into()),
-			gas_price_speed: GasPriceSpeed::from_str("fast").unwrap(),
 			gas_price_timeout: Duration::from_secs(5),
-			default_gas_price: 15_000_000_000,
-			concurrent_http_requests: DEFAULT_CONCURRENCY,
+			..Node::default_for_tests()
 		};
 		let timer = Timer::default();
 		let mut stream = GasPriceStream::new_with_retriever(&node, CorrectJson, &timer);
@@ -362,5 +619,138 @@ mod tests {
 		}
 	}
 
-}
+	struct FixedSource(Option<u64>);
+
+	impl<'a> GasPriceSource<'a> for FixedSource {
+		fn gas_price(&self) -> Box<Future<Item = u64, Error = Error> + 'a> {
+			match self.0 {
+				Some(price) => Box::new(ok(price)),
+				None => Box::new(err(ErrorKind::OtherError("something went wrong".into()).into())),
+			}
+		}
+
+		fn name(&self) -> String {
+			"fixed".into()
+		}
+	}
+
+	fn first_price(node: &Node, sources: Vec<Option<u64>>) -> u64 {
+		let timer = Timer::default();
+		let sources = sources.into_iter().map(|price| Box::new(FixedSource(price)) as Box<GasPriceSource>).collect();
+		let mut stream = GasPriceStream::new_with_sources(node, sources, &timer);
+		loop {
+			match stream.poll() {
+				Ok(Async::Ready(Some(v))) => return v,
+				Err(_) => panic!("should not error out"),
+				_ => (),
+			}
+		}
+	}
 
+	#[test]
+	fn median_of_sources() {
+		let mut node = Node {
+			gas_price_timeout: Duration::from_secs(5),
+			..Node::default_for_tests()
+		};
+		assert_eq!(first_price(&node, vec![Some(10), Some(30), Some(20)]), 20);
+		// failed sources are ignored
+		assert_eq!(first_price(&node, vec![Some(10), None, Some(30), None]), 20);
+
+		node.min_gas_price = Some(25);
+		node.max_gas_price = Some(40);
+		assert_eq!(first_price(&node, vec![Some(10), Some(20)]), 25);
+		assert_eq!(first_price(&node, vec![Some(50), Some(60)]), 40);
+		assert_eq!(first_price(&node, vec![Some(30)]), 30);
+	}
+
+	#[test]
+	fn stale_gas_price() {
+		let mut node = Node {
+			gas_price_oracle_url: Some("https://gas.price".into()),
+			gas_price_timeout: Duration::from_secs(5),
+			gas_price_interval: Duration::from_millis(10),
+			..Node::default_for_tests()
+		};
+
+		// without max age the price never gets stale
+		let gas_price = GasPrice::new(&node);
+		assert_eq!(gas_price.age(), None);
+		assert!(!gas_price.is_stale());
+		assert_eq!(gas_price.get(), Some(15_000_000_000));
+
+		node.gas_price_max_age = Some(Duration::from_millis(50));
+		let gas_price = GasPrice::new(&node);
+		assert!(gas_price.is_stale());
+		gas_price.set(20_000_000_000);
+		assert!(gas_price.age().is_some());
+		assert_eq!(gas_price.get(), Some(20_000_000_000));
+		::std::thread::sleep(Duration::from_millis(100));
+		assert!(gas_price.is_stale());
+		assert!(!gas_price.is_refused());
+		assert_eq!(gas_price.get(), Some(15_000_000_000));
+		assert_eq!(gas_price.last(), 20_000_000_000);
+
+		node.stale_gas_price = StaleGasPrice::Refuse;
+		let gas_price = GasPrice::new(&node);
+		assert!(gas_price.is_refused());
+		assert_eq!(gas_price.get(), None);
+		gas_price.set(20_000_000_000);
+		assert!(!gas_price.is_refused());
+		assert_eq!(gas_price.get(), Some(20_000_000_000));
+		::std::thread::sleep(Duration::from_millis(100));
+		assert!(gas_price.is_refused());
+		assert_eq!(gas_price.get(), None);
+	}
+
+	#[test]
+	fn test_median() {
+		assert_eq!(median(vec![]), None);
+		assert_eq!(median(vec![5]), Some(5));
+		assert_eq!(median(vec![7, 1, 3]), Some(3));
+		assert_eq!(median(vec![4, 1, 3, 10]), Some(3));
+		assert_eq!(median(vec![u64::max_value(), u64::max_value()]), Some(u64::max_value()));
+	}
+
+	#[test]
+	fn test_clamp() {
+		assert_eq!(clamp(5, None, None), 5);
+		assert_eq!(clamp(5, Some(10), None), 10);
+		assert_eq!(clamp(5, None, Some(3)), 3);
+		assert_eq!(clamp(5, Some(1), Some(10)), 5);
+	}
+
+	#[test]
+	fn json_pointer_format() {
+		let format = JsonPointerFormat { pointer: "/result/FastGasPrice".into(), unit: GasPriceUnit::Gwei };
+		assert_eq!(format.gas_price(br#"{"result": {"FastGasPrice": "12.5"}}"#).unwrap(), 12_500_000_000);
+		assert_eq!(format.gas_price(br#"{"result": {"FastGasPrice": 3}}"#).unwrap(), 3_000_000_000);
+		assert!(format.gas_price(br#"{"result": {"SafeGasPrice": 3}}"#).is_err());
+		assert!(format.gas_price(br#"{"result": {"FastGasPrice": "fast"}}"#).is_err());
+		assert!(format.gas_price(b"bad json").is_err());
+
+		let format = JsonPointerFormat { pointer: "/gasPrice".into(), unit: GasPriceUnit::Wei };
+		assert_eq!(format.gas_price(br#"{"gasPrice": 1000}"#).unwrap(), 1000);
+	}
+
+	#[test]
+	fn fee_history_price() {
+		let history = FeeHistory {
+			base_fee_per_gas: vec![90.into(), 100.into(), 110.into()],
+			reward: vec![vec![1.into()], vec![5.into()], vec![3.into()]],
+		};
+		assert_eq!(fee_history_gas_price(&history), Some(113.into()));
+
+		let history = FeeHistory {
+			base_fee_per_gas: vec![100.into()],
+			reward: vec![],
+		};
+		assert_eq!(fee_history_gas_price(&history), Some(100.into()));
+
+		let history = FeeHistory {
+			base_fee_per_gas: vec![],
+			reward: vec![],
+		};
+		assert_eq!(fee_history_gas_price(&history), None);
+	}
+}
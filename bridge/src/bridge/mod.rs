@@ -1,123 +1,215 @@
 mod deploy;
 mod balance;
+mod authorities;
 mod chain_id;
 pub mod nonce;
 mod deposit_relay;
 mod withdraw_confirm;
 mod withdraw_relay;
 mod gas_price;
+mod base_fee;
+mod gas_limits;
+mod pending_transactions;
 
-use std::fs;
 use std::sync::{Arc, RwLock};
-use std::path::PathBuf;
 use futures::{Stream, Poll, Async};
 use web3::Transport;
 use web3::types::U256;
+use admin::State;
 use app::App;
-use database::Database;
+use database::{Database, DatabaseBackend, TransactionRecord, PendingTransaction};
 use error::{Error, ErrorKind};
+use metrics::{METRICS, Chain};
 use tokio_core::reactor::Handle;
 
 pub use self::deploy::{Deploy, Deployed, create_deploy};
 pub use self::balance::{BalanceCheck, create_balance_check};
+pub use self::authorities::{AuthoritySet, AuthoritiesWatch, create_authorities_watch};
 pub use self::chain_id::{ChainIdRetrieval, create_chain_id_retrieval};
 pub use self::deposit_relay::{DepositRelay, create_deposit_relay};
 pub use self::withdraw_relay::{WithdrawRelay, create_withdraw_relay};
 pub use self::withdraw_confirm::{WithdrawConfirm, create_withdraw_confirm};
-pub use self::gas_price::StandardGasPriceStream;
+pub use self::gas_price::{GasPrice, GasPriceStream, GasPriceSource, ResponseFormat, gas_price_task};
+pub use self::base_fee::{BaseFeeStream, create_base_fee_stream};
+pub use self::gas_limits::{GasLimit, GasLimitsWatch, create_gas_limits_watch};
+pub use self::pending_transactions::{PendingTransactionsMonitor, create_pending_transactions_monitor};
 
 /// Last block checked by the bridge components.
-#[derive(Clone, Copy)]
+#[derive(Debug, PartialEq, Clone, Copy)]
 pub enum BridgeChecked {
 	DepositRelay(u64),
 	WithdrawRelay(u64),
 	WithdrawConfirm(u64),
 }
 
-pub struct Bridge<ES: Stream<Item = BridgeChecked>> {
-	path: PathBuf,
+/// Event yielded by the bridge components.
+#[derive(Debug, PartialEq)]
+pub enum BridgeEvent {
+	/// All blocks till given one have been checked.
+	Checked(BridgeChecked),
+	/// Transactions have been sent.
+	Sent(Vec<TransactionRecord>),
+	/// Transactions awaiting to be mined have been mined or replaced.
+	PendingTransactionsChanged,
+}
+
+pub struct Bridge<ES: Stream<Item = BridgeEvent>> {
+	backend: Box<DatabaseBackend>,
 	database: Database,
 	event_stream: ES,
+	/// Transactions sent to home which have not been mined yet, stored along with the database.
+	home_pending: Arc<RwLock<Vec<PendingTransaction>>>,
+	/// Transactions sent to foreign which have not been mined yet, stored along with the database.
+	foreign_pending: Arc<RwLock<Vec<PendingTransaction>>>,
+	/// State reported by the admin API.
+	state: Arc<State>,
+}
+
+impl<ES: Stream<Item = BridgeEvent>> Bridge<ES> {
+	/// Returns state of the bridge which is updated as it runs.
+	pub fn state(&self) -> Arc<State> {
+		self.state.clone()
+	}
 }
 
-impl<ES: Stream<Item = BridgeChecked, Error = Error>> Stream for Bridge<ES> {
+impl<ES: Stream<Item = BridgeEvent, Error = Error>> Stream for Bridge<ES> {
 	type Item = ();
 	type Error = Error;
 
 	fn poll(&mut self) -> Poll<Option<Self::Item>, Self::Error> {
-		let check = try_stream!(self.event_stream.poll());
-		match check {
-			BridgeChecked::DepositRelay(n) => {
+		let event = try_stream!(self.event_stream.poll());
+		if let BridgeEvent::Checked(checked) = event {
+			METRICS.checked(checked);
+		}
+		match event {
+			BridgeEvent::Checked(BridgeChecked::DepositRelay(n)) => {
 				self.database.checked_deposit_relay = n;
 			},
-			BridgeChecked::WithdrawRelay(n) => {
+			BridgeEvent::Checked(BridgeChecked::WithdrawRelay(n)) => {
 				self.database.checked_withdraw_relay = n;
 			},
-			BridgeChecked::WithdrawConfirm(n) => {
+			BridgeEvent::Checked(BridgeChecked::WithdrawConfirm(n)) => {
 				self.database.checked_withdraw_confirm = n;
 			},
+			BridgeEvent::Sent(records) => {
+				for record in &records {
+					METRICS.sent(record.kind, 1);
+				}
+				self.backend.record_transactions(&records)?;
+				return Ok(Async::Ready(Some(())));
+			},
+			BridgeEvent::PendingTransactionsChanged => (),
 		}
-		let file = fs::OpenOptions::new()
-			.write(true)
-			.create(true)
-			.open(&self.path)?;
-
-		self.database.save(file)?;
+		self.database.home_pending_transactions = self.home_pending.read().unwrap().clone();
+		self.database.foreign_pending_transactions = self.foreign_pending.read().unwrap().clone();
+		self.backend.save(&self.database)?;
+		*self.state.database.write().unwrap() = self.database.clone();
 		Ok(Async::Ready(Some(())))
 	}
 }
 
 
 /// Creates new bridge.
-pub fn create_bridge<'a, T: Transport + 'a + Clone>(app: Arc<App<T>>, init: &Database, handle: &Handle, home_chain_id: u64, foreign_chain_id: u64) -> Bridge<BridgeEventStream<'a, T>> {
+pub fn create_bridge<'a, T: Transport + 'a + Clone>(app: Arc<App<T>>, backend: Box<DatabaseBackend>, init: &Database, handle: &Handle, home_chain_id: u64, foreign_chain_id: u64) -> Bridge<BridgeEventStream<'a, T>>
+	where T: 'static, T::Out: 'static {
+	let home_pending = app.config.home.info.pending_transactions.clone();
+	let foreign_pending = app.config.foreign.info.pending_transactions.clone();
+	// resume monitoring transactions which were not mined before the bridge has been stopped
+	home_pending.write().unwrap().extend(init.home_pending_transactions.iter().cloned());
+	foreign_pending.write().unwrap().extend(init.foreign_pending_transactions.iter().cloned());
+
+	let event_stream = create_bridge_event_stream(app, init, handle, home_chain_id, foreign_chain_id);
+	let state = State {
+		home_chain_id,
+		foreign_chain_id,
+		database: RwLock::new(init.clone()),
+		home_balance: event_stream.home_balance.clone(),
+		foreign_balance: event_stream.foreign_balance.clone(),
+		home_gas_price: event_stream.home_gas_price.clone(),
+		foreign_gas_price: event_stream.foreign_gas_price.clone(),
+	};
+
 	Bridge {
-		path: app.database_path.clone(),
+		backend,
 		database: init.clone(),
-		event_stream: create_bridge_event_stream(app, init, handle, home_chain_id, foreign_chain_id),
+		event_stream,
+		home_pending,
+		foreign_pending,
+		state: Arc::new(state),
 	}
 }
 
 /// Creates new bridge writing to custom backend.
-pub fn create_bridge_event_stream<'a, T: Transport + 'a + Clone>(app: Arc<App<T>>, init: &Database, handle: &Handle, home_chain_id: u64, foreign_chain_id: u64) -> BridgeEventStream<'a, T> {
+///
+/// Gas prices are updated by tasks spawned on `handle`.
+pub fn create_bridge_event_stream<'a, T: Transport + 'a + Clone>(app: Arc<App<T>>, init: &Database, handle: &Handle, home_chain_id: u64, foreign_chain_id: u64) -> BridgeEventStream<'a, T>
+	where T: 'static, T::Out: 'static {
 	let home_balance = Arc::new(RwLock::new(None));
 	let foreign_balance = Arc::new(RwLock::new(None));
 
-	let home_gas_stream = if app.config.home.gas_price_oracle_url.is_some() {
-		let stream = StandardGasPriceStream::new(&app.config.home, handle, &app.timer);
-		Some(stream)
-	} else {
-		None
-	};
+	let home_gas_price = GasPrice::new(&app.config.home);
+	let foreign_gas_price = GasPrice::new(&app.config.foreign);
+	METRICS.gas_price(Chain::Home, home_gas_price.last());
+	METRICS.gas_price(Chain::Foreign, foreign_gas_price.last());
 
-	let foreign_gas_stream = if app.config.foreign.gas_price_oracle_url.is_some() {
-		let stream = StandardGasPriceStream::new(&app.config.foreign, handle, &app.timer);
-		Some(stream)
-	} else {
-		None
-	};
+	if app.config.home.gas_price_oracle_url.is_some() || !app.config.home.gas_price_oracles.is_empty() {
+		let stream = GasPriceStream::new(&app.config.home, app.connections.home.clone(), handle, &app.timer);
+		handle.spawn(gas_price_task(stream, home_gas_price.clone(), Chain::Home));
+	}
+
+	if app.config.foreign.gas_price_oracle_url.is_some() || !app.config.foreign.gas_price_oracles.is_empty() {
+		let stream = GasPriceStream::new(&app.config.foreign, app.connections.foreign.clone(), handle, &app.timer);
+		handle.spawn(gas_price_task(stream, foreign_gas_price.clone(), Chain::Foreign));
+	}
+
+	let home_base_fee_stream = create_base_fee_stream(app.clone(), app.connections.home.clone(), app.config.home.clone());
+	let foreign_base_fee_stream = create_base_fee_stream(app.clone(), app.connections.foreign.clone(), app.config.foreign.clone());
+
+	let home_authorities = Arc::new(RwLock::new(None));
+	let foreign_authorities = Arc::new(RwLock::new(None));
 
-	let home_gas_price = Arc::new(RwLock::new(app.config.home.default_gas_price));
-	let foreign_gas_price = Arc::new(RwLock::new(app.config.foreign.default_gas_price));
+	let deposit_relay_gas = GasLimit::new(app.config.txs.deposit_relay.gas);
+	let withdraw_relay_gas = GasLimit::new(app.config.txs.withdraw_relay.gas);
+	let withdraw_confirm_gas = GasLimit::new(app.config.txs.withdraw_confirm.gas);
 
-	let deposit_relay = create_deposit_relay(app.clone(), init, foreign_balance.clone(), foreign_chain_id, foreign_gas_price.clone())
+	let deposit_relay = create_deposit_relay(app.clone(), init, foreign_balance.clone(), foreign_chain_id, foreign_gas_price.clone(), foreign_authorities.clone(), deposit_relay_gas.clone())
 		.map_err(|e| ErrorKind::ContextualizedError(Box::new(e), "deposit_relay").into());
-	let withdraw_relay = create_withdraw_relay(app.clone(), init, home_balance.clone(), home_chain_id, home_gas_price.clone())
+	let withdraw_relay = create_withdraw_relay(app.clone(), init, home_balance.clone(), home_chain_id, home_gas_price.clone(), home_authorities.clone(), withdraw_relay_gas.clone())
 		.map_err(|e| ErrorKind::ContextualizedError(Box::new(e), "withdraw_relay").into());
-	let withdraw_confirm = create_withdraw_confirm(app.clone(), init, foreign_balance.clone(), foreign_chain_id, foreign_gas_price.clone())
+	let withdraw_confirm = create_withdraw_confirm(app.clone(), init, foreign_balance.clone(), foreign_chain_id, foreign_gas_price.clone(), foreign_authorities.clone(), withdraw_confirm_gas.clone())
 		.map_err(|e| ErrorKind::ContextualizedError(Box::new(e), "withdraw_confirm").into());
 
-	let bridge = Box::new(deposit_relay.select(withdraw_relay).select(withdraw_confirm));
+	let home_pending = create_pending_transactions_monitor(app.clone(), app.connections.home.clone(), app.config.home.clone(), app.signers.home.clone(), home_chain_id)
+		.map_err(|e| ErrorKind::ContextualizedError(Box::new(e), "home_pending_transactions").into());
+	let foreign_pending = create_pending_transactions_monitor(app.clone(), app.connections.foreign.clone(), app.config.foreign.clone(), app.signers.foreign.clone(), foreign_chain_id)
+		.map_err(|e| ErrorKind::ContextualizedError(Box::new(e), "foreign_pending_transactions").into());
+
+	let bridge = Box::new(deposit_relay
+		.select(withdraw_relay)
+		.select(withdraw_confirm)
+		.select(home_pending)
+		.select(foreign_pending));
 
 	BridgeEventStream {
 		foreign_balance_check: create_balance_check(app.clone(), app.connections.foreign.clone(), app.config.foreign.clone()),
 		home_balance_check: create_balance_check(app.clone(), app.connections.home.clone(), app.config.home.clone()),
 		foreign_balance: foreign_balance.clone(),
 		home_balance: home_balance.clone(),
+		home_authorities_watch: Some(create_authorities_watch(app.clone(), app.connections.home.clone(), app.config.home.clone(),
+			app.new_heads.home.clone(), Chain::Home, init.home_contract_address, init.home_deploy)),
+		foreign_authorities_watch: Some(create_authorities_watch(app.clone(), app.connections.foreign.clone(), app.config.foreign.clone(),
+			app.new_heads.foreign.clone(), Chain::Foreign, init.foreign_contract_address, init.foreign_deploy)),
+		home_authorities,
+		foreign_authorities,
+		home_gas_limits_watch: create_gas_limits_watch(app.clone(), app.connections.home.clone(), app.config.home.clone(),
+			app.new_heads.home.clone(), Chain::Home, init.home_contract_address, vec![withdraw_relay_gas]),
+		foreign_gas_limits_watch: create_gas_limits_watch(app.clone(), app.connections.foreign.clone(), app.config.foreign.clone(),
+			app.new_heads.foreign.clone(), Chain::Foreign, init.foreign_contract_address, vec![deposit_relay_gas, withdraw_confirm_gas]),
 		bridge,
 		state: BridgeStatus::Init,
 		running: app.running.clone(),
-		home_gas_stream,
-		foreign_gas_stream,
+		home_base_fee_stream,
+		foreign_base_fee_stream,
 		home_gas_price,
 		foreign_gas_price,
 	}
@@ -126,7 +218,7 @@ pub fn create_bridge_event_stream<'a, T: Transport + 'a + Clone>(app: Arc<App<T>
 enum BridgeStatus {
 	Init,
 	Wait,
-	NextItem(Option<BridgeChecked>),
+	NextItem(Option<BridgeEvent>),
 }
 
 pub struct BridgeEventStream<'a, T: Transport + 'a> {
@@ -134,18 +226,71 @@ pub struct BridgeEventStream<'a, T: Transport + 'a> {
 	foreign_balance_check: BalanceCheck<T>,
 	home_balance: Arc<RwLock<Option<U256>>>,
 	foreign_balance: Arc<RwLock<Option<U256>>>,
-	bridge: Box<Stream<Item = BridgeChecked, Error = Error> + 'a>,
+	home_authorities_watch: Option<AuthoritiesWatch<T>>,
+	foreign_authorities_watch: Option<AuthoritiesWatch<T>>,
+	/// Authorities of the home contract, `None` until known or if the contract does not announce them.
+	home_authorities: Arc<RwLock<Option<AuthoritySet>>>,
+	/// Authorities of the foreign contract, `None` until known or if the contract does not announce them.
+	foreign_authorities: Arc<RwLock<Option<AuthoritySet>>>,
+	home_gas_limits_watch: GasLimitsWatch<T>,
+	foreign_gas_limits_watch: GasLimitsWatch<T>,
+	bridge: Box<Stream<Item = BridgeEvent, Error = Error> + 'a>,
 	state: BridgeStatus,
 	running: Arc<AtomicBool>,
-	home_gas_stream: Option<StandardGasPriceStream>,
-	foreign_gas_stream: Option<StandardGasPriceStream>,
-	home_gas_price: Arc<RwLock<u64>>,
-	foreign_gas_price: Arc<RwLock<u64>>,
+	/// Base fee of home, set if home is sent EIP-1559 transactions.
+	home_base_fee_stream: Option<BaseFeeStream<T>>,
+	/// Base fee of foreign, set if foreign is sent EIP-1559 transactions.
+	foreign_base_fee_stream: Option<BaseFeeStream<T>>,
+	/// Gas price, or `max_fee_per_gas` of EIP-1559 transactions, used by home transactions.
+	home_gas_price: GasPrice,
+	/// Gas price, or `max_fee_per_gas` of EIP-1559 transactions, used by foreign transactions.
+	foreign_gas_price: GasPrice,
 }
 
 use std::sync::atomic::{AtomicBool, Ordering};
 
-impl<'a, T: Transport + 'a> BridgeEventStream<'a, T> {
+/// Stores authority sets yielded by `watch` in `authorities`.
+///
+/// Stops following authorities of a contract which does not announce them,
+/// the relays fall back to the signatures collected by `ForeignBridge` then.
+/// Returns true if there is nothing more to wait for.
+fn poll_authorities<T: Transport>(watch: &mut Option<AuthoritiesWatch<T>>, authorities: &RwLock<Option<AuthoritySet>>, chain: Chain) -> Result<bool, Error> {
+	loop {
+		let result = match *watch {
+			Some(ref mut watch) => watch.poll(),
+			None => return Ok(true),
+		};
+		match result {
+			Ok(Async::Ready(Some(set))) => {
+				info!("{:?} authorities: {:?}, {} signatures required", chain, set.accounts, set.required_signatures);
+				*authorities.write().unwrap() = Some(set);
+			},
+			Ok(_) => return Ok(authorities.read().unwrap().is_some()),
+			Err(Error(ErrorKind::NoRequiredSignaturesChanged, _)) => {
+				warn!("{:?} bridge contract does not announce its authorities, not following them", chain);
+				*watch = None;
+			},
+			Err(err) => return Err(err),
+		}
+	}
+}
+
+/// Stores `max_fee_per_gas` yielded by `stream` in `gas_price`.
+///
+/// Returns true if there is nothing more to wait for.
+fn poll_base_fee<T: Transport>(stream: &mut Option<BaseFeeStream<T>>, gas_price: &GasPrice, chain: Chain) -> Result<bool, Error> {
+	let stream = match *stream {
+		Some(ref mut stream) => stream,
+		None => return Ok(true),
+	};
+	while let Async::Ready(Some(max_fee)) = stream.poll()? {
+		gas_price.set(max_fee.low_u64());
+		METRICS.gas_price(chain, max_fee.low_u64());
+	}
+	Ok(stream.is_known())
+}
+
+impl<'a, T: Transport + Clone + 'a> BridgeEventStream<'a, T> {
 	fn check_balances(&mut self) -> Poll<Option<()>, Error> {
 		let mut home_balance = self.home_balance.write().unwrap();
 		let mut foreign_balance = self.foreign_balance.write().unwrap();
@@ -153,6 +298,12 @@ impl<'a, T: Transport + 'a> BridgeEventStream<'a, T> {
 		let foreign_balance_known = foreign_balance.is_some();
 		*home_balance = try_bridge!(self.home_balance_check.poll()).or(*home_balance);
 		*foreign_balance = try_bridge!(self.foreign_balance_check.poll()).or(*foreign_balance);
+		if let Some(balance) = *home_balance {
+			METRICS.balance(Chain::Home, balance);
+		}
+		if let Some(balance) = *foreign_balance {
+			METRICS.balance(Chain::Foreign, balance);
+		}
 		if !home_balance_known && home_balance.is_some() {
 				info!("Retrieved home contract balance");
 		}
@@ -166,23 +317,42 @@ impl<'a, T: Transport + 'a> BridgeEventStream<'a, T> {
 		}
 	}
 
-	fn get_gas_prices(&mut self) -> Poll<Option<()>, Error> {
-		if let Some(ref mut home_gas_stream) = self.home_gas_stream {
-			let mut home_price = self.home_gas_price.write().unwrap();
-			*home_price = try_bridge!(home_gas_stream.poll()).unwrap_or(*home_price);
+	/// Returns `Ready` once authorities of both contracts are known or turned out not to be announced.
+	fn follow_authorities(&mut self) -> Poll<Option<()>, Error> {
+		let home_known = poll_authorities(&mut self.home_authorities_watch, &self.home_authorities, Chain::Home)?;
+		let foreign_known = poll_authorities(&mut self.foreign_authorities_watch, &self.foreign_authorities, Chain::Foreign)?;
+		if home_known && foreign_known {
+			Ok(Async::Ready(None))
+		} else {
+			Ok(Async::NotReady)
 		}
+	}
 
-		if let Some(ref mut foreign_gas_stream) = self.foreign_gas_stream {
-			let mut foreign_price = self.foreign_gas_price.write().unwrap();
-			*foreign_price = try_bridge!(foreign_gas_stream.poll()).unwrap_or(*foreign_price);
+	/// Returns `Ready` once gas limits set in both contracts have been read.
+	fn get_gas_limits(&mut self) -> Poll<Option<()>, Error> {
+		while let Some(()) = try_bridge!(self.home_gas_limits_watch.poll()) {}
+		while let Some(()) = try_bridge!(self.foreign_gas_limits_watch.poll()) {}
+		if self.home_gas_limits_watch.is_following() && self.foreign_gas_limits_watch.is_following() {
+			Ok(Async::Ready(None))
+		} else {
+			Ok(Async::NotReady)
 		}
+	}
 
-		Ok(Async::Ready(None))
+	/// Returns `Ready` once fees of chains sent EIP-1559 transactions are known.
+	fn get_base_fees(&mut self) -> Poll<Option<()>, Error> {
+		let home_known = poll_base_fee(&mut self.home_base_fee_stream, &self.home_gas_price, Chain::Home)?;
+		let foreign_known = poll_base_fee(&mut self.foreign_base_fee_stream, &self.foreign_gas_price, Chain::Foreign)?;
+		if home_known && foreign_known {
+			Ok(Async::Ready(None))
+		} else {
+			Ok(Async::NotReady)
+		}
 	}
 }
 
-impl<'a, T: Transport + 'a> Stream for BridgeEventStream<'a, T> {
-	type Item = BridgeChecked;
+impl<'a, T: Transport + Clone + 'a> Stream for BridgeEventStream<'a, T> {
+	type Item = BridgeEvent;
 	type Error = Error;
 
 	fn poll(&mut self) -> Poll<Option<Self::Item>, Self::Error> {
@@ -193,6 +363,18 @@ impl<'a, T: Transport + 'a> Stream for BridgeEventStream<'a, T> {
 						Async::NotReady => return Ok(Async::NotReady),
 						_ => (),
 					}
+					match self.follow_authorities()? {
+						Async::NotReady => return Ok(Async::NotReady),
+						_ => (),
+					}
+					match self.get_gas_limits()? {
+						Async::NotReady => return Ok(Async::NotReady),
+						_ => (),
+					}
+					match self.get_base_fees()? {
+						Async::NotReady => return Ok(Async::NotReady),
+						_ => (),
+					}
 					BridgeStatus::Wait
 				},
 				BridgeStatus::Wait => {
@@ -200,7 +382,9 @@ impl<'a, T: Transport + 'a> Stream for BridgeEventStream<'a, T> {
 						return Err(ErrorKind::ShutdownRequested.into())
 					}
 
-					let _ = self.get_gas_prices();
+					let _ = self.get_base_fees()?;
+					let _ = self.follow_authorities()?;
+					let _ = self.get_gas_limits()?;
 
 					let item = try_stream!(self.bridge.poll());
 					BridgeStatus::NextItem(Some(item))
@@ -222,8 +406,9 @@ impl<'a, T: Transport + 'a> Stream for BridgeEventStream<'a, T> {
 mod tests {
 	extern crate tempdir;
 	use self::tempdir::TempDir;
-	use database::Database;
-	use super::{Bridge, BridgeChecked};
+	use std::sync::{Arc, RwLock};
+	use database::{Database, DatabaseBackend, FileBackend, KeyValueBackend, TransactionKind, TransactionRecord, PendingTransaction};
+	use super::{Bridge, BridgeChecked, BridgeEvent};
 	use error::Error;
 	use tokio_core::reactor::Core;
 	use futures::{Stream, stream};
@@ -235,9 +420,12 @@ mod tests {
 		path.push("db");
 
 		let bridge = Bridge {
-			path: path.clone(),
+			backend: Box::new(FileBackend::new(&path)),
 			database: Database::default(),
-			event_stream: stream::iter_ok::<_, Error>(vec![BridgeChecked::DepositRelay(1)]),
+			event_stream: stream::iter_ok::<_, Error>(vec![BridgeEvent::Checked(BridgeChecked::DepositRelay(1))]),
+			home_pending: Default::default(),
+			foreign_pending: Default::default(),
+			state: Default::default(),
 		};
 
 		let mut event_loop = Core::new().unwrap();
@@ -249,9 +437,16 @@ mod tests {
 		assert_eq!(0, db.checked_withdraw_relay);
 
 		let bridge = Bridge {
-			path: path.clone(),
+			backend: Box::new(FileBackend::new(&path)),
 			database: Database::default(),
-			event_stream: stream::iter_ok::<_, Error>(vec![BridgeChecked::DepositRelay(2), BridgeChecked::WithdrawConfirm(3), BridgeChecked::WithdrawRelay(2)]),
+			event_stream: stream::iter_ok::<_, Error>(vec![
+				BridgeEvent::Checked(BridgeChecked::DepositRelay(2)),
+				BridgeEvent::Checked(BridgeChecked::WithdrawConfirm(3)),
+				BridgeEvent::Checked(BridgeChecked::WithdrawRelay(2)),
+			]),
+			home_pending: Default::default(),
+			foreign_pending: Default::default(),
+			state: Default::default(),
 		};
 
 		let mut event_loop = Core::new().unwrap();
@@ -262,4 +457,72 @@ mod tests {
 		assert_eq!(3, db.checked_withdraw_confirm);
 		assert_eq!(2, db.checked_withdraw_relay);
 	}
+
+	#[test]
+	fn test_key_value_backend_updates() {
+		let tempdir = TempDir::new("test_key_value_backend").unwrap();
+		let path = tempdir.path().join("db");
+
+		let record = TransactionRecord {
+			kind: TransactionKind::WithdrawRelay,
+			source_transaction_hash: 3.into(),
+			transaction_hash: 4.into(),
+			block: 5,
+		};
+
+		let bridge = Bridge {
+			backend: Box::new(KeyValueBackend::open(&path).unwrap()),
+			database: Database::default(),
+			event_stream: stream::iter_ok::<_, Error>(vec![
+				BridgeEvent::Sent(vec![record.clone()]),
+				BridgeEvent::Checked(BridgeChecked::WithdrawRelay(5)),
+			]),
+			home_pending: Default::default(),
+			foreign_pending: Default::default(),
+			state: Default::default(),
+		};
+
+		let mut event_loop = Core::new().unwrap();
+		let _ = event_loop.run(bridge.collect());
+
+		let backend = KeyValueBackend::open(&path).unwrap();
+		assert_eq!(5, backend.load().unwrap().checked_withdraw_relay);
+		assert_eq!(vec![record], backend.transactions().unwrap());
+	}
+
+	#[test]
+	fn test_pending_transactions_are_stored() {
+		let tempdir = TempDir::new("test_pending_transactions").unwrap();
+		let path = tempdir.path().join("db");
+
+		let pending = PendingTransaction {
+			nonce: 1.into(),
+			hash: 2.into(),
+			replaced_hashes: vec![],
+			raw: vec![0xf8].into(),
+			gas_price: 3.into(),
+			max_priority_fee_per_gas: None,
+			gas: 4.into(),
+			value: 0.into(),
+			data: vec![].into(),
+			to: Some(5.into()),
+			sent_at: 6,
+		};
+
+		let bridge = Bridge {
+			backend: Box::new(FileBackend::new(&path)),
+			database: Database::default(),
+			event_stream: stream::iter_ok::<_, Error>(vec![BridgeEvent::PendingTransactionsChanged]),
+			home_pending: Default::default(),
+			foreign_pending: Arc::new(RwLock::new(vec![pending.clone()])),
+			state: Default::default(),
+		};
+
+		let mut event_loop = Core::new().unwrap();
+		let _ = event_loop.run(bridge.collect());
+
+		let db = Database::load(&path).unwrap();
+		assert!(db.home_pending_transactions.is_empty());
+		assert_eq!(vec![pending], db.foreign_pending_transactions);
+	}
 }
@@ -6,8 +6,11 @@ use ethcore_transaction::Transaction;
 use api::{self, ApiCall};
 use error::{Error, ErrorKind};
 use config::Node;
-use transaction::prepare_raw_transaction;
+use transaction::{prepare_raw_transaction, pending_transaction, raw_transaction_hash, max_priority_fee_per_gas};
+use database::PendingTransaction;
 use app::App;
+use retry::Backoff;
+use signer::{Signer, SignerFuture};
 use std::sync::Arc;
 use rpc;
 
@@ -23,9 +26,24 @@ enum NonceCheckState<T: Transport, S: TransactionSender> {
 	},
 	/// Nonce available
 	Nonce(U256),
+	/// Transaction with the nonce is being signed.
+	Sign {
+		future: Timeout<SignerFuture<Bytes>>,
+		/// Tip of an EIP-1559 transaction.
+		tip: Option<U256>,
+	},
+	/// Waiting before a failed request is retried, with the nonce of the transaction
+	/// or `None` if the nonce request has failed.
+	Retry {
+		nonce: Option<U256>,
+	},
 	/// Transaction is in progress
 	TransactionRequest {
 		future: Timeout<S::Future>,
+		/// Hash of the signed transaction, reported if the node has already imported it.
+		hash: H256,
+		/// Record of the transaction to track once it's accepted by the node.
+		pending: Option<PendingTransaction>,
 	},
 }
 
@@ -34,9 +52,11 @@ pub struct NonceCheck<T: Transport, S: TransactionSender> {
 	transport: T,
 	state: NonceCheckState<T, S>,
 	node: Node,
+	signer: Arc<Signer>,
 	transaction: Transaction,
 	chain_id: u64,
 	sender: S,
+	backoff: Backoff,
 }
 
 use std::fmt::{self, Debug};
@@ -48,12 +68,14 @@ impl<T: Transport, S: TransactionSender> Debug for NonceCheck<T, S> {
 
 }
 
-pub fn send_transaction_with_nonce<T: Transport + Clone, S: TransactionSender>(transport: T, app: Arc<App<T>>, node: Node, transaction: Transaction, chain_id: u64, sender: S) -> NonceCheck<T, S> {
+pub fn send_transaction_with_nonce<T: Transport + Clone, S: TransactionSender>(transport: T, app: Arc<App<T>>, node: Node, signer: Arc<Signer>, transaction: Transaction, chain_id: u64, sender: S) -> NonceCheck<T, S> {
 	NonceCheck {
+		backoff: Backoff::new(app.timer.clone(), app.config.retry.clone()),
 		app,
 		state: NonceCheckState::Ready,
 		transport,
 		node,
+		signer,
 		transaction,
 		chain_id,
 		sender,
@@ -82,24 +104,56 @@ impl<T: Transport, S: TransactionSender> Future for NonceCheck<T, S> {
 						                           self.node.request_timeout),
 					}
 				},
-				NonceCheckState::NonceRequest { ref mut future } => {
-					let nonce = try_ready!(future.poll());
-					let mut node_nonce = self.node.info.nonce.write().unwrap();
-					*node_nonce = nonce;
-					NonceCheckState::Nonce(nonce)
+				NonceCheckState::NonceRequest { ref mut future } => match future.poll() {
+					Ok(Async::Ready(nonce)) => {
+						let mut node_nonce = self.node.info.nonce.write().unwrap();
+						*node_nonce = nonce;
+						NonceCheckState::Nonce(nonce)
+					},
+					Ok(Async::NotReady) => return Ok(Async::NotReady),
+					Err(e) => {
+						self.backoff.retry(e, "nonce request")?;
+						NonceCheckState::Retry { nonce: None }
+					},
+				},
+				NonceCheckState::Retry { nonce } => {
+					try_ready!(self.backoff.poll());
+					match nonce {
+						Some(nonce) => NonceCheckState::Nonce(nonce),
+						None => NonceCheckState::Reacquire,
+					}
 				},
 				NonceCheckState::Nonce(mut nonce) => {
 					self.transaction.nonce = nonce;
-					match prepare_raw_transaction(self.transaction.clone(), &self.app, &self.node, self.chain_id) {
-						Ok(tx) => NonceCheckState::TransactionRequest {
-							future: self.app.timer.timeout(self.sender.send(tx), self.node.request_timeout)
-						},
-						Err(e) => return Err(e),
+					let tip = max_priority_fee_per_gas(&self.node, self.transaction.gas_price);
+					NonceCheckState::Sign {
+						future: self.app.timer.timeout(prepare_raw_transaction(self.transaction.clone(), tip, &*self.signer, &self.node, self.chain_id),
+						                           self.node.request_timeout),
+						tip,
 					}
 				},
-				NonceCheckState::TransactionRequest { ref mut future } => {
+				NonceCheckState::Sign { ref mut future, tip } => match future.poll() {
+					Ok(Async::Ready(tx)) => NonceCheckState::TransactionRequest {
+						hash: raw_transaction_hash(&tx),
+						pending: if self.sender.track_pending() {
+							Some(pending_transaction(&self.transaction, tip, tx.clone()))
+						} else {
+							None
+						},
+						future: self.app.timer.timeout(self.sender.send(tx), self.node.request_timeout)
+					},
+					Ok(Async::NotReady) => return Ok(Async::NotReady),
+					Err(e) => {
+						self.backoff.retry(e, "signing transaction")?;
+						NonceCheckState::Retry { nonce: Some(self.transaction.nonce) }
+					},
+				},
+				NonceCheckState::TransactionRequest { ref mut future, hash, ref mut pending } => {
 					match future.poll() {
-						Ok(Async::Ready(t)) => return Ok(Async::Ready(t)),
+						Ok(Async::Ready(t)) => {
+							track_pending(&self.node, pending.take());
+							return Ok(Async::Ready(t))
+						},
 						Ok(Async::NotReady) => return Ok(Async::NotReady),
 						Err(e) => match e {
 							Error(ErrorKind::Web3(web3::error::Error(web3::error::ErrorKind::Rpc(rpc_err), _)), _) => {
@@ -107,14 +161,19 @@ impl<T: Transport, S: TransactionSender> Future for NonceCheck<T, S> {
 									// restart the process
 									NonceCheckState::Reacquire
 								} else if rpc_err.code == rpc::ErrorCode::ServerError(-32010) && rpc_err.message.ends_with("already imported.") {
-									let hash = self.transaction.hash(Some(self.chain_id));
-									info!("{} already imported on {}, skipping", hash, self.node.rpc_host);
+									info!("{} already imported on {}, skipping", hash, self.node.description());
+									track_pending(&self.node, pending.take());
 									return Ok(Async::Ready(self.sender.ignore(hash)))
 								} else {
-									return Err(ErrorKind::Web3(web3::error::ErrorKind::Rpc(rpc_err).into()).into());
+									self.backoff.retry(ErrorKind::Web3(web3::error::ErrorKind::Rpc(rpc_err).into()).into(), "transaction request")?;
+									// the same transaction is sent again, it is skipped if it has been imported in the meantime
+									NonceCheckState::Retry { nonce: Some(self.transaction.nonce) }
 								}
 							},
-							e => return Err(From::from(e)),
+							e => {
+								self.backoff.retry(e, "transaction request")?;
+								NonceCheckState::Retry { nonce: Some(self.transaction.nonce) }
+							},
 						},
 					}
 				},
@@ -124,11 +183,22 @@ impl<T: Transport, S: TransactionSender> Future for NonceCheck<T, S> {
 	}
 }
 
+/// Adds transaction accepted by the node to the ones monitored until they get mined.
+fn track_pending(node: &Node, pending: Option<PendingTransaction>) {
+	if let Some(pending) = pending {
+		node.info.pending_transactions.write().unwrap().push(pending);
+	}
+}
+
 pub trait TransactionSender {
 	type T;
 	type Future : Future<Item = Self::T, Error = Error>;
 	fn send(&self, tx: Bytes) -> Self::Future;
 	fn ignore(&self, hash: H256) -> Self::T;
+	/// Returns true if sent transactions should be monitored until they get mined.
+	fn track_pending(&self) -> bool {
+		true
+	}
 }
 
 pub struct SendRawTransaction<T: Transport>(pub T);
@@ -167,6 +237,11 @@ impl<T: Transport + Clone> TransactionSender for TransactionWithConfirmation<T>
 		receipt
 	}
 
+	fn track_pending(&self) -> bool {
+		// sending is complete only once the transaction is confirmed
+		false
+	}
+
 }
 
 fn web3_error_to_error(err: web3::Error) -> Error {
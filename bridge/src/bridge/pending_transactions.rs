@@ -0,0 +1,405 @@
+use std::mem;
+use std::sync::Arc;
+use std::collections::HashSet;
+use futures::{Future, Stream, Poll, Async};
+use futures::future::{JoinAll, join_all};
+use tokio_timer::{Interval, Timeout};
+use web3::Transport;
+use web3::types::{U256, H256, Bytes};
+use ethabi::{self, ParamType, Token};
+use ethcore_transaction::Transaction;
+use keccak_hash::keccak;
+use app::App;
+use api::{self, ApiCall, TransactionReceipt};
+use config::Node;
+use database::PendingTransaction;
+use error::{Error, ErrorKind};
+use message_to_mainnet::{MessageToMainnet, MESSAGE_LENGTH_V1, MESSAGE_LENGTH_V2};
+use signer::{Signer, SignerFuture};
+use transaction::{prepare_raw_transaction, raw_transaction_hash, unix_timestamp, unsigned_transaction};
+use super::BridgeEvent;
+
+/// Replacement of a stuck transaction.
+struct Replacement {
+	/// Hash of the replaced transaction.
+	hash: H256,
+	tx: Transaction,
+	/// Tip of an EIP-1559 replacement.
+	tip: Option<U256>,
+}
+
+enum PendingTransactionsState<T: Transport> {
+	/// Waiting for the next poll.
+	Wait,
+	/// Fetching receipts of all sent versions of pending transactions.
+	FetchReceipts {
+		future: JoinAll<Vec<Timeout<ApiCall<Option<TransactionReceipt>, T::Out>>>>,
+	},
+	/// Checking whether messages of reverted transactions have already been handled, which explains the reverts.
+	CheckReverts {
+		future: JoinAll<Vec<Timeout<ApiCall<Bytes, T::Out>>>>,
+		/// hashes of the checked reverted transactions
+		checked: Vec<H256>,
+		unexplained: Vec<H256>,
+		queue: Vec<Replacement>,
+		changed: bool,
+	},
+	/// Signing replacements of stuck transactions, one by one.
+	Sign {
+		future: Timeout<SignerFuture<Bytes>>,
+		replacement: Replacement,
+		queue: Vec<Replacement>,
+		reverted: Vec<H256>,
+	},
+	/// Sending a signed replacement.
+	Replace {
+		future: Timeout<ApiCall<H256, T::Out>>,
+		queue: Vec<Replacement>,
+		reverted: Vec<H256>,
+	},
+	/// Reporting changes of pending transactions, then failing if any of them has been reverted.
+	Yield {
+		event: Option<BridgeEvent>,
+		reverted: Vec<H256>,
+	},
+}
+
+/// Monitors transactions sent to a node until they get mined.
+///
+/// Transactions which are not mined within `Node::transaction_replacement_timeout`
+/// are replaced with ones using the same nonce and a gas price increased by
+/// `Node::gas_price_bump_percent`. Mined transactions which failed are dropped
+/// if the contract reports their message as already handled, e.g. by an earlier
+/// transaction, and reported as `ErrorKind::TransactionReverted` otherwise.
+pub struct PendingTransactionsMonitor<T: Transport> {
+	app: Arc<App<T>>,
+	transport: T,
+	node: Node,
+	signer: Arc<Signer>,
+	chain_id: u64,
+	interval: Interval,
+	state: PendingTransactionsState<T>,
+}
+
+pub fn create_pending_transactions_monitor<T: Transport + Clone>(app: Arc<App<T>>, transport: T, node: Node, signer: Arc<Signer>, chain_id: u64) -> PendingTransactionsMonitor<T> {
+	PendingTransactionsMonitor {
+		interval: app.timer.interval(node.poll_interval),
+		app,
+		transport,
+		node,
+		signer,
+		chain_id,
+		state: PendingTransactionsState::Wait,
+	}
+}
+
+/// Returns gas price increased by `percent`.
+fn bump_gas_price(gas_price: U256, percent: u64) -> U256 {
+	let bumped = gas_price * U256::from(100 + percent) / U256::from(100);
+	// make sure that the replacement is never priced the same as the original
+	if bumped > gas_price { bumped } else { gas_price + U256::one() }
+}
+
+/// Removes mined transactions and prepares replacements of stuck ones.
+///
+/// Returns replacements to sign, reverted transactions with their mined hashes and whether pending transactions have changed.
+fn process_receipts(node: &Node, receipts: Vec<Option<TransactionReceipt>>) -> (Vec<Replacement>, Vec<(H256, PendingTransaction)>, bool) {
+	let receipts: Vec<TransactionReceipt> = receipts.into_iter().filter_map(|r| r).collect();
+	let mined: HashSet<H256> = receipts.iter().map(|r| r.transaction_hash).collect();
+	let reverted_hashes: HashSet<H256> = receipts.iter().filter(|r| r.is_reverted()).map(|r| r.transaction_hash).collect();
+
+	let now = unix_timestamp();
+	let timeout = node.transaction_replacement_timeout.as_secs();
+	let mut pending = node.info.pending_transactions.write().unwrap();
+	let reverted: Vec<(H256, PendingTransaction)> = pending.iter()
+		.filter_map(|tx| tx.hashes().into_iter()
+			.find(|hash| reverted_hashes.contains(hash))
+			.map(|hash| (hash, tx.clone())))
+		.collect();
+	for &(hash, _) in &reverted {
+		warn!("transaction {:?} sent to {} has been reverted", hash, node.description());
+	}
+
+	let before = pending.len();
+	pending.retain(|tx| !tx.hashes().iter().any(|hash| mined.contains(hash)));
+	let changed = pending.len() != before;
+
+	let replacements = pending.iter()
+		.filter(|tx| now.saturating_sub(tx.sent_at) >= timeout)
+		.map(|tx| {
+			let mut unsigned = unsigned_transaction(tx);
+			unsigned.gas_price = bump_gas_price(tx.gas_price, node.gas_price_bump_percent);
+			Replacement {
+				hash: tx.hash,
+				tx: unsigned,
+				// nodes require both fees of an EIP-1559 replacement to be increased
+				tip: tx.max_priority_fee_per_gas.map(|tip| bump_gas_price(tip, node.gas_price_bump_percent)),
+			}
+		})
+		.collect();
+
+	(replacements, reverted, changed)
+}
+
+/// Decodes arguments of `data` if it calls the function with `signature`, e.g. `deposit(address,uint256,bytes32)`.
+fn decode_call(signature: &str, params: &[ParamType], data: &[u8]) -> Option<Vec<Token>> {
+	if data.len() < 4 || keccak(signature)[..4] != data[..4] {
+		return None;
+	}
+	ethabi::decode(params, &data[4..]).ok()
+}
+
+fn to_hash(token: Token) -> Option<[u8; 32]> {
+	let bytes = token.to_fixed_bytes()?;
+	if bytes.len() != 32 {
+		return None;
+	}
+	let mut hash = [0u8; 32];
+	hash.copy_from_slice(&bytes);
+	Some(hash)
+}
+
+/// Returns hash of the foreign transaction of a withdraw relayed with `args` of `HomeBridge.withdraw`.
+fn withdraw_hash(args: Vec<Token>) -> Option<[u8; 32]> {
+	let message = args.into_iter().nth(3)?.to_bytes()?;
+	if message.len() != MESSAGE_LENGTH_V1 && message.len() != MESSAGE_LENGTH_V2 {
+		return None;
+	}
+	Some(MessageToMainnet::from_bytes(&message).sidenet_transaction_hash.0)
+}
+
+/// Returns payload of a call asking the contract of a reverted transaction with `data` whether its message
+/// has already been handled, the same check which is done before relaying the message.
+///
+/// Returns `None` for transactions which are not relays.
+fn handled_payload<T: Transport>(app: &App<T>, data: &[u8]) -> Option<Bytes> {
+	let foreign = app.foreign_bridge.functions();
+	let deposit_params = [ParamType::Address, ParamType::Uint(256), ParamType::FixedBytes(32), ParamType::Bytes];
+	if let Some(args) = decode_call("deposit(address,uint256,bytes32,bytes)", &deposit_params, data) {
+		let mut args = args.into_iter();
+		let recipient = args.next()?.to_address()?;
+		let value = args.next()?.to_uint()?;
+		let hash = to_hash(args.next()?)?;
+		return Some(foreign.is_deposit_signed().input(app.config.validator_account(), recipient, value, hash).into());
+	}
+
+	let deposit_token_params = [ParamType::Address, ParamType::Address, ParamType::Uint(256), ParamType::FixedBytes(32), ParamType::Bytes];
+	if let Some(args) = decode_call("depositToken(address,address,uint256,bytes32,bytes)", &deposit_token_params, data) {
+		let mut args = args.into_iter();
+		let token = args.next()?.to_address()?;
+		let recipient = args.next()?.to_address()?;
+		let value = args.next()?.to_uint()?;
+		let hash = to_hash(args.next()?)?;
+		return Some(foreign.is_token_deposit_signed().input(app.config.validator_account(), token, recipient, value, hash).into());
+	}
+
+	if let Some(args) = decode_call("submitSignature(bytes,bytes)", &[ParamType::Bytes, ParamType::Bytes], data) {
+		let message = args.into_iter().nth(1)?.to_bytes()?;
+		return Some(foreign.is_message_signed().input(app.config.validator_account(), message).into());
+	}
+
+	let withdraw_params = [
+		ParamType::Array(Box::new(ParamType::Uint(8))),
+		ParamType::Array(Box::new(ParamType::FixedBytes(32))),
+		ParamType::Array(Box::new(ParamType::FixedBytes(32))),
+		ParamType::Bytes,
+	];
+	// withdraws are relayed by any authority, so they are handled once relayed by anyone
+	if let Some(args) = decode_call("withdraw(uint8[],bytes32[],bytes32[],bytes)", &withdraw_params, data) {
+		return Some(app.home_bridge.functions().withdraws().input(withdraw_hash(args)?).into());
+	}
+	if let Some(args) = decode_call("releaseTokens(uint8[],bytes32[],bytes32[],bytes)", &withdraw_params, data) {
+		return Some(app.home_erc20_bridge.functions().withdraws().input(withdraw_hash(args)?).into());
+	}
+
+	None
+}
+
+/// Updates the record of the transaction replaced with `raw`.
+///
+/// Returns `raw` unless the transaction has been mined in the meantime.
+fn replace_pending(node: &Node, replacement: &Replacement, raw: Bytes) -> Option<Bytes> {
+	let mut pending = node.info.pending_transactions.write().unwrap();
+	let tx = pending.iter_mut().find(|tx| tx.hash == replacement.hash)?;
+	info!("transaction {:?} with nonce {} sent to {} is not mined, replacing it with gas price {}",
+		tx.hash, tx.nonce, node.description(), replacement.tx.gas_price);
+	let previous = mem::replace(&mut tx.hash, raw_transaction_hash(&raw));
+	tx.replaced_hashes.push(previous);
+	tx.raw = raw.clone();
+	tx.gas_price = replacement.tx.gas_price;
+	tx.max_priority_fee_per_gas = replacement.tip;
+	tx.sent_at = unix_timestamp();
+	Some(raw)
+}
+
+/// Starts replacing stuck transactions from `queue` or, if there are none, reports the changes.
+fn replace_stuck<T: Transport>(app: &App<T>, signer: &Signer, node: &Node, chain_id: u64, queue: Vec<Replacement>, reverted: Vec<H256>, changed: bool) -> PendingTransactionsState<T> {
+	if queue.is_empty() {
+		PendingTransactionsState::Yield {
+			event: if changed { Some(BridgeEvent::PendingTransactionsChanged) } else { None },
+			reverted,
+		}
+	} else {
+		next_replacement(app, signer, node, chain_id, queue, reverted)
+	}
+}
+
+/// Starts signing the next replacement from `queue`, reports the changes once all of them have been sent.
+fn next_replacement<T: Transport>(app: &App<T>, signer: &Signer, node: &Node, chain_id: u64, mut queue: Vec<Replacement>, reverted: Vec<H256>) -> PendingTransactionsState<T> {
+	match queue.pop() {
+		Some(replacement) => PendingTransactionsState::Sign {
+			future: app.timer.timeout(prepare_raw_transaction(replacement.tx.clone(), replacement.tip, signer, node, chain_id), node.request_timeout),
+			replacement,
+			queue,
+			reverted,
+		},
+		None => PendingTransactionsState::Yield {
+			event: Some(BridgeEvent::PendingTransactionsChanged),
+			reverted,
+		},
+	}
+}
+
+impl<T: Transport> Stream for PendingTransactionsMonitor<T> {
+	type Item = BridgeEvent;
+	type Error = Error;
+
+	fn poll(&mut self) -> Poll<Option<Self::Item>, Self::Error> {
+		loop {
+			let next_state = match self.state {
+				PendingTransactionsState::Wait => {
+					let _ = try_stream!(self.interval.poll());
+					let hashes: Vec<H256> = self.node.info.pending_transactions.read().unwrap()
+						.iter()
+						.flat_map(|tx| tx.hashes())
+						.collect();
+					if hashes.is_empty() {
+						PendingTransactionsState::Wait
+					} else {
+						let receipts = hashes.into_iter()
+							.map(|hash| self.app.timer.timeout(api::transaction_receipt(&self.transport, hash), self.node.request_timeout))
+							.collect();
+						PendingTransactionsState::FetchReceipts {
+							future: join_all(receipts),
+						}
+					}
+				},
+				PendingTransactionsState::FetchReceipts { ref mut future } => {
+					let receipts = try_ready!(future.poll());
+					let (queue, reverted, changed) = process_receipts(&self.node, receipts);
+					let mut checked = Vec::new();
+					let mut checks = Vec::new();
+					let mut unexplained = Vec::new();
+					for (hash, tx) in reverted {
+						match (tx.to, handled_payload(&self.app, &tx.data.0)) {
+							(Some(contract), Some(payload)) => {
+								checked.push(hash);
+								checks.push(self.app.timer.timeout(api::call(&self.transport, contract, payload), self.node.request_timeout));
+							},
+							_ => unexplained.push(hash),
+						}
+					}
+					if checks.is_empty() {
+						replace_stuck(&self.app, &*self.signer, &self.node, self.chain_id, queue, unexplained, changed)
+					} else {
+						PendingTransactionsState::CheckReverts {
+							future: join_all(checks),
+							checked,
+							unexplained,
+							queue,
+							changed,
+						}
+					}
+				},
+				PendingTransactionsState::CheckReverts { ref mut future, ref checked, ref mut unexplained, ref mut queue, changed } => {
+					let handled = try_ready!(future.poll().map_err(|e| ErrorKind::ContextualizedError(Box::new(e), "checking messages of reverted transactions")));
+					for (hash, output) in checked.iter().zip(handled.iter()) {
+						match ethabi::decode(&[ParamType::Bool], &output.0).ok().and_then(|tokens| tokens.into_iter().next()).and_then(Token::to_bool) {
+							Some(true) => info!("message of reverted transaction {:?} has already been handled, dropping it", hash),
+							_ => unexplained.push(*hash),
+						}
+					}
+					replace_stuck(&self.app, &*self.signer, &self.node, self.chain_id,
+						mem::replace(queue, Vec::new()), mem::replace(unexplained, Vec::new()), changed)
+				},
+				PendingTransactionsState::Sign { ref mut future, ref replacement, ref mut queue, ref mut reverted } => {
+					let raw = match future.poll() {
+						Ok(Async::NotReady) => return Ok(Async::NotReady),
+						Ok(Async::Ready(raw)) => replace_pending(&self.node, replacement, raw),
+						// the transaction is going to be replaced again after the next receipts check.
+						Err(err) => {
+							warn!("failed to sign replacement of transaction {:?} sent to {}: {}", replacement.hash, self.node.description(), err);
+							None
+						},
+					};
+					let queue = mem::replace(queue, Vec::new());
+					let reverted = mem::replace(reverted, Vec::new());
+					match raw {
+						Some(raw) => PendingTransactionsState::Replace {
+							future: self.app.timer.timeout(api::send_raw_transaction(&self.transport, raw), self.node.request_timeout),
+							queue,
+							reverted,
+						},
+						None => next_replacement(&self.app, &*self.signer, &self.node, self.chain_id, queue, reverted),
+					}
+				},
+				PendingTransactionsState::Replace { ref mut future, ref mut queue, ref mut reverted } => {
+					match future.poll() {
+						Ok(Async::NotReady) => return Ok(Async::NotReady),
+						Ok(Async::Ready(hash)) => info!("replacement transaction {:?} sent to {}", hash, self.node.description()),
+						// the replaced transaction might have been mined in the meantime,
+						// it's going to be found by the next receipts check.
+						Err(err) => warn!("failed to send replacement transaction to {}: {}", self.node.description(), err),
+					}
+					next_replacement(&self.app, &*self.signer, &self.node, self.chain_id,
+						mem::replace(queue, Vec::new()), mem::replace(reverted, Vec::new()))
+				},
+				PendingTransactionsState::Yield { ref mut event, ref reverted } => match event.take() {
+					Some(event) => return Ok(Some(event).into()),
+					None => match reverted.first() {
+						Some(hash) => return Err(ErrorKind::TransactionReverted(*hash).into()),
+						None => PendingTransactionsState::Wait,
+					},
+				},
+			};
+			self.state = next_state;
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use web3::types::{Address, H256, U256};
+	use ethabi::{ParamType, Token};
+	use contracts::foreign;
+	use super::{bump_gas_price, decode_call};
+
+	#[test]
+	fn test_bump_gas_price() {
+		assert_eq!(bump_gas_price(100.into(), 20), 120.into());
+		assert_eq!(bump_gas_price(1_000_000_000.into(), 15), 1_150_000_000.into());
+		assert_eq!(bump_gas_price(1.into(), 10), 2.into());
+		assert_eq!(bump_gas_price(0.into(), 10), 1.into());
+	}
+
+	#[test]
+	fn test_decode_call() {
+		let foreign = foreign::ForeignBridge::default();
+		let recipient: Address = 1.into();
+		let value: U256 = 2.into();
+		let hash: H256 = 3.into();
+		let signature = vec![4u8; 65];
+		let params = [ParamType::Address, ParamType::Uint(256), ParamType::FixedBytes(32), ParamType::Bytes];
+		let deposit = foreign.functions().deposit().input(recipient, value, hash.0, signature.clone());
+		assert_eq!(Some(vec![
+			Token::Address(recipient),
+			Token::Uint(value),
+			Token::FixedBytes(hash.to_vec()),
+			Token::Bytes(signature.clone()),
+		]), decode_call("deposit(address,uint256,bytes32,bytes)", &params, &deposit));
+
+		let deposit_token = foreign.functions().deposit_token().input(recipient, recipient, value, hash.0, signature);
+		assert_eq!(None, decode_call("deposit(address,uint256,bytes32,bytes)", &params, &deposit_token));
+		assert_eq!(None, decode_call("deposit(address,uint256,bytes32,bytes)", &params, &[]));
+	}
+}
@@ -1,49 +1,138 @@
 use std::sync::{Arc, RwLock};
 use std::ops;
-use futures::{self, Future, Stream, stream::{Collect, FuturesUnordered, futures_unordered}, Poll};
+use futures::{self, Future, Stream, future::{JoinAll, join_all}, Poll};
+use tokio_timer::Timeout;
 use web3::Transport;
-use web3::types::{U256, H520, Address, Bytes, FilterBuilder};
-use api::{self, LogStream};
+use web3::types::{U256, H256, H520, Address, Bytes, FilterBuilder, Log};
+use ethabi;
+use api::{self, LogStream, LogStreamEvent, ApiCall};
 use app::App;
+use retry::Backoff;
+use metrics::{METRICS, Chain};
 use contracts::foreign;
-use util::web3_filter;
-use database::Database;
+use util::web3_events_filter;
+use config::TokenPair;
+use database::{Database, TransactionKind, TransactionRecord};
 use error::{Error, ErrorKind};
-use message_to_mainnet::{MessageToMainnet, MESSAGE_LENGTH};
+use signer::SignerFuture;
+use message_to_mainnet::{MessageToMainnet, MESSAGE_LENGTH_V1, MESSAGE_LENGTH_V2};
 use ethcore_transaction::{Transaction, Action};
 use itertools::Itertools;
 use super::nonce::{NonceCheck, SendRawTransaction};
-use super::BridgeChecked;
+use super::{BridgeChecked, BridgeEvent};
+use super::authorities::{AuthoritySet, may_sign};
+use super::gas_limits::{GasLimit, TransactionsGas, transactions_gas};
+use super::gas_price::GasPrice;
 
+/// returns a filter for `ForeignBridge.Withdraw` and `ForeignBridge.TokenWithdraw` events
 fn withdraws_filter(foreign: &foreign::ForeignBridge, address: Address) -> FilterBuilder {
-	let filter = foreign.events().withdraw().create_filter();
-	web3_filter(filter, ::std::iter::once(address))
+	let filters = vec![
+		foreign.events().withdraw().create_filter(),
+		foreign.events().token_withdraw().create_filter(),
+	];
+	web3_events_filter(filters, ::std::iter::once(address))
+}
+
+fn is_token_withdraw(foreign: &foreign::ForeignBridge, log: &Log) -> bool {
+	let signature: Vec<ethabi::Hash> = foreign.events().token_withdraw().create_filter().topic0.into();
+	log.topics.first().map_or(false, |topic| signature.contains(topic))
+}
+
+/// Returns the message authorities sign off on for a `Withdraw` or `TokenWithdraw` log.
+fn withdraw_message(foreign: &foreign::ForeignBridge, tokens: &[TokenPair], log: Log) -> Result<MessageToMainnet, Error> {
+	if is_token_withdraw(foreign, &log) {
+		MessageToMainnet::from_token_log(log, tokens)
+	} else {
+		MessageToMainnet::from_log(log)
+	}
+}
+
+/// Returns sources and messages of withdraws logged in a range ending with `block`.
+///
+/// Withdraws of tokens missing from `tokens` are skipped: they can't be released on home,
+/// e.g. because the token has been registered on foreign after the bridge was configured.
+fn withdraws(foreign: &foreign::ForeignBridge, tokens: &[TokenPair], logs: Vec<Log>, block: u64) -> Result<Vec<((H256, u64), Vec<u8>)>, Error> {
+	logs.into_iter()
+		.filter_map(|log| {
+			let source_block = log.block_number.map_or(block, |number| number.low_u64());
+			match withdraw_message(foreign, tokens, log) {
+				Ok(message) => {
+					info!("withdraw is ready for signature submission. tx hash {}", message.sidenet_transaction_hash);
+					Some(Ok(((message.sidenet_transaction_hash, source_block), message.to_bytes())))
+				},
+				Err(Error(ErrorKind::UnknownToken(token), _)) => {
+					warn!("withdraw of token {:?} which is not configured in `tokens`, skipping", token);
+					None
+				},
+				Err(err) => Some(Err(err)),
+			}
+		})
+		.collect()
 }
 
 fn withdraw_submit_signature_payload(foreign: &foreign::ForeignBridge, withdraw_message: Vec<u8>, signature: H520) -> Bytes {
-	assert_eq!(withdraw_message.len(), MESSAGE_LENGTH, "ForeignBridge never accepts messages with len != {} bytes; qed", MESSAGE_LENGTH);
+	assert!(withdraw_message.len() == MESSAGE_LENGTH_V1 || withdraw_message.len() == MESSAGE_LENGTH_V2,
+		"ForeignBridge never accepts messages with len other than {} or {} bytes; qed", MESSAGE_LENGTH_V1, MESSAGE_LENGTH_V2);
 	foreign.functions().submit_signature().input(signature.0.to_vec(), withdraw_message).into()
 }
 
+/// Returns payload of `ForeignBridge.isMessageSigned` call checking whether `authority` has already confirmed the withdraw.
+fn message_signed_payload(foreign: &foreign::ForeignBridge, authority: Address, withdraw_message: Vec<u8>) -> Bytes {
+	foreign.functions().is_message_signed().input(authority, withdraw_message).into()
+}
+
 /// State of withdraw confirmation.
 enum WithdrawConfirmState<T: Transport> {
 	/// Withdraw confirm is waiting for logs.
 	Wait,
+	/// Checking which withdraws have already been confirmed by this authority.
+	CheckWithdraws {
+		future: JoinAll<Vec<Timeout<ApiCall<Bytes, T::Out>>>>,
+		/// Sources and messages of withdraws to confirm.
+		withdraws: Vec<((H256, u64), Vec<u8>)>,
+		block: u64,
+	},
+	/// Signing withdraws which have not been confirmed yet.
+	SignWithdraws {
+		future: JoinAll<Vec<Timeout<SignerFuture<H520>>>>,
+		/// Hashes of foreign transactions and numbers of blocks containing withdraws to sign.
+		sources: Vec<(H256, u64)>,
+		messages: Vec<Vec<u8>>,
+		block: u64,
+	},
+	/// Computing gas limits of signature submissions.
+	EstimateGas {
+		future: TransactionsGas<T>,
+		/// Hashes of foreign transactions and numbers of blocks containing withdraws to confirm.
+		sources: Vec<(H256, u64)>,
+		payloads: Vec<Bytes>,
+		block: u64,
+	},
 	/// Confirming withdraws.
 	ConfirmWithdraws {
-		future: Collect<FuturesUnordered<NonceCheck<T, SendRawTransaction<T>>>>,
+		future: JoinAll<Vec<NonceCheck<T, SendRawTransaction<T>>>>,
+		/// Hashes of foreign transactions and numbers of blocks containing confirmed withdraws.
+		sources: Vec<(H256, u64)>,
+		block: u64,
+	},
+	/// Recording sent transactions.
+	Record {
+		records: Option<Vec<TransactionRecord>>,
 		block: u64,
 	},
 	/// All withdraws till given block has been confirmed.
 	Yield(Option<u64>),
 }
 
-pub fn create_withdraw_confirm<T: Transport + Clone>(app: Arc<App<T>>, init: &Database, foreign_balance: Arc<RwLock<Option<U256>>>, foreign_chain_id: u64, foreign_gas_price: Arc<RwLock<u64>>) -> WithdrawConfirm<T> {
+pub fn create_withdraw_confirm<T: Transport + Clone>(app: Arc<App<T>>, init: &Database, foreign_balance: Arc<RwLock<Option<U256>>>, foreign_chain_id: u64, foreign_gas_price: GasPrice, foreign_authorities: Arc<RwLock<Option<AuthoritySet>>>, gas_limit: GasLimit) -> WithdrawConfirm<T> {
 	let logs_init = api::LogStreamInit {
 		after: init.checked_withdraw_confirm,
 		request_timeout: app.config.foreign.request_timeout,
 		poll_interval: app.config.foreign.poll_interval,
 		confirmations: app.config.foreign.required_confirmations,
+		max_block_range: app.config.foreign.max_block_range,
+		retry: app.config.retry.clone(),
+		new_heads: app.new_heads.foreign.clone(),
 		filter: withdraws_filter(&app.foreign_bridge, init.foreign_contract_address.clone()),
 	};
 
@@ -51,10 +140,14 @@ pub fn create_withdraw_confirm<T: Transport + Clone>(app: Arc<App<T>>, init: &Da
 		logs: api::log_stream(app.connections.foreign.clone(), app.timer.clone(), logs_init),
 		foreign_contract: init.foreign_contract_address,
 		state: WithdrawConfirmState::Wait,
+		checked: init.checked_withdraw_confirm,
+		backoff: Backoff::new(app.timer.clone(), app.config.retry.clone()),
 		app,
 		foreign_balance,
 		foreign_chain_id,
 		foreign_gas_price,
+		foreign_authorities,
+		gas_limit,
 	}
 }
 
@@ -62,70 +155,164 @@ pub struct WithdrawConfirm<T: Transport> {
 	app: Arc<App<T>>,
 	logs: LogStream<T>,
 	state: WithdrawConfirmState<T>,
+	/// Last block which has been completely processed.
+	checked: u64,
+	backoff: Backoff,
 	foreign_contract: Address,
 	foreign_balance: Arc<RwLock<Option<U256>>>,
 	foreign_chain_id: u64,
-	foreign_gas_price: Arc<RwLock<u64>>,
+	foreign_gas_price: GasPrice,
+	/// Authorities of the foreign contract, only authorities are allowed to sign.
+	foreign_authorities: Arc<RwLock<Option<AuthoritySet>>>,
+	/// Gas limit of signature submissions.
+	gas_limit: GasLimit,
 }
 
 impl<T: Transport> Stream for WithdrawConfirm<T> {
-	type Item = BridgeChecked;
+	type Item = BridgeEvent;
 	type Error = Error;
 
 	fn poll(&mut self) -> Poll<Option<Self::Item>, Self::Error> {
+		loop {
+			try_ready!(self.backoff.poll());
+			match self.poll_relay() {
+				Err(err) => {
+					self.backoff.retry(err, "confirming withdraws")?;
+					// process all blocks after the last completed one again
+					self.logs.rewind(self.checked);
+					self.state = WithdrawConfirmState::Wait;
+				},
+				result => {
+					if let Ok(futures::Async::Ready(Some(BridgeEvent::Checked(BridgeChecked::WithdrawConfirm(block))))) = result {
+						self.checked = block;
+						self.backoff.reset();
+					}
+					return result;
+				},
+			}
+		}
+	}
+}
+
+impl<T: Transport> WithdrawConfirm<T> {
+	fn poll_relay(&mut self) -> Poll<Option<BridgeEvent>, Error> {
 		// borrow checker...
 		let app = &self.app;
-		let gas = self.app.config.txs.withdraw_confirm.gas.into();
-		let gas_price = U256::from(*self.foreign_gas_price.read().unwrap());
 		let contract = self.foreign_contract.clone();
 		loop {
 			let next_state = match self.state {
 				WithdrawConfirmState::Wait => {
+					if let futures::Async::NotReady = self.app.switches.withdraw_confirm.poll_resumed() {
+						return Ok(futures::Async::NotReady);
+					}
 					let foreign_balance = self.foreign_balance.read().unwrap();
 					if foreign_balance.is_none() {
 						warn!("foreign contract balance is unknown");
 						return Ok(futures::Async::NotReady);
 					}
+					if !may_sign(&self.foreign_authorities.read().unwrap(), &self.app.config.validator_account()) {
+						warn!("{:?} is not a foreign authority, waiting until it is added", self.app.config.validator_account());
+						return Ok(futures::Async::NotReady);
+					}
+					if self.foreign_gas_price.is_refused() {
+						warn!("foreign gas price is stale, waiting until it is updated");
+						return Ok(futures::Async::NotReady);
+					}
 
-					let item = try_stream!(self.logs.poll().map_err(|e| ErrorKind::ContextualizedError(Box::new(e), "polling foreign for withdrawals")));
-					let len = item.logs.len();
-					info!("got {} new withdraws to sign", len);
-					let mut messages = item.logs
-						.into_iter()
-						.map(|log| {
-							 info!("withdraw is ready for signature submission. tx hash {}", log.transaction_hash.unwrap());
-							 Ok(MessageToMainnet::from_log(log)?.to_bytes())
-						})
-						.collect::<Result<Vec<_>, Error>>()?;
+					let item = match try_stream!(self.logs.poll().map_err(|e| ErrorKind::ContextualizedError(Box::new(e), "polling foreign for withdrawals"))) {
+						LogStreamEvent::Logs(item) => item,
+						LogStreamEvent::Rewind(block) => {
+							warn!("foreign chain has been reorganized, rescanning withdraws after block {}", block);
+							return Ok(Some(BridgeEvent::Checked(BridgeChecked::WithdrawConfirm(block))).into());
+						},
+					};
+					if let Some(head_block) = self.logs.head_block() {
+						METRICS.head_block(Chain::Foreign, head_block);
+					}
+					info!("got {} new withdraws to sign", item.logs.len());
+					let block = item.to;
+					let withdraws = withdraws(&app.foreign_bridge, &app.config.tokens, item.logs, block)?;
 
-					info!("signing");
+					let checks = withdraws.iter()
+						.map(|&(_, ref message)| message_signed_payload(&app.foreign_bridge, app.config.validator_account(), message.clone()))
+						.map(|payload| app.timer.timeout(
+							api::call(app.connections.foreign.clone(), contract, payload),
+							app.config.foreign.request_timeout))
+						.collect_vec();
 
-					let signatures = messages.clone()
-						.into_iter()
-						.map(|message|
-							app.keystore.sign(self.app.config.foreign.account, None, api::eth_data_hash(message)))
-						.map_results(|sig| H520::from(sig.into_electrum()))
-						.fold_results(vec![], |mut acc, sig| {
-							acc.push(sig);
-							acc
+					WithdrawConfirmState::CheckWithdraws {
+						future: join_all(checks),
+						withdraws,
+						block,
+					}
+				},
+				WithdrawConfirmState::CheckWithdraws { ref mut future, ref mut withdraws, block } => {
+					let signed = try_ready!(future.poll().map_err(|e| ErrorKind::ContextualizedError(Box::new(e), "checking confirmed withdraws on foreign")));
+					let is_message_signed = app.foreign_bridge.functions().is_message_signed();
+					let signed = signed.iter()
+						.map(|output| is_message_signed.output(output.0.as_slice()))
+						.collect::<::ethabi::Result<Vec<bool>>>()?;
+
+					let (sources, messages): (Vec<_>, Vec<_>) = withdraws.drain(..)
+						.zip(signed.into_iter())
+						.filter_map(|((source, message), signed)| if signed {
+							info!("withdraw {} has already been confirmed, skipping", source.0);
+							None
+						} else {
+							Some((source, message))
 						})
-						.map_err(|e| ErrorKind::SignError(e))?;
+						.unzip();
 
-					let block = item.to;
+					info!("signing");
 
-					let balance_required = gas * gas_price * U256::from(signatures.len());
-					if balance_required > *foreign_balance.as_ref().unwrap() {
-						return Err(ErrorKind::InsufficientFunds.into())
+					let signatures = messages.iter()
+						.map(|message| app.timer.timeout(
+							app.signers.validator.sign_message(app.config.validator_account(), message.clone()),
+							app.config.foreign.request_timeout))
+						.collect_vec();
+
+					WithdrawConfirmState::SignWithdraws {
+						future: join_all(signatures),
+						sources,
+						messages,
+						block,
 					}
+				},
+				WithdrawConfirmState::SignWithdraws { ref mut future, ref mut sources, ref mut messages, block } => {
+					let signatures = try_ready!(future.poll().map_err(|e| ErrorKind::ContextualizedError(Box::new(e), "signing withdraws")));
 
 					info!("signing complete");
-					let confirmations = messages
+					let payloads = messages
 						.drain(ops::RangeFull)
 						.zip(signatures.into_iter())
 						.map(|(withdraw_message, signature)| {
 							 withdraw_submit_signature_payload(&app.foreign_bridge, withdraw_message, signature)
 						})
-						.map(|payload| {
+						.collect_vec();
+
+					let future = transactions_gas(&app.connections.foreign, &app.timer, &app.config.foreign, contract,
+												  &self.gas_limit, app.config.txs.withdraw_confirm.estimate_gas, &payloads);
+					WithdrawConfirmState::EstimateGas {
+						future,
+						sources: sources.drain(..).collect(),
+						payloads,
+						block,
+					}
+				},
+				WithdrawConfirmState::EstimateGas { ref mut future, ref mut sources, ref mut payloads, block } => {
+					let gas = try_ready!(future.poll().map_err(|e| ErrorKind::ContextualizedError(Box::new(e), "estimating gas of signature submissions on foreign")));
+					let len = payloads.len();
+
+					let gas_price = U256::from(self.foreign_gas_price.get().ok_or(ErrorKind::StaleGasPrice)?);
+					let balance_required = gas.iter().fold(U256::zero(), |total, gas| total + *gas * gas_price);
+					let foreign_balance = *self.foreign_balance.read().unwrap();
+					if balance_required > foreign_balance.unwrap_or_default() {
+						return Err(ErrorKind::InsufficientFunds.into())
+					}
+
+					let confirmations = payloads.drain(..)
+						.zip(gas.into_iter())
+						.map(|(payload, gas)| {
 							let tx = Transaction {
 								gas,
 								gas_price,
@@ -135,29 +322,95 @@ impl<T: Transport> Stream for WithdrawConfirm<T> {
 								action: Action::Call(contract),
 							};
 							api::send_transaction_with_nonce(self.app.connections.foreign.clone(), self.app.clone(), self.app.config.foreign.clone(),
-															 tx, self.foreign_chain_id, SendRawTransaction(self.app.connections.foreign.clone()))
+															 self.app.signers.foreign.clone(), tx, self.foreign_chain_id, SendRawTransaction(self.app.connections.foreign.clone()))
 						}).collect_vec();
 
 					info!("submitting {} signatures", len);
 					WithdrawConfirmState::ConfirmWithdraws {
-						future: futures_unordered(confirmations).collect(),
+						future: join_all(confirmations),
+						sources: sources.drain(..).collect(),
 						block,
 					}
 				},
-				WithdrawConfirmState::ConfirmWithdraws { ref mut future, block } => {
-					let _ = try_ready!(future.poll().map_err(|e| ErrorKind::ContextualizedError(Box::new(e), "sending signature submissions to foreign")));
+				WithdrawConfirmState::ConfirmWithdraws { ref mut future, ref sources, block } => {
+					let hashes = try_ready!(future.poll().map_err(|e| ErrorKind::ContextualizedError(Box::new(e), "sending signature submissions to foreign")));
 					info!("submitting signatures complete");
-					WithdrawConfirmState::Yield(Some(block))
+					let records = sources.iter()
+						.zip(hashes.into_iter())
+						.map(|(&(source_transaction_hash, source_block), transaction_hash)| TransactionRecord {
+							kind: TransactionKind::WithdrawConfirm,
+							source_transaction_hash,
+							transaction_hash,
+							block: source_block,
+						})
+						.collect();
+					WithdrawConfirmState::Record {
+						records: Some(records),
+						block,
+					}
+				},
+				WithdrawConfirmState::Record { ref mut records, block } => match records.take() {
+					Some(records) => return Ok(Some(BridgeEvent::Sent(records)).into()),
+					None => WithdrawConfirmState::Yield(Some(block)),
 				},
 				WithdrawConfirmState::Yield(ref mut block) => match block.take() {
 					None => {
 						info!("waiting for new withdraws that should get signed");
 						WithdrawConfirmState::Wait
 					},
-					Some(v) => return Ok(Some(BridgeChecked::WithdrawConfirm(v)).into()),
+					Some(v) => return Ok(Some(BridgeEvent::Checked(BridgeChecked::WithdrawConfirm(v))).into()),
 				}
 			};
 			self.state = next_state;
 		}
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use rustc_hex::FromHex;
+	use web3::types::{Log, Address, H256};
+	use ethabi;
+	use contracts::foreign;
+	use config::TokenPair;
+	use message_to_mainnet::MessageToMainnet;
+	use super::withdraws;
+
+	fn token_withdraw_log(token: &str, transaction_hash: &str) -> Log {
+		let topics: Vec<ethabi::Hash> = foreign::ForeignBridge::default().events().token_withdraw().create_filter().topic0.into();
+		let data = format!(
+			"000000000000000000000000{}000000000000000000000000aff3454fce5edbc8cca8697c15331677e6ebcccc00000000000000000000000000000000000000000000000000000000000000f0{:064x}",
+			token, 1);
+		Log {
+			data: data.from_hex().unwrap().into(),
+			topics,
+			transaction_hash: Some(transaction_hash.into()),
+			address: Address::zero(),
+			block_hash: None,
+			transaction_index: None,
+			log_index: None,
+			transaction_log_index: None,
+			log_type: None,
+			block_number: Some(5.into()),
+			removed: None,
+		}
+	}
+
+	#[test]
+	fn test_withdraws_of_unknown_tokens_are_skipped() {
+		let tokens = vec![TokenPair {
+			home: "0000000000000000000000000000000000000005".into(),
+			foreign: "0000000000000000000000000000000000000006".into(),
+		}];
+		let logs = vec![
+			token_withdraw_log("0000000000000000000000000000000000000007", "884edad9ce6fa2440d8a54cc123490eb96d2768479d49ff9c7366125a9424364"),
+			token_withdraw_log("0000000000000000000000000000000000000006", "9bf38d1e0ef1cd04dfb1a10c21b3bc05b1c50de88ef5b6c0af6bdc3b63e7ae1d"),
+		];
+
+		let withdraws = withdraws(&foreign::ForeignBridge::default(), &tokens, logs, 10).unwrap();
+		assert_eq!(1, withdraws.len());
+		assert_eq!((H256::from("9bf38d1e0ef1cd04dfb1a10c21b3bc05b1c50de88ef5b6c0af6bdc3b63e7ae1d"), 5), withdraws[0].0);
+		let message = MessageToMainnet::from_bytes(&withdraws[0].1);
+		assert_eq!(Some("0000000000000000000000000000000000000005".into()), message.token);
+	}
+}
@@ -1,27 +1,41 @@
 use std::sync::{Arc, RwLock};
-use futures::{self, Future, Stream, stream::{Collect, FuturesUnordered, futures_unordered}, Poll};
+use futures::{self, Future, Stream, Poll};
 use futures::future::{JoinAll, join_all, Join};
 use tokio_timer::Timeout;
 use web3::Transport;
-use web3::types::{U256, Address, FilterBuilder, Log, Bytes};
+use web3::types::{U256, H256, Address, FilterBuilder, Log, Bytes};
 use ethabi::{RawLog, self};
 use app::App;
-use api::{self, LogStream, ApiCall};
+use retry::Backoff;
+use metrics::{METRICS, Chain};
+use api::{self, LogStream, LogStreamEvent, ApiCall};
 use contracts::foreign;
-use util::web3_filter;
-use database::Database;
+use config::BridgeMode;
+use util::web3_events_filter;
+use database::{Database, TransactionKind, TransactionRecord};
 use error::{self, Error, ErrorKind};
 use message_to_mainnet::MessageToMainnet;
 use signature::Signature;
 use ethcore_transaction::{Transaction, Action};
 use super::nonce::{NonceCheck, SendRawTransaction};
-use super::BridgeChecked;
+use super::{BridgeChecked, BridgeEvent};
+use super::authorities::AuthoritySet;
+use super::gas_limits::{GasLimit, TransactionsGas, transactions_gas};
+use super::gas_price::GasPrice;
 use itertools::Itertools;
 
-/// returns a filter for `ForeignBridge.CollectedSignatures` events
+/// returns a filter for `ForeignBridge.CollectedSignatures` and `ForeignBridge.CollectedAdditionalSignature` events
 fn collected_signatures_filter<I: IntoIterator<Item = Address>>(foreign: &foreign::ForeignBridge, addresses: I) -> FilterBuilder {
-	let filter = foreign.events().collected_signatures().create_filter();
-	web3_filter(filter, addresses)
+	let filters = vec![
+		foreign.events().collected_signatures().create_filter(),
+		foreign.events().collected_additional_signature().create_filter(),
+	];
+	web3_events_filter(filters, addresses)
+}
+
+fn is_additional_signature(foreign: &foreign::ForeignBridge, log: &Log) -> bool {
+	let signature: Vec<ethabi::Hash> = foreign.events().collected_additional_signature().create_filter().topic0.into();
+	log.topics.first().map_or(false, |topic| signature.contains(topic))
 }
 
 /// payloads for calls to `ForeignBridge.signature` and `ForeignBridge.message`
@@ -34,27 +48,51 @@ struct RelayAssignment {
 	message_payload: Bytes,
 }
 
-fn signatures_payload(foreign: &foreign::ForeignBridge, my_address: Address, log: Log) -> error::Result<Option<RelayAssignment>> {
+/// `required_signatures` is the number of signatures currently required by the home contract,
+/// if it is not known all collected signatures are relayed.
+///
+/// A withdraw is relayed once: with `CollectedSignatures` if enough signatures have been collected,
+/// otherwise with the `CollectedAdditionalSignature` completing the signatures home requires.
+fn signatures_payload(foreign: &foreign::ForeignBridge, my_address: Address, required_signatures: Option<u32>, log: Log) -> error::Result<Option<RelayAssignment>> {
+	let additional = is_additional_signature(foreign, &log);
 	// convert web3::Log to ethabi::RawLog since ethabi events can
 	// only be parsed from the latter
 	let raw_log = RawLog {
 		topics: log.topics.into_iter().map(|t| t.0.into()).collect(),
 		data: log.data.0,
 	};
-	let collected_signatures = foreign.events().collected_signatures().parse_log(raw_log)?;
-	if collected_signatures.authority_responsible_for_relay != my_address.0.into() {
+	let (authority, message_hash, collected) = if additional {
+		let signature = foreign.events().collected_additional_signature().parse_log(raw_log)?;
+		(signature.authority_responsible_for_relay, signature.message_hash, signature.number_of_collected_signatures)
+	} else {
+		let signatures = foreign.events().collected_signatures().parse_log(raw_log)?;
+		(signatures.authority_responsible_for_relay, signatures.message_hash, signatures.number_of_collected_signatures)
+	};
+	if authority != my_address.0.into() {
 		info!("bridge not responsible for relaying transaction to home. tx hash: {}", log.transaction_hash.unwrap());
 		// this authority is not responsible for relaying this transaction.
 		// someone else will relay this transaction to home.
 		return Ok(None);
 	}
 
-	let required_signatures: U256 = (&foreign.functions().message().input(collected_signatures.number_of_collected_signatures)[4..]).into();
-	let signature_payloads = (0..required_signatures.low_u32()).into_iter()
-		.map(|index| foreign.functions().signature().input(collected_signatures.message_hash, index))
+	let collected: U256 = (&foreign.functions().message().input(collected)[4..]).into();
+	let collected = collected.low_u32();
+	let required_signatures = match required_signatures {
+		Some(required) if required > collected => {
+			info!("home requires {} signatures, only {} have been collected, waiting for more. tx hash: {}", required, collected, log.transaction_hash.unwrap());
+			return Ok(None);
+		},
+		// the withdraw has been relayed with fewer signatures
+		Some(required) if additional && required < collected => return Ok(None),
+		Some(required) => required,
+		None if additional => return Ok(None),
+		None => collected,
+	};
+	let signature_payloads = (0..required_signatures).into_iter()
+		.map(|index| foreign.functions().signature().input(message_hash, index))
 		.map(Into::into)
 		.collect();
-	let message_payload = foreign.functions().message().input(collected_signatures.message_hash).into();
+	let message_payload = foreign.functions().message().input(message_hash).into();
 
 	Ok(Some(RelayAssignment {
 		signature_payloads,
@@ -62,6 +100,33 @@ fn signatures_payload(foreign: &foreign::ForeignBridge, my_address: Address, log
 	}))
 }
 
+/// payload of a call to `withdraws` of the home contract checking whether the withdraw
+/// made in foreign transaction `hash` has already been relayed
+fn withdrawn_payload<T: Transport>(app: &App<T>, hash: H256) -> Bytes {
+	match app.config.bridge_mode {
+		BridgeMode::NativeToErc => app.home_bridge.functions().withdraws().input(hash.0).into(),
+		BridgeMode::ErcToErc { .. } => app.home_erc20_bridge.functions().withdraws().input(hash.0).into(),
+	}
+}
+
+fn withdrawn_output<T: Transport>(app: &App<T>, output: &Bytes) -> ethabi::Result<bool> {
+	match app.config.bridge_mode {
+		BridgeMode::NativeToErc => app.home_bridge.functions().withdraws().output(output.0.as_slice()),
+		BridgeMode::ErcToErc { .. } => app.home_erc20_bridge.functions().withdraws().output(output.0.as_slice()),
+	}
+}
+
+/// payload of `HomeBridge.withdraw`, or of `HomeBridgeErc20.releaseTokens` releasing locked tokens
+fn withdraw_payload<T: Transport>(app: &App<T>, message: &Bytes, signatures: &[Signature]) -> Bytes {
+	let vs = signatures.iter().map(|x| x.v);
+	let rs = signatures.iter().map(|x| x.r);
+	let ss = signatures.iter().map(|x| x.s);
+	match app.config.bridge_mode {
+		BridgeMode::NativeToErc => app.home_bridge.functions().withdraw().input(vs, rs, ss, message.0.clone()).into(),
+		BridgeMode::ErcToErc { .. } => app.home_erc20_bridge.functions().release_tokens().input(vs, rs, ss, message.0.clone()).into(),
+	}
+}
+
 /// state of the withdraw relay state machine
 pub enum WithdrawRelayState<T: Transport> {
 	Wait,
@@ -70,21 +135,46 @@ pub enum WithdrawRelayState<T: Transport> {
 			JoinAll<Vec<Timeout<ApiCall<Bytes, T::Out>>>>,
 			JoinAll<Vec<JoinAll<Vec<Timeout<ApiCall<Bytes, T::Out>>>>>>
 		>,
+		/// numbers of blocks containing relayed `CollectedSignatures` events
+		source_blocks: Vec<u64>,
+		block: u64,
+	},
+	CheckWithdraws {
+		future: JoinAll<Vec<Timeout<ApiCall<Bytes, T::Out>>>>,
+		/// sources, messages and signatures of withdraws to relay
+		withdraws: Vec<((H256, u64), Bytes, Vec<Signature>)>,
+		block: u64,
+	},
+	EstimateGas {
+		future: TransactionsGas<T>,
+		/// sources of withdraws to relay
+		sources: Vec<(H256, u64)>,
+		/// payloads and gas prices of withdraw relays
+		withdraws: Vec<(Bytes, U256)>,
 		block: u64,
 	},
 	RelayWithdraws {
-		future: Collect<FuturesUnordered<NonceCheck<T, SendRawTransaction<T>>>>,
+		future: JoinAll<Vec<NonceCheck<T, SendRawTransaction<T>>>>,
+		/// hashes of foreign transactions and numbers of blocks containing relayed withdraws
+		sources: Vec<(H256, u64)>,
+		block: u64,
+	},
+	Record {
+		records: Option<Vec<TransactionRecord>>,
 		block: u64,
 	},
 	Yield(Option<u64>),
 }
 
-pub fn create_withdraw_relay<T: Transport + Clone>(app: Arc<App<T>>, init: &Database, home_balance: Arc<RwLock<Option<U256>>>, home_chain_id: u64, home_gas_price: Arc<RwLock<u64>>) -> WithdrawRelay<T> {
+pub fn create_withdraw_relay<T: Transport + Clone>(app: Arc<App<T>>, init: &Database, home_balance: Arc<RwLock<Option<U256>>>, home_chain_id: u64, home_gas_price: GasPrice, home_authorities: Arc<RwLock<Option<AuthoritySet>>>, gas_limit: GasLimit) -> WithdrawRelay<T> {
 	let logs_init = api::LogStreamInit {
 		after: init.checked_withdraw_relay,
 		request_timeout: app.config.foreign.request_timeout,
 		poll_interval: app.config.foreign.poll_interval,
 		confirmations: app.config.foreign.required_confirmations,
+		max_block_range: app.config.foreign.max_block_range,
+		retry: app.config.retry.clone(),
+		new_heads: app.new_heads.foreign.clone(),
 		filter: collected_signatures_filter(&app.foreign_bridge, vec![init.foreign_contract_address]),
 	};
 
@@ -93,10 +183,14 @@ pub fn create_withdraw_relay<T: Transport + Clone>(app: Arc<App<T>>, init: &Data
 		home_contract: init.home_contract_address,
 		foreign_contract: init.foreign_contract_address,
 		state: WithdrawRelayState::Wait,
+		checked: init.checked_withdraw_relay,
+		backoff: Backoff::new(app.timer.clone(), app.config.retry.clone()),
 		app,
 		home_balance,
 		home_chain_id,
 		home_gas_price,
+		home_authorities,
+		gas_limit,
 	}
 }
 
@@ -104,48 +198,100 @@ pub struct WithdrawRelay<T: Transport> {
 	app: Arc<App<T>>,
 	logs: LogStream<T>,
 	state: WithdrawRelayState<T>,
+	/// Last block which has been completely processed.
+	checked: u64,
+	backoff: Backoff,
 	foreign_contract: Address,
 	home_contract: Address,
 	home_balance: Arc<RwLock<Option<U256>>>,
 	home_chain_id: u64,
-	home_gas_price: Arc<RwLock<u64>>,
+	home_gas_price: GasPrice,
+	/// Authorities of the home contract, their required signatures are relayed.
+	home_authorities: Arc<RwLock<Option<AuthoritySet>>>,
+	/// Gas limit of withdraw relays.
+	gas_limit: GasLimit,
 }
 
 impl<T: Transport> Stream for WithdrawRelay<T> {
-	type Item = BridgeChecked;
+	type Item = BridgeEvent;
 	type Error = Error;
 
 	fn poll(&mut self) -> Poll<Option<Self::Item>, Self::Error> {
+		loop {
+			try_ready!(self.backoff.poll());
+			match self.poll_relay() {
+				Err(err) => {
+					self.backoff.retry(err, "relaying withdraws")?;
+					// process all blocks after the last completed one again
+					self.logs.rewind(self.checked);
+					self.state = WithdrawRelayState::Wait;
+				},
+				result => {
+					if let Ok(futures::Async::Ready(Some(BridgeEvent::Checked(BridgeChecked::WithdrawRelay(block))))) = result {
+						self.checked = block;
+						self.backoff.reset();
+					}
+					return result;
+				},
+			}
+		}
+	}
+}
+
+impl<T: Transport> WithdrawRelay<T> {
+	fn poll_relay(&mut self) -> Poll<Option<BridgeEvent>, Error> {
 		let app = &self.app;
-		let gas = self.app.config.txs.withdraw_relay.gas.into();
-		let gas_price = U256::from(*self.home_gas_price.read().unwrap());
+		// withdraws pay the gas price chosen by their senders, the current one only estimates the balance required
+		let gas_price = U256::from(self.home_gas_price.last());
 		let contract = self.home_contract.clone();
 		let home = &self.app.config.home;
 		let t = &self.app.connections.home;
 		let foreign = &self.app.connections.foreign;
 		let chain_id = self.home_chain_id;
 		let foreign_bridge = &self.app.foreign_bridge;
-		let foreign_account = self.app.config.foreign.account;
+		let validator_account = self.app.config.validator_account();
 		let timer = &self.app.timer;
 		let foreign_contract = self.foreign_contract;
 		let foreign_request_timeout = self.app.config.foreign.request_timeout;
+		let required_signatures = self.home_authorities.read().unwrap().as_ref().map(|set| set.required_signatures);
 
 		loop {
 			let next_state = match self.state {
 				WithdrawRelayState::Wait => {
-					let item = try_stream!(self.logs.poll().map_err(|e| ErrorKind::ContextualizedError(Box::new(e), "polling foreign for collected signatures")));
+					if let futures::Async::NotReady = self.app.switches.withdraw_relay.poll_resumed() {
+						return Ok(futures::Async::NotReady);
+					}
+					let item = match try_stream!(self.logs.poll().map_err(|e| ErrorKind::ContextualizedError(Box::new(e), "polling foreign for collected signatures"))) {
+						LogStreamEvent::Logs(item) => item,
+						LogStreamEvent::Rewind(block) => {
+							warn!("foreign chain has been reorganized, rescanning collected signatures after block {}", block);
+							return Ok(Some(BridgeEvent::Checked(BridgeChecked::WithdrawRelay(block))).into());
+						},
+					};
+					if let Some(head_block) = self.logs.head_block() {
+						METRICS.head_block(Chain::Foreign, head_block);
+					}
 					info!("got {} new signed withdraws to relay", item.logs.len());
+					let block = item.to;
 					let assignments = item.logs
 						.into_iter()
-						.map(|log| signatures_payload(
+						.map(|log| {
+							let source_block = log.block_number.map_or(block, |number| number.low_u64());
+							signatures_payload(
 								foreign_bridge,
-								foreign_account,
-								 log))
-						.collect::<error::Result<Vec<_>>>()?;
+								validator_account,
+								required_signatures,
+								log)
+								.map(|assignment| assignment.map(|assignment| (source_block, assignment)))
+						})
+						.collect::<error::Result<Vec<_>>>()?
+						.into_iter()
+						.filter_map(|a| a)
+						.collect::<Vec<_>>();
 
+					let source_blocks = assignments.iter().map(|&(source_block, _)| source_block).collect();
 					let (signatures, messages): (Vec<_>, Vec<_>) = assignments.into_iter()
-						.filter_map(|a| a)
-						.map(|assignment| (assignment.signature_payloads, assignment.message_payload))
+						.map(|(_, assignment)| (assignment.signature_payloads, assignment.message_payload))
 						.unzip();
 
 					let message_calls = messages.into_iter()
@@ -172,10 +318,11 @@ impl<T: Transport> Stream for WithdrawRelay<T> {
 					info!("fetching messages and signatures");
 					WithdrawRelayState::FetchMessagesSignatures {
 						future: join_all(message_calls).join(join_all(signature_calls)),
-						block: item.to,
+						source_blocks,
+						block,
 					}
 				},
-				WithdrawRelayState::FetchMessagesSignatures { ref mut future, block } => {
+				WithdrawRelayState::FetchMessagesSignatures { ref mut future, ref source_blocks, block } => {
 					let home_balance = self.home_balance.read().unwrap();
 					if home_balance.is_none() {
 						warn!("home contract balance is unknown");
@@ -186,11 +333,6 @@ impl<T: Transport> Stream for WithdrawRelay<T> {
 					info!("fetching messages and signatures complete");
 					assert_eq!(messages_raw.len(), signatures_raw.len());
 
-					let balance_required = gas * gas_price * U256::from(messages_raw.len());
-					if balance_required > *home_balance.as_ref().unwrap() {
-						return Err(ErrorKind::InsufficientFunds.into())
-					}
-
 					let messages = messages_raw
 						.iter()
 						.map(|message| {
@@ -199,7 +341,10 @@ impl<T: Transport> Stream for WithdrawRelay<T> {
 						.collect::<ethabi::Result<Vec<_>>>()
 						.map_err(error::Error::from)?;
 
-					let len = messages.len();
+					let sources = messages.iter()
+						.map(|message| MessageToMainnet::from_bytes(message.0.as_slice()).sidenet_transaction_hash)
+						.zip(source_blocks.iter().cloned())
+						.collect::<Vec<_>>();
 
 					let signatures = signatures_raw
 						.iter()
@@ -219,15 +364,66 @@ impl<T: Transport> Stream for WithdrawRelay<T> {
 						)
 						.collect::<error::Result<Vec<_>>>()?;
 
-					let relays = messages.into_iter()
-						.zip(signatures.into_iter())
-						.map(|(message, signatures)| {
-							let payload: Bytes = app.home_bridge.functions().withdraw().input(
-								signatures.iter().map(|x| x.v),
-								signatures.iter().map(|x| x.r),
-								signatures.iter().map(|x| x.s),
-								message.clone().0).into();
-							let gas_price = MessageToMainnet::from_bytes(message.0.as_slice()).mainnet_gas_price;
+					let checks = sources.iter()
+						.map(|&(hash, _)| withdrawn_payload(app, hash))
+						.map(|payload| app.timer.timeout(api::call(t.clone(), contract, payload), home.request_timeout))
+						.collect_vec();
+
+					WithdrawRelayState::CheckWithdraws {
+						future: join_all(checks),
+						withdraws: sources.into_iter()
+							.zip(messages.into_iter())
+							.zip(signatures.into_iter())
+							.map(|((source, message), signatures)| (source, message, signatures))
+							.collect(),
+						block,
+					}
+				},
+				WithdrawRelayState::CheckWithdraws { ref mut future, ref mut withdraws, block } => {
+					let withdrawn = try_ready!(future.poll().map_err(|e| ErrorKind::ContextualizedError(Box::new(e), "checking relayed withdraws on home")));
+					let withdrawn = withdrawn.iter()
+						.map(|output| withdrawn_output(app, output))
+						.collect::<ethabi::Result<Vec<bool>>>()?;
+
+					let (sources, withdraws): (Vec<_>, Vec<_>) = withdraws.drain(..)
+						.zip(withdrawn.into_iter())
+						.filter_map(|((source, message, signatures), withdrawn)| if withdrawn {
+							info!("withdraw {} has already been relayed, skipping", source.0);
+							None
+						} else {
+							Some((source, (message, signatures)))
+						})
+						.unzip();
+
+					let withdraws = withdraws.into_iter()
+						.map(|(message, signatures)| (
+							withdraw_payload(app, &message, &signatures),
+							MessageToMainnet::from_bytes(message.0.as_slice()).mainnet_gas_price,
+						))
+						.collect_vec();
+					let payloads = withdraws.iter().map(|&(ref payload, _)| payload.clone()).collect_vec();
+
+					let future = transactions_gas(t, &app.timer, home, contract, &self.gas_limit, app.config.txs.withdraw_relay.estimate_gas, &payloads);
+					WithdrawRelayState::EstimateGas {
+						future,
+						sources,
+						withdraws,
+						block,
+					}
+				},
+				WithdrawRelayState::EstimateGas { ref mut future, ref mut sources, ref mut withdraws, block } => {
+					let gas = try_ready!(future.poll().map_err(|e| ErrorKind::ContextualizedError(Box::new(e), "estimating gas of withdraw relays on home")));
+					let len = withdraws.len();
+
+					let balance_required = gas.iter().fold(U256::zero(), |total, gas| total + *gas * gas_price);
+					let home_balance = *self.home_balance.read().unwrap();
+					if balance_required > home_balance.unwrap_or_default() {
+						return Err(ErrorKind::InsufficientFunds.into())
+					}
+
+					let relays = withdraws.drain(..)
+						.zip(gas.into_iter())
+						.map(|((payload, gas_price), gas)| {
 							let tx = Transaction {
 									gas,
 									gas_price,
@@ -236,26 +432,43 @@ impl<T: Transport> Stream for WithdrawRelay<T> {
 									nonce: U256::zero(),
 									action: Action::Call(contract),
 								};
-							    api::send_transaction_with_nonce(t.clone(), app.clone(), home.clone(), tx, chain_id, SendRawTransaction(t.clone()))
+							    api::send_transaction_with_nonce(t.clone(), app.clone(), home.clone(), app.signers.home.clone(), tx, chain_id, SendRawTransaction(t.clone()))
 							}).collect_vec();
 
 					info!("relaying {} withdraws", len);
 					WithdrawRelayState::RelayWithdraws {
-						future: futures_unordered(relays).collect(),
+						future: join_all(relays),
+						sources: sources.drain(..).collect(),
 						block,
 					}
 				},
-				WithdrawRelayState::RelayWithdraws { ref mut future, block } => {
-					let _ = try_ready!(future.poll().map_err(|e| ErrorKind::ContextualizedError(Box::new(e), "sending withdrawal to home")));
+				WithdrawRelayState::RelayWithdraws { ref mut future, ref sources, block } => {
+					let hashes = try_ready!(future.poll().map_err(|e| ErrorKind::ContextualizedError(Box::new(e), "sending withdrawal to home")));
 					info!("relaying withdraws complete");
-					WithdrawRelayState::Yield(Some(block))
+					let records = sources.iter()
+						.zip(hashes.into_iter())
+						.map(|(&(source_transaction_hash, source_block), transaction_hash)| TransactionRecord {
+							kind: TransactionKind::WithdrawRelay,
+							source_transaction_hash,
+							transaction_hash,
+							block: source_block,
+						})
+						.collect();
+					WithdrawRelayState::Record {
+						records: Some(records),
+						block,
+					}
+				},
+				WithdrawRelayState::Record { ref mut records, block } => match records.take() {
+					Some(records) => return Ok(Some(BridgeEvent::Sent(records)).into()),
+					None => WithdrawRelayState::Yield(Some(block)),
 				},
 				WithdrawRelayState::Yield(ref mut block) => match block.take() {
 					None => {
 						info!("waiting for signed withdraws to relay");
 						WithdrawRelayState::Wait
 					},
-					Some(v) => return Ok(Some(BridgeChecked::WithdrawRelay(v)).into()),
+					Some(v) => return Ok(Some(BridgeEvent::Checked(BridgeChecked::WithdrawRelay(v))).into()),
 				}
 			};
 			self.state = next_state;
@@ -267,6 +480,7 @@ impl<T: Transport> Stream for WithdrawRelay<T> {
 mod tests {
 	use rustc_hex::FromHex;
 	use web3::types::{Log, Bytes, Address};
+	use ethabi;
 	use contracts::foreign;
 	use super::signatures_payload;
 
@@ -291,7 +505,7 @@ mod tests {
 			removed: None,
 		};
 
-		let assignment = signatures_payload(&foreign, my_address, log).unwrap().unwrap();
+		let assignment = signatures_payload(&foreign, my_address, None, log).unwrap().unwrap();
 		let expected_message: Bytes = "490a32c600000000000000000000000000000000000000000000000000000000000000f0".from_hex().unwrap().into();
 		let expected_signatures: Vec<Bytes> = vec![
 			"1812d99600000000000000000000000000000000000000000000000000000000000000f00000000000000000000000000000000000000000000000000000000000000000".from_hex().unwrap().into(),
@@ -322,7 +536,76 @@ mod tests {
 			removed: None,
 		};
 
-		let assignment = signatures_payload(&foreign, my_address, log).unwrap();
+		let assignment = signatures_payload(&foreign, my_address, None, log).unwrap();
 		assert_eq!(None, assignment);
 	}
+
+	#[test]
+	fn test_signatures_payload_current_required_signatures() {
+		let foreign = foreign::ForeignBridge::default();
+		let my_address = "aff3454fce5edbc8cca8697c15331677e6ebcccc".into();
+
+		let data = "000000000000000000000000aff3454fce5edbc8cca8697c15331677e6ebcccc00000000000000000000000000000000000000000000000000000000000000f00000000000000000000000000000000000000000000000000000000000000002".from_hex().unwrap();
+
+		let log = Log {
+			data: data.into(),
+			topics: vec!["415557404d88a0c0b8e3b16967cafffc511213fd9c465c16832ee17ed57d7237".into()],
+			transaction_hash: Some("884edad9ce6fa2440d8a54cc123490eb96d2768479d49ff9c7366125a9424364".into()),
+			address: Address::zero(),
+			block_hash: None,
+			transaction_index: None,
+			log_index: None,
+			transaction_log_index: None,
+			log_type: None,
+			block_number: None,
+			removed: None,
+		};
+
+		// required signatures have been lowered after the signatures have been collected
+		let assignment = signatures_payload(&foreign, my_address, Some(1), log.clone()).unwrap().unwrap();
+		let expected_signatures: Vec<Bytes> = vec![
+			"1812d99600000000000000000000000000000000000000000000000000000000000000f00000000000000000000000000000000000000000000000000000000000000000".from_hex().unwrap().into(),
+		];
+		assert_eq!(expected_signatures, assignment.signature_payloads);
+
+		// home requires more signatures than have been collected
+		let assignment = signatures_payload(&foreign, my_address, Some(3), log).unwrap();
+		assert_eq!(None, assignment);
+	}
+
+	#[test]
+	fn test_signatures_payload_additional_signature() {
+		let foreign = foreign::ForeignBridge::default();
+		let my_address = "aff3454fce5edbc8cca8697c15331677e6ebcccc".into();
+
+		// third signature of the message collected with 2 signatures
+		let data = "000000000000000000000000aff3454fce5edbc8cca8697c15331677e6ebcccc00000000000000000000000000000000000000000000000000000000000000f00000000000000000000000000000000000000000000000000000000000000003".from_hex().unwrap();
+
+		let log = Log {
+			data: data.into(),
+			topics: foreign.events().collected_additional_signature().create_filter().topic0.into(),
+			transaction_hash: Some("884edad9ce6fa2440d8a54cc123490eb96d2768479d49ff9c7366125a9424364".into()),
+			address: Address::zero(),
+			block_hash: None,
+			transaction_index: None,
+			log_index: None,
+			transaction_log_index: None,
+			log_type: None,
+			block_number: None,
+			removed: None,
+		};
+
+		// home requires the signatures completed by this one
+		let assignment = signatures_payload(&foreign, my_address, Some(3), log.clone()).unwrap().unwrap();
+		let expected_message: Bytes = "490a32c600000000000000000000000000000000000000000000000000000000000000f0".from_hex().unwrap().into();
+		assert_eq!(expected_message, assignment.message_payload);
+		assert_eq!(3, assignment.signature_payloads.len());
+
+		// the withdraw has been relayed with `CollectedSignatures`
+		assert_eq!(None, signatures_payload(&foreign, my_address, Some(2), log.clone()).unwrap());
+		assert_eq!(None, signatures_payload(&foreign, my_address, None, log.clone()).unwrap());
+
+		// home requires even more signatures
+		assert_eq!(None, signatures_payload(&foreign, my_address, Some(4), log).unwrap());
+	}
 }
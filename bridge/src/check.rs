@@ -0,0 +1,317 @@
+//! Checks of a config which can't be done while parsing it, reporting all found problems at once.
+
+use std::fs;
+use futures::{future, Future};
+use tokio_core::reactor::Handle;
+use tokio_timer::Timer;
+use web3::Transport;
+use web3::types::{Address, Bytes, U256};
+use api;
+use app::{Connections, build_timer};
+use config::{Config, Node, RpcTransport, SignerConfig, Transactions};
+use error::Error;
+use keystore;
+
+/// Networks of real value, on which events are never relayed without confirmations: Ethereum, POA Core and xDai.
+const MAINNET_CHAIN_IDS: [u64; 3] = [1, 99, 100];
+
+/// Returns problems which can be found without connecting to the nodes.
+///
+/// Passwords of keystore accounts are read, so a `password_prompt` asks for them.
+pub fn check_config(config: &Config) -> Vec<String> {
+	let mut problems = check_transactions(&config.txs);
+	problems.extend(check_keystore_accounts(config));
+	problems
+}
+
+/// Returns problems with gas limits of transactions, which default to zero.
+pub fn check_transactions(txs: &Transactions) -> Vec<String> {
+	let transactions = vec![
+		("deposit_relay", &txs.deposit_relay),
+		("withdraw_confirm", &txs.withdraw_confirm),
+		("withdraw_relay", &txs.withdraw_relay),
+	];
+	#[cfg(feature = "deploy")]
+	let transactions = {
+		let mut transactions = transactions;
+		transactions.push(("home_deploy", &txs.home_deploy));
+		transactions.push(("foreign_deploy", &txs.foreign_deploy));
+		transactions
+	};
+
+	transactions.into_iter()
+		.filter_map(|(name, tx)| match tx.estimate_gas {
+			None if tx.gas == 0 => Some(format!("transactions.{}.gas is missing or zero, set it or estimate_gas", name)),
+			Some(ref estimation) if estimation.max_gas == 0 => Some(format!("transactions.{}.max_gas is zero", name)),
+			_ => None,
+		})
+		.collect()
+}
+
+/// Checks that accounts of keystore signers are in `keystore` and can be unlocked with their passwords.
+fn check_keystore_accounts(config: &Config) -> Vec<String> {
+	let mut accounts = Vec::new();
+	if config.home.signer == SignerConfig::Keystore {
+		accounts.push(("home", config.home.account, config.home.password()));
+	}
+	if config.foreign.signer == SignerConfig::Keystore {
+		accounts.push(("foreign", config.foreign.account, config.foreign.password()));
+	}
+	if let Some(ref validator) = config.validator {
+		if validator.signer == SignerConfig::Keystore {
+			accounts.push(("validator", validator.account, validator.password()));
+		}
+	}
+	if accounts.is_empty() {
+		return vec![];
+	}
+
+	let mut problems = Vec::new();
+	// a missing keystore directory is opened as an empty one
+	let store = fs::read_dir(&config.keystore)
+		.map_err(Error::from)
+		.and_then(|_| keystore::open(&config.keystore))
+		.and_then(|store| keystore::list_accounts(&store).map(|stored| (store, stored)));
+	let store = match store {
+		Ok(store) => Some(store),
+		Err(err) => {
+			problems.push(format!("Cannot read keystore {}: {}", config.keystore.display(), err));
+			None
+		},
+	};
+
+	for (name, account, password) in accounts {
+		let password = match password {
+			Ok(password) => password,
+			Err(err) => {
+				problems.push(format!("Cannot read the password of {}.account: {}", name, err));
+				continue;
+			},
+		};
+		let (store, stored) = match store {
+			Some((ref store, ref stored)) => (store, stored),
+			None => continue,
+		};
+		if !stored.contains(&account) {
+			problems.push(format!("{}.account {:?} is not in keystore {}", name, account, config.keystore.display()));
+			continue;
+		}
+		match keystore::test_password(store, account, &password) {
+			Ok(true) => (),
+			Ok(false) => problems.push(format!("{}.account {:?} can't be unlocked with its password", name, account)),
+			Err(err) => problems.push(format!("Cannot unlock {}.account {:?}: {}", name, account, err)),
+		}
+	}
+
+	problems
+}
+
+/// Queries both nodes with `eth_chainId` and `eth_getBalance` and checks that accounts of node signers are unlocked.
+///
+/// Failed requests are reported as problems, the returned future doesn't fail.
+pub fn check_nodes(config: &Config, handle: &Handle) -> Box<Future<Item = Vec<String>, Error = Error>> {
+	let timer = build_timer(config);
+	let checks = match config.home.rpc_transport() {
+		RpcTransport::Http => Connections::new_http(handle, &timer, &config.home, &config.foreign)
+			.map(|connections| check_connections(config, connections, &timer)),
+		RpcTransport::WebSocket => Connections::new_ws(handle, &config.home, &config.foreign)
+			.map(|connections| check_connections(config, connections, &timer)),
+		RpcTransport::Ipc => Connections::new_ipc(handle, &config.home, &config.foreign)
+			.map(|connections| check_connections(config, connections, &timer)),
+	};
+	match checks {
+		Ok(checks) => checks,
+		Err(err) => Box::new(future::ok(vec![describe(&err)])),
+	}
+}
+
+fn check_connections<T>(config: &Config, connections: Connections<T>, timer: &Timer) -> Box<Future<Item = Vec<String>, Error = Error>>
+	where T: Transport + Clone + 'static, T::Out: 'static {
+	let mut home_signed = Vec::new();
+	let mut foreign_signed = Vec::new();
+	if config.home.signer == SignerConfig::Node {
+		home_signed.push(config.home.account);
+	}
+	if config.foreign.signer == SignerConfig::Node {
+		foreign_signed.push(config.foreign.account);
+	}
+	if let Some(ref validator) = config.validator {
+		// the node signer of the validator is the foreign node
+		if validator.signer == SignerConfig::Node {
+			foreign_signed.push(validator.account);
+		}
+	}
+
+	let home = check_node("home", &config.home, home_signed, connections.home, timer);
+	let foreign = check_node("foreign", &config.foreign, foreign_signed, connections.foreign, timer);
+	Box::new(home.join(foreign).map(|(mut home, foreign)| {
+		home.extend(foreign);
+		home
+	}))
+}
+
+fn check_node<T>(name: &'static str, node: &Node, node_signed: Vec<Address>, transport: T, timer: &Timer) -> Box<Future<Item = Vec<String>, Error = Error>>
+	where T: Transport + Clone + 'static, T::Out: 'static {
+	let required_confirmations = node.required_confirmations;
+	let chain_id = timer.timeout(api::chain_id(transport.clone()), node.request_timeout)
+		.then(move |result| Ok::<_, Error>(match result {
+			Ok(chain_id) => check_network(name, required_confirmations, chain_id),
+			Err(err) => Some(format!("Cannot get eth_chainId of the {} node: {}", name, describe(&err))),
+		}));
+
+	let account = node.account;
+	let balance = timer.timeout(api::balance(transport.clone(), account, None), node.request_timeout)
+		.then(move |result| Ok::<_, Error>(result.err().map(|err| {
+			format!("Cannot get balance of {}.account {:?}: {}", name, account, describe(&err))
+		})));
+
+	// signing an empty message fails if the account is locked
+	let signatures = node_signed.into_iter()
+		.map(|account| timer.timeout(api::eth_sign(transport.clone(), account, Bytes(vec![])), node.request_timeout)
+			.then(move |result| Ok::<_, Error>(result.err().map(|err| {
+				format!("Account {:?} can't sign on the {} node, it may be locked: {}", account, name, describe(&err))
+			}))))
+		.collect::<Vec<_>>();
+
+	Box::new(chain_id.join3(balance, future::join_all(signatures))
+		.map(|(chain_id, balance, signatures)| chain_id.into_iter()
+			.chain(balance)
+			.chain(signatures.into_iter().filter_map(|problem| problem))
+			.collect()))
+}
+
+/// Checks the chain id returned by `eth_chainId`.
+///
+/// Network ids returned by `net_version` are not the chain ids of all networks, e.g. Ethereum Classic reports 1.
+fn check_network(name: &str, required_confirmations: usize, chain_id: U256) -> Option<String> {
+	if required_confirmations == 0 && MAINNET_CHAIN_IDS.iter().any(|id| chain_id == (*id).into()) {
+		Some(format!("{}.required_confirmations is 0 on mainnet {}, events of blocks which are reorganized away would be relayed", name, chain_id))
+	} else {
+		None
+	}
+}
+
+/// Returns the error with its causes.
+fn describe(err: &Error) -> String {
+	err.iter().map(|e| e.to_string()).collect::<Vec<_>>().join(": ")
+}
+
+#[cfg(test)]
+mod tests {
+	extern crate tempdir;
+
+	use std::fs;
+	use std::io::Write;
+	use std::os::unix::fs::PermissionsExt;
+	use std::path::Path;
+	use self::tempdir::TempDir;
+	use config::Config;
+	use keystore;
+	use super::{check_config, check_transactions, check_network};
+
+	fn write_password_file(path: &Path, password: &str) {
+		fs::File::create(path).unwrap().write_all(password.as_bytes()).unwrap();
+		fs::set_permissions(path, fs::Permissions::from_mode(0o600)).unwrap();
+	}
+
+	#[test]
+	fn test_check_transactions() {
+		let toml = r#"
+keystore = "/keys/"
+
+[home]
+account = "0x1B68Cb0B50181FC4006Ce572cF346e596E51818b"
+rpc_host = "127.0.0.1"
+signer = "node"
+
+[foreign]
+account = "0x0000000000000000000000000000000000000001"
+rpc_host = "127.0.0.1"
+signer = "node"
+
+[authorities]
+required_signatures = 2
+
+[transactions]
+deposit_relay = { gas = 0 }
+withdraw_relay = { estimate_gas = true, max_gas = 0 }
+"#;
+		let config = Config::load_from_str(toml, true).unwrap();
+		assert_eq!(vec![
+			"transactions.deposit_relay.gas is missing or zero, set it or estimate_gas".to_owned(),
+			"transactions.withdraw_confirm.gas is missing or zero, set it or estimate_gas".to_owned(),
+			"transactions.withdraw_relay.max_gas is zero".to_owned(),
+		], check_transactions(&config.txs));
+
+		let valid = toml
+			.replace("gas = 0", "gas = 100000")
+			.replace("max_gas = 0", "max_gas = 200000")
+			.replace("withdraw_relay", "withdraw_confirm = { gas = 100000 }\nwithdraw_relay");
+		let config = Config::load_from_str(&valid, true).unwrap();
+		assert!(check_config(&config).is_empty());
+	}
+
+	#[test]
+	fn test_check_keystore_accounts() {
+		let tempdir = TempDir::new("check_keystore_accounts").unwrap();
+		let keys = tempdir.path().join("keys");
+		let account = keystore::new_account(&keystore::create(&keys).unwrap(), "password").unwrap();
+		let home_password = tempdir.path().join("home_password");
+		write_password_file(&home_password, "password");
+		let foreign_password = tempdir.path().join("foreign_password");
+		let toml = format!(r#"
+keystore = "{}"
+
+[home]
+account = "{:?}"
+rpc_host = "127.0.0.1"
+password = "{}"
+
+[foreign]
+account = "{:?}"
+rpc_host = "127.0.0.1"
+password = "{}"
+
+[authorities]
+required_signatures = 2
+
+[transactions]
+deposit_relay = {{ gas = 100000 }}
+withdraw_confirm = {{ gas = 100000 }}
+withdraw_relay = {{ gas = 100000 }}
+"#, keys.display(), account, home_password.display(), account, foreign_password.display());
+
+		// all problems are reported at once
+		let config = Config::load_from_str(&toml, true).unwrap();
+		let problems = check_config(&config);
+		assert_eq!(1, problems.len());
+		assert!(problems[0].starts_with("Cannot read the password of foreign.account"));
+
+		write_password_file(&foreign_password, "wrong");
+		assert_eq!(vec![
+			format!("foreign.account {:?} can't be unlocked with its password", account),
+		], check_config(&config));
+
+		let other = toml.replacen(&format!("{:?}", account), "0x0000000000000000000000000000000000000001", 1);
+		let config = Config::load_from_str(&other, true).unwrap();
+		assert_eq!(vec![
+			format!("home.account 0x0000000000000000000000000000000000000001 is not in keystore {}", keys.display()),
+			format!("foreign.account {:?} can't be unlocked with its password", account),
+		], check_config(&config));
+
+		let missing_keystore = toml.replace(&keys.display().to_string(), "/nonexistent/keys");
+		let config = Config::load_from_str(&missing_keystore, true).unwrap();
+		let problems = check_config(&config);
+		assert_eq!(1, problems.len());
+		assert!(problems[0].starts_with("Cannot read keystore /nonexistent/keys"));
+	}
+
+	#[test]
+	fn test_check_network() {
+		assert_eq!(None, check_network("home", 12, 1.into()));
+		assert_eq!(None, check_network("home", 0, 77.into()));
+		assert!(check_network("home", 0, 1.into()).is_some());
+		assert!(check_network("foreign", 0, 99.into()).is_some());
+		assert!(check_network("foreign", 0, 100.into()).is_some());
+	}
+}
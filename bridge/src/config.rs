@@ -3,68 +3,228 @@ use std::fs;
 use std::io::Read;
 use std::str::FromStr;
 use std::time::Duration;
+use std::net::SocketAddr;
 #[cfg(feature = "deploy")]
 use rustc_hex::FromHex;
-use web3::types::Address;
+use web3::types::{Address, U256};
 #[cfg(feature = "deploy")]
 use web3::types::Bytes;
 use error::{ResultExt, Error, ErrorKind};
-use {toml};
+use {password, toml};
 
 const DEFAULT_POLL_INTERVAL: u64 = 1;
 const DEFAULT_CONFIRMATIONS: usize = 12;
 const DEFAULT_TIMEOUT: u64 = 3600;
 const DEFAULT_RPC_PORT: u16 = 8545;
+const DEFAULT_RPC_QUORUM: usize = 1;
 pub(crate) const DEFAULT_CONCURRENCY: usize = 64;
 const DEFAULT_GAS_PRICE_SPEED: GasPriceSpeed = GasPriceSpeed::Fast;
 const DEFAULT_GAS_PRICE_TIMEOUT_SECS: u64 = 10;
 const DEFAULT_GAS_PRICE_WEI: u64 = 15_000_000_000;
+const DEFAULT_GAS_PRICE_INTERVAL_SECS: u64 = 300;
+const DEFAULT_TRANSACTION_REPLACEMENT_TIMEOUT_SECS: u64 = 300;
+const DEFAULT_GAS_PRICE_BUMP_PERCENT: u64 = 20;
+const DEFAULT_DATABASE_BACKEND: DatabaseBackendKind = DatabaseBackendKind::Toml;
+const DEFAULT_RETRY_INITIAL_DELAY_SECS: u64 = 1;
+const DEFAULT_RETRY_MAX_DELAY_SECS: u64 = 60;
+const DEFAULT_RETRY_JITTER_PERCENT: u64 = 20;
+const DEFAULT_GAS_MULTIPLIER_PERCENT: u64 = 120;
+const DEFAULT_MAX_PRIORITY_FEE_PER_GAS: u64 = 1_000_000_000;
+const DEFAULT_BASE_FEE_MULTIPLIER_PERCENT: u64 = 200;
+const DEFAULT_FEE_HISTORY_BLOCKS: u64 = 10;
+const DEFAULT_FEE_HISTORY_PERCENTILE: u8 = 50;
 
 /// Application config.
 #[derive(Debug, PartialEq, Clone)]
 pub struct Config {
 	pub home: Node,
 	pub foreign: Node,
+	/// Authority key signing withdraw messages and deposits, `None` if they are signed by `foreign.account`.
+	pub validator: Option<Validator>,
 	pub authorities: Authorities,
 	pub txs: Transactions,
+	pub retry: RetryConfig,
+	pub bridge_mode: BridgeMode,
+	/// Tokens bridged in addition to `home_token_address` in `BridgeMode::ErcToErc`.
+	pub tokens: Vec<TokenPair>,
 	#[cfg(feature = "deploy")]
 	pub estimated_gas_cost_of_withdraw: u32,
 	pub keystore: PathBuf,
+	pub database_backend: DatabaseBackendKind,
+	/// Address of the HTTP listener exposing Prometheus metrics.
+	pub metrics_address: Option<SocketAddr>,
+	/// Address of the HTTP listener serving the admin JSON-RPC API.
+	pub admin_rpc_address: Option<SocketAddr>,
 }
 
 impl Config {
 	pub fn load<P: AsRef<Path>>(path: P, allow_insecure_rpc_endpoints: bool) -> Result<Config, Error> {
 		let mut file = fs::File::open(path).chain_err(|| "Cannot open config")?;
 		let mut buffer = String::new();
-		file.read_to_string(&mut buffer).expect("TODO");
+		file.read_to_string(&mut buffer).chain_err(|| "Cannot read config")?;
 		Self::load_from_str(&buffer, allow_insecure_rpc_endpoints)
 	}
 
-	fn load_from_str(s: &str, allow_insecure_rpc_endpoints: bool) -> Result<Config, Error> {
+	pub(crate) fn load_from_str(s: &str, allow_insecure_rpc_endpoints: bool) -> Result<Config, Error> {
 		let config: load::Config = toml::from_str(s).chain_err(|| "Cannot parse config")?;
 		Config::from_load_struct(config, allow_insecure_rpc_endpoints)
 	}
 
 	fn from_load_struct(config: load::Config, allow_insecure_rpc_endpoints: bool) -> Result<Config, Error> {
-		let result = Config {
-			home: Node::from_load_struct(config.home, allow_insecure_rpc_endpoints)?,
-			foreign: Node::from_load_struct(config.foreign, allow_insecure_rpc_endpoints)?,
-			authorities: Authorities {
-				#[cfg(feature = "deploy")]
-				accounts: config.authorities.accounts,
+		// sections are validated independently, so that problems of all of them are reported at once
+		let mut problems = Problems::default();
+
+		let database_backend = problems.check(match config.database_backend {
+			Some(ref s) => DatabaseBackendKind::from_str(s)
+				.map_err(|_| Error::from(ErrorKind::ConfigError(format!("Unknown database backend {}", s)))),
+			None => Ok(DEFAULT_DATABASE_BACKEND),
+		});
+
+		let metrics_address = problems.check(match config.metrics_address {
+			Some(ref s) => s.parse::<SocketAddr>().map(Some)
+				.map_err(|_| Error::from(ErrorKind::ConfigError(format!("Invalid metrics address {}", s)))),
+			None => Ok(None),
+		});
+
+		let admin_rpc_address = problems.check(match config.admin_rpc_address {
+			Some(ref s) => s.parse::<SocketAddr>().map(Some)
+				.map_err(|_| Error::from(ErrorKind::ConfigError(format!("Invalid admin rpc address {}", s)))),
+			None => Ok(None),
+		});
+
+		// the admin API can pause the bridge and has no authentication
+		if let Some(Some(address)) = admin_rpc_address {
+			if !address.ip().is_loopback() {
+				problems.push(format!("admin_rpc_address {} must be a loopback address", address));
+			}
+		}
+
+		let retry = problems.check(match config.retry {
+			Some(retry) => RetryConfig::from_load_struct(retry),
+			None => Ok(RetryConfig::default()),
+		});
+
+		let bridge_mode = problems.check(BridgeMode::from_load_struct(config.bridge_mode, config.home_token_address));
+
+		let tokens = config.tokens.unwrap_or_default()
+			.into_iter()
+			.map(|token| TokenPair {
+				home: token.home,
+				foreign: token.foreign,
+			})
+			.collect::<Vec<_>>();
+		match bridge_mode {
+			Some(BridgeMode::ErcToErc { home_token }) => {
+				for (index, token) in tokens.iter().enumerate() {
+					let duplicate = token.home == home_token || tokens[..index].iter()
+						.any(|other| other.home == token.home || other.foreign == token.foreign);
+					if duplicate {
+						problems.push(format!("Token {:?} is configured more than once", token.home));
+					}
+				}
+			},
+			_ if !tokens.is_empty() => problems.push("tokens are only supported in erc_to_erc bridge mode".into()),
+			_ => (),
+		}
+
+		let home = problems.check(Node::from_load_struct(config.home, allow_insecure_rpc_endpoints));
+		let foreign = problems.check(Node::from_load_struct(config.foreign, allow_insecure_rpc_endpoints));
+		if let (&Some(ref home), &Some(ref foreign)) = (&home, &foreign) {
+			if home.rpc_transport() != foreign.rpc_transport() {
+				problems.push("home and foreign nodes must be connected to over the same transport".into());
+			}
+		}
+
+		let validator = problems.check(match config.validator {
+			Some(validator) => Validator::from_load_struct(validator).map(Some),
+			None => Ok(None),
+		});
+
+		let txs = problems.check(match config.transactions {
+			Some(txs) => Transactions::from_load_struct(txs),
+			None => Ok(Transactions::default()),
+		});
+
+		if !problems.is_empty() {
+			return Err(problems.into_error());
+		}
+
+		match (home, foreign, validator, txs, retry, bridge_mode, database_backend, metrics_address, admin_rpc_address) {
+			(Some(home), Some(foreign), Some(validator), Some(txs), Some(retry), Some(bridge_mode), Some(database_backend), Some(metrics_address), Some(admin_rpc_address)) => Ok(Config {
+				home,
+				foreign,
+				validator,
+				authorities: Authorities {
+					#[cfg(feature = "deploy")]
+					accounts: config.authorities.accounts,
+					#[cfg(feature = "deploy")]
+					required_signatures: config.authorities.required_signatures,
+				},
+				txs,
+				retry,
+				bridge_mode,
+				tokens,
 				#[cfg(feature = "deploy")]
-				required_signatures: config.authorities.required_signatures,
+				estimated_gas_cost_of_withdraw: config.estimated_gas_cost_of_withdraw,
+				keystore: config.keystore,
+				database_backend,
+				metrics_address,
+				admin_rpc_address,
+			}),
+			_ => unreachable!("invalid values are reported as problems; qed"),
+		}
+	}
+}
+
+/// Problems found while validating a config.
+#[derive(Default)]
+struct Problems(Vec<String>);
+
+impl Problems {
+	/// Returns the value, or `None` if it is invalid and then remembers the problem.
+	fn check<T>(&mut self, result: Result<T, Error>) -> Option<T> {
+		match result {
+			Ok(value) => Some(value),
+			Err(err) => {
+				self.0.push(err.iter().map(|e| e.to_string()).collect::<Vec<_>>().join(": "));
+				None
 			},
-			txs: config.transactions.map(Transactions::from_load_struct).unwrap_or_default(),
-			#[cfg(feature = "deploy")]
-			estimated_gas_cost_of_withdraw: config.estimated_gas_cost_of_withdraw,
-			keystore: config.keystore,
-		};
+		}
+	}
 
-		Ok(result)
+	fn push(&mut self, problem: String) {
+		self.0.push(problem);
+	}
+
+	fn is_empty(&self) -> bool {
+		self.0.is_empty()
+	}
+
+	/// Returns an error listing all problems, one per line.
+	fn into_error(self) -> Error {
+		ErrorKind::ConfigError(self.0.join("\n")).into()
 	}
 }
 
+impl Config {
+	/// Returns the authority account signing withdraw messages and deposits.
+	pub fn validator_account(&self) -> Address {
+		self.validator.as_ref().map_or(self.foreign.account, |validator| validator.account)
+	}
+}
+
+fn is_websocket_url(url: &str) -> bool {
+	url.starts_with("ws://") || url.starts_with("wss://")
+}
+
+/// Transport used to connect to a node.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum RpcTransport {
+	Http,
+	WebSocket,
+	Ipc,
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub struct Node {
 	pub account: Address,
@@ -75,27 +235,59 @@ pub struct Node {
 	pub required_confirmations: usize,
 	pub rpc_host: String,
 	pub rpc_port: u16,
-	pub password: PathBuf,
+	/// Urls of additional RPC endpoints of the same chain used when `rpc_host` fails.
+	pub rpc_endpoints: Vec<String>,
+	/// Number of endpoints which have to agree on the best block and logs.
+	pub rpc_quorum: usize,
+	/// Path to the IPC socket of a node running on the same host. If set, it is used instead of `rpc_host`.
+	pub ipc_path: Option<PathBuf>,
+	/// Source of the password of `account` in the keystore, used only by the keystore signer.
+	pub password: Option<PasswordSource>,
+	/// Signer of transactions and messages sent by `account`.
+	pub signer: SignerConfig,
 	pub info: NodeInfo,
 	pub gas_price_oracle_url: Option<String>,
 	pub gas_price_speed: GasPriceSpeed,
+	/// Gas price sources queried along with `gas_price_oracle_url`, the median of their prices is used.
+	pub gas_price_oracles: Vec<GasPriceOracle>,
 	pub gas_price_timeout: Duration,
+	/// Time between gas price queries.
+	pub gas_price_interval: Duration,
+	/// Age after which the gas price is stale, `None` if it never is.
+	pub gas_price_max_age: Option<Duration>,
+	/// What is done with a stale gas price.
+	pub stale_gas_price: StaleGasPrice,
 	pub default_gas_price: u64,
+	/// Gas prices below this value are raised to it.
+	pub min_gas_price: Option<u64>,
+	/// Gas prices above this value are lowered to it.
+	pub max_gas_price: Option<u64>,
 	pub concurrent_http_requests: usize,
+	pub max_block_range: Option<u64>,
+	/// Time after which a transaction which is still not mined gets replaced with a higher gas price.
+	pub transaction_replacement_timeout: Duration,
+	/// Percentage by which the gas price of a replaced transaction is increased.
+	pub gas_price_bump_percent: u64,
+	/// Type of transactions sent to the node.
+	pub transaction_type: TransactionType,
 }
 
 use std::sync::{Arc, RwLock};
 use web3::types::U256;
+use database::PendingTransaction;
 
 #[derive(Debug, Clone)]
 pub struct NodeInfo {
     pub nonce: Arc<RwLock<U256>>,
+    /// Transactions sent to the node which have not been mined yet.
+    pub pending_transactions: Arc<RwLock<Vec<PendingTransaction>>>,
 }
 
 impl Default for NodeInfo {
 	fn default() -> Self {
 		NodeInfo {
 			nonce: Arc::new(RwLock::new(U256::zero())),
+			pending_transactions: Arc::new(RwLock::new(Vec::new())),
 		}
 	}
 }
@@ -111,7 +303,8 @@ impl Node {
 		let gas_price_oracle_url = node.gas_price_oracle_url.clone();
 
 		let gas_price_speed = match node.gas_price_speed {
-			Some(ref s) => GasPriceSpeed::from_str(s).unwrap(),
+			Some(ref s) => GasPriceSpeed::from_str(s)
+				.map_err(|_| ErrorKind::ConfigError(format!("Unknown gas_price_speed {}", s)))?,
 			None => DEFAULT_GAS_PRICE_SPEED
 		};
 
@@ -120,17 +313,116 @@ impl Node {
 			Duration::from_secs(n_secs)
 		};
 
+		let gas_price_oracles = node.gas_price_oracles.unwrap_or_default()
+			.into_iter()
+			.map(GasPriceOracle::from_load_struct)
+			.collect::<Result<Vec<_>, Error>>()?;
+
+		let gas_price_interval = match node.gas_price_interval.unwrap_or(DEFAULT_GAS_PRICE_INTERVAL_SECS) {
+			0 => return Err(ErrorKind::ConfigError("gas_price_interval must be greater than 0".into()).into()),
+			n_secs => Duration::from_secs(n_secs),
+		};
+
+		if let (Some(min), Some(max)) = (node.min_gas_price, node.max_gas_price) {
+			if min > max {
+				return Err(ErrorKind::ConfigError("min_gas_price must not be greater than max_gas_price".into()).into());
+			}
+		}
+
 		let default_gas_price = node.default_gas_price.unwrap_or(DEFAULT_GAS_PRICE_WEI);
 		let concurrent_http_requests = node.concurrent_http_requests.unwrap_or(DEFAULT_CONCURRENCY);
 
-		let rpc_host = node.rpc_host.unwrap();
+		let rpc_host = match (node.rpc_host, node.ipc_path.is_some()) {
+			(Some(rpc_host), _) => rpc_host,
+			(None, true) => String::new(),
+			(None, false) => return Err(ErrorKind::ConfigError("either rpc_host or ipc_path is required".into()).into()),
+		};
+
+		if node.max_block_range == Some(0) {
+			return Err(ErrorKind::ConfigError("max_block_range must be greater than 0".into()).into());
+		}
+
+		let gas_price_bump_percent = node.gas_price_bump_percent.unwrap_or(DEFAULT_GAS_PRICE_BUMP_PERCENT);
+		if gas_price_bump_percent == 0 {
+			return Err(ErrorKind::ConfigError("gas_price_bump_percent must be greater than 0".into()).into());
+		}
 
-		if !rpc_host.starts_with("https://") {
-			if !allow_insecure_rpc_endpoints {
-				return Err(ErrorKind::ConfigError(format!("RPC endpoints must use TLS, {} doesn't", rpc_host)).into());
-			} else {
-				warn!("RPC endpoints must use TLS, {} doesn't", rpc_host);
+		let transaction_type = match node.transaction_type.as_ref().map(String::as_str) {
+			None | Some("legacy") => {
+				if node.max_priority_fee_per_gas.is_some() || node.base_fee_multiplier_percent.is_some() {
+					return Err(ErrorKind::ConfigError("max_priority_fee_per_gas and base_fee_multiplier_percent are only used with eip1559 transactions".into()).into());
+				}
+				TransactionType::Legacy
+			},
+			Some("eip1559") => {
+				// the fees are derived from the base fee reported by the node
+				if gas_price_oracle_url.is_some() || !gas_price_oracles.is_empty() {
+					return Err(ErrorKind::ConfigError("gas_price_oracle_url and gas_price_oracles are not used with eip1559 transactions".into()).into());
+				}
+				let base_fee_multiplier_percent = node.base_fee_multiplier_percent.unwrap_or(DEFAULT_BASE_FEE_MULTIPLIER_PERCENT);
+				if base_fee_multiplier_percent < 100 {
+					return Err(ErrorKind::ConfigError("base_fee_multiplier_percent must not be less than 100".into()).into());
+				}
+				TransactionType::Eip1559 {
+					max_priority_fee_per_gas: node.max_priority_fee_per_gas.unwrap_or(DEFAULT_MAX_PRIORITY_FEE_PER_GAS),
+					base_fee_multiplier_percent,
+				}
+			},
+			Some(s) => return Err(ErrorKind::ConfigError(format!("Unknown transaction type {}", s)).into()),
+		};
+
+		let gas_price_max_age = node.gas_price_max_age.map(Duration::from_secs);
+		if let Some(max_age) = gas_price_max_age {
+			// only prices retrieved by the bridge get old
+			if transaction_type == TransactionType::Legacy && gas_price_oracle_url.is_none() && gas_price_oracles.is_empty() {
+				return Err(ErrorKind::ConfigError("gas_price_max_age requires gas_price_oracle_url, gas_price_oracles or eip1559 transactions".into()).into());
 			}
+			if transaction_type == TransactionType::Legacy && max_age <= gas_price_interval {
+				return Err(ErrorKind::ConfigError("gas_price_max_age must be greater than gas_price_interval".into()).into());
+			}
+		}
+
+		let stale_gas_price = match (node.stale_gas_price.as_ref().map(String::as_str), &transaction_type) {
+			(None, &TransactionType::Legacy) | (Some("default"), &TransactionType::Legacy) => StaleGasPrice::UseDefault,
+			// `default_gas_price` is not a valid fee of eip1559 transactions
+			(Some("default"), _) => return Err(ErrorKind::ConfigError("stale_gas_price = \"default\" is not supported with eip1559 transactions".into()).into()),
+			(None, _) | (Some("refuse"), _) => StaleGasPrice::Refuse,
+			(Some(s), _) => return Err(ErrorKind::ConfigError(format!("Unknown stale_gas_price {}", s)).into()),
+		};
+		if node.stale_gas_price.is_some() && gas_price_max_age.is_none() {
+			return Err(ErrorKind::ConfigError("stale_gas_price requires gas_price_max_age".into()).into());
+		}
+
+		let password = PasswordSource::from_load_struct(node.password, node.password_env, node.password_prompt, node.password_command)?;
+		let signer = SignerConfig::from_load_struct(node.signer, node.signer_url, password.as_ref())?;
+
+		let rpc_endpoints = node.rpc_endpoints.unwrap_or_default();
+		if node.ipc_path.is_some() && !rpc_endpoints.is_empty() {
+			return Err(ErrorKind::ConfigError("rpc_endpoints are not supported over IPC".into()).into());
+		}
+
+		// unix sockets are only reachable locally, so IPC doesn't need TLS
+		let urls: Vec<&String> = match node.ipc_path {
+			Some(_) => vec![],
+			None => ::std::iter::once(&rpc_host).chain(rpc_endpoints.iter()).collect(),
+		};
+		for url in urls {
+			if !url.starts_with("https://") && !url.starts_with("wss://") {
+				if !allow_insecure_rpc_endpoints {
+					return Err(ErrorKind::ConfigError(format!("RPC endpoints must use TLS, {} doesn't", url)).into());
+				} else {
+					warn!("RPC endpoints must use TLS, {} doesn't", url);
+				}
+			}
+		}
+
+		if is_websocket_url(&rpc_host) && !rpc_endpoints.is_empty() {
+			return Err(ErrorKind::ConfigError("rpc_endpoints are not supported over WebSocket".into()).into());
+		}
+
+		let rpc_quorum = node.rpc_quorum.unwrap_or(DEFAULT_RPC_QUORUM);
+		if rpc_quorum == 0 || rpc_quorum > rpc_endpoints.len() + 1 {
+			return Err(ErrorKind::ConfigError(format!("rpc_quorum must be between 1 and the number of RPC endpoints ({})", rpc_endpoints.len() + 1)).into());
 		}
 
 		let result = Node {
@@ -149,25 +441,175 @@ impl Node {
 			required_confirmations: node.required_confirmations.unwrap_or(DEFAULT_CONFIRMATIONS),
 			rpc_host,
 			rpc_port: node.rpc_port.unwrap_or(DEFAULT_RPC_PORT),
-			password: node.password,
+			rpc_endpoints,
+			rpc_quorum,
+			ipc_path: node.ipc_path,
+			password,
+			signer,
 			info: Default::default(),
 			gas_price_oracle_url,
 			gas_price_speed,
+			gas_price_oracles,
 			gas_price_timeout,
+			gas_price_interval,
+			gas_price_max_age,
+			stale_gas_price,
 			default_gas_price,
+			min_gas_price: node.min_gas_price,
+			max_gas_price: node.max_gas_price,
 			concurrent_http_requests,
+			max_block_range: node.max_block_range,
+			transaction_replacement_timeout: Duration::from_secs(node.transaction_replacement_timeout.unwrap_or(DEFAULT_TRANSACTION_REPLACEMENT_TIMEOUT_SECS)),
+			gas_price_bump_percent,
+			transaction_type,
 		};
 
 		Ok(result)
 	}
 
+	/// Returns url of the primary RPC endpoint.
+	pub fn rpc_url(&self) -> String {
+		format!("{}:{}", self.rpc_host, self.rpc_port)
+	}
+
+	/// Returns the transport used to connect to the node.
+	pub fn rpc_transport(&self) -> RpcTransport {
+		if self.ipc_path.is_some() {
+			RpcTransport::Ipc
+		} else if is_websocket_url(&self.rpc_host) {
+			RpcTransport::WebSocket
+		} else {
+			RpcTransport::Http
+		}
+	}
+
+	/// Returns urls of all RPC endpoints, starting with `rpc_host`.
+	pub fn rpc_urls(&self) -> Vec<String> {
+		::std::iter::once(self.rpc_url())
+			.chain(self.rpc_endpoints.iter().cloned())
+			.collect()
+	}
+
+	/// Returns the endpoint of the node for logs: the IPC socket, or the primary RPC url and the number of other endpoints.
+	pub fn description(&self) -> String {
+		match self.ipc_path {
+			Some(ref path) => format!("ipc:{}", path.display()),
+			None if self.rpc_endpoints.is_empty() => self.rpc_url(),
+			None => format!("{} (+{} endpoints)", self.rpc_url(), self.rpc_endpoints.len()),
+		}
+	}
+
+	pub fn password(&self) -> Result<String, Error> {
+		read_password(self.password.as_ref(), self.account)
+	}
+
+	/// Node loaded from a config with only `rpc_host = ""` and `password = "password"`,
+	/// fixtures override the fields they are about with the struct update syntax.
+	#[doc(hidden)]
+	pub fn default_for_tests() -> Node {
+		Node {
+			account: Address::default(),
+			#[cfg(feature = "deploy")]
+			contract: ContractConfig {
+				bin: Default::default(),
+			},
+			request_timeout: Duration::from_secs(DEFAULT_TIMEOUT),
+			poll_interval: Duration::from_secs(DEFAULT_POLL_INTERVAL),
+			required_confirmations: DEFAULT_CONFIRMATIONS,
+			rpc_host: "".into(),
+			rpc_port: DEFAULT_RPC_PORT,
+			rpc_endpoints: vec![],
+			rpc_quorum: DEFAULT_RPC_QUORUM,
+			ipc_path: None,
+			password: Some(PasswordSource::File("password".into())),
+			signer: SignerConfig::Keystore,
+			info: Default::default(),
+			gas_price_oracle_url: None,
+			gas_price_speed: DEFAULT_GAS_PRICE_SPEED,
+			gas_price_oracles: vec![],
+			gas_price_timeout: Duration::from_secs(DEFAULT_GAS_PRICE_TIMEOUT_SECS),
+			gas_price_interval: Duration::from_secs(DEFAULT_GAS_PRICE_INTERVAL_SECS),
+			gas_price_max_age: None,
+			stale_gas_price: StaleGasPrice::UseDefault,
+			default_gas_price: DEFAULT_GAS_PRICE_WEI,
+			min_gas_price: None,
+			max_gas_price: None,
+			concurrent_http_requests: DEFAULT_CONCURRENCY,
+			max_block_range: None,
+			transaction_replacement_timeout: Duration::from_secs(DEFAULT_TRANSACTION_REPLACEMENT_TIMEOUT_SECS),
+			gas_price_bump_percent: DEFAULT_GAS_PRICE_BUMP_PERCENT,
+			transaction_type: TransactionType::Legacy,
+		}
+	}
+}
+
+/// Authority key signing withdraw messages and deposits.
+///
+/// Signatures can be submitted by any account, so the key does not need to pay gas
+/// and can be kept in a more protected signer than the accounts sending transactions.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Validator {
+	pub account: Address,
+	/// Source of the password of `account` in the keystore, used only by the keystore signer.
+	pub password: Option<PasswordSource>,
+	/// Signer of withdraw messages, the node signer uses the foreign node.
+	pub signer: SignerConfig,
+}
+
+impl Validator {
+	fn from_load_struct(validator: load::Validator) -> Result<Self, Error> {
+		let password = PasswordSource::from_load_struct(validator.password, validator.password_env, validator.password_prompt, validator.password_command)?;
+		let signer = SignerConfig::from_load_struct(validator.signer, validator.signer_url, password.as_ref())?;
+		let result = Validator {
+			account: validator.account,
+			password,
+			signer,
+		};
+		Ok(result)
+	}
+
 	pub fn password(&self) -> Result<String, Error> {
-		use std::io::Read;
-		use std::fs;
-		let mut f = fs::File::open(&self.password)?;
-		let mut s = String::new();
-		f.read_to_string(&mut s)?;
-		Ok(s.split("\n").next().unwrap().to_string())
+		read_password(self.password.as_ref(), self.account)
+	}
+}
+
+fn read_password(source: Option<&PasswordSource>, account: Address) -> Result<String, Error> {
+	let source = source.ok_or_else(|| ErrorKind::ConfigError("password is not set".into()))?;
+	password::read_password(source, account)
+}
+
+/// Source of the password of a keystore account.
+#[derive(Clone, Debug, PartialEq)]
+pub enum PasswordSource {
+	/// First line of a file owned by the user running the bridge and inaccessible to anyone else.
+	File(PathBuf),
+	/// Value of an environment variable.
+	Env(String),
+	/// Typed in on the terminal the bridge is started from.
+	Prompt,
+	/// First line printed by a shell command.
+	Command(String),
+}
+
+impl PasswordSource {
+	fn from_load_struct(file: Option<PathBuf>, env: Option<String>, prompt: Option<bool>, command: Option<String>) -> Result<Option<Self>, Error> {
+		let mut sources = Vec::new();
+		if let Some(path) = file {
+			sources.push(PasswordSource::File(path));
+		}
+		if let Some(var) = env {
+			sources.push(PasswordSource::Env(var));
+		}
+		if prompt == Some(true) {
+			sources.push(PasswordSource::Prompt);
+		}
+		if let Some(command) = command {
+			sources.push(PasswordSource::Command(command));
+		}
+		if sources.len() > 1 {
+			return Err(ErrorKind::ConfigError("only one of password, password_env, password_prompt and password_command can be set".into()).into());
+		}
+		Ok(sources.pop())
 	}
 }
 
@@ -183,16 +625,25 @@ pub struct Transactions {
 }
 
 impl Transactions {
-	fn from_load_struct(cfg: load::Transactions) -> Self {
-		Transactions {
+	fn from_load_struct(cfg: load::Transactions) -> Result<Self, Error> {
+		let result = Transactions {
 			#[cfg(feature = "deploy")]
-			home_deploy: cfg.home_deploy.map(TransactionConfig::from_load_struct).unwrap_or_default(),
+			home_deploy: TransactionConfig::from_load_struct(cfg.home_deploy, "home_deploy")?,
 			#[cfg(feature = "deploy")]
-			foreign_deploy: cfg.foreign_deploy.map(TransactionConfig::from_load_struct).unwrap_or_default(),
-			deposit_relay: cfg.deposit_relay.map(TransactionConfig::from_load_struct).unwrap_or_default(),
-			withdraw_confirm: cfg.withdraw_confirm.map(TransactionConfig::from_load_struct).unwrap_or_default(),
-			withdraw_relay: cfg.withdraw_relay.map(TransactionConfig::from_load_struct).unwrap_or_default(),
+			foreign_deploy: TransactionConfig::from_load_struct(cfg.foreign_deploy, "foreign_deploy")?,
+			deposit_relay: TransactionConfig::from_load_struct(cfg.deposit_relay, "deposit_relay")?,
+			withdraw_confirm: TransactionConfig::from_load_struct(cfg.withdraw_confirm, "withdraw_confirm")?,
+			withdraw_relay: TransactionConfig::from_load_struct(cfg.withdraw_relay, "withdraw_relay")?,
+		};
+
+		#[cfg(feature = "deploy")]
+		{
+			if result.home_deploy.estimate_gas.is_some() || result.foreign_deploy.estimate_gas.is_some() {
+				return Err(ErrorKind::ConfigError("estimate_gas is not supported for deploy transactions".into()).into());
+			}
 		}
+
+		Ok(result)
 	}
 }
 
@@ -200,14 +651,106 @@ impl Transactions {
 pub struct TransactionConfig {
 	pub gas: u64,
 	pub gas_price: u64,
+	/// If set, gas of each transaction is estimated instead of using `gas`.
+	pub estimate_gas: Option<GasEstimation>,
 }
 
 impl TransactionConfig {
-	fn from_load_struct(cfg: load::TransactionConfig) -> Self {
-		TransactionConfig {
+	fn from_load_struct(cfg: Option<load::TransactionConfig>, name: &str) -> Result<Self, Error> {
+		let cfg = match cfg {
+			Some(cfg) => cfg,
+			None => return Ok(TransactionConfig::default()),
+		};
+
+		let estimate_gas = if cfg.estimate_gas.unwrap_or(false) {
+			let max_gas = cfg.max_gas
+				.ok_or_else(|| ErrorKind::ConfigError(format!("transactions.{}.max_gas is required with estimate_gas", name)))?;
+			Some(GasEstimation {
+				multiplier_percent: cfg.gas_multiplier_percent.unwrap_or(DEFAULT_GAS_MULTIPLIER_PERCENT),
+				max_gas,
+			})
+		} else if cfg.gas_multiplier_percent.is_some() || cfg.max_gas.is_some() {
+			return Err(ErrorKind::ConfigError(format!("transactions.{}.gas_multiplier_percent and max_gas require estimate_gas", name)).into());
+		} else {
+			None
+		};
+
+		Ok(TransactionConfig {
 			gas: cfg.gas.unwrap_or_default(),
 			gas_price: cfg.gas_price.unwrap_or_default(),
+			estimate_gas,
+		})
+	}
+}
+
+/// Gas of each transaction is estimated with `eth_estimateGas`.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct GasEstimation {
+	/// Percentage of the estimate used as the gas limit, e.g. `120` adds a 20% safety margin.
+	pub multiplier_percent: u64,
+	/// Transactions needing more gas are not sent.
+	pub max_gas: u64,
+}
+
+impl GasEstimation {
+	/// Returns gas limit of a transaction with given estimate.
+	///
+	/// Fails if the limit exceeds `max_gas`, the transaction would run out of gas with the capped one.
+	pub fn gas(&self, estimate: U256) -> Result<U256, Error> {
+		let gas = estimate * U256::from(self.multiplier_percent) / U256::from(100);
+		if gas > U256::from(self.max_gas) {
+			return Err(ErrorKind::GasEstimateTooHigh(gas, self.max_gas).into());
+		}
+		Ok(gas)
+	}
+}
+
+/// Retries of requests which failed because of a transient error, e.g. a timeout or a dropped connection.
+#[derive(Debug, PartialEq, Clone)]
+pub struct RetryConfig {
+	/// Delay before the first retry, doubled with each next one.
+	pub initial_delay: Duration,
+	pub max_delay: Duration,
+	/// Percentage by which delays are randomly changed, so that requests failed at once are not retried at once.
+	pub jitter_percent: u64,
+	/// Number of retries after which the bridge gives up, unlimited if `None`.
+	pub max_attempts: Option<u32>,
+}
+
+impl Default for RetryConfig {
+	fn default() -> Self {
+		RetryConfig {
+			initial_delay: Duration::from_secs(DEFAULT_RETRY_INITIAL_DELAY_SECS),
+			max_delay: Duration::from_secs(DEFAULT_RETRY_MAX_DELAY_SECS),
+			jitter_percent: DEFAULT_RETRY_JITTER_PERCENT,
+			max_attempts: None,
+		}
+	}
+}
+
+impl RetryConfig {
+	fn from_load_struct(cfg: load::RetryConfig) -> Result<Self, Error> {
+		let result = RetryConfig {
+			initial_delay: Duration::from_secs(cfg.initial_delay.unwrap_or(DEFAULT_RETRY_INITIAL_DELAY_SECS)),
+			max_delay: Duration::from_secs(cfg.max_delay.unwrap_or(DEFAULT_RETRY_MAX_DELAY_SECS)),
+			jitter_percent: cfg.jitter_percent.unwrap_or(DEFAULT_RETRY_JITTER_PERCENT),
+			max_attempts: cfg.max_attempts,
+		};
+
+		if result.initial_delay > result.max_delay {
+			return Err(ErrorKind::ConfigError("retry.initial_delay must not be greater than retry.max_delay".into()).into());
+		}
+
+		if result.jitter_percent > 100 {
+			return Err(ErrorKind::ConfigError("retry.jitter_percent must not be greater than 100".into()).into());
 		}
+
+		Ok(result)
+	}
+
+	/// Returns the longest delay before a retry, including jitter.
+	pub fn longest_delay(&self) -> Duration {
+		self.max_delay * (100 + self.jitter_percent as u32) / 100
 	}
 }
 
@@ -225,6 +768,165 @@ pub struct Authorities {
 	pub required_signatures: u32,
 }
 
+/// Type of transactions sent to a node.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TransactionType {
+	/// Transactions paying a single `gas_price`.
+	Legacy,
+	/// EIP-1559 transactions paying the base fee of the block and a tip.
+	Eip1559 {
+		/// Tip paid to the block producer, in WEI.
+		max_priority_fee_per_gas: u64,
+		/// `max_fee_per_gas` is the current base fee times this percentage plus the tip.
+		base_fee_multiplier_percent: u64,
+	},
+}
+
+/// Signer of transactions and messages sent to a node.
+#[derive(Clone, Debug, PartialEq)]
+pub enum SignerConfig {
+	/// Account of the local keystore, unlocked with the password read from its `PasswordSource`.
+	Keystore,
+	/// External signer, such as Clef, serving `account_signTransaction` and `account_signData`.
+	External {
+		url: String,
+	},
+	/// The node itself, with the account unlocked there, using `eth_signTransaction` and `eth_sign`.
+	Node,
+}
+
+impl SignerConfig {
+	fn from_load_struct(signer: Option<String>, signer_url: Option<String>, password: Option<&PasswordSource>) -> Result<Self, Error> {
+		if signer_url.is_some() && signer.as_ref().map(String::as_str) != Some("external") {
+			return Err(ErrorKind::ConfigError("signer_url is only used by the external signer".into()).into());
+		}
+		let result = match signer.as_ref().map(String::as_str) {
+			None | Some("keystore") => {
+				if password.is_none() {
+					return Err(ErrorKind::ConfigError("password, password_env, password_prompt or password_command is required by the keystore signer".into()).into());
+				}
+				SignerConfig::Keystore
+			},
+			Some("external") => SignerConfig::External {
+				url: signer_url
+					.ok_or_else(|| ErrorKind::ConfigError("signer_url is required by the external signer".into()))?,
+			},
+			Some("node") => SignerConfig::Node,
+			Some(s) => return Err(ErrorKind::ConfigError(format!("Unknown signer {}", s)).into()),
+		};
+		if password.is_some() && result != SignerConfig::Keystore {
+			return Err(ErrorKind::ConfigError("passwords are only used by the keystore signer".into()).into());
+		}
+		Ok(result)
+	}
+}
+
+/// What is done with a gas price older than `gas_price_max_age`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StaleGasPrice {
+	/// Transactions pay `default_gas_price`.
+	UseDefault,
+	/// No transactions are sent until the price is updated.
+	Refuse,
+}
+
+impl Default for StaleGasPrice {
+	fn default() -> Self {
+		StaleGasPrice::UseDefault
+	}
+}
+
+/// Source of gas prices.
+#[derive(Clone, Debug, PartialEq)]
+pub enum GasPriceOracle {
+	/// HTTP endpoint returning gas prices as JSON.
+	Http {
+		url: String,
+		format: GasPriceFormat,
+	},
+	/// `eth_gasPrice` of the node.
+	EthGasPrice,
+	/// Base fee of the next block plus a percentile of tips paid in recent blocks, read with `eth_feeHistory`.
+	FeeHistory {
+		/// Number of recent blocks.
+		blocks: u64,
+		/// Percentile of tips paid in each block.
+		percentile: u8,
+	},
+}
+
+impl GasPriceOracle {
+	fn from_load_struct(oracle: load::GasPriceOracle) -> Result<Self, Error> {
+		let result = match oracle.kind.as_str() {
+			"http" => {
+				let url = oracle.url.ok_or_else(|| ErrorKind::ConfigError("gas_price_oracles.url is required by http oracles".into()))?;
+				let format = match oracle.format.as_ref().map(String::as_str) {
+					None | Some("poa_network") => {
+						let speed = match oracle.speed {
+							Some(ref s) => GasPriceSpeed::from_str(s)
+								.map_err(|_| ErrorKind::ConfigError(format!("Unknown gas price speed {}", s)))?,
+							None => DEFAULT_GAS_PRICE_SPEED,
+						};
+						GasPriceFormat::PoaNetwork(speed)
+					},
+					Some("json_pointer") => GasPriceFormat::JsonPointer {
+						pointer: oracle.pointer.ok_or_else(|| ErrorKind::ConfigError("gas_price_oracles.pointer is required by json_pointer format".into()))?,
+						unit: match oracle.unit.as_ref().map(String::as_str) {
+							None | Some("gwei") => GasPriceUnit::Gwei,
+							Some("wei") => GasPriceUnit::Wei,
+							Some(s) => return Err(ErrorKind::ConfigError(format!("Unknown gas price unit {}", s)).into()),
+						},
+					},
+					Some(s) => return Err(ErrorKind::ConfigError(format!("Unknown gas price oracle format {}", s)).into()),
+				};
+				GasPriceOracle::Http { url, format }
+			},
+			"eth_gas_price" => GasPriceOracle::EthGasPrice,
+			"fee_history" => {
+				let blocks = oracle.blocks.unwrap_or(DEFAULT_FEE_HISTORY_BLOCKS);
+				let percentile = oracle.percentile.unwrap_or(DEFAULT_FEE_HISTORY_PERCENTILE);
+				if blocks == 0 {
+					return Err(ErrorKind::ConfigError("gas_price_oracles.blocks must be greater than 0".into()).into());
+				}
+				if percentile > 100 {
+					return Err(ErrorKind::ConfigError("gas_price_oracles.percentile must not be greater than 100".into()).into());
+				}
+				GasPriceOracle::FeeHistory { blocks, percentile }
+			},
+			s => return Err(ErrorKind::ConfigError(format!("Unknown gas price oracle kind {}", s)).into()),
+		};
+		Ok(result)
+	}
+}
+
+/// Shape of JSON returned by a gas price oracle.
+#[derive(Clone, Debug, PartialEq)]
+pub enum GasPriceFormat {
+	/// Prices in gwei by speed, e.g. `{"fast": 12.0}`, as returned by https://gasprice.poa.network
+	PoaNetwork(GasPriceSpeed),
+	/// Price at a JSON pointer, e.g. `/result/FastGasPrice`, given as a number or a numeric string.
+	JsonPointer {
+		pointer: String,
+		unit: GasPriceUnit,
+	},
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GasPriceUnit {
+	Wei,
+	Gwei,
+}
+
+impl GasPriceUnit {
+	/// Returns the number of WEI in the unit.
+	pub fn wei(&self) -> u64 {
+		match *self {
+			GasPriceUnit::Wei => 1,
+			GasPriceUnit::Gwei => 1_000_000_000,
+		}
+	}
+}
+
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub enum GasPriceSpeed {
     Instant,
@@ -259,6 +961,71 @@ impl GasPriceSpeed {
 	}
 }
 
+/// Storage used for the bridge database.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DatabaseBackendKind {
+	/// TOML file.
+	Toml,
+	/// Embedded key-value store.
+	KeyValue,
+}
+
+impl FromStr for DatabaseBackendKind {
+	type Err = ();
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let kind = match s {
+			"toml" => DatabaseBackendKind::Toml,
+			"kv" => DatabaseBackendKind::KeyValue,
+			_ => return Err(()),
+		};
+		Ok(kind)
+	}
+}
+
+/// Assets exchanged by the bridge.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BridgeMode {
+	/// Native coins deposited to `HomeBridge` are exchanged for ERC20 tokens on foreign.
+	NativeToErc,
+	/// ERC20 tokens locked in `HomeBridgeErc20` are exchanged for ERC20 tokens on foreign.
+	ErcToErc {
+		/// Address of the token locked on home.
+		home_token: Address,
+	},
+}
+
+impl BridgeMode {
+	fn from_load_struct(mode: Option<String>, home_token_address: Option<Address>) -> Result<Self, Error> {
+		match mode.as_ref().map(String::as_str) {
+			None | Some("native_to_erc") => {
+				if home_token_address.is_some() {
+					return Err(ErrorKind::ConfigError("home_token_address is only used in erc_to_erc bridge mode".into()).into());
+				}
+				Ok(BridgeMode::NativeToErc)
+			},
+			Some("erc_to_erc") => Ok(BridgeMode::ErcToErc {
+				home_token: home_token_address
+					.ok_or_else(|| ErrorKind::ConfigError("home_token_address is required in erc_to_erc bridge mode".into()))?,
+			}),
+			Some(s) => Err(ErrorKind::ConfigError(format!("Unknown bridge mode {}", s)).into()),
+		}
+	}
+}
+
+impl Default for BridgeMode {
+	fn default() -> Self {
+		BridgeMode::NativeToErc
+	}
+}
+
+/// Token locked on home together with the token minted for it on foreign.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TokenPair {
+	pub home: Address,
+	pub foreign: Address,
+}
+
 /// Some config values may not be defined in `toml` file, but they should be specified at runtime.
 /// `load` module separates `Config` representation in file with optional from the one used
 /// in application.
@@ -271,11 +1038,19 @@ mod load {
 	pub struct Config {
 		pub home: Node,
 		pub foreign: Node,
+		pub validator: Option<Validator>,
 		pub authorities: Authorities,
 		pub transactions: Option<Transactions>,
+		pub retry: Option<RetryConfig>,
+		pub bridge_mode: Option<String>,
+		pub home_token_address: Option<Address>,
+		pub tokens: Option<Vec<TokenPair>>,
 		#[cfg(feature = "deploy")]
 		pub estimated_gas_cost_of_withdraw: u32,
 		pub keystore: PathBuf,
+		pub database_backend: Option<String>,
+		pub metrics_address: Option<String>,
+		pub admin_rpc_address: Option<String>,
 	}
 
 	#[derive(Deserialize)]
@@ -289,12 +1064,57 @@ mod load {
 		pub required_confirmations: Option<usize>,
 		pub rpc_host: Option<String>,
 		pub rpc_port: Option<u16>,
-		pub password: PathBuf,
+		pub rpc_endpoints: Option<Vec<String>>,
+		pub rpc_quorum: Option<usize>,
+		pub ipc_path: Option<PathBuf>,
+		pub password: Option<PathBuf>,
+		pub password_env: Option<String>,
+		pub password_prompt: Option<bool>,
+		pub password_command: Option<String>,
+		pub signer: Option<String>,
+		pub signer_url: Option<String>,
 		pub gas_price_oracle_url: Option<String>,
 		pub gas_price_speed: Option<String>,
+		pub gas_price_oracles: Option<Vec<GasPriceOracle>>,
 		pub gas_price_timeout: Option<u64>,
+		pub gas_price_interval: Option<u64>,
+		pub gas_price_max_age: Option<u64>,
+		pub stale_gas_price: Option<String>,
 		pub default_gas_price: Option<u64>,
+		pub min_gas_price: Option<u64>,
+		pub max_gas_price: Option<u64>,
 		pub concurrent_http_requests: Option<usize>,
+		pub max_block_range: Option<u64>,
+		pub transaction_replacement_timeout: Option<u64>,
+		pub gas_price_bump_percent: Option<u64>,
+		pub transaction_type: Option<String>,
+		pub max_priority_fee_per_gas: Option<u64>,
+		pub base_fee_multiplier_percent: Option<u64>,
+	}
+
+	#[derive(Deserialize)]
+	#[serde(deny_unknown_fields)]
+	pub struct Validator {
+		pub account: Address,
+		pub password: Option<PathBuf>,
+		pub password_env: Option<String>,
+		pub password_prompt: Option<bool>,
+		pub password_command: Option<String>,
+		pub signer: Option<String>,
+		pub signer_url: Option<String>,
+	}
+
+	#[derive(Deserialize)]
+	#[serde(deny_unknown_fields)]
+	pub struct GasPriceOracle {
+		pub kind: String,
+		pub url: Option<String>,
+		pub format: Option<String>,
+		pub speed: Option<String>,
+		pub pointer: Option<String>,
+		pub unit: Option<String>,
+		pub blocks: Option<u64>,
+		pub percentile: Option<u8>,
 	}
 
 	#[derive(Deserialize)]
@@ -314,6 +1134,25 @@ mod load {
 	pub struct TransactionConfig {
 		pub gas: Option<u64>,
 		pub gas_price: Option<u64>,
+		pub estimate_gas: Option<bool>,
+		pub gas_multiplier_percent: Option<u64>,
+		pub max_gas: Option<u64>,
+	}
+
+	#[derive(Deserialize)]
+	#[serde(deny_unknown_fields)]
+	pub struct RetryConfig {
+		pub initial_delay: Option<u64>,
+		pub max_delay: Option<u64>,
+		pub jitter_percent: Option<u64>,
+		pub max_attempts: Option<u32>,
+	}
+
+	#[derive(Deserialize)]
+	#[serde(deny_unknown_fields)]
+	pub struct TokenPair {
+		pub home: Address,
+		pub foreign: Address,
 	}
 
 	#[derive(Deserialize)]
@@ -335,19 +1174,24 @@ mod load {
 #[cfg(test)]
 mod tests {
 	use std::time::Duration;
+	use web3::types::{Address, U256};
+	use error::{Error, ErrorKind};
 	#[cfg(feature = "deploy")]
 	use rustc_hex::FromHex;
-	use super::{Config, Node, Transactions, Authorities};
+	use super::{Config, Node, Transactions, TransactionConfig, GasEstimation, Authorities, DatabaseBackendKind, RetryConfig, RpcTransport, BridgeMode, TokenPair, TransactionType, GasPriceOracle, GasPriceFormat, GasPriceUnit, GasPriceSpeed, StaleGasPrice, SignerConfig, Validator, PasswordSource};
 	#[cfg(feature = "deploy")]
 	use super::ContractConfig;
-	#[cfg(feature = "deploy")]
-    use super::TransactionConfig;
-	use super::{DEFAULT_TIMEOUT, DEFAULT_CONCURRENCY, DEFAULT_GAS_PRICE_SPEED, DEFAULT_GAS_PRICE_TIMEOUT_SECS, DEFAULT_GAS_PRICE_WEI};
+	use super::{DEFAULT_GAS_PRICE_INTERVAL_SECS, DEFAULT_DATABASE_BACKEND, DEFAULT_GAS_MULTIPLIER_PERCENT, DEFAULT_BASE_FEE_MULTIPLIER_PERCENT};
 
 	#[test]
 	fn load_full_setup_from_str() {
 		let toml = r#"
 keystore = "/keys"
+database_backend = "kv"
+metrics_address = "127.0.0.1:9187"
+admin_rpc_address = "127.0.0.1:8645"
+bridge_mode = "erc_to_erc"
+home_token_address = "0x0000000000000000000000000000000000000002"
 
 [home]
 account = "0x1B68Cb0B50181FC4006Ce572cF346e596E51818b"
@@ -355,63 +1199,107 @@ poll_interval = 2
 required_confirmations = 100
 rpc_host = "127.0.0.1"
 rpc_port = 8545
+rpc_endpoints = ["http://127.0.0.1:8546", "http://127.0.0.1:8547"]
+rpc_quorum = 2
 password = "password"
+max_block_range = 1000
+transaction_replacement_timeout = 120
+gas_price_bump_percent = 15
 
 [foreign]
 account = "0x0000000000000000000000000000000000000001"
 rpc_host = "127.0.0.1"
 rpc_port = 8545
 password = "password"
+transaction_type = "eip1559"
+max_priority_fee_per_gas = 2000000000
+gas_price_max_age = 60
 
 [authorities]
 required_signatures = 2
 
 [transactions]
+deposit_relay = { gas = 300000, estimate_gas = true, max_gas = 500000 }
+
+[[tokens]]
+home = "0x0000000000000000000000000000000000000003"
+foreign = "0x0000000000000000000000000000000000000004"
+
+[retry]
+initial_delay = 2
+max_delay = 30
+max_attempts = 10
 "#;
 
 		#[allow(unused_mut)]
 		let mut expected = Config {
-			txs: Transactions::default(),
+			txs: Transactions {
+				deposit_relay: TransactionConfig {
+					gas: 300000,
+					gas_price: 0,
+					estimate_gas: Some(GasEstimation {
+						multiplier_percent: DEFAULT_GAS_MULTIPLIER_PERCENT,
+						max_gas: 500000,
+					}),
+				},
+				..Default::default()
+			},
+			retry: RetryConfig {
+				initial_delay: Duration::from_secs(2),
+				max_delay: Duration::from_secs(30),
+				jitter_percent: 20,
+				max_attempts: Some(10),
+			},
+			bridge_mode: BridgeMode::ErcToErc {
+				home_token: "0000000000000000000000000000000000000002".into(),
+			},
+			tokens: vec![TokenPair {
+				home: "0000000000000000000000000000000000000003".into(),
+				foreign: "0000000000000000000000000000000000000004".into(),
+			}],
 			home: Node {
 				account: "1B68Cb0B50181FC4006Ce572cF346e596E51818b".into(),
 				poll_interval: Duration::from_secs(2),
-				request_timeout: Duration::from_secs(DEFAULT_TIMEOUT),
 				required_confirmations: 100,
 				rpc_host: "127.0.0.1".into(),
-				rpc_port: 8545,
-				password: "password".into(),
-				info: Default::default(),
-				gas_price_oracle_url: None,
-				gas_price_speed: DEFAULT_GAS_PRICE_SPEED,
-				gas_price_timeout: Duration::from_secs(DEFAULT_GAS_PRICE_TIMEOUT_SECS),
-				default_gas_price: DEFAULT_GAS_PRICE_WEI,
-				concurrent_http_requests: DEFAULT_CONCURRENCY,
+				rpc_endpoints: vec!["http://127.0.0.1:8546".into(), "http://127.0.0.1:8547".into()],
+				rpc_quorum: 2,
+				max_block_range: Some(1000),
+				transaction_replacement_timeout: Duration::from_secs(120),
+				gas_price_bump_percent: 15,
+				..Node::default_for_tests()
 			},
 			foreign: Node {
 				account: "0000000000000000000000000000000000000001".into(),
-				poll_interval: Duration::from_secs(1),
-				request_timeout: Duration::from_secs(DEFAULT_TIMEOUT),
-				required_confirmations: 12,
 				rpc_host: "127.0.0.1".into(),
-				rpc_port: 8545,
-				password: "password".into(),
-				info: Default::default(),
-				gas_price_oracle_url: None,
-				gas_price_speed: DEFAULT_GAS_PRICE_SPEED,
-				gas_price_timeout: Duration::from_secs(DEFAULT_GAS_PRICE_TIMEOUT_SECS),
-				default_gas_price: DEFAULT_GAS_PRICE_WEI,
-				concurrent_http_requests: DEFAULT_CONCURRENCY,
+				gas_price_max_age: Some(Duration::from_secs(60)),
+				stale_gas_price: StaleGasPrice::Refuse,
+				transaction_type: TransactionType::Eip1559 {
+					max_priority_fee_per_gas: 2_000_000_000,
+					base_fee_multiplier_percent: DEFAULT_BASE_FEE_MULTIPLIER_PERCENT,
+				},
+				..Node::default_for_tests()
 			},
+			validator: None,
 			authorities: Authorities {
 				#[cfg(feature = "deploy")]
 				accounts: vec![
 				],
 			},
 			keystore: "/keys/".into(),
+			database_backend: DatabaseBackendKind::KeyValue,
+			metrics_address: Some("127.0.0.1:9187".parse().unwrap()),
+			admin_rpc_address: Some("127.0.0.1:8645".parse().unwrap()),
 		};
 
 		let config = Config::load_from_str(toml, true).unwrap();
 		assert_eq!(expected, config);
+		assert_eq!("127.0.0.1:8545 (+2 endpoints)", config.home.description());
+		assert_eq!("127.0.0.1:8545", config.foreign.description());
+
+		// the unauthenticated admin API is served only locally
+		let public_admin = toml.replace("admin_rpc_address = \"127.0.0.1:8645\"", "admin_rpc_address = \"0.0.0.0:8645\"");
+		assert!(Config::load_from_str(&public_admin, true).is_err());
 	}
 
 	#[test]
@@ -434,45 +1322,406 @@ required_signatures = 2
 "#;
 		let expected = Config {
 			txs: Transactions::default(),
+			retry: RetryConfig::default(),
+			bridge_mode: BridgeMode::NativeToErc,
+			tokens: vec![],
 			home: Node {
 				account: "1B68Cb0B50181FC4006Ce572cF346e596E51818b".into(),
-				poll_interval: Duration::from_secs(1),
-				request_timeout: Duration::from_secs(DEFAULT_TIMEOUT),
-				required_confirmations: 12,
-				rpc_host: "".into(),
-				rpc_port: 8545,
-				password: "password".into(),
-				info: Default::default(),
-				gas_price_oracle_url: None,
-				gas_price_speed: DEFAULT_GAS_PRICE_SPEED,
-				gas_price_timeout: Duration::from_secs(DEFAULT_GAS_PRICE_TIMEOUT_SECS),
-				default_gas_price: DEFAULT_GAS_PRICE_WEI,
-				concurrent_http_requests: DEFAULT_CONCURRENCY,
+				..Node::default_for_tests()
 			},
 			foreign: Node {
 				account: "0000000000000000000000000000000000000001".into(),
-				poll_interval: Duration::from_secs(1),
-				request_timeout: Duration::from_secs(DEFAULT_TIMEOUT),
-				required_confirmations: 12,
-				rpc_host: "".into(),
-				rpc_port: 8545,
-				password: "password".into(),
-				info: Default::default(),
-				gas_price_oracle_url: None,
-				gas_price_speed: DEFAULT_GAS_PRICE_SPEED,
-				gas_price_timeout: Duration::from_secs(DEFAULT_GAS_PRICE_TIMEOUT_SECS),
-				default_gas_price: DEFAULT_GAS_PRICE_WEI,
-				concurrent_http_requests: DEFAULT_CONCURRENCY,
+				..Node::default_for_tests()
 			},
+			validator: None,
 			authorities: Authorities {
 				#[cfg(feature = "deploy")]
 				accounts: vec![
 				],
 			},
 			keystore: "/keys/".into(),
+			database_backend: DEFAULT_DATABASE_BACKEND,
+			metrics_address: None,
+			admin_rpc_address: None,
 		};
 
 		let config = Config::load_from_str(toml, true).unwrap();
 		assert_eq!(expected, config);
 	}
+
+	#[test]
+	fn load_reports_all_problems() {
+		let toml = r#"
+keystore = "/keys/"
+database_backend = "unknown"
+admin_rpc_address = "0.0.0.0:8645"
+
+[home]
+account = "0x1B68Cb0B50181FC4006Ce572cF346e596E51818b"
+rpc_host = ""
+password = "password"
+
+[foreign]
+account = "0x0000000000000000000000000000000000000001"
+ipc_path = "/home/geth/geth.ipc"
+password = "password"
+
+[authorities]
+required_signatures = 2
+"#;
+		let err = Config::load_from_str(toml, true).unwrap_err();
+		assert_eq!(vec![
+			"Unknown database backend unknown",
+			"admin_rpc_address 0.0.0.0:8645 must be a loopback address",
+			"home and foreign nodes must be connected to over the same transport",
+		], err.to_string().lines().collect::<Vec<_>>());
+	}
+
+	#[test]
+	fn load_ipc_setup_from_str() {
+		let toml = r#"
+keystore = "/keys/"
+
+[home]
+account = "0x1B68Cb0B50181FC4006Ce572cF346e596E51818b"
+ipc_path = "/home/parity/jsonrpc.ipc"
+password = "password"
+
+[foreign]
+account = "0x0000000000000000000000000000000000000001"
+ipc_path = "/home/geth/geth.ipc"
+password = "password"
+
+[authorities]
+required_signatures = 2
+"#;
+
+		// TLS is not required for unix sockets
+		let config = Config::load_from_str(toml, false).unwrap();
+		assert_eq!(RpcTransport::Ipc, config.home.rpc_transport());
+		assert_eq!(Some("/home/parity/jsonrpc.ipc".into()), config.home.ipc_path);
+		assert_eq!(RpcTransport::Ipc, config.foreign.rpc_transport());
+		assert_eq!("ipc:/home/geth/geth.ipc", config.foreign.description());
+
+		let mixed = toml.replace("ipc_path = \"/home/geth/geth.ipc\"", "rpc_host = \"https://foreign.example.com\"");
+		assert!(Config::load_from_str(&mixed, false).is_err());
+	}
+
+	#[test]
+	fn load_gas_estimation_from_str() {
+		let toml = r#"
+keystore = "/keys/"
+
+[home]
+account = "0x1B68Cb0B50181FC4006Ce572cF346e596E51818b"
+rpc_host = "127.0.0.1"
+password = "password"
+
+[foreign]
+account = "0x0000000000000000000000000000000000000001"
+rpc_host = "127.0.0.1"
+password = "password"
+
+[authorities]
+required_signatures = 2
+
+[transactions]
+withdraw_relay = { estimate_gas = true, gas_multiplier_percent = 150, max_gas = 400000 }
+"#;
+
+		let config = Config::load_from_str(toml, true).unwrap();
+		let estimation = config.txs.withdraw_relay.estimate_gas.unwrap();
+		assert_eq!(U256::from(150000u64), estimation.gas(100000u64.into()).unwrap());
+		assert_eq!(U256::from(400000u64), estimation.gas(266667u64.into()).unwrap());
+		// transactions needing more than the cap are refused rather than sent underfunded
+		match estimation.gas(300000u64.into()) {
+			Err(Error(ErrorKind::GasEstimateTooHigh(gas, 400000), _)) => assert_eq!(U256::from(450000u64), gas),
+			other => panic!("unexpected result {:?}", other),
+		}
+
+		// the cap is required
+		let uncapped = toml.replace(", max_gas = 400000", "");
+		assert!(Config::load_from_str(&uncapped, true).is_err());
+
+		let not_estimated = toml.replace("estimate_gas = true, ", "");
+		assert!(Config::load_from_str(&not_estimated, true).is_err());
+	}
+
+	#[test]
+	fn load_transaction_type_from_str() {
+		let toml = r#"
+keystore = "/keys/"
+
+[home]
+account = "0x1B68Cb0B50181FC4006Ce572cF346e596E51818b"
+rpc_host = "127.0.0.1"
+password = "password"
+transaction_type = "eip1559"
+base_fee_multiplier_percent = 150
+
+[foreign]
+account = "0x0000000000000000000000000000000000000001"
+rpc_host = "127.0.0.1"
+password = "password"
+
+[authorities]
+required_signatures = 2
+"#;
+
+		let config = Config::load_from_str(toml, true).unwrap();
+		assert_eq!(TransactionType::Eip1559 { max_priority_fee_per_gas: 1_000_000_000, base_fee_multiplier_percent: 150 }, config.home.transaction_type);
+		assert_eq!(TransactionType::Legacy, config.foreign.transaction_type);
+
+		// fees are derived from the base fee, not queried from an oracle
+		let with_oracle = toml.replace("base_fee_multiplier_percent = 150", "gas_price_oracle_url = \"https://gasprice.poa.network\"");
+		assert!(Config::load_from_str(&with_oracle, true).is_err());
+
+		let legacy = toml.replace("\"eip1559\"", "\"legacy\"");
+		assert!(Config::load_from_str(&legacy, true).is_err());
+
+		let unknown = toml.replace("\"eip1559\"", "\"eip2930\"");
+		assert!(Config::load_from_str(&unknown, true).is_err());
+	}
+
+	#[test]
+	fn load_gas_price_oracles_from_str() {
+		let toml = r#"
+keystore = "/keys/"
+
+[home]
+account = "0x1B68Cb0B50181FC4006Ce572cF346e596E51818b"
+rpc_host = "127.0.0.1"
+password = "password"
+min_gas_price = 1000000000
+max_gas_price = 100000000000
+
+[[home.gas_price_oracles]]
+kind = "http"
+url = "https://gasprice.poa.network"
+speed = "instant"
+
+[[home.gas_price_oracles]]
+kind = "http"
+url = "https://api.example.com/gasoracle"
+format = "json_pointer"
+pointer = "/result/FastGasPrice"
+
+[[home.gas_price_oracles]]
+kind = "eth_gas_price"
+
+[[home.gas_price_oracles]]
+kind = "fee_history"
+percentile = 60
+
+[foreign]
+account = "0x0000000000000000000000000000000000000001"
+rpc_host = "127.0.0.1"
+password = "password"
+
+[authorities]
+required_signatures = 2
+"#;
+
+		let config = Config::load_from_str(toml, true).unwrap();
+		assert_eq!(vec![
+			GasPriceOracle::Http {
+				url: "https://gasprice.poa.network".into(),
+				format: GasPriceFormat::PoaNetwork(GasPriceSpeed::Instant),
+			},
+			GasPriceOracle::Http {
+				url: "https://api.example.com/gasoracle".into(),
+				format: GasPriceFormat::JsonPointer { pointer: "/result/FastGasPrice".into(), unit: GasPriceUnit::Gwei },
+			},
+			GasPriceOracle::EthGasPrice,
+			GasPriceOracle::FeeHistory { blocks: 10, percentile: 60 },
+		], config.home.gas_price_oracles);
+		assert_eq!(Some(1_000_000_000), config.home.min_gas_price);
+		assert_eq!(Some(100_000_000_000), config.home.max_gas_price);
+		assert!(config.foreign.gas_price_oracles.is_empty());
+
+		let inverted_bounds = toml.replace("min_gas_price = 1000000000", "min_gas_price = 200000000000");
+		assert!(Config::load_from_str(&inverted_bounds, true).is_err());
+
+		let no_pointer = toml.replace("pointer = \"/result/FastGasPrice\"", "");
+		assert!(Config::load_from_str(&no_pointer, true).is_err());
+
+		let unknown = toml.replace("\"eth_gas_price\"", "\"eth_maxPriorityFeePerGas\"");
+		assert!(Config::load_from_str(&unknown, true).is_err());
+
+		let unknown_speed = toml.replace("max_gas_price = 100000000000", "max_gas_price = 100000000000\ngas_price_speed = \"fastest\"");
+		assert!(Config::load_from_str(&unknown_speed, true).is_err());
+	}
+
+	#[test]
+	fn load_gas_price_age_from_str() {
+		let toml = r#"
+keystore = "/keys/"
+
+[home]
+account = "0x1B68Cb0B50181FC4006Ce572cF346e596E51818b"
+rpc_host = "127.0.0.1"
+password = "password"
+gas_price_oracle_url = "https://gasprice.poa.network"
+gas_price_interval = 60
+gas_price_max_age = 600
+stale_gas_price = "refuse"
+
+[foreign]
+account = "0x0000000000000000000000000000000000000001"
+rpc_host = "127.0.0.1"
+password = "password"
+
+[authorities]
+required_signatures = 2
+"#;
+
+		let config = Config::load_from_str(toml, true).unwrap();
+		assert_eq!(Duration::from_secs(60), config.home.gas_price_interval);
+		assert_eq!(Some(Duration::from_secs(600)), config.home.gas_price_max_age);
+		assert_eq!(StaleGasPrice::Refuse, config.home.stale_gas_price);
+		assert_eq!(Duration::from_secs(DEFAULT_GAS_PRICE_INTERVAL_SECS), config.foreign.gas_price_interval);
+		assert_eq!(None, config.foreign.gas_price_max_age);
+
+		let use_default = toml.replace("\"refuse\"", "\"default\"");
+		assert_eq!(StaleGasPrice::UseDefault, Config::load_from_str(&use_default, true).unwrap().home.stale_gas_price);
+
+		// the price would get stale between two updates
+		let too_young = toml.replace("gas_price_max_age = 600", "gas_price_max_age = 60");
+		assert!(Config::load_from_str(&too_young, true).is_err());
+
+		// the default price never gets old
+		let no_oracle = toml.replace("gas_price_oracle_url = \"https://gasprice.poa.network\"", "");
+		assert!(Config::load_from_str(&no_oracle, true).is_err());
+
+		let no_max_age = toml.replace("gas_price_max_age = 600", "");
+		assert!(Config::load_from_str(&no_max_age, true).is_err());
+
+		let no_interval = toml.replace("gas_price_interval = 60", "gas_price_interval = 0");
+		assert!(Config::load_from_str(&no_interval, true).is_err());
+	}
+
+	#[test]
+	fn load_signer_from_str() {
+		let toml = r#"
+keystore = "/keys/"
+
+[home]
+account = "0x1B68Cb0B50181FC4006Ce572cF346e596E51818b"
+rpc_host = "127.0.0.1"
+signer = "external"
+signer_url = "http://127.0.0.1:8550"
+
+[foreign]
+account = "0x0000000000000000000000000000000000000001"
+rpc_host = "127.0.0.1"
+password = "password"
+
+[authorities]
+required_signatures = 2
+"#;
+
+		let config = Config::load_from_str(toml, true).unwrap();
+		assert_eq!(SignerConfig::External { url: "http://127.0.0.1:8550".into() }, config.home.signer);
+		assert_eq!(None, config.home.password);
+		assert_eq!(SignerConfig::Keystore, config.foreign.signer);
+
+		let node = toml.replace("signer = \"external\"\nsigner_url = \"http://127.0.0.1:8550\"", "signer = \"node\"");
+		assert_eq!(SignerConfig::Node, Config::load_from_str(&node, true).unwrap().home.signer);
+
+		let no_url = toml.replace("signer_url = \"http://127.0.0.1:8550\"", "");
+		assert!(Config::load_from_str(&no_url, true).is_err());
+
+		// keys of remote signers are not unlocked by the bridge
+		let with_password = toml.replace("signer = \"external\"", "signer = \"external\"\npassword = \"password\"");
+		assert!(Config::load_from_str(&with_password, true).is_err());
+
+		let keystore = toml.replace("signer = \"external\"\nsigner_url = \"http://127.0.0.1:8550\"", "signer = \"keystore\"");
+		assert!(Config::load_from_str(&keystore, true).is_err());
+
+		let keystore_with_url = toml.replace("signer = \"external\"", "password = \"password\"");
+		assert!(Config::load_from_str(&keystore_with_url, true).is_err());
+
+		let unknown = toml.replace("\"external\"", "\"ledger\"");
+		assert!(Config::load_from_str(&unknown, true).is_err());
+	}
+
+	#[test]
+	fn load_validator_from_str() {
+		let toml = r#"
+keystore = "/keys/"
+
+[home]
+account = "0x1B68Cb0B50181FC4006Ce572cF346e596E51818b"
+rpc_host = "127.0.0.1"
+password = "password"
+
+[foreign]
+account = "0x0000000000000000000000000000000000000001"
+rpc_host = "127.0.0.1"
+password = "password"
+
+[validator]
+account = "0x0000000000000000000000000000000000000002"
+signer = "external"
+signer_url = "http://127.0.0.1:8550"
+
+[authorities]
+required_signatures = 2
+"#;
+
+		let config = Config::load_from_str(toml, true).unwrap();
+		assert_eq!(Some(Validator {
+			account: "0000000000000000000000000000000000000002".into(),
+			password: None,
+			signer: SignerConfig::External { url: "http://127.0.0.1:8550".into() },
+		}), config.validator);
+		assert_eq!(Address::from("0000000000000000000000000000000000000002"), config.validator_account());
+
+		// withdraws are signed by the foreign account by default
+		let no_validator = toml.replace("[validator]\naccount = \"0x0000000000000000000000000000000000000002\"\nsigner = \"external\"\nsigner_url = \"http://127.0.0.1:8550\"\n", "");
+		let config = Config::load_from_str(&no_validator, true).unwrap();
+		assert_eq!(None, config.validator);
+		assert_eq!(config.foreign.account, config.validator_account());
+
+		let no_url = toml.replace("signer_url = \"http://127.0.0.1:8550\"", "");
+		assert!(Config::load_from_str(&no_url, true).is_err());
+	}
+	#[test]
+	fn load_password_sources_from_str() {
+		let toml = r#"
+keystore = "/keys/"
+
+[home]
+account = "0x1B68Cb0B50181FC4006Ce572cF346e596E51818b"
+rpc_host = "127.0.0.1"
+password_env = "HOME_PASSWORD"
+
+[foreign]
+account = "0x0000000000000000000000000000000000000001"
+rpc_host = "127.0.0.1"
+password_command = "pass show bridge/foreign"
+
+[validator]
+account = "0x0000000000000000000000000000000000000002"
+password_prompt = true
+
+[authorities]
+required_signatures = 2
+"#;
+
+		let config = Config::load_from_str(toml, true).unwrap();
+		assert_eq!(Some(PasswordSource::Env("HOME_PASSWORD".into())), config.home.password);
+		assert_eq!(Some(PasswordSource::Command("pass show bridge/foreign".into())), config.foreign.password);
+		assert_eq!(Some(PasswordSource::Prompt), config.validator.unwrap().password);
+
+		let two_sources = toml.replace("password_env = \"HOME_PASSWORD\"", "password_env = \"HOME_PASSWORD\"\npassword = \"password\"");
+		assert!(Config::load_from_str(&two_sources, true).is_err());
+
+		// the keystore signer needs a password from somewhere
+		let no_prompt = toml.replace("password_prompt = true", "password_prompt = false");
+		assert!(Config::load_from_str(&no_prompt, true).is_err());
+
+		let node_with_command = toml.replace("password_command", "signer = \"node\"\npassword_command");
+		assert!(Config::load_from_str(&node_with_command, true).is_err());
+	}
 }
@@ -1,3 +1,7 @@
 use_contract!(home, "HomeBridge", "../compiled_contracts/HomeBridge.abi");
+use_contract!(home_erc20, "HomeBridgeErc20", "../compiled_contracts/HomeBridgeErc20.abi");
 use_contract!(foreign, "ForeignBridge", "../compiled_contracts/ForeignBridge.abi");
 use_contract!(erc20, "ERC20", "../compiled_contracts/ERC20.abi");
+use_contract!(authorities, "BridgeAuthorities", "../compiled_contracts/BridgeAuthorities.abi");
+use_contract!(home_gas_limits, "HomeBridgeGasConsumptionLimitsStorage", "../compiled_contracts/HomeBridgeGasConsumptionLimitsStorage.abi");
+use_contract!(foreign_gas_limits, "ForeignBridgeGasConsumptionLimitsStorage", "../compiled_contracts/ForeignBridgeGasConsumptionLimitsStorage.abi");
@@ -0,0 +1,39 @@
+use std::path::{Path, PathBuf};
+use error::Error;
+use super::{Database, DatabaseBackend, TransactionRecord};
+
+/// Stores the bridge state in a TOML file.
+///
+/// Records of sent transactions are only logged.
+pub struct FileBackend {
+	path: PathBuf,
+}
+
+impl FileBackend {
+	pub fn new<P: AsRef<Path>>(path: P) -> Self {
+		FileBackend {
+			path: path.as_ref().to_path_buf(),
+		}
+	}
+}
+
+impl DatabaseBackend for FileBackend {
+	fn load(&self) -> Result<Database, Error> {
+		Database::load(&self.path)
+	}
+
+	fn save(&mut self, database: &Database) -> Result<(), Error> {
+		database.save(&self.path)
+	}
+
+	fn record_transactions(&mut self, records: &[TransactionRecord]) -> Result<(), Error> {
+		for record in records {
+			info!("sent {:?} transaction {} for {}", record.kind, record.transaction_hash, record.source_transaction_hash);
+		}
+		Ok(())
+	}
+
+	fn transactions(&self) -> Result<Vec<TransactionRecord>, Error> {
+		Ok(vec![])
+	}
+}
@@ -0,0 +1,104 @@
+use std::path::Path;
+use serde_json;
+use kvdb::KeyValueDB;
+use kvdb_rocksdb::{self, DatabaseConfig};
+use error::{Error, ErrorKind, ResultExt};
+use super::{Database, DatabaseBackend, TransactionRecord};
+
+/// Column holding the bridge state.
+const COL_STATE: Option<u32> = Some(0);
+/// Column holding records of sent transactions keyed by their hashes.
+const COL_TRANSACTIONS: Option<u32> = Some(1);
+/// Number of columns of the database.
+const NUM_COLUMNS: Option<u32> = Some(2);
+
+/// Key under which the bridge state is stored.
+const STATE_KEY: &[u8] = b"state";
+
+/// Stores the bridge state and records of sent transactions in an embedded key-value store.
+pub struct KeyValueBackend {
+	db: kvdb_rocksdb::Database,
+}
+
+impl KeyValueBackend {
+	pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+		let path = path.as_ref().to_str().ok_or("Database path is not valid UTF-8")?;
+		let db = kvdb_rocksdb::Database::open(&DatabaseConfig::with_columns(NUM_COLUMNS), path).chain_err(|| "Cannot open database")?;
+		let result = KeyValueBackend {
+			db,
+		};
+		Ok(result)
+	}
+}
+
+impl DatabaseBackend for KeyValueBackend {
+	fn load(&self) -> Result<Database, Error> {
+		match self.db.get(COL_STATE, STATE_KEY).chain_err(|| "Cannot read database")? {
+			Some(state) => serde_json::from_slice(&state).chain_err(|| "Cannot parse database"),
+			None => Err(ErrorKind::MissingFile(format!("{:?}", STATE_KEY)).into()),
+		}
+	}
+
+	fn save(&mut self, database: &Database) -> Result<(), Error> {
+		let mut transaction = self.db.transaction();
+		transaction.put(COL_STATE, STATE_KEY, &serde_json::to_vec(database)?);
+		self.db.write(transaction).chain_err(|| "Cannot write database")?;
+		Ok(())
+	}
+
+	fn record_transactions(&mut self, records: &[TransactionRecord]) -> Result<(), Error> {
+		let mut transaction = self.db.transaction();
+		for record in records {
+			transaction.put(COL_TRANSACTIONS, &record.transaction_hash.0[..], &serde_json::to_vec(record)?);
+		}
+		self.db.write(transaction).chain_err(|| "Cannot write transactions")?;
+		Ok(())
+	}
+
+	fn transactions(&self) -> Result<Vec<TransactionRecord>, Error> {
+		// unlike `KeyValueDB::iter`, the inherent `Database::iter` returns an `Option`
+		KeyValueDB::iter(&self.db, COL_TRANSACTIONS)
+			.map(|(_, value)| serde_json::from_slice(&value).map_err(Into::into))
+			.collect()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	extern crate tempdir;
+	use self::tempdir::TempDir;
+	use database::{Database, DatabaseBackend, TransactionKind, TransactionRecord};
+	use error::ErrorKind;
+	use super::KeyValueBackend;
+
+	#[test]
+	fn key_value_backend_roundtrip() {
+		let tempdir = TempDir::new("key_value_backend_roundtrip").unwrap();
+		let path = tempdir.path().join("db");
+
+		let mut backend = KeyValueBackend::open(&path).unwrap();
+		match backend.load().map_err(ErrorKind::from) {
+			Err(ErrorKind::MissingFile(_)) => (),
+			other => panic!("expected missing database, got {:?}", other),
+		}
+
+		let database = Database {
+			checked_deposit_relay: 10,
+			checked_withdraw_confirm: 11,
+			..Default::default()
+		};
+		let record = TransactionRecord {
+			kind: TransactionKind::DepositRelay,
+			source_transaction_hash: 1.into(),
+			transaction_hash: 2.into(),
+			block: 9,
+		};
+		backend.save(&database).unwrap();
+		backend.record_transactions(&[record.clone()]).unwrap();
+		drop(backend);
+
+		let backend = KeyValueBackend::open(&path).unwrap();
+		assert_eq!(database, backend.load().unwrap());
+		assert_eq!(vec![record], backend.transactions().unwrap());
+	}
+}
@@ -0,0 +1,362 @@
+mod file;
+mod kv;
+
+use std::path::{Path, PathBuf};
+use std::{io, str, fs, fmt};
+use std::io::{Read, Write};
+use web3::types::{Address, H256, U256, Bytes};
+use toml;
+use config::DatabaseBackendKind;
+use error::{Error, ResultExt, ErrorKind};
+
+pub use self::file::FileBackend;
+pub use self::kv::KeyValueBackend;
+
+/// Application "database".
+#[derive(Debug, PartialEq, Deserialize, Serialize, Default, Clone)]
+pub struct Database {
+	/// Address of home contract.
+	pub home_contract_address: Address,
+	/// Address of foreign contract.
+	pub foreign_contract_address: Address,
+	/// Number of block at which home contract has been deployed.
+	pub home_deploy: Option<u64>,
+	/// Number of block at which foreign contract has been deployed.
+	pub foreign_deploy: Option<u64>,
+	/// Number of last block which has been checked for deposit relays.
+	pub checked_deposit_relay: u64,
+	/// Number of last block which has been checked for withdraw relays.
+	pub checked_withdraw_relay: u64,
+	/// Number of last block which has been checked for withdraw confirms.
+	pub checked_withdraw_confirm: u64,
+	/// Transactions sent to home which have not been mined yet.
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub home_pending_transactions: Vec<PendingTransaction>,
+	/// Transactions sent to foreign which have not been mined yet.
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub foreign_pending_transactions: Vec<PendingTransaction>,
+}
+
+impl str::FromStr for Database {
+	type Err = Error;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		toml::from_str(s).chain_err(|| "Cannot parse database")
+	}
+}
+
+impl fmt::Display for Database {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.write_str(&toml::to_string(self).expect("serialization can't fail; qed"))
+	}
+}
+
+/// Kind of a transaction sent by this authority.
+#[derive(Debug, PartialEq, Deserialize, Serialize, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum TransactionKind {
+	/// `ForeignBridge.deposit` call relaying a deposit made on home.
+	DepositRelay,
+	/// `ForeignBridge.submitSignature` call confirming a withdraw made on foreign.
+	WithdrawConfirm,
+	/// `HomeBridge.withdraw` call relaying collected signatures.
+	WithdrawRelay,
+}
+
+/// Record of a transaction sent by this authority.
+#[derive(Debug, PartialEq, Deserialize, Serialize, Clone)]
+pub struct TransactionRecord {
+	pub kind: TransactionKind,
+	/// Hash of the transaction which emitted the relayed event: deposit on home or withdraw on foreign.
+	pub source_transaction_hash: H256,
+	/// Hash of the transaction sent by this authority.
+	pub transaction_hash: H256,
+	/// Number of the block containing the relayed event.
+	pub block: u64,
+}
+
+/// Transaction sent by this authority which has not been mined yet.
+#[derive(Debug, PartialEq, Deserialize, Serialize, Clone)]
+pub struct PendingTransaction {
+	pub nonce: U256,
+	/// Hash of the most recently sent version of the transaction.
+	pub hash: H256,
+	/// Hashes of the versions replaced by the current one. Any of them may still get mined.
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub replaced_hashes: Vec<H256>,
+	/// Signed transaction, as sent.
+	pub raw: Bytes,
+	/// Gas price, or `max_fee_per_gas` of an EIP-1559 transaction.
+	pub gas_price: U256,
+	/// Tip of an EIP-1559 transaction, `None` for legacy transactions.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub max_priority_fee_per_gas: Option<U256>,
+	pub gas: U256,
+	pub value: U256,
+	pub data: Bytes,
+	/// Recipient, `None` for contract creation.
+	pub to: Option<Address>,
+	/// Unix timestamp (in seconds) at which the current version has been sent.
+	pub sent_at: u64,
+}
+
+impl PendingTransaction {
+	/// Returns hashes of all sent versions of the transaction.
+	pub fn hashes(&self) -> Vec<H256> {
+		let mut hashes = self.replaced_hashes.clone();
+		hashes.push(self.hash);
+		hashes
+	}
+}
+
+/// Storage of the bridge state.
+pub trait DatabaseBackend {
+	/// Loads the stored state. Fails with `ErrorKind::MissingFile` if nothing has been stored yet.
+	fn load(&self) -> Result<Database, Error>;
+
+	/// Stores the state.
+	fn save(&mut self, database: &Database) -> Result<(), Error>;
+
+	/// Stores records of transactions sent by this authority.
+	fn record_transactions(&mut self, records: &[TransactionRecord]) -> Result<(), Error>;
+
+	/// Returns all stored records of transactions sent by this authority.
+	fn transactions(&self) -> Result<Vec<TransactionRecord>, Error>;
+}
+
+/// Opens database backend of given kind at `path`.
+pub fn open<P: AsRef<Path>>(kind: DatabaseBackendKind, path: P) -> Result<Box<DatabaseBackend>, Error> {
+	match kind {
+		DatabaseBackendKind::Toml => Ok(Box::new(FileBackend::new(path))),
+		DatabaseBackendKind::KeyValue => Ok(Box::new(KeyValueBackend::open(path)?)),
+	}
+}
+
+/// Returns path of a file living next to the database file, with `extension` appended to its name.
+fn sibling_path(path: &Path, extension: &str) -> PathBuf {
+	let mut name = path.file_name().map(|name| name.to_os_string()).unwrap_or_default();
+	name.push(extension);
+	path.with_file_name(name)
+}
+
+/// Path of the previous version of the database.
+pub fn backup_path<P: AsRef<Path>>(path: P) -> PathBuf {
+	sibling_path(path.as_ref(), ".bak")
+}
+
+/// Path of the database version which is being written.
+fn temp_path(path: &Path) -> PathBuf {
+	sibling_path(path, ".tmp")
+}
+
+impl Database {
+	/// Loads the database from `path`.
+	///
+	/// Falls back to the backup of the previous version if the file at `path` can't be read or parsed.
+	pub fn load<P: AsRef<Path>>(path: P) -> Result<Database, Error> {
+		let path = path.as_ref();
+		let err = match Self::load_file(path) {
+			Ok(database) => return Ok(database),
+			Err(err) => err,
+		};
+
+		if let ErrorKind::MissingFile(_) = *err.kind() {
+			return Err(err);
+		}
+
+		let backup = backup_path(path);
+		warn!("Cannot load database {:?}: {}. Trying backup {:?}", path, err, backup);
+		Self::load_file(&backup).chain_err(|| format!("Cannot load database {:?} nor its backup", path))
+	}
+
+	fn load_file(path: &Path) -> Result<Database, Error> {
+		let mut file = match fs::File::open(path) {
+			Ok(file) => file,
+			Err(ref err) if err.kind() == io::ErrorKind::NotFound => return Err(ErrorKind::MissingFile(format!("{:?}", path)).into()),
+			Err(err) => return Err(err).chain_err(|| "Cannot open database"),
+		};
+
+		let mut buffer = String::new();
+		file.read_to_string(&mut buffer)?;
+		buffer.parse()
+	}
+
+	/// Atomically replaces the database at `path`.
+	///
+	/// The new version is written to a temporary file and synced to disk before it is renamed over
+	/// the current one, so a crash never leaves a partially written database behind.
+	/// The current version is kept as a backup by linking it, so the backup is the complete file
+	/// which has been synced when it was saved, rather than a copy which may not have reached the disk.
+	pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+		let path = path.as_ref();
+		let temp = temp_path(path);
+
+		{
+			let mut file = fs::File::create(&temp).chain_err(|| "Cannot create temporary database file")?;
+			self.write(&mut file)?;
+			file.sync_all().chain_err(|| "Cannot sync temporary database file")?;
+		}
+
+		if path.exists() {
+			let backup = backup_path(path);
+			if backup.exists() {
+				fs::remove_file(&backup).chain_err(|| "Cannot remove database backup")?;
+			}
+			fs::hard_link(path, &backup).chain_err(|| "Cannot backup database")?;
+		}
+
+		fs::rename(&temp, path).chain_err(|| "Cannot replace database")?;
+		sync_parent_dir(path)?;
+		Ok(())
+	}
+
+	pub fn write<W: Write>(&self, mut write: W) -> Result<(), Error> {
+		write.write_all(self.to_string().as_bytes())?;
+		Ok(())
+	}
+}
+
+/// Makes the rename of the database file durable.
+#[cfg(unix)]
+fn sync_parent_dir(path: &Path) -> Result<(), Error> {
+	let dir = match path.parent() {
+		Some(dir) if dir.as_os_str().is_empty() => Path::new("."),
+		Some(dir) => dir,
+		None => return Ok(()),
+	};
+	fs::File::open(dir).and_then(|dir| dir.sync_all()).chain_err(|| "Cannot sync database directory")
+}
+
+#[cfg(not(unix))]
+fn sync_parent_dir(_path: &Path) -> Result<(), Error> {
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	extern crate tempdir;
+	use std::fs;
+	use std::io::Write;
+	use self::tempdir::TempDir;
+	use super::{Database, PendingTransaction, backup_path};
+
+	#[test]
+	fn database_to_and_from_str() {
+		let toml =
+r#"home_contract_address = "0x49edf201c1e139282643d5e7c6fb0c7219ad1db7"
+foreign_contract_address = "0x49edf201c1e139282643d5e7c6fb0c7219ad1db8"
+home_deploy = 100
+foreign_deploy = 101
+checked_deposit_relay = 120
+checked_withdraw_relay = 121
+checked_withdraw_confirm = 121
+"#;
+
+		let expected = Database {
+			home_contract_address: "49edf201c1e139282643d5e7c6fb0c7219ad1db7".into(),
+			foreign_contract_address: "49edf201c1e139282643d5e7c6fb0c7219ad1db8".into(),
+			home_deploy: Some(100),
+			foreign_deploy: Some(101),
+			checked_deposit_relay: 120,
+			checked_withdraw_relay: 121,
+			checked_withdraw_confirm: 121,
+			..Default::default()
+		};
+
+		let database = toml.parse().unwrap();
+		assert_eq!(expected, database);
+		let s = database.to_string();
+		assert_eq!(s, toml);
+	}
+
+	#[test]
+	fn database_save_keeps_backup() {
+		let tempdir = TempDir::new("database_save_keeps_backup").unwrap();
+		let path = tempdir.path().join("db.toml");
+
+		let first = Database {
+			checked_deposit_relay: 1,
+			..Default::default()
+		};
+		first.save(&path).unwrap();
+		assert!(!backup_path(&path).exists());
+
+		let second = Database {
+			checked_deposit_relay: 2,
+			..Default::default()
+		};
+		second.save(&path).unwrap();
+
+		assert_eq!(second, Database::load(&path).unwrap());
+		assert_eq!(first, Database::load(backup_path(&path)).unwrap());
+
+		// the previous backup is replaced
+		let third = Database {
+			checked_deposit_relay: 3,
+			..Default::default()
+		};
+		third.save(&path).unwrap();
+		assert_eq!(third, Database::load(&path).unwrap());
+		assert_eq!(second, Database::load(backup_path(&path)).unwrap());
+	}
+
+	#[test]
+	fn database_save_truncates_previous_content() {
+		let tempdir = TempDir::new("database_save_truncates_previous_content").unwrap();
+		let path = tempdir.path().join("db.toml");
+
+		let long = Database {
+			home_deploy: Some(1_000_000),
+			foreign_deploy: Some(1_000_000),
+			checked_deposit_relay: 1_000_000,
+			..Default::default()
+		};
+		long.save(&path).unwrap();
+
+		let short = Database::default();
+		short.save(&path).unwrap();
+
+		assert_eq!(short, Database::load(&path).unwrap());
+	}
+
+	#[test]
+	fn database_load_falls_back_to_backup() {
+		let tempdir = TempDir::new("database_load_falls_back_to_backup").unwrap();
+		let path = tempdir.path().join("db.toml");
+
+		let database = Database {
+			checked_withdraw_relay: 5,
+			..Default::default()
+		};
+		database.save(&path).unwrap();
+		database.save(&path).unwrap();
+
+		fs::File::create(&path).unwrap().write_all(b"checked_deposit_relay = ").unwrap();
+		assert_eq!(database, Database::load(&path).unwrap());
+	}
+
+	#[test]
+	fn database_pending_transactions_roundtrip() {
+		let database = Database {
+			checked_deposit_relay: 7,
+			foreign_pending_transactions: vec![PendingTransaction {
+				nonce: 3.into(),
+				hash: 2.into(),
+				replaced_hashes: vec![1.into()],
+				raw: vec![0xf8, 0x6b].into(),
+				gas_price: 1_000_000_000.into(),
+				max_priority_fee_per_gas: Some(100_000_000.into()),
+				gas: 100_000.into(),
+				value: 0.into(),
+				data: vec![0x12, 0x34].into(),
+				to: Some(5.into()),
+				sent_at: 1_500_000_000,
+			}],
+			..Default::default()
+		};
+
+		let s = database.to_string();
+		assert!(!s.contains("home_pending_transactions"));
+		assert_eq!(database, s.parse().unwrap());
+	}
+}
@@ -3,6 +3,7 @@
 use std::io;
 use tokio_timer::{TimerError, TimeoutError};
 use {web3, toml, ethabi, rustc_hex};
+use web3::types::{H256, U256, Address};
 use ethcore::ethstore;
 use ethcore::account_provider::{SignError, Error as AccountError};
 use serde_json;
@@ -29,6 +30,28 @@ error_chain! {
 		NoRequiredSignaturesChanged {
 		   description("No RequiredSignaturesChanged has been observed")
 		}
+		UnknownToken(token: Address) {
+			description("unknown token"),
+			display("Token {:?} is not configured in `tokens`", token),
+		}
+		NoBaseFee {
+			description("node does not report a base fee, it may not support EIP-1559 transactions")
+		}
+		StaleGasPrice {
+			description("gas price is older than gas_price_max_age")
+		}
+		GasEstimateTooHigh(gas: U256, max_gas: u64) {
+			description("gas estimate exceeds max_gas"),
+			display("Transaction needs {} gas, more than max_gas {}", gas, max_gas),
+		}
+		TransactionReverted(hash: H256) {
+			description("transaction reverted"),
+			display("Transaction {:?} has been mined but its execution failed", hash),
+		}
+		ReorgTooDeep(block: u64) {
+			description("chain reorganization is deeper than tracked history"),
+			display("Chain has been reorganized at or before block {}, the oldest one remembered by the log stream", block),
+		}
 		// api timeout
 		Timeout(request: &'static str) {
 			description("Request timeout"),
@@ -53,10 +76,18 @@ error_chain! {
 		    description("signing error")
 		    display("signing error {:?}", err),
 		}
+		InvalidSignature(len: usize) {
+			description("invalid signature"),
+			display("Signer returned a signature of {} bytes, expected 65", len),
+		}
 		AccountError(err: AccountError) {
 		    description("account error")
 		    display("account error {:?}", err),
 		}
+		RetriesExhausted(err: Box<Error>, attempts: u32) {
+			description("retries exhausted"),
+			display("{} (gave up after {} retries)", err, attempts),
+		}
 		ContextualizedError(err: Box<Error>, context: &'static str) {
 		    description("contextualized error")
 		    display("{:?} in {}", err, context)
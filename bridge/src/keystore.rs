@@ -0,0 +1,124 @@
+//! Managing accounts of the local keystore.
+
+use std::path::Path;
+use std::str::FromStr;
+use ethcore::ethstore::{self, EthStore, SecretStore, SimpleSecretStore, SecretVaultRef, StoreAccountRef};
+use ethcore::ethstore::accounts_dir::RootDiskDirectory;
+use ethcore::ethstore::ethkey::{Generator, KeyPair, Random, Secret};
+use web3::types::Address;
+use error::{Error, ErrorKind};
+
+/// Opens the keystore in `path`.
+pub fn open(path: &Path) -> Result<EthStore, Error> {
+	EthStore::open(Box::new(RootDiskDirectory::at(path))).map_err(|e| ErrorKind::KeyStore(e).into())
+}
+
+/// Opens the keystore in `path`, creating the directory if it doesn't exist.
+pub fn create(path: &Path) -> Result<EthStore, Error> {
+	let directory = RootDiskDirectory::create(path).map_err(ErrorKind::KeyStore)?;
+	EthStore::open(Box::new(directory)).map_err(|e| ErrorKind::KeyStore(e).into())
+}
+
+/// Returns addresses of all accounts in `store`.
+pub fn list_accounts(store: &EthStore) -> Result<Vec<Address>, Error> {
+	let accounts = store.accounts().map_err(ErrorKind::KeyStore)?;
+	Ok(accounts.into_iter().map(|account| account.address).collect())
+}
+
+/// Creates an account with a random key encrypted with `password`.
+pub fn new_account(store: &EthStore, password: &str) -> Result<Address, Error> {
+	let keypair = Random.generate().map_err(|e| ErrorKind::OtherError(format!("Cannot generate a key: {:?}", e)))?;
+	insert_secret(store, keypair.secret().clone(), password)
+}
+
+/// Imports a raw hex encoded private key, encrypting it with `password`.
+pub fn import_raw_key(store: &EthStore, key: &str, password: &str) -> Result<Address, Error> {
+	let key = key.trim();
+	let key = if key.starts_with("0x") { &key[2..] } else { key };
+	let secret = Secret::from_str(key)
+		.map_err(|_| ErrorKind::OtherError("Private key must be 32 hex encoded bytes".into()))?;
+	insert_secret(store, secret, password)
+}
+
+/// Copies the JSON key file at `key` to the keystore in `path`, the key stays encrypted with its own password.
+pub fn import_json_key(path: &Path, key: &Path) -> Result<Address, Error> {
+	let directory = RootDiskDirectory::create(path).map_err(ErrorKind::KeyStore)?;
+	ethstore::import_account(key, &directory).map_err(|e| ErrorKind::KeyStore(e).into())
+}
+
+/// Encrypts the key of `account` with `new_password` instead of `old_password`.
+pub fn change_password(store: &EthStore, account: Address, old_password: &str, new_password: &str) -> Result<(), Error> {
+	store.change_password(&StoreAccountRef::root(account), old_password, new_password).map_err(|e| ErrorKind::KeyStore(e).into())
+}
+
+/// Returns true if `password` decrypts the key of `account`.
+pub fn test_password(store: &EthStore, account: Address, password: &str) -> Result<bool, Error> {
+	store.test_password(&StoreAccountRef::root(account), password).map_err(|e| ErrorKind::KeyStore(e).into())
+}
+
+fn insert_secret(store: &EthStore, secret: Secret, password: &str) -> Result<Address, Error> {
+	// validates the secret before it's stored
+	KeyPair::from_secret(secret.clone()).map_err(|_| ErrorKind::OtherError("Invalid private key".into()))?;
+	let account = store.insert_account(SecretVaultRef::Root, secret, password).map_err(ErrorKind::KeyStore)?;
+	Ok(account.address)
+}
+
+#[cfg(test)]
+mod tests {
+	extern crate tempdir;
+
+	use std::fs;
+	use self::tempdir::TempDir;
+	use web3::types::Address;
+	use super::{create, open, list_accounts, new_account, import_raw_key, import_json_key, change_password, test_password};
+
+	#[test]
+	fn test_new_account() {
+		let tempdir = TempDir::new("keystore_new_account").unwrap();
+		let path = tempdir.path().join("keys");
+		let store = create(&path).unwrap();
+		let account = new_account(&store, "password").unwrap();
+		assert_eq!(vec![account], list_accounts(&store).unwrap());
+
+		// the key is on disk
+		assert_eq!(vec![account], list_accounts(&open(&path).unwrap()).unwrap());
+		assert!(test_password(&store, account, "password").unwrap());
+	}
+
+	#[test]
+	fn test_import_raw_key() {
+		let tempdir = TempDir::new("keystore_import_raw_key").unwrap();
+		let store = create(tempdir.path()).unwrap();
+		let account = import_raw_key(&store, "0x4646464646464646464646464646464646464646464646464646464646464646\n", "password").unwrap();
+		assert_eq!(Address::from("9d8a62f656a8d1615c1294fd71e9cfb3e4855a4f"), account);
+		assert_eq!(vec![account], list_accounts(&store).unwrap());
+
+		assert!(import_raw_key(&store, "0x46", "password").is_err());
+	}
+
+	#[test]
+	fn test_import_json_key() {
+		let source = TempDir::new("keystore_import_json_key_source").unwrap();
+		let account = new_account(&create(source.path()).unwrap(), "password").unwrap();
+		let key = fs::read_dir(source.path()).unwrap().next().unwrap().unwrap().path();
+
+		let tempdir = TempDir::new("keystore_import_json_key").unwrap();
+		assert_eq!(account, import_json_key(tempdir.path(), &key).unwrap());
+		let store = open(tempdir.path()).unwrap();
+		assert_eq!(vec![account], list_accounts(&store).unwrap());
+		assert!(test_password(&store, account, "password").unwrap());
+	}
+
+	#[test]
+	fn test_change_password() {
+		let tempdir = TempDir::new("keystore_change_password").unwrap();
+		let store = create(tempdir.path()).unwrap();
+		let account = new_account(&store, "old").unwrap();
+
+		assert!(change_password(&store, account, "wrong", "new").is_err());
+		change_password(&store, account, "old", "new").unwrap();
+		let store = open(tempdir.path()).unwrap();
+		assert!(test_password(&store, account, "new").unwrap());
+		assert!(!test_password(&store, account, "old").unwrap());
+	}
+}
@@ -32,6 +32,12 @@ extern crate jsonrpc_core as rpc;
 extern crate itertools;
 extern crate hyper;
 extern crate hyper_tls;
+extern crate kvdb;
+extern crate kvdb_rocksdb;
+#[macro_use]
+extern crate lazy_static;
+extern crate rand;
+extern crate libc;
 
 #[cfg(test)]
 #[macro_use]
@@ -40,14 +46,22 @@ extern crate quickcheck;
 #[macro_use]
 mod macros;
 
+pub mod admin;
 pub mod api;
 pub mod app;
 pub mod config;
 pub mod bridge;
+pub mod check;
 pub mod contracts;
 pub mod database;
 pub mod error;
+pub mod keystore;
+pub mod metrics;
+pub mod password;
+pub mod retry;
+pub mod signer;
 pub mod util;
 pub mod message_to_mainnet;
 pub mod signature;
 pub mod transaction;
+pub mod transport;
@@ -1,8 +1,9 @@
 use ethereum_types::{Address, U256, H256};
-use contracts::foreign::events::Withdraw;
+use contracts::foreign::events::{Withdraw, TokenWithdraw};
 use web3::types::Log;
 use ethabi;
-use error::Error;
+use config::TokenPair;
+use error::{Error, ErrorKind};
 
 /// the message that is relayed from side to main.
 /// contains all the information required for the relay.
@@ -13,21 +14,39 @@ pub struct MessageToMainnet {
 	pub value: U256,
 	pub sidenet_transaction_hash: H256,
 	pub mainnet_gas_price: U256,
+	/// token released on main, `None` for the token (or coin) the bridge was deployed for
+	pub token: Option<Address>,
 }
 
-/// length of a `MessageToMainnet.to_bytes()` in bytes
-pub const MESSAGE_LENGTH: usize = 116;
+/// length of a `MessageToMainnet.to_bytes()` in bytes for messages without a token
+pub const MESSAGE_LENGTH_V1: usize = 116;
+
+/// length of a `MessageToMainnet.to_bytes()` in bytes for messages with a token
+pub const MESSAGE_LENGTH_V2: usize = 136;
 
 impl MessageToMainnet {
 	/// parses message from a byte slice
 	pub fn from_bytes(bytes: &[u8]) -> Self {
-		assert_eq!(bytes.len(), MESSAGE_LENGTH);
+		let token = match bytes.len() {
+			MESSAGE_LENGTH_V1 => None,
+			MESSAGE_LENGTH_V2 => Some(bytes[MESSAGE_LENGTH_V1..MESSAGE_LENGTH_V2].into()),
+			len => panic!("message must be {} or {} bytes long, got {}", MESSAGE_LENGTH_V1, MESSAGE_LENGTH_V2, len),
+		};
 
-                Self {
+		Self {
 			recipient: bytes[0..20].into(),
 			value: (&bytes[20..52]).into(),
 			sidenet_transaction_hash: bytes[52..84].into(),
-			mainnet_gas_price: (&bytes[84..MESSAGE_LENGTH]).into(),
+			mainnet_gas_price: (&bytes[84..MESSAGE_LENGTH_V1]).into(),
+			token,
+		}
+	}
+
+	/// length of a `MessageToMainnet.to_bytes()` in bytes
+	pub fn encoded_len(&self) -> usize {
+		match self.token {
+			Some(_) => MESSAGE_LENGTH_V2,
+			None => MESSAGE_LENGTH_V1,
 		}
 	}
 
@@ -44,6 +63,29 @@ impl MessageToMainnet {
 			value: withdraw_log.value,
 			sidenet_transaction_hash: hash,
 			mainnet_gas_price: withdraw_log.home_gas_price,
+			token: None,
+		})
+	}
+
+	/// construct a message from a `TokenWithdraw` event that was logged on `foreign`,
+	/// releasing the home token paired with the withdrawn one in `tokens`
+	pub fn from_token_log(web3_log: Log, tokens: &[TokenPair]) -> Result<Self, Error> {
+		let ethabi_raw_log = ethabi::RawLog {
+			topics: web3_log.topics,
+			data: web3_log.data.0,
+		};
+		let withdraw_log = TokenWithdraw::default().parse_log(ethabi_raw_log)?;
+		let token = tokens.iter()
+			.find(|pair| pair.foreign == withdraw_log.token)
+			.ok_or_else(|| ErrorKind::UnknownToken(withdraw_log.token))?
+			.home;
+		let hash = web3_log.transaction_hash.ok_or_else(|| "`log` must be mined and contain `transaction_hash`")?;
+		Ok(Self {
+			recipient: withdraw_log.recipient,
+			value: withdraw_log.value,
+			sidenet_transaction_hash: hash,
+			mainnet_gas_price: withdraw_log.home_gas_price,
+			token: Some(token),
 		})
 	}
 
@@ -51,11 +93,14 @@ impl MessageToMainnet {
 	/// mainly used to construct the message byte vector that is then signed
 	/// and passed to `ForeignBridge.submitSignature`
 	pub fn to_bytes(&self) -> Vec<u8> {
-		let mut result = vec![0u8; MESSAGE_LENGTH];
+		let mut result = vec![0u8; self.encoded_len()];
 		result[0..20].copy_from_slice(&self.recipient.0[..]);
 		result[20..52].copy_from_slice(&H256::from(self.value));
 		result[52..84].copy_from_slice(&self.sidenet_transaction_hash.0[..]);
-		result[84..MESSAGE_LENGTH].copy_from_slice(&H256::from(self.mainnet_gas_price));
+		result[84..MESSAGE_LENGTH_V1].copy_from_slice(&H256::from(self.mainnet_gas_price));
+		if let Some(token) = self.token {
+			result[MESSAGE_LENGTH_V1..MESSAGE_LENGTH_V2].copy_from_slice(&token.0[..]);
+		}
 		return result;
 	}
 
@@ -75,7 +120,8 @@ mod test {
 			recipient_raw: Vec<u8>,
 			value_raw: u64,
 			sidenet_transaction_hash_raw: Vec<u8>,
-			mainnet_gas_price_raw: u64
+			mainnet_gas_price_raw: u64,
+			with_token: bool
 		) -> TestResult {
 			if recipient_raw.len() != 20 || sidenet_transaction_hash_raw.len() != 32 {
 				return TestResult::discard();
@@ -85,15 +131,22 @@ mod test {
 			let value: U256 = value_raw.into();
 			let sidenet_transaction_hash: H256 = sidenet_transaction_hash_raw.as_slice().into();
 			let mainnet_gas_price: U256 = mainnet_gas_price_raw.into();
+			let token: Option<Address> = if with_token {
+				Some(sidenet_transaction_hash_raw[..20].into())
+			} else {
+				None
+			};
 
 			let message = MessageToMainnet {
 				recipient,
 				value,
 				sidenet_transaction_hash,
-				mainnet_gas_price
+				mainnet_gas_price,
+				token,
 			};
 
 			let bytes = message.to_bytes();
+			assert_eq!(message.encoded_len(), bytes.len());
 			assert_eq!(message, MessageToMainnet::from_bytes(bytes.as_slice()));
 
 			let payload = message.to_payload();
@@ -0,0 +1,278 @@
+//! Prometheus metrics of the bridge.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::net::SocketAddr;
+use std::sync::RwLock;
+use futures::{future, Future, Stream};
+use hyper::{self, Method, StatusCode};
+use hyper::header::{ContentLength, ContentType};
+use hyper::server::{Http, Request, Response, Service};
+use tokio_core::net::TcpListener;
+use tokio_core::reactor::Handle;
+use web3::types::U256;
+use bridge::BridgeChecked;
+use database::TransactionKind;
+use error::Error;
+
+lazy_static! {
+	/// Metrics of this bridge process.
+	pub static ref METRICS: Metrics = Metrics::default();
+}
+
+/// Chain the bridge is connected to.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Chain {
+	Home,
+	Foreign,
+}
+
+#[derive(Default)]
+struct ChainValues {
+	head_block: Option<u64>,
+	gas_price: Option<u64>,
+	balance: Option<U256>,
+}
+
+#[derive(Default)]
+struct Values {
+	checked_deposit_relay: Option<u64>,
+	checked_withdraw_relay: Option<u64>,
+	checked_withdraw_confirm: Option<u64>,
+	deposits_relayed: u64,
+	signatures_submitted: u64,
+	withdraws_relayed: u64,
+	rpc_errors: BTreeMap<&'static str, u64>,
+	home: ChainValues,
+	foreign: ChainValues,
+}
+
+impl Values {
+	fn chain(&mut self, chain: Chain) -> &mut ChainValues {
+		match chain {
+			Chain::Home => &mut self.home,
+			Chain::Foreign => &mut self.foreign,
+		}
+	}
+}
+
+/// Counters and gauges describing bridge health and throughput.
+#[derive(Default)]
+pub struct Metrics {
+	values: RwLock<Values>,
+}
+
+impl Metrics {
+	/// Records the last block checked by a bridge component.
+	pub fn checked(&self, checked: BridgeChecked) {
+		let mut values = self.values.write().unwrap();
+		match checked {
+			BridgeChecked::DepositRelay(n) => values.checked_deposit_relay = Some(n),
+			BridgeChecked::WithdrawRelay(n) => values.checked_withdraw_relay = Some(n),
+			BridgeChecked::WithdrawConfirm(n) => values.checked_withdraw_confirm = Some(n),
+		}
+	}
+
+	/// Records `count` transactions of given kind sent by this authority.
+	pub fn sent(&self, kind: TransactionKind, count: usize) {
+		let mut values = self.values.write().unwrap();
+		let counter = match kind {
+			TransactionKind::DepositRelay => &mut values.deposits_relayed,
+			TransactionKind::WithdrawConfirm => &mut values.signatures_submitted,
+			TransactionKind::WithdrawRelay => &mut values.withdraws_relayed,
+		};
+		*counter += count as u64;
+	}
+
+	/// Records a failed RPC request.
+	pub fn rpc_error(&self, method: &'static str) {
+		*self.values.write().unwrap().rpc_errors.entry(method).or_insert(0) += 1;
+	}
+
+	/// Records the latest block number of a chain.
+	pub fn head_block(&self, chain: Chain, block: u64) {
+		self.values.write().unwrap().chain(chain).head_block = Some(block);
+	}
+
+	/// Records gas price used for transactions sent to a chain.
+	pub fn gas_price(&self, chain: Chain, gas_price: u64) {
+		self.values.write().unwrap().chain(chain).gas_price = Some(gas_price);
+	}
+
+	/// Records balance of the authority account on a chain.
+	pub fn balance(&self, chain: Chain, balance: U256) {
+		self.values.write().unwrap().chain(chain).balance = Some(balance);
+	}
+
+	/// Renders metrics in Prometheus text exposition format.
+	pub fn render(&self) -> String {
+		let values = self.values.read().unwrap();
+		let mut out = String::new();
+
+		header(&mut out, "bridge_last_checked_block", "gauge", "Number of the last block checked by a bridge component.");
+		for &(component, block) in &[
+			("deposit_relay", values.checked_deposit_relay),
+			("withdraw_relay", values.checked_withdraw_relay),
+			("withdraw_confirm", values.checked_withdraw_confirm),
+		] {
+			if let Some(block) = block {
+				sample(&mut out, "bridge_last_checked_block", "component", component, block);
+			}
+		}
+
+		header(&mut out, "bridge_head_block", "gauge", "Number of the latest block of a chain.");
+		for &(chain, chain_values) in &[("home", &values.home), ("foreign", &values.foreign)] {
+			if let Some(block) = chain_values.head_block {
+				sample(&mut out, "bridge_head_block", "chain", chain, block);
+			}
+		}
+
+		header(&mut out, "bridge_lag_blocks", "gauge", "Number of blocks of a chain which have not been checked yet.");
+		let foreign_checked = match (values.checked_withdraw_relay, values.checked_withdraw_confirm) {
+			(Some(relay), Some(confirm)) => Some(relay.min(confirm)),
+			(relay, confirm) => relay.or(confirm),
+		};
+		for &(chain, head, checked) in &[
+			("home", values.home.head_block, values.checked_deposit_relay),
+			("foreign", values.foreign.head_block, foreign_checked),
+		] {
+			if let (Some(head), Some(checked)) = (head, checked) {
+				sample(&mut out, "bridge_lag_blocks", "chain", chain, head.saturating_sub(checked));
+			}
+		}
+
+		header(&mut out, "bridge_deposits_relayed_total", "counter", "Number of deposits relayed to foreign.");
+		let _ = writeln!(out, "bridge_deposits_relayed_total {}", values.deposits_relayed);
+		header(&mut out, "bridge_signatures_submitted_total", "counter", "Number of withdraw signatures submitted to foreign.");
+		let _ = writeln!(out, "bridge_signatures_submitted_total {}", values.signatures_submitted);
+		header(&mut out, "bridge_withdraws_relayed_total", "counter", "Number of withdraws relayed to home.");
+		let _ = writeln!(out, "bridge_withdraws_relayed_total {}", values.withdraws_relayed);
+
+		header(&mut out, "bridge_rpc_errors_total", "counter", "Number of failed RPC requests by method.");
+		for (method, count) in &values.rpc_errors {
+			sample(&mut out, "bridge_rpc_errors_total", "method", method, count);
+		}
+
+		header(&mut out, "bridge_gas_price_wei", "gauge", "Gas price used for transactions sent to a chain.");
+		for &(chain, chain_values) in &[("home", &values.home), ("foreign", &values.foreign)] {
+			if let Some(gas_price) = chain_values.gas_price {
+				sample(&mut out, "bridge_gas_price_wei", "chain", chain, gas_price);
+			}
+		}
+
+		header(&mut out, "bridge_balance_wei", "gauge", "Balance of the authority account on a chain.");
+		for &(chain, chain_values) in &[("home", &values.home), ("foreign", &values.foreign)] {
+			if let Some(balance) = chain_values.balance {
+				sample(&mut out, "bridge_balance_wei", "chain", chain, balance);
+			}
+		}
+
+		out
+	}
+}
+
+fn header(out: &mut String, name: &str, kind: &str, help: &str) {
+	let _ = writeln!(out, "# HELP {} {}", name, help);
+	let _ = writeln!(out, "# TYPE {} {}", name, kind);
+}
+
+fn sample<V: ::std::fmt::Display>(out: &mut String, name: &str, label: &str, label_value: &str, value: V) {
+	let _ = writeln!(out, "{}{{{}=\"{}\"}} {}", name, label, label_value, value);
+}
+
+/// Serves `METRICS` at `/metrics`.
+struct MetricsService;
+
+impl Service for MetricsService {
+	type Request = Request;
+	type Response = Response;
+	type Error = hyper::Error;
+	type Future = future::FutureResult<Response, hyper::Error>;
+
+	fn call(&self, request: Request) -> Self::Future {
+		let response = match (request.method(), request.path()) {
+			(&Method::Get, "/metrics") => {
+				let body = METRICS.render();
+				Response::new()
+					.with_header(ContentType::plaintext())
+					.with_header(ContentLength(body.len() as u64))
+					.with_body(body)
+			},
+			_ => Response::new().with_status(StatusCode::NotFound),
+		};
+		future::ok(response)
+	}
+}
+
+/// Starts HTTP listener serving metrics at `address` on the event loop of `handle`.
+pub fn serve(address: &SocketAddr, handle: &Handle) -> Result<(), Error> {
+	let listener = TcpListener::bind(address, handle)?;
+	let http = Http::new();
+	let connection_handle = handle.clone();
+	let server = listener.incoming().for_each(move |(socket, _)| {
+		connection_handle.spawn(http.serve_connection(socket, MetricsService)
+			.map(|_| ())
+			.map_err(|err| warn!("metrics connection failed: {}", err)));
+		Ok(())
+	});
+	handle.spawn(server.map_err(|err| error!("metrics listener failed: {}", err)));
+	info!("serving metrics at http://{}/metrics", address);
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use bridge::BridgeChecked;
+	use database::TransactionKind;
+	use super::{Metrics, Chain};
+
+	#[test]
+	fn test_render_metrics() {
+		let metrics = Metrics::default();
+		metrics.checked(BridgeChecked::DepositRelay(90));
+		metrics.checked(BridgeChecked::WithdrawRelay(40));
+		metrics.checked(BridgeChecked::WithdrawConfirm(45));
+		metrics.head_block(Chain::Home, 100);
+		metrics.head_block(Chain::Foreign, 50);
+		metrics.sent(TransactionKind::DepositRelay, 2);
+		metrics.sent(TransactionKind::WithdrawConfirm, 1);
+		metrics.rpc_error("eth_getLogs");
+		metrics.rpc_error("eth_getLogs");
+		metrics.gas_price(Chain::Foreign, 1_000_000_000);
+		metrics.balance(Chain::Home, 5.into());
+
+		let expected = r#"# HELP bridge_last_checked_block Number of the last block checked by a bridge component.
+# TYPE bridge_last_checked_block gauge
+bridge_last_checked_block{component="deposit_relay"} 90
+bridge_last_checked_block{component="withdraw_relay"} 40
+bridge_last_checked_block{component="withdraw_confirm"} 45
+# HELP bridge_head_block Number of the latest block of a chain.
+# TYPE bridge_head_block gauge
+bridge_head_block{chain="home"} 100
+bridge_head_block{chain="foreign"} 50
+# HELP bridge_lag_blocks Number of blocks of a chain which have not been checked yet.
+# TYPE bridge_lag_blocks gauge
+bridge_lag_blocks{chain="home"} 10
+bridge_lag_blocks{chain="foreign"} 10
+# HELP bridge_deposits_relayed_total Number of deposits relayed to foreign.
+# TYPE bridge_deposits_relayed_total counter
+bridge_deposits_relayed_total 2
+# HELP bridge_signatures_submitted_total Number of withdraw signatures submitted to foreign.
+# TYPE bridge_signatures_submitted_total counter
+bridge_signatures_submitted_total 1
+# HELP bridge_withdraws_relayed_total Number of withdraws relayed to home.
+# TYPE bridge_withdraws_relayed_total counter
+bridge_withdraws_relayed_total 0
+# HELP bridge_rpc_errors_total Number of failed RPC requests by method.
+# TYPE bridge_rpc_errors_total counter
+bridge_rpc_errors_total{method="eth_getLogs"} 2
+# HELP bridge_gas_price_wei Gas price used for transactions sent to a chain.
+# TYPE bridge_gas_price_wei gauge
+bridge_gas_price_wei{chain="foreign"} 1000000000
+# HELP bridge_balance_wei Balance of the authority account on a chain.
+# TYPE bridge_balance_wei gauge
+bridge_balance_wei{chain="home"} 5
+"#;
+		assert_eq!(expected, metrics.render());
+	}
+}
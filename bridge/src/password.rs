@@ -0,0 +1,207 @@
+//! Reading passwords of keystore accounts from their configured sources.
+
+use std::{env, fs, io, mem};
+use std::io::{BufRead, Read, Write};
+use std::os::unix::fs::MetadataExt;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use libc;
+use web3::types::Address;
+use config::PasswordSource;
+use error::{Error, ErrorKind};
+
+/// Reads the password of `account` from `source`.
+pub fn read_password(source: &PasswordSource, account: Address) -> Result<String, Error> {
+	match *source {
+		PasswordSource::File(ref path) => read_file(path),
+		PasswordSource::Env(ref var) => read_env(var),
+		PasswordSource::Prompt => prompt(&format!("Password of {:?}: ", account)),
+		PasswordSource::Command(ref command) => run_command(command),
+	}
+}
+
+/// Reads the first line of a password file, refusing files which other users could read or replace.
+pub fn read_file(path: &Path) -> Result<String, Error> {
+	let metadata = fs::metadata(path)
+		.map_err(|e| ErrorKind::ConfigError(format!("Cannot read password file {}: {}", path.display(), e)))?;
+	check_file_permissions(path, metadata.mode(), metadata.uid(), unsafe { libc::geteuid() })?;
+	let mut file = fs::File::open(path)?;
+	let mut content = String::new();
+	file.read_to_string(&mut content)?;
+	Ok(first_line(&content))
+}
+
+fn check_file_permissions(path: &Path, mode: u32, owner: u32, user: u32) -> Result<(), Error> {
+	if owner != user {
+		return Err(ErrorKind::ConfigError(format!("Password file {} must be owned by the user running the bridge", path.display())).into());
+	}
+	if mode & 0o077 != 0 {
+		return Err(ErrorKind::ConfigError(format!("Password file {} must not be accessible by other users, its mode is {:o} instead of 600", path.display(), mode & 0o777)).into());
+	}
+	Ok(())
+}
+
+fn read_env(var: &str) -> Result<String, Error> {
+	env::var(var).map_err(|e| match e {
+		env::VarError::NotPresent => ErrorKind::ConfigError(format!("Password environment variable {} is not set", var)).into(),
+		env::VarError::NotUnicode(_) => ErrorKind::ConfigError(format!("Password environment variable {} is not valid unicode", var)).into(),
+	})
+}
+
+/// Runs `command` with `sh`, the password is the first line of its output.
+fn run_command(command: &str) -> Result<String, Error> {
+	// stdin and stderr are inherited, so the command can ask for a passphrase itself
+	let output = Command::new("sh")
+		.arg("-c")
+		.arg(command)
+		.stdin(Stdio::inherit())
+		.stderr(Stdio::inherit())
+		.output()
+		.map_err(|e| ErrorKind::ConfigError(format!("Cannot run password command `{}`: {}", command, e)))?;
+	if !output.status.success() {
+		return Err(ErrorKind::ConfigError(format!("Password command `{}` failed with {}", command, output.status)).into());
+	}
+	let stdout = String::from_utf8(output.stdout)
+		.map_err(|_| ErrorKind::ConfigError(format!("Password command `{}` printed invalid unicode", command)))?;
+	Ok(first_line(&stdout))
+}
+
+/// Asks for a password on the terminal with `message`, without echoing it.
+pub fn prompt(message: &str) -> Result<String, Error> {
+	if unsafe { libc::isatty(libc::STDIN_FILENO) } != 1 {
+		return Err(ErrorKind::ConfigError("password prompt requires the bridge to be started from a terminal".into()).into());
+	}
+	let stderr = io::stderr();
+	let mut stderr = stderr.lock();
+	write!(stderr, "{}", message)?;
+	stderr.flush()?;
+	let password = {
+		let _echo_off = EchoOff::new(libc::STDIN_FILENO)?;
+		let stdin = io::stdin();
+		let mut stdin = stdin.lock();
+		read_line(&mut stdin)
+	};
+	writeln!(stderr)?;
+	password
+}
+
+fn read_line<R: BufRead>(input: &mut R) -> Result<String, Error> {
+	let mut line = String::new();
+	if input.read_line(&mut line)? == 0 {
+		return Err(ErrorKind::ConfigError("No password has been entered".into()).into());
+	}
+	Ok(first_line(&line))
+}
+
+fn first_line(s: &str) -> String {
+	s.lines().next().unwrap_or("").to_owned()
+}
+
+/// Disables echo of a terminal until dropped.
+struct EchoOff {
+	fd: libc::c_int,
+	original: libc::termios,
+}
+
+impl EchoOff {
+	fn new(fd: libc::c_int) -> io::Result<Self> {
+		let mut original: libc::termios = unsafe { mem::zeroed() };
+		if unsafe { libc::tcgetattr(fd, &mut original) } != 0 {
+			return Err(io::Error::last_os_error());
+		}
+		let mut silent = original;
+		silent.c_lflag &= !libc::ECHO;
+		if unsafe { libc::tcsetattr(fd, libc::TCSANOW, &silent) } != 0 {
+			return Err(io::Error::last_os_error());
+		}
+		Ok(EchoOff { fd, original })
+	}
+}
+
+impl Drop for EchoOff {
+	fn drop(&mut self) {
+		unsafe { libc::tcsetattr(self.fd, libc::TCSANOW, &self.original); }
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	extern crate tempdir;
+
+	use std::{env, fs};
+	use std::io::{Cursor, Write};
+	use std::os::unix::fs::PermissionsExt;
+	use std::path::Path;
+	use self::tempdir::TempDir;
+	use web3::types::Address;
+	use config::PasswordSource;
+	use super::{read_password, check_file_permissions, read_line};
+
+	fn write_password_file(dir: &TempDir, content: &str, mode: u32) -> PasswordSource {
+		let path = dir.path().join("password");
+		fs::File::create(&path).unwrap().write_all(content.as_bytes()).unwrap();
+		fs::set_permissions(&path, fs::Permissions::from_mode(mode)).unwrap();
+		PasswordSource::File(path)
+	}
+
+	#[test]
+	fn test_password_from_file() {
+		let tempdir = TempDir::new("password_from_file").unwrap();
+		let source = write_password_file(&tempdir, "secret\nignored\n", 0o600);
+		assert_eq!("secret", read_password(&source, Address::default()).unwrap());
+
+		let source = write_password_file(&tempdir, "secret", 0o400);
+		assert_eq!("secret", read_password(&source, Address::default()).unwrap());
+	}
+
+	#[test]
+	fn test_password_file_readable_by_others_is_refused() {
+		let tempdir = TempDir::new("password_file_readable_by_others").unwrap();
+		let source = write_password_file(&tempdir, "secret", 0o644);
+		assert!(read_password(&source, Address::default()).is_err());
+
+		let source = write_password_file(&tempdir, "secret", 0o660);
+		assert!(read_password(&source, Address::default()).is_err());
+	}
+
+	#[test]
+	fn test_password_file_of_another_user_is_refused() {
+		let path = Path::new("password");
+		assert!(check_file_permissions(path, 0o100600, 1000, 1000).is_ok());
+		assert!(check_file_permissions(path, 0o100600, 0, 1000).is_err());
+	}
+
+	#[test]
+	fn test_missing_password_file() {
+		let tempdir = TempDir::new("missing_password_file").unwrap();
+		let source = PasswordSource::File(tempdir.path().join("password"));
+		assert!(read_password(&source, Address::default()).is_err());
+	}
+
+	#[test]
+	fn test_password_from_env() {
+		env::set_var("BRIDGE_TEST_PASSWORD_FROM_ENV", "secret");
+		let source = PasswordSource::Env("BRIDGE_TEST_PASSWORD_FROM_ENV".into());
+		assert_eq!("secret", read_password(&source, Address::default()).unwrap());
+
+		let source = PasswordSource::Env("BRIDGE_TEST_PASSWORD_NOT_SET".into());
+		assert!(read_password(&source, Address::default()).is_err());
+	}
+
+	#[test]
+	fn test_password_from_command() {
+		let source = PasswordSource::Command("echo secret; echo ignored".into());
+		assert_eq!("secret", read_password(&source, Address::default()).unwrap());
+
+		let source = PasswordSource::Command("echo secret; exit 1".into());
+		assert!(read_password(&source, Address::default()).is_err());
+	}
+
+	#[test]
+	fn test_password_from_prompt_input() {
+		assert_eq!("secret", read_line(&mut Cursor::new("secret\n")).unwrap());
+		assert_eq!("", read_line(&mut Cursor::new("\n")).unwrap());
+		// closed input
+		assert!(read_line(&mut Cursor::new("")).is_err());
+	}
+}
@@ -0,0 +1,198 @@
+//! Retrying transient failures with exponential backoff.
+
+use std::time::Duration;
+use futures::{Async, Future, Poll};
+use rand::{self, Rng};
+use tokio_timer::{Sleep, Timer};
+use web3;
+use rpc;
+use config::RetryConfig;
+use error::{Error, ErrorKind};
+
+/// Returns true if `err` is caused by a failure which might not happen again,
+/// e.g. a request timeout, a dropped connection, a 5xx HTTP response, rate limiting or a stale gas price.
+pub fn is_transient(err: &Error) -> bool {
+	match *err.kind() {
+		ErrorKind::Timeout(_) => true,
+		ErrorKind::StaleGasPrice => true,
+		ErrorKind::ContextualizedError(ref err, _) => is_transient(err),
+		ErrorKind::Web3(ref err) => is_transient_web3_error(err),
+		_ => false,
+	}
+}
+
+/// Returns true if web3 request failed because of a transient error.
+pub fn is_transient_web3_error(err: &web3::Error) -> bool {
+	match *err.kind() {
+		web3::ErrorKind::Io(_) => true,
+		web3::ErrorKind::Transport(ref message) => is_transient_transport_error(message),
+		web3::ErrorKind::Rpc(ref err) => is_rate_limited(err),
+		_ => false,
+	}
+}
+
+/// Transport errors are connection failures, unless the node responded with a status
+/// which means that the request itself is invalid.
+fn is_transient_transport_error(message: &str) -> bool {
+	const STATUS_PREFIX: &str = "Unexpected response status code: ";
+	if !message.starts_with(STATUS_PREFIX) {
+		return true;
+	}
+	let status = message[STATUS_PREFIX.len()..]
+		.split_whitespace()
+		.next()
+		.and_then(|status| status.parse::<u16>().ok());
+	match status {
+		Some(status) => status >= 500 || status == 429,
+		None => true,
+	}
+}
+
+fn is_rate_limited(err: &rpc::Error) -> bool {
+	// -32005 is the "limit exceeded" code of EIP-1474
+	err.code == rpc::ErrorCode::ServerError(-32005) || err.message.to_lowercase().contains("rate limit")
+}
+
+/// Returns delay before retry number `attempt` (counting from 0).
+///
+/// The delay doubles with each attempt up to `max_delay` and is then randomly
+/// changed by up to `jitter_percent`, `jitter` being a random number in range `[0, 1)`.
+fn backoff_delay(config: &RetryConfig, attempt: u32, jitter: f64) -> Duration {
+	let initial = duration_to_millis(config.initial_delay);
+	let max = duration_to_millis(config.max_delay);
+	let mut delay = initial.min(max);
+	for _ in 0..attempt {
+		if delay >= max {
+			break;
+		}
+		delay = delay.saturating_mul(2).min(max);
+	}
+	let jitter = (jitter * 2.0 - 1.0) * config.jitter_percent as f64 / 100.0;
+	let delay = (delay as f64 * (1.0 + jitter)).max(0.0) as u64;
+	Duration::from_millis(delay)
+}
+
+fn duration_to_millis(duration: Duration) -> u64 {
+	duration.as_secs() * 1_000 + u64::from(duration.subsec_nanos()) / 1_000_000
+}
+
+/// Schedules retries of failed requests.
+pub struct Backoff {
+	timer: Timer,
+	config: RetryConfig,
+	attempts: u32,
+	delay: Option<Sleep>,
+}
+
+impl Backoff {
+	pub fn new(timer: Timer, config: RetryConfig) -> Self {
+		Backoff {
+			timer,
+			config,
+			attempts: 0,
+			delay: None,
+		}
+	}
+
+	/// Schedules a retry after transient `err`.
+	///
+	/// Returns `err` if it is not transient, or `ErrorKind::RetriesExhausted`
+	/// if `RetryConfig::max_attempts` have already been made.
+	pub fn retry(&mut self, err: Error, context: &str) -> Result<(), Error> {
+		if !is_transient(&err) {
+			return Err(err);
+		}
+		if self.config.max_attempts.map_or(false, |max| self.attempts >= max) {
+			return Err(ErrorKind::RetriesExhausted(Box::new(err), self.attempts).into());
+		}
+		let delay = backoff_delay(&self.config, self.attempts, rand::thread_rng().gen());
+		self.attempts += 1;
+		warn!("{} failed: {}, retrying in {}ms (attempt {})", context, err, duration_to_millis(delay), self.attempts);
+		self.delay = Some(self.timer.sleep(delay));
+		Ok(())
+	}
+
+	/// Forgets failed attempts after a request succeeded.
+	pub fn reset(&mut self) {
+		self.attempts = 0;
+	}
+
+	/// Returns `Ready` once the scheduled retry is due.
+	pub fn poll(&mut self) -> Poll<(), Error> {
+		if let Some(ref mut delay) = self.delay {
+			try_ready!(delay.poll());
+		}
+		self.delay = None;
+		Ok(Async::Ready(()))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::time::Duration;
+	use web3;
+	use rpc;
+	use config::RetryConfig;
+	use error::{Error, ErrorKind};
+	use super::{is_transient, backoff_delay};
+
+	fn config(jitter_percent: u64) -> RetryConfig {
+		RetryConfig {
+			initial_delay: Duration::from_secs(1),
+			max_delay: Duration::from_secs(10),
+			jitter_percent,
+			max_attempts: None,
+		}
+	}
+
+	#[test]
+	fn test_backoff_delay() {
+		let config = config(0);
+		assert_eq!(Duration::from_secs(1), backoff_delay(&config, 0, 0.3));
+		assert_eq!(Duration::from_secs(2), backoff_delay(&config, 1, 0.3));
+		assert_eq!(Duration::from_secs(8), backoff_delay(&config, 3, 0.3));
+		assert_eq!(Duration::from_secs(10), backoff_delay(&config, 4, 0.3));
+		assert_eq!(Duration::from_secs(10), backoff_delay(&config, 100, 0.3));
+	}
+
+	#[test]
+	fn test_backoff_delay_jitter() {
+		let config = config(20);
+		assert_eq!(Duration::from_millis(800), backoff_delay(&config, 0, 0.0));
+		assert_eq!(Duration::from_millis(2000), backoff_delay(&config, 1, 0.5));
+		assert_eq!(Duration::from_millis(11000), backoff_delay(&config, 5, 0.75));
+	}
+
+	fn web3_error(kind: web3::ErrorKind) -> Error {
+		ErrorKind::Web3(kind.into()).into()
+	}
+
+	#[test]
+	fn test_is_transient() {
+		let timeout: Error = ErrorKind::Timeout("eth_getLogs").into();
+		assert!(is_transient(&timeout));
+		assert!(is_transient(&ErrorKind::ContextualizedError(Box::new(timeout), "polling home for deposits").into()));
+		assert!(is_transient(&web3_error(web3::ErrorKind::Transport("Connection refused".into()))));
+		assert!(is_transient(&web3_error(web3::ErrorKind::Transport("Unexpected response status code: 502 Bad Gateway".into()))));
+		assert!(is_transient(&web3_error(web3::ErrorKind::Transport("Unexpected response status code: 429 Too Many Requests".into()))));
+		assert!(!is_transient(&web3_error(web3::ErrorKind::Transport("Unexpected response status code: 401 Unauthorized".into()))));
+
+		let rate_limited = rpc::Error {
+			code: rpc::ErrorCode::ServerError(-32005),
+			message: "limit exceeded".into(),
+			data: None,
+		};
+		assert!(is_transient(&web3_error(web3::ErrorKind::Rpc(rate_limited))));
+		let reverted = rpc::Error {
+			code: rpc::ErrorCode::ServerError(-32015),
+			message: "VM execution error.".into(),
+			data: None,
+		};
+		assert!(!is_transient(&web3_error(web3::ErrorKind::Rpc(reverted))));
+
+		assert!(!is_transient(&ErrorKind::InsufficientFunds.into()));
+		assert!(is_transient(&ErrorKind::StaleGasPrice.into()));
+		let exhausted: Error = ErrorKind::RetriesExhausted(Box::new(ErrorKind::Timeout("eth_getLogs").into()), 3).into();
+		assert!(!is_transient(&exhausted));
+	}
+}
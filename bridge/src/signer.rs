@@ -0,0 +1,354 @@
+//! Signing of transactions and messages sent by the bridge.
+
+use std::sync::Arc;
+use futures::{future, Future};
+use web3::Transport;
+use web3::types::{Address, Bytes, H256, H520, U256};
+use ethcore::account_provider::SignError;
+use ethcore::ethstore::{SecretStore, StoreAccountRef, OpaqueSecret};
+use ethcore::ethstore::ethkey::Signature;
+use ethcore_transaction::{Transaction, Action};
+use api::{self, SignTransactionRequest};
+use error::{Error, ErrorKind};
+use transaction::{signing_hash, signed_transaction};
+
+/// Future resolving to a signed transaction or a signature.
+pub type SignerFuture<T> = Box<Future<Item = T, Error = Error>>;
+
+/// Signs transactions and messages on behalf of accounts.
+pub trait Signer {
+	/// Signs `tx` sent by `account` to the chain with `chain_id`, the result is encoded as sent with `eth_sendRawTransaction`.
+	///
+	/// If `max_priority_fee_per_gas` is set, an EIP-1559 transaction paying at most `tx.gas_price`
+	/// per gas is signed, otherwise a legacy one.
+	fn sign_transaction(&self, account: Address, tx: Transaction, max_priority_fee_per_gas: Option<U256>, chain_id: u64) -> SignerFuture<Bytes>;
+
+	/// Signs `message` prefixed as in `eth_sign`, the signature is in electrum format (`v` is 27 or 28).
+	fn sign_message(&self, account: Address, message: Vec<u8>) -> SignerFuture<H520>;
+}
+
+/// Signers of transactions sent to each chain and of withdraw messages.
+#[derive(Clone)]
+pub struct Signers {
+	pub home: Arc<Signer>,
+	pub foreign: Arc<Signer>,
+	/// Signer of `Config::validator_account`.
+	pub validator: Arc<Signer>,
+}
+
+/// Signs with the key of one account of the local keystore.
+///
+/// The key is decrypted once when the signer is created and only this signer keeps it,
+/// the keystore itself never has unlocked accounts.
+pub struct KeystoreSigner {
+	store: Arc<SecretStore>,
+	account: Address,
+	secret: OpaqueSecret,
+}
+
+impl KeystoreSigner {
+	/// Decrypts the key of `account` in `store` with `password`.
+	pub fn unlock(store: Arc<SecretStore>, account: Address, password: &str) -> Result<Self, Error> {
+		let secret = store.raw_secret(&StoreAccountRef::root(account), password).map_err(ErrorKind::KeyStore)?;
+		Ok(KeystoreSigner {
+			store,
+			account,
+			secret,
+		})
+	}
+
+	fn sign(&self, account: Address, hash: H256) -> Result<Signature, Error> {
+		if account != self.account {
+			return Err(ErrorKind::SignError(SignError::NotFound).into());
+		}
+		self.store.sign_with_secret(&self.secret, &hash).map_err(|e| ErrorKind::KeyStore(e).into())
+	}
+}
+
+impl Signer for KeystoreSigner {
+	fn sign_transaction(&self, account: Address, tx: Transaction, max_priority_fee_per_gas: Option<U256>, chain_id: u64) -> SignerFuture<Bytes> {
+		let hash = signing_hash(&tx, max_priority_fee_per_gas, chain_id);
+		let result = self.sign(account, hash)
+			.map(|sig| signed_transaction(tx, max_priority_fee_per_gas, chain_id, sig));
+		Box::new(future::result(result))
+	}
+
+	fn sign_message(&self, account: Address, message: Vec<u8>) -> SignerFuture<H520> {
+		let result = self.sign(account, api::eth_data_hash(message))
+			.map(|sig| H520::from(sig.into_electrum()));
+		Box::new(future::result(result))
+	}
+}
+
+/// JSON-RPC API of a remote signer.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum RpcSignerApi {
+	/// `account_signTransaction` and `account_signData` of an external signer such as Clef.
+	External,
+	/// `eth_signTransaction` and `eth_sign` of a node with the account unlocked.
+	Node,
+}
+
+/// Signs with accounts of a remote signer, keys never leave it.
+pub struct RpcSigner<T> {
+	transport: T,
+	api: RpcSignerApi,
+}
+
+impl<T: Transport> RpcSigner<T> {
+	/// Signer using `account_signTransaction` and `account_signData`.
+	pub fn external(transport: T) -> Self {
+		RpcSigner {
+			transport,
+			api: RpcSignerApi::External,
+		}
+	}
+
+	/// Signer using `eth_signTransaction` and `eth_sign`.
+	pub fn node(transport: T) -> Self {
+		RpcSigner {
+			transport,
+			api: RpcSignerApi::Node,
+		}
+	}
+}
+
+impl<T> Signer for RpcSigner<T> where T: Transport + 'static, T::Out: 'static {
+	fn sign_transaction(&self, account: Address, tx: Transaction, max_priority_fee_per_gas: Option<U256>, chain_id: u64) -> SignerFuture<Bytes> {
+		let method = match self.api {
+			RpcSignerApi::External => "account_signTransaction",
+			RpcSignerApi::Node => "eth_signTransaction",
+		};
+		let request = sign_transaction_request(account, tx, max_priority_fee_per_gas, chain_id);
+		Box::new(api::sign_transaction(&self.transport, method, &request).map(|signed| signed.raw))
+	}
+
+	fn sign_message(&self, account: Address, message: Vec<u8>) -> SignerFuture<H520> {
+		let signature = match self.api {
+			RpcSignerApi::External => api::account_sign_data(&self.transport, account, Bytes(message)),
+			RpcSignerApi::Node => api::eth_sign(&self.transport, account, Bytes(message)),
+		};
+		Box::new(signature.and_then(electrum_signature))
+	}
+}
+
+/// Describes `tx` for a remote signer.
+fn sign_transaction_request(account: Address, tx: Transaction, max_priority_fee_per_gas: Option<U256>, chain_id: u64) -> SignTransactionRequest {
+	let (gas_price, max_fee_per_gas) = match max_priority_fee_per_gas {
+		Some(_) => (None, Some(tx.gas_price)),
+		None => (Some(tx.gas_price), None),
+	};
+	SignTransactionRequest {
+		from: account,
+		to: match tx.action {
+			Action::Create => None,
+			Action::Call(address) => Some(address),
+		},
+		gas: tx.gas,
+		gas_price,
+		max_fee_per_gas,
+		max_priority_fee_per_gas,
+		value: tx.value,
+		data: Bytes(tx.data),
+		nonce: tx.nonce,
+		chain_id: chain_id.into(),
+	}
+}
+
+/// Converts a signature returned by a remote signer to electrum format.
+fn electrum_signature(signature: Bytes) -> Result<H520, Error> {
+	if signature.0.len() != 65 {
+		return Err(ErrorKind::InvalidSignature(signature.0.len()).into());
+	}
+	let mut electrum = [0u8; 65];
+	electrum.copy_from_slice(&signature.0);
+	// some signers return the recovery id instead of `v`
+	if electrum[64] < 27 {
+		electrum[64] += 27;
+	}
+	Ok(H520::from(electrum))
+}
+
+#[cfg(test)]
+mod tests {
+	use std::sync::{Arc, Mutex};
+	use futures::{Future, Stream};
+	use hyper::{self, Method, StatusCode};
+	use hyper::header::{ContentLength, ContentType};
+	use hyper::server::{Http, Request, Response, Service};
+	use rpc::{IoHandler, Params, Value};
+	use rustc_hex::FromHex;
+	use serde_json;
+	use tokio_core::net::TcpListener;
+	use tokio_core::reactor::Core;
+	use web3::transports::Http as HttpTransport;
+	use web3::types::{Address, Bytes, H520};
+	use ethcore::ethstore::{EthStore, SimpleSecretStore, SecretVaultRef};
+	use ethcore::ethstore::accounts_dir::MemoryDirectory;
+	use ethcore::ethstore::ethkey::{self, Secret, Signature};
+	use ethcore_transaction::{Transaction, Action};
+	use api::eth_data_hash;
+	use super::{Signer, KeystoreSigner, RpcSigner, electrum_signature};
+
+	/// EIP-155 example transfer.
+	fn transfer() -> Transaction {
+		Transaction {
+			nonce: 9.into(),
+			gas_price: 20_000_000_000u64.into(),
+			gas: 21000.into(),
+			action: Action::Call("3535353535353535353535353535353535353535".into()),
+			value: 1_000_000_000_000_000_000u64.into(),
+			data: vec![],
+		}
+	}
+
+	const TRANSFER_RAW: &str = "f86c098504a817c800825208943535353535353535353535353535353535353535880de0b6b3a76400008025a028ef61340bd939bc2195fe537567866003e1a15d3c71ff63e1590620aa636276a067cbe9d8997f761aecb703304b3800ccf555c9f3dc64214b297fb1966a3b6d83";
+
+	/// Serves JSON-RPC requests POSTed to `/` with `handler`.
+	struct MockSigner(Arc<IoHandler>);
+
+	impl Service for MockSigner {
+		type Request = Request;
+		type Response = Response;
+		type Error = hyper::Error;
+		type Future = Box<Future<Item = Response, Error = hyper::Error>>;
+
+		fn call(&self, request: Request) -> Self::Future {
+			assert_eq!(*request.method(), Method::Post);
+			let handler = self.0.clone();
+			Box::new(request.body().concat2().map(move |body| {
+				let request = String::from_utf8(body.to_vec()).unwrap();
+				let response = handler.handle_request_sync(&request).unwrap_or_default();
+				Response::new()
+					.with_status(StatusCode::Ok)
+					.with_header(ContentType::json())
+					.with_header(ContentLength(response.len() as u64))
+					.with_body(response)
+			}))
+		}
+	}
+
+	/// Starts a mock signer on the event loop of `core`, returns transport connected to it.
+	fn mock_signer(core: &Core, handler: IoHandler) -> HttpTransport {
+		let listener = TcpListener::bind(&"127.0.0.1:0".parse().unwrap(), &core.handle()).unwrap();
+		let address = listener.local_addr().unwrap();
+		let handler = Arc::new(handler);
+		let handle = core.handle();
+		let server = listener.incoming().for_each(move |(socket, _)| {
+			handle.spawn(Http::new().serve_connection(socket, MockSigner(handler.clone())).map(|_| ()).map_err(|_| ()));
+			Ok(())
+		});
+		core.handle().spawn(server.map_err(|_| ()));
+		HttpTransport::with_event_loop(&format!("http://{}", address), &core.handle(), 1).unwrap()
+	}
+
+	fn json(value: &str) -> Value {
+		serde_json::from_str(value).unwrap()
+	}
+
+	/// Adds `method` to `handler`, it records its params in `calls` and returns `result`.
+	fn add_method(handler: &mut IoHandler, method: &str, result: Value, calls: Arc<Mutex<Vec<Value>>>) {
+		handler.add_method(method, move |params: Params| {
+			let params: Value = params.parse().unwrap();
+			calls.lock().unwrap().push(params);
+			Ok(result.clone())
+		});
+	}
+
+	#[test]
+	fn test_keystore_signer() {
+		let store = Arc::new(EthStore::open(Box::new(MemoryDirectory::default())).unwrap());
+		let secret: Secret = "4646464646464646464646464646464646464646464646464646464646464646".parse().unwrap();
+		let account = store.insert_account(SecretVaultRef::Root, secret, "password").unwrap().address;
+		assert!(KeystoreSigner::unlock(store.clone(), account, "wrong").is_err());
+		let signer = KeystoreSigner::unlock(store, account, "password").unwrap();
+
+		let raw = signer.sign_transaction(account, transfer(), None, 1).wait().unwrap();
+		assert_eq!(raw.0, TRANSFER_RAW.from_hex().unwrap());
+
+		let message = b"withdraw".to_vec();
+		let signature = signer.sign_message(account, message.clone()).wait().unwrap();
+		assert!(signature.0[64] == 27 || signature.0[64] == 28);
+		let public = ethkey::recover(&Signature::from_electrum(&signature.0), &eth_data_hash(message)).unwrap();
+		assert_eq!(ethkey::public_to_address(&public), account);
+
+		// the signer only has the key of its account
+		assert!(signer.sign_message(Address::default(), b"withdraw".to_vec()).wait().is_err());
+	}
+
+	#[test]
+	fn test_external_signer() {
+		let mut core = Core::new().unwrap();
+		let calls = Arc::new(Mutex::new(Vec::new()));
+		let mut handler = IoHandler::new();
+		add_method(&mut handler, "account_signTransaction", json(&format!(r#"{{"raw": "0x{}", "tx": {{}}}}"#, TRANSFER_RAW)), calls.clone());
+		// signature with a recovery id instead of `v`
+		add_method(&mut handler, "account_signData", format!("0x{}{}00", "11".repeat(32), "22".repeat(32)).into(), calls.clone());
+		let signer = RpcSigner::external(mock_signer(&core, handler));
+		let account: Address = "9d8a25d3d1e7f5e8b0f8c1f1b5e5f3a4d0c9b8a7".into();
+
+		let raw = core.run(signer.sign_transaction(account, transfer(), None, 1)).unwrap();
+		assert_eq!(raw.0, TRANSFER_RAW.from_hex().unwrap());
+
+		let signature = core.run(signer.sign_message(account, b"withdraw".to_vec())).unwrap();
+		assert_eq!(signature, format!("{}{}1b", "11".repeat(32), "22".repeat(32)).parse::<H520>().unwrap());
+
+		let calls = calls.lock().unwrap();
+		assert_eq!(*calls, vec![
+			json(r#"[{
+				"from": "0x9d8a25d3d1e7f5e8b0f8c1f1b5e5f3a4d0c9b8a7",
+				"to": "0x3535353535353535353535353535353535353535",
+				"gas": "0x5208",
+				"gasPrice": "0x4a817c800",
+				"value": "0xde0b6b3a7640000",
+				"data": "0x",
+				"nonce": "0x9",
+				"chainId": "0x1"
+			}]"#),
+			json(r#"["text/plain", "0x9d8a25d3d1e7f5e8b0f8c1f1b5e5f3a4d0c9b8a7", "0x7769746864726177"]"#),
+		]);
+	}
+
+	#[test]
+	fn test_node_signer() {
+		let mut core = Core::new().unwrap();
+		let calls = Arc::new(Mutex::new(Vec::new()));
+		let mut handler = IoHandler::new();
+		add_method(&mut handler, "eth_signTransaction", json(r#"{"raw": "0x02f8", "tx": {}}"#), calls.clone());
+		add_method(&mut handler, "eth_sign", format!("0x{}{}1c", "11".repeat(32), "22".repeat(32)).into(), calls.clone());
+		let signer = RpcSigner::node(mock_signer(&core, handler));
+		let account: Address = "9d8a25d3d1e7f5e8b0f8c1f1b5e5f3a4d0c9b8a7".into();
+
+		let mut tx = transfer();
+		tx.action = Action::Create;
+		tx.gas_price = 40_000_000_000u64.into();
+		let raw = core.run(signer.sign_transaction(account, tx, Some(2_000_000_000u64.into()), 100)).unwrap();
+		assert_eq!(raw, Bytes(vec![0x02, 0xf8]));
+
+		let signature = core.run(signer.sign_message(account, b"withdraw".to_vec())).unwrap();
+		assert_eq!(signature, format!("{}{}1c", "11".repeat(32), "22".repeat(32)).parse::<H520>().unwrap());
+
+		let calls = calls.lock().unwrap();
+		assert_eq!(*calls, vec![
+			json(r#"[{
+				"from": "0x9d8a25d3d1e7f5e8b0f8c1f1b5e5f3a4d0c9b8a7",
+				"gas": "0x5208",
+				"maxFeePerGas": "0x9502f9000",
+				"maxPriorityFeePerGas": "0x77359400",
+				"value": "0xde0b6b3a7640000",
+				"data": "0x",
+				"nonce": "0x9",
+				"chainId": "0x64"
+			}]"#),
+			json(r#"["0x9d8a25d3d1e7f5e8b0f8c1f1b5e5f3a4d0c9b8a7", "0x7769746864726177"]"#),
+		]);
+	}
+
+	#[test]
+	fn test_electrum_signature() {
+		let expected = format!("{}1c", "01".repeat(64)).parse::<H520>().unwrap();
+		assert_eq!(electrum_signature(Bytes(vec![1; 65])).unwrap(), expected);
+		assert_eq!(electrum_signature(Bytes(expected.0.to_vec())).unwrap(), expected);
+		assert!(electrum_signature(Bytes(vec![1; 64])).is_err());
+	}
+}
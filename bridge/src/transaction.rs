@@ -1,19 +1,200 @@
-use error::{Error, ErrorKind};
-use ethcore_transaction::{Transaction, SignedTransaction};
-use web3::types::Bytes;
-use config::Node;
-use app::App;
-use web3::Transport;
+use std::cmp;
+use std::time::{SystemTime, UNIX_EPOCH};
+use ethcore_transaction::{Transaction, SignedTransaction, Action};
+use ethcore::ethstore::ethkey::Signature;
+use rlp::RlpStream;
+use web3::types::{Bytes, H256, U256};
+use keccak_hash::keccak;
+use config::{Node, TransactionType};
+use database::PendingTransaction;
+use signer::{Signer, SignerFuture};
 
-pub fn prepare_raw_transaction<T: Transport>(tx: Transaction, app: &App<T>, node: &Node, chain_id: u64) -> Result<Bytes, Error> {
-	let hash = tx.hash(Some(chain_id));
+/// EIP-2718 type of EIP-1559 transactions.
+const DYNAMIC_FEE_TRANSACTION_TYPE: u8 = 2;
 
-	let sig = app.keystore.sign(node.account, None, hash).map_err(|e| ErrorKind::SignError(e))?;
-	let tx = SignedTransaction::new(tx.with_signature(sig, Some(chain_id))).unwrap();
+/// Signs `tx` with the account of `node` using `signer`.
+///
+/// If `max_priority_fee_per_gas` is set, an EIP-1559 transaction paying at most `tx.gas_price`
+/// per gas is created, otherwise a legacy one.
+pub fn prepare_raw_transaction(tx: Transaction, max_priority_fee_per_gas: Option<U256>, signer: &Signer, node: &Node, chain_id: u64) -> SignerFuture<Bytes> {
+	signer.sign_transaction(node.account, tx, max_priority_fee_per_gas, chain_id)
+}
+
+/// Returns the hash signed by the sender of `tx`.
+pub fn signing_hash(tx: &Transaction, max_priority_fee_per_gas: Option<U256>, chain_id: u64) -> H256 {
+	match max_priority_fee_per_gas {
+		Some(max_priority_fee_per_gas) => {
+			let mut stream = RlpStream::new_list(9);
+			append_dynamic_fee_fields(&mut stream, tx, max_priority_fee_per_gas, chain_id);
+			keccak(typed_envelope(stream))
+		},
+		None => tx.hash(Some(chain_id)),
+	}
+}
+
+/// Returns `tx` signed with `sig`, encoded as sent with `eth_sendRawTransaction`.
+pub fn signed_transaction(tx: Transaction, max_priority_fee_per_gas: Option<U256>, chain_id: u64, sig: Signature) -> Bytes {
+	match max_priority_fee_per_gas {
+		Some(max_priority_fee_per_gas) => {
+			let mut stream = RlpStream::new_list(12);
+			append_dynamic_fee_fields(&mut stream, &tx, max_priority_fee_per_gas, chain_id);
+			stream.append(&sig.v());
+			stream.append(&U256::from(sig.r()));
+			stream.append(&U256::from(sig.s()));
+			Bytes(typed_envelope(stream))
+		},
+		None => {
+			let tx = SignedTransaction::new(tx.with_signature(sig, Some(chain_id))).unwrap();
+
+			use rlp::Encodable;
+			let mut stream = RlpStream::new();
+			tx.rlp_append(&mut stream);
+
+			Bytes(stream.out())
+		},
+	}
+}
+
+/// Appends unsigned fields of an EIP-1559 transaction, `tx.gas_price` is its `max_fee_per_gas`.
+fn append_dynamic_fee_fields(stream: &mut RlpStream, tx: &Transaction, max_priority_fee_per_gas: U256, chain_id: u64) {
+	stream.append(&chain_id);
+	stream.append(&tx.nonce);
+	stream.append(&max_priority_fee_per_gas);
+	stream.append(&tx.gas_price);
+	stream.append(&tx.gas);
+	stream.append(&tx.action);
+	stream.append(&tx.value);
+	stream.append(&tx.data);
+	// access list
+	stream.begin_list(0);
+}
+
+/// Prefixes an EIP-1559 transaction payload with its EIP-2718 type.
+fn typed_envelope(stream: RlpStream) -> Vec<u8> {
+	let mut envelope = vec![DYNAMIC_FEE_TRANSACTION_TYPE];
+	envelope.extend(stream.out());
+	envelope
+}
+
+/// Returns tip of transactions sent to `node` paying at most `max_fee_per_gas`,
+/// `None` if the node is sent legacy transactions.
+pub fn max_priority_fee_per_gas(node: &Node, max_fee_per_gas: U256) -> Option<U256> {
+	match node.transaction_type {
+		TransactionType::Legacy => None,
+		TransactionType::Eip1559 { max_priority_fee_per_gas, .. } => Some(cmp::min(max_priority_fee_per_gas.into(), max_fee_per_gas)),
+	}
+}
+
+/// Returns hash of a signed transaction.
+pub fn raw_transaction_hash(raw: &Bytes) -> H256 {
+	keccak(&raw.0)
+}
+
+/// Seconds since unix epoch.
+pub fn unix_timestamp() -> u64 {
+	SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+/// Creates a record of `tx` which has just been sent as `raw`.
+pub fn pending_transaction(tx: &Transaction, max_priority_fee_per_gas: Option<U256>, raw: Bytes) -> PendingTransaction {
+	PendingTransaction {
+		nonce: tx.nonce,
+		hash: raw_transaction_hash(&raw),
+		replaced_hashes: Vec::new(),
+		raw,
+		gas_price: tx.gas_price,
+		max_priority_fee_per_gas,
+		gas: tx.gas,
+		value: tx.value,
+		data: tx.data.clone().into(),
+		to: match tx.action {
+			Action::Create => None,
+			Action::Call(address) => Some(address),
+		},
+		sent_at: unix_timestamp(),
+	}
+}
+
+/// Recreates the transaction described by `pending`, so that it can be signed again.
+pub fn unsigned_transaction(pending: &PendingTransaction) -> Transaction {
+	Transaction {
+		nonce: pending.nonce,
+		gas_price: pending.gas_price,
+		gas: pending.gas,
+		action: match pending.to {
+			Some(address) => Action::Call(address),
+			None => Action::Create,
+		},
+		value: pending.value,
+		data: pending.data.0.clone(),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use rustc_hex::FromHex;
+	use ethcore_transaction::{Transaction, Action};
+	use ethcore::ethstore::ethkey::Signature;
+	use web3::types::{H256, H520, U256};
+	use super::{signing_hash, signed_transaction, raw_transaction_hash};
+
+	fn transfer() -> Transaction {
+		Transaction {
+			nonce: 9.into(),
+			gas_price: 20_000_000_000u64.into(),
+			gas: 21000.into(),
+			action: Action::Call("3535353535353535353535353535353535353535".into()),
+			value: 1_000_000_000_000_000_000u64.into(),
+			data: vec![],
+		}
+	}
+
+	fn signature(rsv: &str) -> Signature {
+		let rsv: H520 = rsv.into();
+		Signature::from(rsv.0)
+	}
+
+	#[test]
+	fn test_legacy_transaction_encoding() {
+		// example from EIP-155, signed with 0x4646...46
+		let tx = transfer();
+		assert_eq!(signing_hash(&tx, None, 1), H256::from("daf5a779ae972f972197303d7b574746c7ef83eadac0f2791ad23db92e4c8e53"));
+
+		let sig = signature("28ef61340bd939bc2195fe537567866003e1a15d3c71ff63e1590620aa63627667cbe9d8997f761aecb703304b3800ccf555c9f3dc64214b297fb1966a3b6d8300");
+		let expected = "f86c098504a817c800825208943535353535353535353535353535353535353535880de0b6b3a76400008025a028ef61340bd939bc2195fe537567866003e1a15d3c71ff63e1590620aa636276a067cbe9d8997f761aecb703304b3800ccf555c9f3dc64214b297fb1966a3b6d83";
+		assert_eq!(signed_transaction(tx, None, 1, sig).0, expected.from_hex().unwrap());
+	}
+
+	#[test]
+	fn test_dynamic_fee_transaction_encoding() {
+		// the EIP-155 transfer as an EIP-1559 transaction, signed with 0x4646...46
+		let mut tx = transfer();
+		tx.gas_price = 40_000_000_000u64.into();
+		let tip = Some(U256::from(2_000_000_000u64));
+		assert_eq!(signing_hash(&tx, tip, 1), H256::from("98460353e96307ae5853dbbf5ef70cee922a8bfd0f04270cb61e8f6cdc9b5728"));
+
+		let sig = signature("d96a939aa598b136ef52239a282ac3a5ace24616dd8df06287aa4a83a327f4070369d2510bd102b461c552a392bf47d333ed735007e5f0934a09ff3186869a9c01");
+		let expected = "02f873010984773594008509502f9000825208943535353535353535353535353535353535353535880de0b6b3a764000080c001a0d96a939aa598b136ef52239a282ac3a5ace24616dd8df06287aa4a83a327f407a00369d2510bd102b461c552a392bf47d333ed735007e5f0934a09ff3186869a9c";
+		let raw = signed_transaction(tx, tip, 1, sig);
+		assert_eq!(raw.0, expected.from_hex().unwrap());
+		assert_eq!(raw_transaction_hash(&raw), H256::from("95169d68c31273bc5f837dd7e573f476170bcbbb9b98004f166dd99eead850b2"));
+	}
 
-	use rlp::{RlpStream, Encodable};
-	let mut stream = RlpStream::new();
-	tx.rlp_append(&mut stream);
+	#[test]
+	fn test_dynamic_fee_contract_creation_encoding() {
+		let tx = Transaction {
+			nonce: 0.into(),
+			gas_price: 3.into(),
+			gas: 100000.into(),
+			action: Action::Create,
+			value: 0.into(),
+			data: "6080604052".from_hex().unwrap(),
+		};
+		let tip = Some(U256::from(1));
+		assert_eq!(signing_hash(&tx, tip, 100), H256::from("a824073e3bcc1ba29a9c0e392fb6d9cc69df33098e65fbe138a7b50bb9315f72"));
 
-	Ok(Bytes(stream.out()))
+		let sig = signature("ebd5de0215c696a065cd5b96e09472d6b225b5edde0ae7b46dc8d826b300e15f28d8ea02453dcbccedd6ca0ebfd422a4f08044d5c993024c8a490668ad7d30f600");
+		let expected = "02f85464800103830186a08080856080604052c080a0ebd5de0215c696a065cd5b96e09472d6b225b5edde0ae7b46dc8d826b300e15fa028d8ea02453dcbccedd6ca0ebfd422a4f08044d5c993024c8a490668ad7d30f6";
+		assert_eq!(signed_transaction(tx, tip, 100, sig).0, expected.from_hex().unwrap());
+	}
 }
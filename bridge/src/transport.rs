@@ -0,0 +1,365 @@
+//! Transport spreading requests over multiple RPC endpoints of the same chain.
+
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use futures::{future, Async, Future, Poll};
+use serde_json;
+use tokio_timer::{Sleep, Timer};
+use web3::{self, Transport};
+use web3::types::U256;
+use rpc::{self, Value};
+use retry::is_transient_web3_error;
+
+/// Time for which an endpoint which failed is skipped, unless all the other endpoints failed too.
+const UNHEALTHY_ENDPOINT_COOLDOWN_SECS: u64 = 30;
+
+/// Methods whose results are used only if enough endpoints agree on them.
+const QUORUM_METHODS: &[&str] = &["eth_blockNumber", "eth_getLogs"];
+
+#[derive(Debug)]
+struct Endpoint<T> {
+	url: String,
+	transport: T,
+}
+
+#[derive(Debug)]
+struct Health {
+	/// Index of the endpoint requests are sent to.
+	current: usize,
+	/// Time of the last failure of each endpoint which has not responded successfully since.
+	failed_at: Vec<Option<Instant>>,
+}
+
+/// Transport sending requests to one of multiple endpoints and switching to the next one
+/// when the current one fails with a transient error.
+///
+/// If `quorum` is greater than 1, `eth_blockNumber` and `eth_getLogs` requests are sent to all
+/// endpoints and their result is used only if at least `quorum` of them agree on it:
+/// - for `eth_blockNumber` the result is the highest block reached by at least `quorum` endpoints
+/// - for `eth_getLogs` at least `quorum` endpoints have to return exactly the same logs
+#[derive(Clone)]
+pub struct FailoverTransport<T> {
+	endpoints: Arc<Vec<Endpoint<T>>>,
+	health: Arc<Mutex<Health>>,
+	quorum: usize,
+	timer: Timer,
+	/// Time after which a request to a single endpoint is considered failed.
+	endpoint_timeout: Duration,
+}
+
+impl<T: fmt::Debug> fmt::Debug for FailoverTransport<T> {
+	fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+		fmt.debug_struct("FailoverTransport")
+			.field("endpoints", &self.endpoints)
+			.field("quorum", &self.quorum)
+			.finish()
+	}
+}
+
+impl<T: Transport> FailoverTransport<T> {
+	/// Creates new transport using `endpoints` given as pairs of url and transport.
+	///
+	/// Requests to a single endpoint time out after `request_timeout` split between all endpoints,
+	/// so that all of them can be tried before the request as a whole times out.
+	pub fn new(endpoints: Vec<(String, T)>, quorum: usize, timer: Timer, request_timeout: Duration) -> Self {
+		assert!(!endpoints.is_empty(), "at least one endpoint is required; qed");
+		let len = endpoints.len();
+		FailoverTransport {
+			endpoints: Arc::new(endpoints.into_iter().map(|(url, transport)| Endpoint { url, transport }).collect()),
+			health: Arc::new(Mutex::new(Health {
+				current: 0,
+				failed_at: vec![None; len],
+			})),
+			quorum,
+			timer,
+			endpoint_timeout: request_timeout / len as u32,
+		}
+	}
+
+	/// Returns index of the endpoint the next request should be sent to.
+	fn next_endpoint(&self) -> usize {
+		let health = self.health.lock().unwrap();
+		let cooldown = Duration::from_secs(UNHEALTHY_ENDPOINT_COOLDOWN_SECS);
+		let len = self.endpoints.len();
+		(0..len)
+			.map(|offset| (health.current + offset) % len)
+			.find(|&index| health.failed_at[index].map_or(true, |failed_at| failed_at.elapsed() >= cooldown))
+			.unwrap_or(health.current)
+	}
+
+	fn failed(&self, index: usize, err: &web3::Error) {
+		let mut health = self.health.lock().unwrap();
+		health.failed_at[index] = Some(Instant::now());
+		if health.current == index && self.endpoints.len() > 1 {
+			health.current = (index + 1) % self.endpoints.len();
+			warn!("RPC endpoint {} failed: {:?}, switching to {}", self.endpoints[index].url, err, self.endpoints[health.current].url);
+		} else {
+			warn!("RPC endpoint {} failed: {:?}", self.endpoints[index].url, err);
+		}
+	}
+
+	fn succeeded(&self, index: usize) {
+		let mut health = self.health.lock().unwrap();
+		if health.failed_at[index].take().is_some() {
+			info!("RPC endpoint {} is healthy again", self.endpoints[index].url);
+		}
+	}
+
+	fn send_to(&self, index: usize, id: usize, request: rpc::Call) -> EndpointRequest<T::Out> {
+		let endpoint = &self.endpoints[index];
+		EndpointRequest {
+			future: endpoint.transport.send(id, request),
+			timeout: self.timer.sleep(self.endpoint_timeout),
+			url: endpoint.url.clone(),
+		}
+	}
+}
+
+/// Request sent to a single endpoint.
+struct EndpointRequest<F> {
+	future: F,
+	timeout: Sleep,
+	url: String,
+}
+
+impl<F: Future<Item = Value, Error = web3::Error>> Future for EndpointRequest<F> {
+	type Item = Value;
+	type Error = web3::Error;
+
+	fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
+		if let Async::Ready(value) = self.future.poll()? {
+			return Ok(Async::Ready(value));
+		}
+		match self.timeout.poll() {
+			Ok(Async::NotReady) => Ok(Async::NotReady),
+			Ok(Async::Ready(())) => Err(web3::ErrorKind::Transport(format!("request to {} timed out", self.url)).into()),
+			Err(err) => Err(web3::ErrorKind::Transport(format!("request to {} failed: {}", self.url, err)).into()),
+		}
+	}
+}
+
+/// Request sent to the next endpoint each time the previous one fails.
+struct Failover<T: Transport> {
+	transport: FailoverTransport<T>,
+	id: usize,
+	request: rpc::Call,
+	/// Number of endpoints the request has been sent to.
+	attempts: usize,
+	pending: Option<(usize, EndpointRequest<T::Out>)>,
+}
+
+impl<T: Transport> Future for Failover<T> {
+	type Item = Value;
+	type Error = web3::Error;
+
+	fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
+		loop {
+			if self.pending.is_none() {
+				let index = self.transport.next_endpoint();
+				self.attempts += 1;
+				self.pending = Some((index, self.transport.send_to(index, self.id, self.request.clone())));
+			}
+
+			let result = match self.pending {
+				Some((_, ref mut future)) => match future.poll() {
+					Ok(Async::NotReady) => return Ok(Async::NotReady),
+					Ok(Async::Ready(value)) => Ok(value),
+					Err(err) => Err(err),
+				},
+				None => unreachable!("request has been sent above; qed"),
+			};
+
+			let (index, _) = self.pending.take().expect("pending request has just been polled; qed");
+			match result {
+				Ok(value) => {
+					self.transport.succeeded(index);
+					return Ok(Async::Ready(value));
+				},
+				Err(err) => {
+					if !is_transient_web3_error(&err) {
+						return Err(err);
+					}
+					self.transport.failed(index, &err);
+					if self.attempts >= self.transport.endpoints.len() {
+						return Err(err);
+					}
+				},
+			}
+		}
+	}
+}
+
+/// Returns the result agreed on by at least `quorum` of `values`.
+fn quorum_result(method: &str, mut values: Vec<Value>, quorum: usize) -> Result<Value, web3::Error> {
+	if values.len() < quorum {
+		return Err(web3::ErrorKind::Transport(format!("only {} endpoints responded to {}, {} required", values.len(), method, quorum)).into());
+	}
+
+	if method == "eth_blockNumber" {
+		let mut numbers = values.into_iter()
+			.map(serde_json::from_value::<U256>)
+			.collect::<Result<Vec<_>, _>>()
+			.map_err(|err| web3::ErrorKind::Transport(format!("invalid response to {}: {}", method, err)))?;
+		numbers.sort_by(|a, b| b.cmp(a));
+		return Ok(serde_json::to_value(numbers[quorum - 1]).expect("serialization of a number can't fail; qed"));
+	}
+
+	while !values.is_empty() {
+		let value = values.swap_remove(0);
+		let before = values.len();
+		values.retain(|other| *other != value);
+		if before - values.len() + 1 >= quorum {
+			return Ok(value);
+		}
+	}
+	Err(web3::ErrorKind::Transport(format!("endpoints do not agree on result of {}", method)).into())
+}
+
+impl<T: Transport + 'static> Transport for FailoverTransport<T> where T::Out: 'static {
+	type Out = web3::Result<Value>;
+
+	fn prepare(&self, method: &str, params: Vec<Value>) -> (usize, rpc::Call) {
+		self.endpoints[0].transport.prepare(method, params)
+	}
+
+	fn send(&self, id: usize, request: rpc::Call) -> Self::Out {
+		let method = match request {
+			rpc::Call::MethodCall(ref call) => call.method.clone(),
+			_ => String::new(),
+		};
+
+		if self.quorum <= 1 || !QUORUM_METHODS.contains(&method.as_str()) {
+			return Box::new(Failover {
+				transport: self.clone(),
+				id,
+				request,
+				attempts: 0,
+				pending: None,
+			});
+		}
+
+		let requests = (0..self.endpoints.len())
+			.map(|index| self.send_to(index, id, request.clone()).then(move |result| future::ok::<_, web3::Error>((index, result))))
+			.collect::<Vec<_>>();
+		let transport = self.clone();
+		let quorum = self.quorum;
+		Box::new(future::join_all(requests).and_then(move |results| {
+			let mut values = Vec::new();
+			for (index, result) in results {
+				match result {
+					Ok(value) => {
+						transport.succeeded(index);
+						values.push(value);
+					},
+					Err(err) => if is_transient_web3_error(&err) {
+						transport.failed(index, &err);
+					} else {
+						warn!("RPC endpoint {} failed: {:?}", transport.endpoints[index].url, err);
+					},
+				}
+			}
+			quorum_result(&method, values, quorum)
+		}))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::cell::Cell;
+	use std::rc::Rc;
+	use std::time::Duration;
+	use futures::{future, Future};
+	use serde_json;
+	use tokio_timer::Timer;
+	use web3::{self, Transport};
+	use web3::helpers::build_request;
+	use rpc::{self, Value};
+	use super::{FailoverTransport, quorum_result};
+
+	/// Transport responding with the same result to all requests and counting them.
+	#[derive(Debug, Clone)]
+	struct MockedEndpoint {
+		response: Result<Value, String>,
+		requests: Rc<Cell<usize>>,
+	}
+
+	impl MockedEndpoint {
+		fn new(response: Result<Value, &str>) -> Self {
+			MockedEndpoint {
+				response: response.map_err(Into::into),
+				requests: Default::default(),
+			}
+		}
+	}
+
+	impl Transport for MockedEndpoint {
+		type Out = web3::Result<Value>;
+
+		fn prepare(&self, method: &str, params: Vec<Value>) -> (usize, rpc::Call) {
+			(1, build_request(1, method, params))
+		}
+
+		fn send(&self, _id: usize, _request: rpc::Call) -> Self::Out {
+			self.requests.set(self.requests.get() + 1);
+			let response = self.response.clone().map_err(|err| web3::ErrorKind::Transport(err).into());
+			Box::new(future::result(response))
+		}
+	}
+
+	fn failover(endpoints: &[&MockedEndpoint], quorum: usize) -> FailoverTransport<MockedEndpoint> {
+		let endpoints = endpoints.iter()
+			.enumerate()
+			.map(|(i, endpoint)| (format!("http://node{}", i), (*endpoint).clone()))
+			.collect();
+		FailoverTransport::new(endpoints, quorum, Timer::default(), Duration::from_secs(10))
+	}
+
+	#[test]
+	fn test_failover_switches_to_next_endpoint() {
+		let down = MockedEndpoint::new(Err("connection refused"));
+		let up = MockedEndpoint::new(Ok(Value::String("0x10".into())));
+		let transport = failover(&[&down, &up], 1);
+
+		assert_eq!(Value::String("0x10".into()), transport.execute("eth_blockNumber", vec![]).wait().unwrap());
+		assert_eq!(Value::String("0x10".into()), transport.execute("eth_blockNumber", vec![]).wait().unwrap());
+		assert_eq!(1, down.requests.get());
+		assert_eq!(2, up.requests.get());
+	}
+
+	#[test]
+	fn test_failover_fails_when_all_endpoints_fail() {
+		let first = MockedEndpoint::new(Err("connection refused"));
+		let second = MockedEndpoint::new(Err("connection reset"));
+		let transport = failover(&[&first, &second], 1);
+
+		assert!(transport.execute("eth_blockNumber", vec![]).wait().is_err());
+		assert_eq!(1, first.requests.get());
+		assert_eq!(1, second.requests.get());
+	}
+
+	#[test]
+	fn test_quorum_read() {
+		let first = MockedEndpoint::new(Ok(Value::String("0x10".into())));
+		let second = MockedEndpoint::new(Ok(Value::String("0x12".into())));
+		let third = MockedEndpoint::new(Err("connection refused"));
+		let transport = failover(&[&first, &second, &third], 2);
+
+		assert_eq!(Value::String("0x10".into()), transport.execute("eth_blockNumber", vec![]).wait().unwrap());
+		assert_eq!(1, third.requests.get());
+	}
+
+	#[test]
+	fn test_quorum_result() {
+		let numbers = vec![Value::String("0x5".into()), Value::String("0x9".into()), Value::String("0x7".into())];
+		assert_eq!(Value::String("0x9".into()), quorum_result("eth_blockNumber", numbers.clone(), 1).unwrap());
+		assert_eq!(Value::String("0x7".into()), quorum_result("eth_blockNumber", numbers.clone(), 2).unwrap());
+		assert_eq!(Value::String("0x5".into()), quorum_result("eth_blockNumber", numbers.clone(), 3).unwrap());
+		assert!(quorum_result("eth_blockNumber", numbers, 4).is_err());
+
+		let logs: Value = serde_json::from_str(r#"[{"transactionHash": "0x01"}]"#).unwrap();
+		let lie: Value = serde_json::from_str(r#"[]"#).unwrap();
+		assert_eq!(logs, quorum_result("eth_getLogs", vec![logs.clone(), lie.clone(), logs.clone()], 2).unwrap());
+		assert!(quorum_result("eth_getLogs", vec![logs.clone(), lie.clone()], 2).is_err());
+	}
+}
@@ -20,3 +20,18 @@ pub fn web3_filter<I: IntoIterator<Item = Address>>(filter: ethabi::TopicFilter,
 		.address(addresses.into_iter().collect())
 		.topics(t0, t1, t2, t3)
 }
+
+/// Returns a filter matching logs of any of the given events.
+///
+/// Only event signatures (`topic0`) of the filters are taken into account.
+pub fn web3_events_filter<I: IntoIterator<Item = Address>>(filters: Vec<ethabi::TopicFilter>, addresses: I) -> FilterBuilder {
+	let t0 = filters.into_iter()
+		.flat_map(|filter| {
+			let t: Vec<ethabi::Hash> = filter.topic0.into();
+			t
+		})
+		.collect();
+	FilterBuilder::default()
+		.address(addresses.into_iter().collect())
+		.topics(Some(t0), None, None, None)
+}
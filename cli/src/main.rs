@@ -14,17 +14,23 @@ extern crate jsonrpc_core as rpc;
 extern crate version;
 
 use std::{env, fs, io};
+use std::io::Read;
+use std::str::FromStr;
 use std::sync::Arc;
 use std::path::PathBuf;
 use docopt::Docopt;
 use futures::{Stream, future};
 use tokio_core::reactor::Core;
 
+use bridge::admin::AdminApi;
 use bridge::app::App;
 use bridge::bridge::{create_bridge, create_deploy, create_chain_id_retrieval, Deployed};
-use bridge::config::Config;
+use bridge::config::{Config, RpcTransport};
+use bridge::database;
 use bridge::error::{Error, ErrorKind};
-use bridge::web3;
+use bridge::{check, keystore, password};
+use bridge::web3::{self, Transport};
+use bridge::web3::types::Address;
 
 const ERR_UNKNOWN: i32 = 1;
 const ERR_IO_ERROR: i32 = 2;
@@ -33,9 +39,11 @@ const ERR_INSUFFICIENT_FUNDS: i32 = 4;
 const ERR_GAS_TOO_LOW: i32 = 5;
 const ERR_GAS_PRICE_TOO_LOW: i32 = 6;
 const ERR_NONCE_REUSE: i32 = 7;
+const ERR_TRANSACTION_REVERTED: i32 = 8;
 const ERR_CANNOT_CONNECT: i32 = 10;
 const ERR_CONNECTION_LOST: i32 = 11;
 const ERR_BRIDGE_CRASH: i32 = 12;
+const ERR_INVALID_CONFIG: i32 = 13;
 const ERR_RPC_ERROR: i32 = 20;
 
 pub struct UserFacingError(i32, Error);
@@ -74,21 +82,52 @@ POA-Ethereum bridge.
 
 Usage:
     bridge [options] --config <config> --database <database>
+    bridge check-config [options] --config <config>
+    bridge account new --keystore <keystore> [--password <file>]
+    bridge account list --keystore <keystore>
+    bridge account import --keystore <keystore> [--password <file>] <key>
+    bridge account change-password --keystore <keystore> [--password <file>] [--new-password <file>] <address>
     bridge -h | --help
     bridge -v | --version
 
 Options:
     -h, --help                        Display help message and exit.
     -v, --version                     Print version and exit.
-    --allow-insecure-rpc-endpoints    Allow non-HTTPS endpoints
+    --allow-insecure-rpc-endpoints    Allow non-HTTPS and non-WSS endpoints
+
+Account options:
+    --keystore <keystore>             Keystore directory, the `keystore` of the config.
+    --password <file>                 File with the password of the account, it's asked for if not set.
+    --new-password <file>             File with the new password of the account, it's asked for if not set.
+
+Commands:
+    check-config                      Check the config and connections to the nodes, report all problems found.
+
+Account commands:
+    new                               Create an account with a random key.
+    list                              Print addresses of all accounts.
+    import                            Import <key>, a JSON key file or a file with a hex encoded private key.
+                                      JSON keys keep their password, raw keys are encrypted with a new one.
+    change-password                   Change the password of the account with <address>.
 "#;
 
 #[derive(Debug, Deserialize)]
 pub struct Args {
 	arg_config: PathBuf,
 	arg_database: PathBuf,
+	arg_key: PathBuf,
+	arg_address: String,
+	cmd_check_config: bool,
+	cmd_account: bool,
+	cmd_new: bool,
+	cmd_list: bool,
+	cmd_import: bool,
+	cmd_change_password: bool,
 	flag_version: bool,
 	flag_allow_insecure_rpc_endpoints: bool,
+	flag_keystore: PathBuf,
+	flag_password: Option<PathBuf>,
+	flag_new_password: Option<PathBuf>,
 }
 
 use std::sync::atomic::{AtomicBool, Ordering};
@@ -129,28 +168,140 @@ fn execute<S, I>(command: I, running: Arc<AtomicBool>) -> Result<String, UserFac
 		return Ok(version!().into())
 	}
 
+	if args.cmd_account {
+		return account(&args);
+	}
+
+	if args.cmd_check_config {
+		return check_config(&args);
+	}
+
 	info!(target: "bridge", "Loading config");
 	let config = Config::load(args.arg_config, args.flag_allow_insecure_rpc_endpoints)?;
+	// transactions without gas would never be mined
+	let problems = check::check_transactions(&config.txs);
+	if !problems.is_empty() {
+		return Err((ERR_INVALID_CONFIG, ErrorKind::ConfigError(problems.join("\n")).into()).into());
+	}
 
 	info!(target: "bridge", "Starting event loop");
 	let mut event_loop = Core::new().unwrap();
 	let handle = event_loop.handle();
 
-	info!(target: "bridge", "Home rpc host {}", config.clone().home.rpc_host);
-	info!(target: "bridge", "Foreign rpc host {}", config.clone().foreign.rpc_host);
+	if let Some(ref address) = config.metrics_address {
+		bridge::metrics::serve(address, &handle)?;
+	}
+
+	match (&config.home.ipc_path, &config.foreign.ipc_path) {
+		(&Some(ref home), &Some(ref foreign)) => {
+			info!(target: "bridge", "Home ipc path {}", home.display());
+			info!(target: "bridge", "Foreign ipc path {}", foreign.display());
+		},
+		_ => {
+			info!(target: "bridge", "Home rpc host {}", config.clone().home.rpc_host);
+			info!(target: "bridge", "Foreign rpc host {}", config.clone().foreign.rpc_host);
+		},
+	}
 
 	info!(target: "bridge", "Establishing connection:");
 
-	info!(target:"bridge", "  using RPC connection");
-	let app = match App::new_http(config.clone(), &args.arg_database, &handle, running.clone()) {
-		Ok(app) => app,
+	match config.home.rpc_transport() {
+		RpcTransport::Http => {
+			info!(target:"bridge", "  using RPC connection");
+			let app = connect(App::new_http(config.clone(), &args.arg_database, &handle, running.clone()))?;
+			run(app, event_loop)
+		},
+		RpcTransport::WebSocket => {
+			info!(target:"bridge", "  using WebSocket connection");
+			let app = connect(App::new_ws(config.clone(), &args.arg_database, &handle, running.clone()))?;
+			run(app, event_loop)
+		},
+		RpcTransport::Ipc => {
+			info!(target:"bridge", "  using IPC connection");
+			let app = connect(App::new_ipc(config.clone(), &args.arg_database, &handle, running.clone()))?;
+			run(app, event_loop)
+		},
+	}
+}
+
+fn check_config(args: &Args) -> Result<String, UserFacingError> {
+	let config = Config::load(&args.arg_config, args.flag_allow_insecure_rpc_endpoints)
+		.map_err(|e| (ERR_INVALID_CONFIG, e))?;
+	let mut problems = check::check_config(&config);
+
+	let mut event_loop = Core::new().unwrap();
+	let handle = event_loop.handle();
+	problems.extend(event_loop.run(check::check_nodes(&config, &handle))?);
+
+	if problems.is_empty() {
+		Ok("Config is valid".into())
+	} else {
+		Err((ERR_INVALID_CONFIG, ErrorKind::ConfigError(problems.join("\n")).into()).into())
+	}
+}
+
+fn account(args: &Args) -> Result<String, UserFacingError> {
+	if args.cmd_list {
+		let store = keystore::open(&args.flag_keystore)?;
+		let accounts = keystore::list_accounts(&store)?;
+		return Ok(accounts.iter().map(|account| format!("{:?}", account)).collect::<Vec<_>>().join("\n"));
+	}
+
+	let account = if args.cmd_new {
+		let password = new_password(args.flag_password.as_ref())?;
+		keystore::new_account(&keystore::create(&args.flag_keystore)?, &password)?
+	} else if args.cmd_import {
+		let mut key = String::new();
+		fs::File::open(&args.arg_key)?.read_to_string(&mut key)?;
+		if key.trim_left().starts_with('{') {
+			if args.flag_password.is_some() {
+				return Err("JSON keys are imported with their own password, --password can't be used".to_owned().into());
+			}
+			keystore::import_json_key(&args.flag_keystore, &args.arg_key)?
+		} else {
+			let password = new_password(args.flag_password.as_ref())?;
+			keystore::import_raw_key(&keystore::create(&args.flag_keystore)?, &key, &password)?
+		}
+	} else {
+		let account = Address::from_str(args.arg_address.trim_left_matches("0x"))
+			.map_err(|_| format!("Invalid address {}", args.arg_address))?;
+		let store = keystore::open(&args.flag_keystore)?;
+		let old_password = match args.flag_password {
+			Some(ref path) => password::read_file(path)?,
+			None => password::prompt(&format!("Password of {:?}: ", account))?,
+		};
+		let new_password = new_password(args.flag_new_password.as_ref())?;
+		keystore::change_password(&store, account, &old_password, &new_password)?;
+		account
+	};
+
+	Ok(format!("{:?}", account))
+}
+
+/// Reads a new password from `file`, or asks for it twice.
+fn new_password(file: Option<&PathBuf>) -> Result<String, UserFacingError> {
+	if let Some(path) = file {
+		return Ok(password::read_file(path)?);
+	}
+	let password = password::prompt("New password: ")?;
+	if password != password::prompt("Repeat the new password: ")? {
+		return Err("Passwords do not match".to_owned().into());
+	}
+	Ok(password)
+}
+
+fn connect<T: Transport>(app: Result<App<T>, Error>) -> Result<Arc<App<T>>, UserFacingError> {
+	match app {
+		Ok(app) => Ok(Arc::new(app)),
 		Err(e) => {
 			warn!("Can't establish an RPC connection: {:?}", e);
-			return Err((ERR_CANNOT_CONNECT, e).into());
+			Err((ERR_CANNOT_CONNECT, e).into())
 		},
-	};
+	}
+}
 
-	let app = Arc::new(app);
+fn run<T: Transport + Clone + 'static>(app: Arc<App<T>>, mut event_loop: Core) -> Result<String, UserFacingError> where T::Out: 'static {
+	let handle = event_loop.handle();
 
 	info!(target: "bridge", "Acquiring home & foreign chain ids");
 	let home_chain_id = event_loop.run(create_chain_id_retrieval(app.clone(), app.connections.home.clone(), app.config.home.clone())).expect("can't retrieve home chain_id");
@@ -178,7 +329,7 @@ fn execute<S, I>(command: I, running: Arc<AtomicBool>) -> Result<String, UserFac
 		Deployed::New(database) => {
 			info!(target: "bridge", "Deployed new bridge contracts");
 			info!(target: "bridge", "\n\n{}\n", database);
-			database.save(fs::File::create(&app.database_path)?)?;
+			database::open(app.config.database_backend, &app.database_path)?.save(&database)?;
 			database
 		},
 		Deployed::Existing(database) => {
@@ -188,7 +339,13 @@ fn execute<S, I>(command: I, running: Arc<AtomicBool>) -> Result<String, UserFac
 	};
 
 	info!(target: "bridge", "Starting listening to events");
-	let bridge = create_bridge(app.clone(), &database, &handle, home_chain_id, foreign_chain_id).and_then(|_| future::ok(true)).collect();
+	let backend = database::open(app.config.database_backend, &app.database_path)?;
+	let bridge = create_bridge(app.clone(), backend, &database, &handle, home_chain_id, foreign_chain_id);
+	if let Some(ref address) = app.config.admin_rpc_address {
+		let api = AdminApi::new(&app.config.home, &app.config.foreign, app.switches.clone(), bridge.state());
+		bridge::admin::serve(address, &handle, api)?;
+	}
+	let bridge = bridge.and_then(|_| future::ok(true)).collect();
 	let mut result = event_loop.run(bridge);
 	loop {
 		match result {
@@ -197,6 +354,11 @@ fn execute<S, I>(command: I, running: Arc<AtomicBool>) -> Result<String, UserFac
 				result = Err(*e);
 				continue;
 			}
+			Err(Error(ErrorKind::RetriesExhausted(e, attempts), _)) => {
+				error!("Giving up after {} retries", attempts);
+				result = Err(*e);
+				continue;
+			}
 			Err(Error(ErrorKind::Web3(web3::error::Error(web3::error::ErrorKind::Io(e), _)), _)) => {
 				if e.kind() == ::std::io::ErrorKind::BrokenPipe {
 					error!("Connection to a node has been severed");
@@ -214,6 +376,14 @@ fn execute<S, I>(command: I, running: Arc<AtomicBool>) -> Result<String, UserFac
 				error!("Insufficient funds, terminating");
 				return Err((ERR_INSUFFICIENT_FUNDS, e.into()).into());
 			},
+			Err(e @ Error(ErrorKind::GasEstimateTooHigh(..), _)) => {
+				error!("Transaction gas estimate exceeds max_gas, terminating");
+				return Err((ERR_GAS_TOO_LOW, e.into()).into());
+			},
+			Err(e @ Error(ErrorKind::TransactionReverted(_), _)) => {
+				error!("Transaction reverted, terminating");
+				return Err((ERR_TRANSACTION_REVERTED, e.into()).into());
+			},
 			Err(Error(ErrorKind::Web3(web3::error::Error(web3::error::ErrorKind::Rpc(e), _)), _)) => {
 				if e.code == rpc::ErrorCode::ServerError(-32010) && e.message.starts_with("Insufficient funds") {
 					error!("Insufficient funds, terminating");
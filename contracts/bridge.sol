@@ -86,6 +86,16 @@ library HelpersTest {
 // `internal` so they get compiled into contracts using them.
 library MessageSigning {
     function recoverAddressFromSignedMessage(bytes signature, bytes message) internal pure returns (address) {
+        return recoverAddress(signature, hashMessage(message));
+    }
+
+    /// same as `recoverAddressFromSignedMessage` for a signed 32 bytes long `hash`.
+    function recoverAddressFromSignedHash(bytes signature, bytes32 hash) internal pure returns (address) {
+        bytes memory prefix = "\x19Ethereum Signed Message:\n32";
+        return recoverAddress(signature, keccak256(prefix, hash));
+    }
+
+    function recoverAddress(bytes signature, bytes32 prefixedHash) internal pure returns (address) {
         require(signature.length == 65);
         bytes32 r;
         bytes32 s;
@@ -96,7 +106,7 @@ library MessageSigning {
             s := mload(add(signature, 0x40))
             v := mload(add(signature, 0x60))
         }
-        return ecrecover(hashMessage(message), uint8(v), r, s);
+        return ecrecover(prefixedHash, uint8(v), r, s);
     }
 
     function hashMessage(bytes message) internal pure returns (bytes32) {
@@ -111,6 +121,10 @@ library MessageSigningTest {
     function recoverAddressFromSignedMessage(bytes signature, bytes message) public pure returns (address) {
         return MessageSigning.recoverAddressFromSignedMessage(signature, message);
     }
+
+    function recoverAddressFromSignedHash(bytes signature, bytes32 hash) public pure returns (address) {
+        return MessageSigning.recoverAddressFromSignedHash(signature, hash);
+    }
 }
 
 
@@ -121,6 +135,7 @@ library Message {
     // offset 52: 32 bytes :: uint256 - value
     // offset 84: 32 bytes :: bytes32 - transaction hash
     // offset 116: 32 bytes :: uint256 - home gas price
+    // offset 148: 20 bytes :: address - home token address (only in 136 bytes long messages)
 
     // bytes 1 to 32 are 0 because message length is stored as little endian.
     // mload always reads 32 bytes.
@@ -168,6 +183,16 @@ library Message {
         }
         return gasPrice;
     }
+
+    function getToken(bytes message) internal pure returns (address) {
+        require(message.length == 136);
+        address token;
+        // solium-disable-next-line security/no-inline-assembly
+        assembly {
+            token := mload(add(message, 136))
+        }
+        return token;
+    }
 }
 
 
@@ -188,6 +213,10 @@ library MessageTest {
     function getHomeGasPrice(bytes message) public pure returns (uint256) {
         return Message.getHomeGasPrice(message);
     }
+
+    function getToken(bytes message) public pure returns (address) {
+        return Message.getToken(message);
+    }
 }
 
 /// This contract introduces a new field which can be used by new bridge
@@ -206,6 +235,133 @@ contract BridgeDeploymentAddressStorage {
     }
 }
 
+/// Authorities of a bridge contract.
+///
+/// Authorities can add and remove authorities and change the number of
+/// required signatures. A change is applied once `requiredSignatures`
+/// authorities requested it. The initial set and all changes are announced
+/// with events, so that bridge processes can follow the current set.
+contract BridgeAuthorities {
+    /// Number of authorities signatures required by the bridge.
+    ///
+    /// Must not be greater than number of authorities.
+    uint256 public requiredSignatures;
+
+    /// Contract authorities.
+    address[] public authorities;
+
+    /// Tells whether an address is one of `authorities`.
+    mapping (address => bool) public isAuthority;
+
+    /// Number of changes applied so far. Approvals of a change
+    /// only count until another change is applied.
+    uint256 public appliedChanges;
+
+    /// Authorities who approved a change and numbers of approvals
+    mapping (bytes32 => bool) change_approvals;
+    mapping (bytes32 => uint256) num_change_approvals;
+
+    /// Event created when an authority is added.
+    event AuthorityAdded(address authority);
+
+    /// Event created when an authority is removed.
+    event AuthorityRemoved(address authority);
+
+    /// Event created when number of required signatures is changed.
+    event RequiredSignaturesChanged(uint256 requiredSignatures);
+
+    /// Constructor.
+    function BridgeAuthorities(uint256 requiredSignaturesParam, address[] authoritiesParam) public {
+        require(requiredSignaturesParam != 0);
+        require(requiredSignaturesParam <= authoritiesParam.length);
+
+        for (uint i = 0; i < authoritiesParam.length; i++) {
+            require(!isAuthority[authoritiesParam[i]]);
+            isAuthority[authoritiesParam[i]] = true;
+            authorities.push(authoritiesParam[i]);
+            AuthorityAdded(authoritiesParam[i]);
+        }
+
+        requiredSignatures = requiredSignaturesParam;
+        RequiredSignaturesChanged(requiredSignatures);
+    }
+
+    /// require that sender is an authority
+    modifier onlyAuthority() {
+        require(isAuthority[msg.sender]);
+        _;
+    }
+
+    /// Returns number of authorities.
+    function authoritiesCount() public view returns (uint256) {
+        return authorities.length;
+    }
+
+    /// Approves adding `authority`.
+    function addAuthority(address authority) public onlyAuthority() {
+        require(authority != address(0x0));
+        require(!isAuthority[authority]);
+
+        if (approveChange(keccak256("addAuthority", authority))) {
+            isAuthority[authority] = true;
+            authorities.push(authority);
+            AuthorityAdded(authority);
+        }
+    }
+
+    /// Approves removing `authority`. Number of authorities
+    /// can not drop below `requiredSignatures`.
+    function removeAuthority(address authority) public onlyAuthority() {
+        require(isAuthority[authority]);
+        require(authorities.length > requiredSignatures);
+
+        if (approveChange(keccak256("removeAuthority", authority))) {
+            for (uint i = 0; i < authorities.length; i++) {
+                if (authorities[i] == authority) {
+                    authorities[i] = authorities[authorities.length - 1];
+                    authorities.length--;
+                    break;
+                }
+            }
+            isAuthority[authority] = false;
+            AuthorityRemoved(authority);
+        }
+    }
+
+    /// Approves changing number of required signatures to `value`.
+    function setRequiredSignatures(uint256 value) public onlyAuthority() {
+        require(value != 0);
+        require(value <= authorities.length);
+        require(value != requiredSignatures);
+
+        if (approveChange(keccak256("setRequiredSignatures", value))) {
+            requiredSignatures = value;
+            RequiredSignaturesChanged(value);
+        }
+    }
+
+    /// Records approval of the `change` by the sender.
+    /// Returns true if the change has been approved by `requiredSignatures` authorities.
+    function approveChange(bytes32 change) internal returns (bool) {
+        bytes32 hash_change = keccak256(change, appliedChanges);
+        bytes32 hash_sender = keccak256(msg.sender, hash_change);
+
+        // Duplicated approvals
+        require(!change_approvals[hash_sender]);
+        change_approvals[hash_sender] = true;
+
+        uint256 approvals = num_change_approvals[hash_change] + 1;
+        num_change_approvals[hash_change] = approvals;
+
+        if (approvals < requiredSignatures) {
+            return false;
+        }
+
+        appliedChanges++;
+        return true;
+    }
+}
+
 /// Due to nature of bridge operations it makes sense to have the same value
 /// of gas consumption limits which will distributed among all validators serving
 /// particular bridge. This approach introduces few advantages:
@@ -215,44 +371,48 @@ contract BridgeDeploymentAddressStorage {
 /// --- as soon as upgradable bridge contract is implemented these limits needs
 ///     to be updated every time the contract is upgraded. Validators could get
 ///     an event that limits updated and use new values to send transactions.
-contract HomeBridgeGasConsumptionLimitsStorage {
+contract HomeBridgeGasConsumptionLimitsStorage is BridgeAuthorities {
     uint256 public gasLimitWithdrawRelay;
 
-    event GasConsumptionLimitsUpdated(uint256);
+    event GasConsumptionLimitsUpdated(uint256 withdrawRelay);
 
-    function setGasLimitWithdrawRelay(uint256 gas) {
-        gasLimitWithdrawRelay = gas;
+    /// Approves changing the gas limit of `HomeBridge.withdraw` transactions.
+    function setGasLimitWithdrawRelay(uint256 gas) public onlyAuthority() {
+        if (approveChange(keccak256("setGasLimitWithdrawRelay", gas))) {
+            gasLimitWithdrawRelay = gas;
 
-        GasConsumptionLimitsUpdated(gasLimitWithdrawRelay);
+            GasConsumptionLimitsUpdated(gasLimitWithdrawRelay);
+        }
     }
 }
 
-contract ForeignBridgeGasConsumptionLimitsStorage {
+contract ForeignBridgeGasConsumptionLimitsStorage is BridgeAuthorities {
     uint256 public gasLimitDepositRelay;
     uint256 public gasLimitWithdrawConfirm;
 
-    event GasConsumptionLimitsUpdated(uint256, uint256);
+    event GasConsumptionLimitsUpdated(uint256 depositRelay, uint256 withdrawConfirm);
 
-    function setGasLimitDepositRelay(uint256 gas) {
-        gasLimitDepositRelay = gas;
+    /// Approves changing the gas limit of `ForeignBridge.deposit` transactions.
+    function setGasLimitDepositRelay(uint256 gas) public onlyAuthority() {
+        if (approveChange(keccak256("setGasLimitDepositRelay", gas))) {
+            gasLimitDepositRelay = gas;
 
-        GasConsumptionLimitsUpdated(gasLimitDepositRelay, gasLimitWithdrawConfirm);
+            GasConsumptionLimitsUpdated(gasLimitDepositRelay, gasLimitWithdrawConfirm);
+        }
     }
 
-    function setGasLimitWithdrawConfirm(uint256 gas) {
-        gasLimitWithdrawConfirm = gas;
+    /// Approves changing the gas limit of `ForeignBridge.submitSignature` transactions.
+    function setGasLimitWithdrawConfirm(uint256 gas) public onlyAuthority() {
+        if (approveChange(keccak256("setGasLimitWithdrawConfirm", gas))) {
+            gasLimitWithdrawConfirm = gas;
 
-        GasConsumptionLimitsUpdated(gasLimitDepositRelay, gasLimitWithdrawConfirm);
+            GasConsumptionLimitsUpdated(gasLimitDepositRelay, gasLimitWithdrawConfirm);
+        }
     }
 }
 
 contract HomeBridge is BridgeDeploymentAddressStorage, 
                        HomeBridgeGasConsumptionLimitsStorage {
-    /// Number of authorities signatures required to withdraw the money.
-    ///
-    /// Must be lesser than number of authorities.
-    uint256 public requiredSignatures;
-
     /// The gas cost of calling `HomeBridge.withdraw`.
     ///
     /// Is subtracted from `value` on withdraw.
@@ -260,11 +420,8 @@ contract HomeBridge is BridgeDeploymentAddressStorage,
     /// this shuts down attacks that exhaust authorities funds on home chain.
     uint256 public estimatedGasCostOfWithdraw;
 
-    /// Contract authorities.
-    address[] public authorities;
-
     /// Used foreign transaction hashes.
-    mapping (bytes32 => bool) withdraws;
+    mapping (bytes32 => bool) public withdraws;
 
     /// Event created on money deposit.
     event Deposit (address recipient, uint256 value);
@@ -277,12 +434,8 @@ contract HomeBridge is BridgeDeploymentAddressStorage,
         uint256 requiredSignaturesParam,
         address[] authoritiesParam,
         uint256 estimatedGasCostOfWithdrawParam
-    ) public
+    ) public BridgeAuthorities(requiredSignaturesParam, authoritiesParam)
     {
-        require(requiredSignaturesParam != 0);
-        require(requiredSignaturesParam <= authoritiesParam.length);
-        requiredSignatures = requiredSignaturesParam;
-        authorities = authoritiesParam;
         estimatedGasCostOfWithdraw = estimatedGasCostOfWithdrawParam;
     }
 
@@ -349,15 +502,89 @@ contract ERC20 {
     function transferFrom(address from, address to, uint256 value) public returns (bool);
     function allowance(address owner, address spender) public constant returns (uint256);
     function balanceOf(address tokenOwner) public constant returns (uint balance);
+
+    event Transfer(address indexed from, address indexed to, uint256 value);
+}
+
+/// Home side of the ERC20-to-ERC20 bridge.
+///
+/// Instead of accepting native coins it locks tokens of `erc20token`.
+/// Authorities pick up `Transfer` events of the token to this contract
+/// and relay them to `ForeignBridge.deposit`.
+contract HomeBridgeErc20 is BridgeDeploymentAddressStorage,
+                            HomeBridgeGasConsumptionLimitsStorage {
+    /// Token locked by the bridge.
+    ERC20 public erc20token;
+
+    /// Other tokens locked by the bridge. Withdraws of these tokens
+    /// carry the token address in the message.
+    mapping (address => bool) public tokens;
+
+    /// Used foreign transaction hashes.
+    mapping (bytes32 => bool) public withdraws;
+
+    /// Event created on tokens release.
+    event Withdraw (address recipient, uint256 value);
+
+    /// Constructor.
+    function HomeBridgeErc20(
+        uint256 requiredSignaturesParam,
+        address[] authoritiesParam,
+        ERC20 erc20tokenParam,
+        address[] tokensParam
+    ) public BridgeAuthorities(requiredSignaturesParam, authoritiesParam)
+    {
+        require(erc20tokenParam != address(0x0));
+        erc20token = erc20tokenParam;
+
+        for (uint i = 0; i < tokensParam.length; i++) {
+            tokens[tokensParam[i]] = true;
+        }
+    }
+
+    /// Locks `_value` of tokens which `_from` allowed to spend with
+    /// `approveAndCall`. Tokens sent to this contract with a plain
+    /// `transfer` are relayed the same way, both emit `Transfer`.
+    function receiveApproval(address _from, uint256 _value, ERC20 _tokenContract, bytes _msg) external returns(bool) {
+        require(msg.sender == address(erc20token) || tokens[msg.sender]);
+        require(ERC20(msg.sender).transferFrom(_from, this, _value));
+
+        return true;
+    }
+
+    /// final step of a withdraw.
+    /// checks that `requiredSignatures` `authorities` have signed of on the `message`.
+    /// then releases `value` of locked tokens to `recipient` (both extracted from `message`).
+    /// 116 bytes long messages release `erc20token`, 136 bytes long messages
+    /// release the token they end with.
+    /// `vs`, `rs`, `ss` are the components of the signatures.
+    function releaseTokens(uint8[] vs, bytes32[] rs, bytes32[] ss, bytes message) public {
+        require(message.length == 116 || message.length == 136);
+
+        // check that at least `requiredSignatures` `authorities` have signed `message`
+        require(Helpers.hasEnoughValidSignatures(message, vs, rs, ss, authorities, requiredSignatures));
+
+        address recipient = Message.getRecipient(message);
+        uint256 value = Message.getValue(message);
+        bytes32 hash = Message.getTransactionHash(message);
+
+        // Duplicated withdraw or reentry.
+        require(!withdraws[hash]);
+        withdraws[hash] = true;
+
+        ERC20 token = erc20token;
+        if (message.length == 136) {
+            token = ERC20(Message.getToken(message));
+            require(tokens[token]);
+        }
+        require(token.transfer(recipient, value));
+
+        Withdraw(recipient, value);
+    }
 }
 
 contract ForeignBridge is BridgeDeploymentAddressStorage, 
                           ForeignBridgeGasConsumptionLimitsStorage {
-    /// Number of authorities signatures required to withdraw the money.
-    ///
-    /// Must be less than number of authorities.
-    uint256 public requiredSignatures;
-
     uint256 public estimatedGasCostOfWithdraw;
 
     // Original parity-bridge assumes that anyone could forward final
@@ -370,9 +597,6 @@ contract ForeignBridge is BridgeDeploymentAddressStorage,
     // to 1 Gwei which is minimal gasprice for POA network.
     uint256 homeGasPrice = 1000000000 wei;
 
-    /// Contract authorities.
-    mapping (address => bool) authorities;
-
     /// Pending mesages
     mapping (bytes32 => bytes) messages;
     /// ???
@@ -381,10 +605,14 @@ contract ForeignBridge is BridgeDeploymentAddressStorage,
     /// Pending deposits and authorities who confirmed them
     mapping (bytes32 => bool) messages_signed;
     mapping (bytes32 => uint) num_messages_signed;
+    /// Messages for which `CollectedSignatures` has been emitted
+    mapping (bytes32 => bool) messages_collected;
 
     /// Pending deposits and authorities who confirmed them
     mapping (bytes32 => bool) deposits_signed;
     mapping (bytes32 => uint) num_deposits_signed;
+    /// Deposits which have been completed
+    mapping (bytes32 => bool) deposits_processed;
 
     /// Token to work with
     ERC20 public erc20token;
@@ -393,42 +621,48 @@ contract ForeignBridge is BridgeDeploymentAddressStorage,
     mapping (bytes32 => bool) tokenAddressAprroval_signs;
     mapping (address => uint256) num_tokenAddressAprroval_signs;
 
+    /// Tokens other than `erc20token` registered by authorities
+    mapping (address => bool) public tokens;
+
+    /// List of authorities confirmed to register a token
+    mapping (bytes32 => bool) tokenRegistration_signs;
+    mapping (address => uint256) num_tokenRegistration_signs;
+
     /// triggered when relay of deposit from HomeBridge is complete
     event Deposit(address recipient, uint256 value);
 
     /// Event created on money withdraw.
     event Withdraw(address recipient, uint256 value, uint256 homeGasPrice);
 
-    /// Collected signatures which should be relayed to home chain.
+    /// Collected signatures which should be relayed to home chain by the authority which signed last.
     event CollectedSignatures(address authorityResponsibleForRelay, bytes32 messageHash, uint256 NumberOfCollectedSignatures);
 
+    /// Signature submitted after `CollectedSignatures`. If home requires more signatures than foreign,
+    /// the withdraw is relayed by the authority whose signature completes them.
+    event CollectedAdditionalSignature(address authorityResponsibleForRelay, bytes32 messageHash, uint256 NumberOfCollectedSignatures);
+
     /// Event created when new token address is set up.
     event TokenAddress(address token);
 
+    /// Event created when a token is registered.
+    event TokenRegistered(address token);
+
+    /// triggered when relay of deposit of a registered token is complete
+    event TokenDeposit(address token, address recipient, uint256 value);
+
+    /// Event created on withdraw of a registered token.
+    event TokenWithdraw(address token, address recipient, uint256 value, uint256 homeGasPrice);
+
     /// Constructor.
     function ForeignBridge(
         uint256 _requiredSignatures,
         address[] _authorities,
         uint256 _estimatedGasCostOfWithdraw
-    ) public
+    ) public BridgeAuthorities(_requiredSignatures, _authorities)
     {
-        require(_requiredSignatures != 0);
-        require(_requiredSignatures <= _authorities.length);
-        requiredSignatures = _requiredSignatures;
-
-        for (uint i = 0; i < _authorities.length; i++) {
-            authorities[_authorities[i]] = true;
-        }
-
         estimatedGasCostOfWithdraw = _estimatedGasCostOfWithdraw;
     }
 
-    /// require that sender is an authority
-    modifier onlyAuthority() {
-        require(authorities[msg.sender]);
-        _;
-    }
-
     /// Set up the token address. It allows to set up or change
     /// the ERC20 token address only if authorities confirmed this.
     ///
@@ -444,13 +678,36 @@ contract ForeignBridge is BridgeDeploymentAddressStorage,
         uint signed = num_tokenAddressAprroval_signs[address(token)] + 1;
         num_tokenAddressAprroval_signs[address(token)] = signed;
 
-        // TODO: this may cause troubles if requriedSignatures len is changed
-        if (signed == requiredSignatures) {
+        // `requiredSignatures` might have been lowered after the last confirmation
+        if (signed >= requiredSignatures && erc20token != token) {
             erc20token = ERC20(token);
             TokenAddress(token);
         }
     }
 
+    /// Registers a token in addition to `erc20token`. The token is
+    /// registered once `requiredSignatures` authorities confirmed it.
+    ///
+    /// token address (address)
+    function registerToken(ERC20 token) public onlyAuthority() {
+        require(token != address(0x0));
+        require(token != erc20token);
+
+        // Duplicated registration
+        bytes32 token_sender = keccak256(msg.sender, token);
+        require(!tokenRegistration_signs[token_sender]);
+        tokenRegistration_signs[token_sender] = true;
+
+        uint signed = num_tokenRegistration_signs[address(token)] + 1;
+        num_tokenRegistration_signs[address(token)] = signed;
+
+        // `requiredSignatures` might have been lowered after the last confirmation
+        if (signed >= requiredSignatures && !tokens[token]) {
+            tokens[token] = true;
+            TokenRegistered(token);
+        }
+    }
+
     /// Used to transfer tokens to the `recipient`.
     /// The bridge contract must own enough tokens to release them for 
     /// recipients. Tokens must be transfered to the bridge contract BEFORE
@@ -462,12 +719,19 @@ contract ForeignBridge is BridgeDeploymentAddressStorage,
     /// deposit recipient (bytes20)
     /// deposit value (uint256)
     /// mainnet transaction hash (bytes32) // to avoid transaction duplication
-    function deposit(address recipient, uint value, bytes32 transactionHash) public onlyAuthority() {
+    /// authority signature of `keccak256(recipient, value, transactionHash)`
+    ///
+    /// The deposit is counted for the authority which signed it,
+    /// so it can be relayed from any account.
+    function deposit(address recipient, uint value, bytes32 transactionHash, bytes signature) public {
         require(erc20token != address(0x0));
 
-        // Protection from misbehaing authority
         bytes32 hash_msg = keccak256(recipient, value, transactionHash);
-        bytes32 hash_sender = keccak256(msg.sender, hash_msg);
+        address authority = MessageSigning.recoverAddressFromSignedHash(signature, hash_msg);
+        require(isAuthority[authority]);
+
+        // Protection from misbehaing authority
+        bytes32 hash_sender = keccak256(authority, hash_msg);
 
         // Duplicated deposits
         require(!deposits_signed[hash_sender]);
@@ -476,8 +740,9 @@ contract ForeignBridge is BridgeDeploymentAddressStorage,
         uint signed = num_deposits_signed[hash_msg] + 1;
         num_deposits_signed[hash_msg] = signed;
 
-        // TODO: this may cause troubles if requriedSignatures len is changed
-        if (signed == requiredSignatures) {
+        // `requiredSignatures` might have been lowered after the last confirmation
+        if (signed >= requiredSignatures && !deposits_processed[hash_msg]) {
+            deposits_processed[hash_msg] = true;
             // If the bridge contract does not own enough tokens to transfer
             // it will couse funds lock on the home side of the bridge
             erc20token.transfer(recipient, value);
@@ -485,6 +750,35 @@ contract ForeignBridge is BridgeDeploymentAddressStorage,
         }
     }
 
+    /// Same as `deposit` for a registered `token`,
+    /// `signature` is of `keccak256(token, recipient, value, transactionHash)`.
+    function depositToken(ERC20 token, address recipient, uint value, bytes32 transactionHash, bytes signature) public {
+        require(tokens[token]);
+
+        bytes32 hash_msg = keccak256(token, recipient, value, transactionHash);
+        address authority = MessageSigning.recoverAddressFromSignedHash(signature, hash_msg);
+        require(isAuthority[authority]);
+
+        // Protection from misbehaing authority
+        bytes32 hash_sender = keccak256(authority, hash_msg);
+
+        // Duplicated deposits
+        require(!deposits_signed[hash_sender]);
+        deposits_signed[hash_sender]= true;
+
+        uint signed = num_deposits_signed[hash_msg] + 1;
+        num_deposits_signed[hash_msg] = signed;
+
+        // `requiredSignatures` might have been lowered after the last confirmation
+        if (signed >= requiredSignatures && !deposits_processed[hash_msg]) {
+            deposits_processed[hash_msg] = true;
+            // If the bridge contract does not own enough tokens to transfer
+            // it will couse funds lock on the home side of the bridge
+            require(token.transfer(recipient, value));
+            TokenDeposit(token, recipient, value);
+        }
+    }
+
     /// Used to transfer `value` of tokens from `_from`s balance on local 
     /// (`foreign`) chain to the same address (`_from`) on `home` chain.
     /// Transfer of tokens within local (`foreign`) chain performed by usual
@@ -502,12 +796,20 @@ contract ForeignBridge is BridgeDeploymentAddressStorage,
     /// An authority will pick up `CollectedSignatures` an call
     /// `HomeBridge.withdraw` which transfers `value - relayCost` to the
     /// recipient completing the transfer.
+    ///
+    /// Withdraws of registered tokens emit `TokenWithdraw` instead.
     function receiveApproval(address _from, uint256 _value, ERC20 _tokenContract, bytes _msg) external returns(bool) {
-        require(erc20token != address(0x0));
-        require(msg.sender == address(erc20token));
-        require(erc20token.allowance(_from, this) >= _value);
-        erc20token.transferFrom(_from, this, _value);
-        Withdraw(_from, _value, homeGasPrice);
+        if (msg.sender == address(erc20token)) {
+            require(erc20token.allowance(_from, this) >= _value);
+            erc20token.transferFrom(_from, this, _value);
+            Withdraw(_from, _value, homeGasPrice);
+        } else {
+            require(tokens[msg.sender]);
+            ERC20 token = ERC20(msg.sender);
+            require(token.allowance(_from, this) >= _value);
+            require(token.transferFrom(_from, this, _value));
+            TokenWithdraw(token, _from, _value, homeGasPrice);
+        }
 
         return true;
     }
@@ -523,15 +825,20 @@ contract ForeignBridge is BridgeDeploymentAddressStorage,
     /// withdrawal recipient (bytes20)
     /// withdrawal value (uint256)
     /// foreign transaction hash (bytes32) // to avoid transaction duplication
-    function submitSignature(bytes signature, bytes message) public onlyAuthority() {
-        // ensure that `signature` is really `message` signed by `msg.sender`
-        require(msg.sender == MessageSigning.recoverAddressFromSignedMessage(signature, message));
+    /// home gas price (uint256)
+    /// home token address (bytes20) // only for withdraws of registered tokens
+    ///
+    /// `signature` has to be made by an authority, but it can be submitted by any account,
+    /// so that authorities don't have to pay gas with their signing keys.
+    function submitSignature(bytes signature, bytes message) public {
+        address authority = MessageSigning.recoverAddressFromSignedMessage(signature, message);
+        require(isAuthority[authority]);
 
-        require(message.length == 116);
+        require(message.length == 116 || message.length == 136);
         bytes32 hash = keccak256(message);
-        bytes32 hash_sender = keccak256(msg.sender, hash);
+        bytes32 hash_sender = keccak256(authority, hash);
 
-        uint signed = num_messages_signed[hash_sender] + 1;
+        uint signed = num_messages_signed[hash] + 1;
 
         if (signed > 1) {
             // Duplicated signatures
@@ -547,11 +854,14 @@ contract ForeignBridge is BridgeDeploymentAddressStorage,
         bytes32 sign_idx = keccak256(hash, (signed-1));
         signatures[sign_idx]= signature;
 
-        num_messages_signed[hash_sender] = signed;
+        num_messages_signed[hash] = signed;
 
-        // TODO: this may cause troubles if requiredSignatures len is changed
-        if (signed == requiredSignatures) {
-            CollectedSignatures(msg.sender, hash, signed);
+        // `requiredSignatures` might have been lowered after the last signature
+        if (signed >= requiredSignatures && !messages_collected[hash]) {
+            messages_collected[hash] = true;
+            CollectedSignatures(authority, hash, signed);
+        } else if (messages_collected[hash]) {
+            CollectedAdditionalSignature(authority, hash, signed);
         }
     }
 
@@ -565,4 +875,21 @@ contract ForeignBridge is BridgeDeploymentAddressStorage,
     function message(bytes32 hash) public view returns (bytes) {
         return messages[hash];
     }
+
+    /// Returns true if a deposit signed by `authority` has already been relayed.
+    function isDepositSigned(address authority, address recipient, uint value, bytes32 transactionHash) public view returns (bool) {
+        bytes32 hash_msg = keccak256(recipient, value, transactionHash);
+        return deposits_signed[keccak256(authority, hash_msg)];
+    }
+
+    /// Returns true if a deposit of a registered `token` signed by `authority` has already been relayed.
+    function isTokenDepositSigned(address authority, address token, address recipient, uint value, bytes32 transactionHash) public view returns (bool) {
+        bytes32 hash_msg = keccak256(token, recipient, value, transactionHash);
+        return deposits_signed[keccak256(authority, hash_msg)];
+    }
+
+    /// Returns true if `authority` has already submitted a signature of the `message`.
+    function isMessageSigned(address authority, bytes message) public view returns (bool) {
+        return messages_signed[keccak256(authority, keccak256(message))];
+    }
 }
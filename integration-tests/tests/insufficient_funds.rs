@@ -192,6 +192,8 @@ fn test_insufficient_funds() {
 		.arg("RUST_BACKTRACE=1")
 		.arg("../target/debug/bridge")
 		.env("RUST_LOG", "info")
+		// the authority account has an empty password
+		.env("BRIDGE_PASSWORD", "")
 		.arg("--config").arg("bridge_config_gas_price.toml")
 		.arg("--database").arg("tmp/bridge1_db.txt")
 		.arg("--allow-insecure-rpc-endpoints")
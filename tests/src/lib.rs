@@ -1,3 +1,4 @@
+#[macro_use]
 extern crate serde_json;
 extern crate futures;
 extern crate jsonrpc_core as rpc;
@@ -10,6 +11,20 @@ extern crate ethcore;
 use std::cell::Cell;
 use web3::Transport;
 
+/// Account of the key signing transactions and messages in `test_app_stream!`.
+pub const AUTHORITY: &str = "9d8a62f656a8d1615c1294fd71e9cfb3e4855a4f";
+/// Secret of `AUTHORITY`.
+pub const AUTHORITY_SECRET: &str = "4646464646464646464646464646464646464646464646464646464646464646";
+
+/// `eth_getBlockByNumber` response for a block of the canonical chain, with hashes derived from numbers.
+pub fn canonical_block(number: u64) -> serde_json::Value {
+	json!({
+		"number": format!("0x{:x}", number),
+		"hash": format!("0x{:064x}", number),
+		"parentHash": format!("0x{:064x}", number - 1),
+	})
+}
+
 #[derive(Debug, Clone)]
 pub struct MockedRequest {
 	pub method: String,
@@ -85,8 +100,12 @@ impl Transport for MockedTransport {
 
 	fn send(&self, _id: usize, _request: rpc::Call) -> web3::Result<rpc::Value> {
 		let response = self.mocked_responses.iter().nth(self.requests.get() - 1).expect("missing response");
-		let f = futures::finished(response.clone());
-		Box::new(f)
+		// responses with an `error` member are rejected requests
+		let result = match response.get("error") {
+			Some(error) => Err(web3::error::ErrorKind::Rpc(serde_json::from_value(error.clone()).expect("invalid mocked error")).into()),
+			None => Ok(response.clone()),
+		};
+		Box::new(futures::future::result(result))
 	}
 }
 
@@ -136,10 +155,13 @@ macro_rules! test_app_stream {
 			use self::std::time::Duration;
 			use self::futures::{Future, Stream};
 			use self::bridge::app::{App, Connections};
-			use self::bridge::contracts::{foreign, home};
-			use self::bridge::config::{Config, Authorities, Node, NodeInfo, ContractConfig, Transactions, TransactionConfig, GasPriceSpeed};
+			use self::bridge::contracts::{foreign, home, home_erc20, erc20};
+			use self::bridge::config::{Config, Authorities, Node, Transactions, TransactionConfig, PasswordSource, DatabaseBackendKind};
 			use self::bridge::database::Database;
-			use ethcore::account_provider::AccountProvider;
+			use ethcore::ethstore::{EthStore, SimpleSecretStore, SecretVaultRef};
+			use ethcore::ethstore::accounts_dir::MemoryDirectory;
+			use ethcore::ethstore::ethkey::Secret;
+			use self::bridge::signer::{Signers, KeystoreSigner};
 			
 			let home = $crate::MockedTransport {
 				requests: Default::default(),
@@ -155,46 +177,37 @@ macro_rules! test_app_stream {
 
 			let config = Config {
 				txs: $txs,
+				retry: Default::default(),
+				bridge_mode: Default::default(),
+				tokens: vec![],
 				home: Node {
 					account: $home_acc.parse().unwrap(),
-					contract: ContractConfig {
-						bin: Default::default(),
-					},
 					poll_interval: Duration::from_secs(0),
 					request_timeout: Duration::from_secs(5),
 					required_confirmations: $home_conf,
-					rpc_host: "".into(),
-					rpc_port: 8545,
-					password: "password.txt".into(),
-					info: NodeInfo::default(),
-					gas_price_oracle_url: None,
-					gas_price_speed: GasPriceSpeed::Fast,
-					gas_price_timeout: Duration::from_secs(5),
+					password: Some(PasswordSource::File("password.txt".into())),
 					default_gas_price: 0,
+					..Node::default_for_tests()
 				},
 				foreign: Node {
 					account: $foreign_acc.parse().unwrap(),
-					contract: ContractConfig {
-						bin: Default::default(),
-					},
 					poll_interval: Duration::from_secs(0),
 					request_timeout: Duration::from_secs(5),
 					required_confirmations: $foreign_conf,
-					rpc_host: "".into(),
-					rpc_port: 8545,
-					password: "password.txt".into(),
-					info: NodeInfo::default(),
-					gas_price_oracle_url: None,
-					gas_price_speed: GasPriceSpeed::Fast,
-					gas_price_timeout: Duration::from_secs(5),
+					password: Some(PasswordSource::File("password.txt".into())),
 					default_gas_price: 0,
+					..Node::default_for_tests()
 				},
+				validator: None,
 				authorities: Authorities {
 					accounts: $authorities_accs.iter().map(|a: &&str| a.parse().unwrap()).collect(),
 					required_signatures: $signatures,
 				},
 				estimated_gas_cost_of_withdraw: 100_000,
 				keystore: "/keys/".into(),
+				database_backend: DatabaseBackendKind::Toml,
+				metrics_address: None,
+				admin_rpc_address: None,
 			};
 
 			let app = App {
@@ -205,10 +218,25 @@ macro_rules! test_app_stream {
 					foreign: &foreign,
 				},
 				home_bridge: home::HomeBridge::default(),
+				home_erc20_bridge: home_erc20::HomeBridgeErc20::default(),
+				erc20_token: erc20::ERC20::default(),
 				foreign_bridge: foreign::ForeignBridge::default(),
 				timer: Default::default(),
 				running: Arc::new(AtomicBool::new(true)),
-				keystore: AccountProvider::transient_provider(),
+				switches: Default::default(),
+				new_heads: Default::default(),
+				signers: {
+					// only the key of `AUTHORITY` is available, accounts of other keys can't sign
+					let store = Arc::new(EthStore::open(Box::new(MemoryDirectory::default())).unwrap());
+					let secret: Secret = $crate::AUTHORITY_SECRET.parse().unwrap();
+					let account = store.insert_account(SecretVaultRef::Root, secret, "").unwrap().address;
+					let signer = Arc::new(KeystoreSigner::unlock(store, account, "").unwrap());
+					Signers {
+						home: signer.clone(),
+						foreign: signer.clone(),
+						validator: signer,
+					}
+				},
 			};
 
 			let app = Arc::new(app);			
@@ -1,62 +1,63 @@
+/// test interactions of deposit_relay state machine with RPC
+
 extern crate futures;
 #[macro_use]
 extern crate serde_json;
+extern crate web3;
 extern crate bridge;
 #[macro_use]
 extern crate tests;
 extern crate ethcore;
 
-use bridge::bridge::create_deposit_relay;
+use std::sync::RwLock;
+use futures::{future, Async};
+use web3::types::U256;
+use bridge::bridge::{create_deposit_relay, AuthoritySet, GasPrice, GasLimit, BridgeEvent, BridgeChecked};
+use bridge::config::GasEstimation;
+use bridge::database::{TransactionRecord, TransactionKind};
+use bridge::error::{Error, ErrorKind};
+use tests::{MockedTransport, AUTHORITY, canonical_block};
 
 const DEPOSIT_TOPIC: &str = "0xe1fffcc4923d04b559f4d29a8bfc6cda04eb5b0d3c460751c2402c5c5cc9109c";
 
-use std::sync::RwLock;
+/// Hash of the home transaction making the deposit.
+const DEPOSIT_HASH: &str = "884edad9ce6fa2440d8a54cc123490eb96d2768479d49ff9c7366125a9424364";
 
-test_app_stream! {
-	name => deposit_relay_basic,
-	database => Database::default(),
-	home =>
-		account => "0000000000000000000000000000000000000001",
-		confirmations => 12;
-	foreign =>
-		account => "0000000000000000000000000000000000000001",
-		confirmations => 12;
-	authorities =>
-		accounts => [
-			"0000000000000000000000000000000000000001",
-			"0000000000000000000000000000000000000002",
-		],
-		signatures => 1;
-	txs => Transactions::default(),
-	init => |app, db| create_deposit_relay(app, db, Arc::new(RwLock::new(Some(99999999999u64.into()))), 17, Arc::new(RwLock::new(1))).take(2),
-	expected => vec![0x1005, 0x1006],
-	home_transport => [
-		"eth_blockNumber" =>
-			req => json!([]),
-			res => json!("0x1011");
-		"eth_getLogs" =>
-			req => json!([{
-				"address": ["0x0000000000000000000000000000000000000000"],
-				"fromBlock": "0x1",
-				"limit": null,
-				"toBlock": "0x1005",
-				"topics": [[DEPOSIT_TOPIC], null, null, null]
-			}]),
-			res => json!([]);
-		"eth_blockNumber" =>
-			req => json!([]),
-			res => json!("0x1012");
-		"eth_getLogs" =>
-			req => json!([{
-				"address": ["0x0000000000000000000000000000000000000000"],
-				"fromBlock": "0x1006",
-				"limit": null,
-				"toBlock": "0x1006",
-				"topics": [[DEPOSIT_TOPIC], null, null, null]
-			}]),
-			res => json!([]);
-	],
-	foreign_transport => []
+/// Hash of the foreign transaction relaying the deposit.
+const RELAY_HASH: &str = "1db8f385535c0d178b8f40016048f3a3cffee8f87e68c8b5d10aeff6d0f7c900";
+
+/// `Deposit` of 0xf0 to 0xaff3454fce5edbc8cca8697c15331677e6ebcccc logged in block 6.
+fn deposit_log() -> serde_json::Value {
+	json!([{
+		"address": "0x0000000000000000000000000000000000000000",
+		"topics": [DEPOSIT_TOPIC],
+		"data": "0x000000000000000000000000aff3454fce5edbc8cca8697c15331677e6ebcccc00000000000000000000000000000000000000000000000000000000000000f0",
+		"type": "",
+		"transactionHash": format!("0x{}", DEPOSIT_HASH),
+	}])
+}
+
+/// `ForeignBridge.isDepositSigned` call checking whether `AUTHORITY` has relayed the deposit of `deposit_log`.
+fn is_deposit_signed_request() -> serde_json::Value {
+	json!([{
+		"data": "0x1f0570600000000000000000000000009d8a62f656a8d1615c1294fd71e9cfb3e4855a4f000000000000000000000000aff3454fce5edbc8cca8697c15331677e6ebcccc00000000000000000000000000000000000000000000000000000000000000f0884edad9ce6fa2440d8a54cc123490eb96d2768479d49ff9c7366125a9424364",
+		"to": "0x0000000000000000000000000000000000000000",
+	}, "latest"])
+}
+
+const FALSE: &str = "0x0000000000000000000000000000000000000000000000000000000000000000";
+const TRUE: &str = "0x0000000000000000000000000000000000000000000000000000000000000001";
+
+fn relayed() -> Vec<BridgeEvent> {
+	vec![
+		BridgeEvent::Sent(vec![TransactionRecord {
+			kind: TransactionKind::DepositRelay,
+			source_transaction_hash: DEPOSIT_HASH.into(),
+			transaction_hash: RELAY_HASH.into(),
+			block: 6,
+		}]),
+		BridgeEvent::Checked(BridgeChecked::DepositRelay(6)),
+	]
 }
 
 test_app_stream! {
@@ -66,299 +67,288 @@ test_app_stream! {
 		..Default::default()
 	},
 	home =>
-		account => "0000000000000000000000000000000000000001",
+		account => AUTHORITY,
 		confirmations => 12;
 	foreign =>
-		account => "0000000000000000000000000000000000000001",
+		account => AUTHORITY,
 		confirmations => 12;
 	authorities =>
 		accounts => [
-			"0000000000000000000000000000000000000001",
+			AUTHORITY,
 			"0000000000000000000000000000000000000002",
 		],
 		signatures => 1;
 	txs => Transactions::default(),
-	init => |app, db| create_deposit_relay(app, db, Arc::new(RwLock::new(Some(99999999999u64.into()))), 17, Arc::new(RwLock::new(1))).take(2),
-	expected => vec![0x1005, 0x1006],
+	init => |app: Arc<App<&MockedTransport>>, db| create_deposit_relay(app.clone(), db, Arc::new(RwLock::new(Some(99999999999u64.into()))), 17,
+		GasPrice::new(&app.config.foreign), Arc::new(RwLock::new(None)), GasLimit::new(100_000)).take(2),
+	expected => relayed(),
 	home_transport => [
 		"eth_blockNumber" =>
 			req => json!([]),
-			res => json!("0x1011");
+			res => json!("0x12");
+		"eth_getBlockByNumber" =>
+			req => json!(["0x6", false]),
+			res => canonical_block(6);
 		"eth_getLogs" =>
 			req => json!([{
 				"address": ["0x0000000000000000000000000000000000000000"],
 				"fromBlock": "0x6",
 				"limit": null,
-				"toBlock":"0x1005",
+				"toBlock": "0x6",
 				"topics": [[DEPOSIT_TOPIC], null, null, null]
 			}]),
-			res => json!([{
-				"address": "0x0000000000000000000000000000000000000000",
-				"topics": [DEPOSIT_TOPIC],
-				"data": "0x000000000000000000000000aff3454fce5edbc8cca8697c15331677e6ebcccc00000000000000000000000000000000000000000000000000000000000000f0",
-				"type": "",
-				"transactionHash": "0x884edad9ce6fa2440d8a54cc123490eb96d2768479d49ff9c7366125a9424364"
-			}]);
-		"eth_blockNumber" =>
-			req => json!([]),
-			res => json!("0x1012");
-		"eth_getLogs" =>
-			req => json!([{
-				"address": ["0x0000000000000000000000000000000000000000"],
-				"fromBlock": "0x1006",
-				"limit": null,
-				"toBlock": "0x1006",
-				"topics":[[DEPOSIT_TOPIC], null, null, null]
-			}]),
-			res => json!([]);
+			res => deposit_log();
 	],
 	foreign_transport => [
-		"eth_sendTransaction" =>
-			req => json!([{
-				"data": "0x26b3293f000000000000000000000000aff3454fce5edbc8cca8697c15331677e6ebcccc00000000000000000000000000000000000000000000000000000000000000f0884edad9ce6fa2440d8a54cc123490eb96d2768479d49ff9c7366125a9424364",
-				"from": "0x0000000000000000000000000000000000000001",
-				"gas": "0x0",
-				"gasPrice": "0x0",
-				"to": "0x0000000000000000000000000000000000000000"
-			}]),
-			res => json!("0x1db8f385535c0d178b8f40016048f3a3cffee8f94e68978ea4b277f57b638f0b");
+		"eth_call" =>
+			req => is_deposit_signed_request(),
+			res => json!(FALSE);
+		"eth_sendRawTransaction" =>
+			req => json!([]),
+			res => json!(format!("0x{}", RELAY_HASH));
 	]
 }
 
 test_app_stream! {
-	name => deposit_relay_check_gas,
+	name => deposit_relay_skips_relayed_deposit,
 	database => Database {
 		checked_deposit_relay: 5,
 		..Default::default()
 	},
 	home =>
-		account => "0000000000000000000000000000000000000001",
+		account => AUTHORITY,
 		confirmations => 12;
 	foreign =>
-		account => "0000000000000000000000000000000000000001",
+		account => AUTHORITY,
 		confirmations => 12;
 	authorities =>
 		accounts => [
-			"0000000000000000000000000000000000000001",
+			AUTHORITY,
 			"0000000000000000000000000000000000000002",
 		],
 		signatures => 1;
-	txs => Transactions {
-		deposit_relay: TransactionConfig {
-			gas: 0xfd,
-			gas_price: 0xa0,
-			concurrency: 100,
-		},
-		..Default::default()
-	},
-	init => |app, db| create_deposit_relay(app, db, Arc::new(RwLock::new(Some(99999999999u64.into()))), 17, Arc::new(RwLock::new(1))).take(1),
-	expected => vec![0x1005],
+	txs => Transactions::default(),
+	init => |app: Arc<App<&MockedTransport>>, db| create_deposit_relay(app.clone(), db, Arc::new(RwLock::new(Some(99999999999u64.into()))), 17,
+		GasPrice::new(&app.config.foreign), Arc::new(RwLock::new(None)), GasLimit::new(100_000)).take(2),
+	expected => vec![
+		BridgeEvent::Sent(vec![]),
+		BridgeEvent::Checked(BridgeChecked::DepositRelay(6)),
+	],
 	home_transport => [
 		"eth_blockNumber" =>
 			req => json!([]),
-			res => json!("0x1011");
+			res => json!("0x12");
+		"eth_getBlockByNumber" =>
+			req => json!(["0x6", false]),
+			res => canonical_block(6);
 		"eth_getLogs" =>
 			req => json!([{
 				"address": ["0x0000000000000000000000000000000000000000"],
 				"fromBlock": "0x6",
 				"limit": null,
-				"toBlock": "0x1005",
+				"toBlock": "0x6",
 				"topics": [[DEPOSIT_TOPIC], null, null, null]
 			}]),
-			res => json!([{
-				"address": "0x0000000000000000000000000000000000000000",
-				"topics": [DEPOSIT_TOPIC],
-				"data": "0x000000000000000000000000aff3454fce5edbc8cca8697c15331677e6ebcccc00000000000000000000000000000000000000000000000000000000000000f0","type":"","transactionHash":"0x884edad9ce6fa2440d8a54cc123490eb96d2768479d49ff9c7366125a9424364"
-			}]);
+			res => deposit_log();
 	],
 	foreign_transport => [
-		"eth_sendTransaction" =>
-			req => json!([{
-				"data": "0x26b3293f000000000000000000000000aff3454fce5edbc8cca8697c15331677e6ebcccc00000000000000000000000000000000000000000000000000000000000000f0884edad9ce6fa2440d8a54cc123490eb96d2768479d49ff9c7366125a9424364",
-				"from": "0x0000000000000000000000000000000000000001",
-				"gas": "0xfd",
-				"gasPrice": "0xa0",
-				"to": "0x0000000000000000000000000000000000000000"
-			}]),
-			res => json!("0x1db8f385535c0d178b8f40016048f3a3cffee8f94e68978ea4b277f57b638f0b");
+		"eth_call" =>
+			req => is_deposit_signed_request(),
+			res => json!(TRUE);
 	]
 }
 
 test_app_stream! {
-	name => deposit_relay_contract_address,
+	name => deposit_relay_waits_until_authority_is_added,
 	database => Database {
-		home_contract_address: "0000000000000000000000000000000000000cc1".into(),
-		foreign_contract_address: "0000000000000000000000000000000000000dd1".into(),
+		checked_deposit_relay: 5,
 		..Default::default()
 	},
 	home =>
-		account => "0000000000000000000000000000000000000001",
+		account => AUTHORITY,
 		confirmations => 12;
 	foreign =>
-		account => "0000000000000000000000000000000000000001",
+		account => AUTHORITY,
 		confirmations => 12;
 	authorities =>
 		accounts => [
-			"0000000000000000000000000000000000000001",
+			AUTHORITY,
 			"0000000000000000000000000000000000000002",
 		],
 		signatures => 1;
 	txs => Transactions::default(),
-	init => |app, db| create_deposit_relay(app, db, Arc::new(RwLock::new(Some(99999999999u64.into()))), 17, Arc::new(RwLock::new(1))).take(1),
-	expected => vec![0x1005],
+	init => |app: Arc<App<&MockedTransport>>, db| {
+		let authorities = Arc::new(RwLock::new(Some(AuthoritySet {
+			accounts: vec!["0000000000000000000000000000000000000002".into()],
+			required_signatures: 1,
+		})));
+		let mut relay = create_deposit_relay(app.clone(), db, Arc::new(RwLock::new(Some(99999999999u64.into()))), 17,
+			GasPrice::new(&app.config.foreign), authorities.clone(), GasLimit::new(100_000));
+		// deposits are not even fetched while the foreign contract does not accept signatures of `AUTHORITY`
+		assert_eq!(Async::NotReady, future::lazy(|| relay.poll()).wait().unwrap());
+		authorities.write().unwrap().as_mut().unwrap().accounts.push(AUTHORITY.into());
+		relay.take(2)
+	},
+	expected => relayed(),
 	home_transport => [
 		"eth_blockNumber" =>
 			req => json!([]),
-			res => json!("0x1011");
+			res => json!("0x12");
+		"eth_getBlockByNumber" =>
+			req => json!(["0x6", false]),
+			res => canonical_block(6);
 		"eth_getLogs" =>
 			req => json!([{
-				"address": ["0x0000000000000000000000000000000000000cc1"],
-				"fromBlock": "0x1",
+				"address": ["0x0000000000000000000000000000000000000000"],
+				"fromBlock": "0x6",
 				"limit": null,
-				"toBlock": "0x1005",
+				"toBlock": "0x6",
 				"topics": [[DEPOSIT_TOPIC], null, null, null]
 			}]),
-			res => json!([{
-				"address": "0x0000000000000000000000000000000000000cc1",
-				"topics": ["0xe1fffcc4923d04b559f4d29a8bfc6cda04eb5b0d3c460751c2402c5c5cc9109c"],
-				"data": "0x000000000000000000000000aff3454fce5edbc8cca8697c15331677e6ebcccc00000000000000000000000000000000000000000000000000000000000000f0",
-				"type": "",
-				"transactionHash": "0x884edad9ce6fa2440d8a54cc123490eb96d2768479d49ff9c7366125a9424364"
-			}]);
+			res => deposit_log();
 	],
 	foreign_transport => [
-		"eth_sendTransaction" =>
-			req => json!([{
-				"data": "0x26b3293f000000000000000000000000aff3454fce5edbc8cca8697c15331677e6ebcccc00000000000000000000000000000000000000000000000000000000000000f0884edad9ce6fa2440d8a54cc123490eb96d2768479d49ff9c7366125a9424364",
-				"from": "0x0000000000000000000000000000000000000001",
-				"gas": "0x0",
-				"gasPrice": "0x0",
-				"to": "0x0000000000000000000000000000000000000dd1"
-			}]),
-			res => json!("0x1db8f385535c0d178b8f40016048f3a3cffee8f94e68978ea4b277f57b638f0b");
+		"eth_call" =>
+			req => is_deposit_signed_request(),
+			res => json!(FALSE);
+		"eth_sendRawTransaction" =>
+			req => json!([]),
+			res => json!(format!("0x{}", RELAY_HASH));
 	]
 }
 
 test_app_stream! {
-	name => deposit_relay_accounts,
+	name => deposit_relay_estimates_gas,
 	database => Database {
-		home_contract_address: "0000000000000000000000000000000000000cc1".into(),
-		foreign_contract_address: "0000000000000000000000000000000000000dd1".into(),
+		checked_deposit_relay: 5,
 		..Default::default()
 	},
 	home =>
-		account => "00000000000000000000000000000000000000ff",
+		account => AUTHORITY,
 		confirmations => 12;
 	foreign =>
-		account => "00000000000000000000000000000000000000ee",
+		account => AUTHORITY,
 		confirmations => 12;
 	authorities =>
 		accounts => [
-			"0000000000000000000000000000000000000001",
+			AUTHORITY,
 			"0000000000000000000000000000000000000002",
 		],
 		signatures => 1;
-	txs => Transactions::default(),
-	init => |app, db| create_deposit_relay(app, db, Arc::new(RwLock::new(Some(99999999999u64.into()))), 17, Arc::new(RwLock::new(1))).take(1),
-	expected => vec![0x1005],
+	txs => Transactions {
+		deposit_relay: TransactionConfig {
+			gas: 0,
+			gas_price: 0,
+			estimate_gas: Some(GasEstimation {
+				multiplier_percent: 120,
+				max_gas: 100_000,
+			}),
+		},
+		..Default::default()
+	},
+	init => |app: Arc<App<&MockedTransport>>, db| create_deposit_relay(app.clone(), db, Arc::new(RwLock::new(Some(99999999999u64.into()))), 17,
+		GasPrice::new(&app.config.foreign), Arc::new(RwLock::new(None)), GasLimit::new(100_000)).take(2),
+	expected => relayed(),
 	home_transport => [
 		"eth_blockNumber" =>
 			req => json!([]),
-			res => json!("0x1011");
+			res => json!("0x12");
+		"eth_getBlockByNumber" =>
+			req => json!(["0x6", false]),
+			res => canonical_block(6);
 		"eth_getLogs" =>
 			req => json!([{
-				"address": ["0x0000000000000000000000000000000000000cc1"],
-				"fromBlock": "0x1",
+				"address": ["0x0000000000000000000000000000000000000000"],
+				"fromBlock": "0x6",
 				"limit": null,
-				"toBlock": "0x1005",
+				"toBlock": "0x6",
 				"topics": [[DEPOSIT_TOPIC], null, null, null]
 			}]),
-			res => json!([{
-				"address": "0x0000000000000000000000000000000000000cc1",
-				"topics": [DEPOSIT_TOPIC],
-				"data": "0x000000000000000000000000aff3454fce5edbc8cca8697c15331677e6ebcccc00000000000000000000000000000000000000000000000000000000000000f0",
-				"type": "",
-				"transactionHash": "0x884edad9ce6fa2440d8a54cc123490eb96d2768479d49ff9c7366125a9424364"
-			}]);
+			res => deposit_log();
 	],
 	foreign_transport => [
-		"eth_sendTransaction" =>
+		"eth_call" =>
+			req => is_deposit_signed_request(),
+			res => json!(FALSE);
+		"eth_estimateGas" =>
 			req => json!([{
-				"data": "0x26b3293f000000000000000000000000aff3454fce5edbc8cca8697c15331677e6ebcccc00000000000000000000000000000000000000000000000000000000000000f0884edad9ce6fa2440d8a54cc123490eb96d2768479d49ff9c7366125a9424364",
-				"from": "0x00000000000000000000000000000000000000ee",
-				"gas": "0x0",
-				"gasPrice": "0x0",
-				"to":"0x0000000000000000000000000000000000000dd1"
+				"from": format!("0x{}", AUTHORITY),
+				"to": "0x0000000000000000000000000000000000000000",
 			}]),
-			res => json!("0x1db8f385535c0d178b8f40016048f3a3cffee8f94e68978ea4b277f57b638f0b");
+			res => json!("0x5208");
+		"eth_sendRawTransaction" =>
+			req => json!([]),
+			res => json!(format!("0x{}", RELAY_HASH));
 	]
 }
 
 test_app_stream! {
-	name => deposit_relay_multiple_logs,
-	database => Database::default(),
+	name => deposit_relay_refuses_gas_estimate_above_max_gas,
+	database => Database {
+		checked_deposit_relay: 5,
+		..Default::default()
+	},
 	home =>
-		account => "0000000000000000000000000000000000000001",
+		account => AUTHORITY,
 		confirmations => 12;
 	foreign =>
-		account => "0000000000000000000000000000000000000001",
+		account => AUTHORITY,
 		confirmations => 12;
 	authorities =>
 		accounts => [
-			"0000000000000000000000000000000000000001",
+			AUTHORITY,
 			"0000000000000000000000000000000000000002",
 		],
 		signatures => 1;
-	txs => Transactions::default(),
-	init => |app, db| create_deposit_relay(app, db, Arc::new(RwLock::new(Some(99999999999u64.into()))), 17, Arc::new(RwLock::new(1))).take(1),
-	expected => vec![0x1005],
+	txs => Transactions {
+		deposit_relay: TransactionConfig {
+			gas: 0,
+			gas_price: 0,
+			estimate_gas: Some(GasEstimation {
+				multiplier_percent: 120,
+				max_gas: 100_000,
+			}),
+		},
+		..Default::default()
+	},
+	init => |app: Arc<App<&MockedTransport>>, db| create_deposit_relay(app.clone(), db, Arc::new(RwLock::new(Some(99999999999u64.into()))), 17,
+		GasPrice::new(&app.config.foreign), Arc::new(RwLock::new(None)), GasLimit::new(100_000))
+		.then(|result| -> Result<(U256, u64), Error> { match result {
+			Err(Error(ErrorKind::ContextualizedError(err, _), _)) => match *err.kind() {
+				ErrorKind::GasEstimateTooHigh(gas, max_gas) => Ok((gas, max_gas)),
+				ref kind => panic!("unexpected error {:?}", kind),
+			},
+			result => panic!("expected the relay to fail, got {:?}", result),
+		}})
+		.take(1),
+	// 120% of the estimate exceeds `max_gas`, the deposit is not relayed
+	expected => vec![(U256::from(120_000), 100_000)],
 	home_transport => [
 		"eth_blockNumber" =>
 			req => json!([]),
-			res => json!("0x1011");
+			res => json!("0x12");
+		"eth_getBlockByNumber" =>
+			req => json!(["0x6", false]),
+			res => canonical_block(6);
 		"eth_getLogs" =>
 			req => json!([{
 				"address": ["0x0000000000000000000000000000000000000000"],
-				"fromBlock": "0x1",
+				"fromBlock": "0x6",
 				"limit": null,
-				"toBlock": "0x1005",
+				"toBlock": "0x6",
 				"topics": [[DEPOSIT_TOPIC], null, null, null]
 			}]),
-			res => json!([
-				{
-					"address": "0x0000000000000000000000000000000000000000",
-					"topics": ["0xe1fffcc4923d04b559f4d29a8bfc6cda04eb5b0d3c460751c2402c5c5cc9109c"],
-					"data": "0x000000000000000000000000aff3454fce5edbc8cca8697c15331677e6ebcccc00000000000000000000000000000000000000000000000000000000000000f0",
-					"type": "",
-					"transactionHash": "0x884edad9ce6fa2440d8a54cc123490eb96d2768479d49ff9c7366125a9424364"
-				},
-				{
-					"address":"0x0000000000000000000000000000000000000000",
-					"topics": ["0xe1fffcc4923d04b559f4d29a8bfc6cda04eb5b0d3c460751c2402c5c5cc9109c"],
-					"data": "0x000000000000000000000000aff3454fce5edbc8cca8697c15331677e6ebcccc00000000000000000000000000000000000000000000000000000000000000f0",
-					"type": "",
-					"transactionHash": "0x884edad9ce6fa2440d8a54cc123490eb96d2768479d49ff9c7366125a942436f"
-				}
-			]);
+			res => deposit_log();
 	],
 	foreign_transport => [
-		"eth_sendTransaction" =>
-			req => json!([{
-				"data": "0x26b3293f000000000000000000000000aff3454fce5edbc8cca8697c15331677e6ebcccc00000000000000000000000000000000000000000000000000000000000000f0884edad9ce6fa2440d8a54cc123490eb96d2768479d49ff9c7366125a9424364",
-				"from": "0x0000000000000000000000000000000000000001",
-				"gas": "0x0",
-				"gasPrice": "0x0",
-				"to": "0x0000000000000000000000000000000000000000"
-			}]),
-			res => json!("0x1db8f385535c0d178b8f40016048f3a3cffee8f94e68978ea4b277f57b638f0b");
-		"eth_sendTransaction" =>
+		"eth_call" =>
+			req => is_deposit_signed_request(),
+			res => json!(FALSE);
+		"eth_estimateGas" =>
 			req => json!([{
-				"data": "0x26b3293f000000000000000000000000aff3454fce5edbc8cca8697c15331677e6ebcccc00000000000000000000000000000000000000000000000000000000000000f0884edad9ce6fa2440d8a54cc123490eb96d2768479d49ff9c7366125a942436f",
-				"from": "0x0000000000000000000000000000000000000001",
-				"gas": "0x0",
-				"gasPrice": "0x0",
-				"to": "0x0000000000000000000000000000000000000000"
+				"from": format!("0x{}", AUTHORITY),
+				"to": "0x0000000000000000000000000000000000000000",
 			}]),
-			res => json!("0x1db8f385535c0d178b8f40016048f3a3cffee8f94e68978ea4b277f57b638f0b");
+			res => json!("0x186a0");
 	]
 }
@@ -9,7 +9,22 @@ extern crate ethcore;
 
 use std::time::Duration;
 use web3::types::{FilterBuilder, H160, H256, Log};
-use bridge::api::{LogStreamInit, log_stream, LogStreamItem};
+use bridge::api::{LogStreamInit, log_stream, LogStreamItem, LogStreamEvent};
+use bridge::error::{Error, ErrorKind};
+
+/// `eth_getBlockByNumber` response with hashes derived from numbers.
+fn block(number: u64, hash: u64, parent_hash: u64) -> serde_json::Value {
+	json!({
+		"number": format!("0x{:x}", number),
+		"hash": format!("0x{:064x}", hash),
+		"parentHash": format!("0x{:064x}", parent_hash),
+	})
+}
+
+/// `eth_getBlockByNumber` response for a block of the canonical chain.
+fn canonical_block(number: u64) -> serde_json::Value {
+	block(number, number, number - 1)
+}
 
 test_transport_stream! {
 	name => log_stream_basic,
@@ -20,22 +35,46 @@ test_transport_stream! {
 			poll_interval: Duration::from_secs(0),
 			request_timeout: Duration::from_secs(5),
 			confirmations: 10,
+			max_block_range: None,
+			retry: Default::default(),
+			new_heads: None,
 		};
 
 		log_stream(transport, Default::default(), init).take(2)
 	},
-	expected => vec![LogStreamItem {
+	expected => vec![LogStreamEvent::Logs(LogStreamItem {
 		from: 0xb,
 		to: 0x1006,
 		logs: vec![],
-	}, LogStreamItem {
+	}), LogStreamEvent::Logs(LogStreamItem {
 		from: 0x1007,
 		to: 0x1007,
 		logs: vec![],
-	}],
+	})],
 	"eth_blockNumber" =>
 		req => json!([]),
 		res => json!("0x1010");
+	"eth_getBlockByNumber" =>
+		req => json!(["0xb", false]),
+		res => canonical_block(0xb);
+	"eth_getBlockByNumber" =>
+		req => json!(["0xff6", false]),
+		res => canonical_block(0xff6);
+	"eth_getBlockByNumber" =>
+		req => json!(["0xffe", false]),
+		res => canonical_block(0xffe);
+	"eth_getBlockByNumber" =>
+		req => json!(["0x1002", false]),
+		res => canonical_block(0x1002);
+	"eth_getBlockByNumber" =>
+		req => json!(["0x1004", false]),
+		res => canonical_block(0x1004);
+	"eth_getBlockByNumber" =>
+		req => json!(["0x1005", false]),
+		res => canonical_block(0x1005);
+	"eth_getBlockByNumber" =>
+		req => json!(["0x1006", false]),
+		res => canonical_block(0x1006);
 	"eth_getLogs" =>
 		req => json!([{
 			"address": null,
@@ -51,6 +90,9 @@ test_transport_stream! {
 	"eth_blockNumber" =>
 		req => json!([]),
 		res => json!("0x1011");
+	"eth_getBlockByNumber" =>
+		req => json!(["0x1007", false]),
+		res => canonical_block(0x1007);
 	"eth_getLogs" =>
 		req => json!([{
 			"address": null,
@@ -71,22 +113,34 @@ test_transport_stream! {
 			poll_interval: Duration::from_secs(0),
 			request_timeout: Duration::from_secs(5),
 			confirmations: 10,
+			max_block_range: None,
+			retry: Default::default(),
+			new_heads: None,
 		};
 
 		log_stream(transport, Default::default(), init).take(2)
 	},
-	expected => vec![LogStreamItem {
+	expected => vec![LogStreamEvent::Logs(LogStreamItem {
 		from: 0xb,
 		to: 0xd,
 		logs: vec![],
-	}, LogStreamItem {
+	}), LogStreamEvent::Logs(LogStreamItem {
 		from: 0xe,
 		to: 0xf,
 		logs: vec![],
-	}],
+	})],
 	"eth_blockNumber" =>
 		req => json!([]),
 		res => json!("0x17");
+	"eth_getBlockByNumber" =>
+		req => json!(["0xb", false]),
+		res => canonical_block(0xb);
+	"eth_getBlockByNumber" =>
+		req => json!(["0xc", false]),
+		res => canonical_block(0xc);
+	"eth_getBlockByNumber" =>
+		req => json!(["0xd", false]),
+		res => canonical_block(0xd);
 	"eth_getLogs" =>
 		req => json!([{
 			"address": null,
@@ -105,6 +159,12 @@ test_transport_stream! {
 	"eth_blockNumber" =>
 		req => json!([]),
 		res => json!("0x19");
+	"eth_getBlockByNumber" =>
+		req => json!(["0xe", false]),
+		res => canonical_block(0xe);
+	"eth_getBlockByNumber" =>
+		req => json!(["0xf", false]),
+		res => canonical_block(0xf);
 	"eth_getLogs" =>
 		req => json!([{
 			"address": null,
@@ -125,15 +185,18 @@ test_transport_stream! {
 			poll_interval: Duration::from_secs(0),
 			request_timeout: Duration::from_secs(5),
 			confirmations: 10,
+			max_block_range: None,
+			retry: Default::default(),
+			new_heads: None,
 		};
 
 		log_stream(transport, Default::default(), init).take(1)
 	},
-	expected => vec![LogStreamItem {
+	expected => vec![LogStreamEvent::Logs(LogStreamItem {
 		from: 0xb,
 		to: 0xd,
 		logs: vec![],
-	}],
+	})],
 	"eth_blockNumber" =>
 		req => json!([]),
 		res => json!("0x13");
@@ -143,6 +206,15 @@ test_transport_stream! {
 	"eth_blockNumber" =>
 		req => json!([]),
 		res => json!("0x17");
+	"eth_getBlockByNumber" =>
+		req => json!(["0xb", false]),
+		res => canonical_block(0xb);
+	"eth_getBlockByNumber" =>
+		req => json!(["0xc", false]),
+		res => canonical_block(0xc);
+	"eth_getBlockByNumber" =>
+		req => json!(["0xd", false]),
+		res => canonical_block(0xd);
 	"eth_getLogs" =>
 		req => json!([{
 			"address": null,
@@ -163,26 +235,44 @@ test_transport_stream! {
 			poll_interval: Duration::from_secs(0),
 			request_timeout: Duration::from_secs(5),
 			confirmations: 0,
+			max_block_range: None,
+			retry: Default::default(),
+			new_heads: None,
 		};
 
 		log_stream(transport, Default::default(), init).take(3)
 	},
-	expected => vec![LogStreamItem {
+	expected => vec![LogStreamEvent::Logs(LogStreamItem {
 		from: 0xb,
 		to: 0x13,
 		logs: vec![],
-	}, LogStreamItem {
+	}), LogStreamEvent::Logs(LogStreamItem {
 		from: 0x14,
 		to: 0x14,
 		logs: vec![],
-	}, LogStreamItem {
+	}), LogStreamEvent::Logs(LogStreamItem {
 		from: 0x15,
 		to: 0x17,
 		logs: vec![],
-	}],
+	})],
 	"eth_blockNumber" =>
 		req => json!([]),
 		res => json!("0x13");
+	"eth_getBlockByNumber" =>
+		req => json!(["0xb", false]),
+		res => canonical_block(0xb);
+	"eth_getBlockByNumber" =>
+		req => json!(["0xf", false]),
+		res => canonical_block(0xf);
+	"eth_getBlockByNumber" =>
+		req => json!(["0x11", false]),
+		res => canonical_block(0x11);
+	"eth_getBlockByNumber" =>
+		req => json!(["0x12", false]),
+		res => canonical_block(0x12);
+	"eth_getBlockByNumber" =>
+		req => json!(["0x13", false]),
+		res => canonical_block(0x13);
 	"eth_getLogs" =>
 		req => json!([{
 			"address": null,
@@ -195,6 +285,9 @@ test_transport_stream! {
 	"eth_blockNumber" =>
 		req => json!([]),
 		res => json!("0x14");
+	"eth_getBlockByNumber" =>
+		req => json!(["0x14", false]),
+		res => canonical_block(0x14);
 	"eth_getLogs" =>
 		req => json!([{
 			"address": null,
@@ -210,6 +303,15 @@ test_transport_stream! {
 	"eth_blockNumber" =>
 		req => json!([]),
 		res => json!("0x17");
+	"eth_getBlockByNumber" =>
+		req => json!(["0x15", false]),
+		res => canonical_block(0x15);
+	"eth_getBlockByNumber" =>
+		req => json!(["0x16", false]),
+		res => canonical_block(0x16);
+	"eth_getBlockByNumber" =>
+		req => json!(["0x17", false]),
+		res => canonical_block(0x17);
 	"eth_getLogs" =>
 		req => json!([{
 			"address": null,
@@ -230,22 +332,40 @@ test_transport_stream! {
 			poll_interval: Duration::from_secs(0),
 			request_timeout: Duration::from_secs(5),
 			confirmations: 0,
+			max_block_range: None,
+			retry: Default::default(),
+			new_heads: None,
 		};
 
 		log_stream(transport, Default::default(), init).take(2)
 	},
-	expected => vec![LogStreamItem {
+	expected => vec![LogStreamEvent::Logs(LogStreamItem {
 		from: 0xc,
 		to: 0x13,
 		logs: vec![],
-	}, LogStreamItem {
+	}), LogStreamEvent::Logs(LogStreamItem {
 		from: 0x14,
 		to: 0x14,
 		logs: vec![],
-	}],
+	})],
 	"eth_blockNumber" =>
 		req => json!([]),
 		res => json!("0x13");
+	"eth_getBlockByNumber" =>
+		req => json!(["0xc", false]),
+		res => canonical_block(0xc);
+	"eth_getBlockByNumber" =>
+		req => json!(["0xf", false]),
+		res => canonical_block(0xf);
+	"eth_getBlockByNumber" =>
+		req => json!(["0x11", false]),
+		res => canonical_block(0x11);
+	"eth_getBlockByNumber" =>
+		req => json!(["0x12", false]),
+		res => canonical_block(0x12);
+	"eth_getBlockByNumber" =>
+		req => json!(["0x13", false]),
+		res => canonical_block(0x13);
 	"eth_getLogs" =>
 		req => json!([{
 			"address": ["0x1111111111111111111111111111111111111111"],
@@ -258,6 +378,9 @@ test_transport_stream! {
 	"eth_blockNumber" =>
 		req => json!([]),
 		res => json!("0x14");
+	"eth_getBlockByNumber" =>
+		req => json!(["0x14", false]),
+		res => canonical_block(0x14);
 	"eth_getLogs" =>
 		req => json!([{
 			"address":["0x1111111111111111111111111111111111111111"],
@@ -278,22 +401,40 @@ test_transport_stream! {
 			poll_interval: Duration::from_secs(0),
 			request_timeout: Duration::from_secs(5),
 			confirmations: 0,
+			max_block_range: None,
+			retry: Default::default(),
+			new_heads: None,
 		};
 
 		log_stream(transport, Default::default(), init).take(2)
 	},
-	expected => vec![LogStreamItem {
+	expected => vec![LogStreamEvent::Logs(LogStreamItem {
 		from: 0xc,
 		to: 0x13,
 		logs: vec![],
-	}, LogStreamItem {
+	}), LogStreamEvent::Logs(LogStreamItem {
 		from: 0x14,
 		to: 0x14,
 		logs: vec![],
-	}],
+	})],
 	"eth_blockNumber" =>
 		req => json!([]),
 		res => json!("0x13");
+	"eth_getBlockByNumber" =>
+		req => json!(["0xc", false]),
+		res => canonical_block(0xc);
+	"eth_getBlockByNumber" =>
+		req => json!(["0xf", false]),
+		res => canonical_block(0xf);
+	"eth_getBlockByNumber" =>
+		req => json!(["0x11", false]),
+		res => canonical_block(0x11);
+	"eth_getBlockByNumber" =>
+		req => json!(["0x12", false]),
+		res => canonical_block(0x12);
+	"eth_getBlockByNumber" =>
+		req => json!(["0x13", false]),
+		res => canonical_block(0x13);
 	"eth_getLogs" =>
 		req => json!([{
 			"address": null,
@@ -306,6 +447,9 @@ test_transport_stream! {
 	"eth_blockNumber" =>
 		req => json!([]),
 		res => json!("0x14");
+	"eth_getBlockByNumber" =>
+		req => json!(["0x14", false]),
+		res => canonical_block(0x14);
 	"eth_getLogs" =>
 		req => json!([{
 			"address": null,
@@ -326,11 +470,14 @@ test_transport_stream! {
 			poll_interval: Duration::from_secs(0),
 			request_timeout: Duration::from_secs(5),
 			confirmations: 10,
+			max_block_range: None,
+			retry: Default::default(),
+			new_heads: None,
 		};
 
 		log_stream(transport, Default::default(), init).take(1)
 	},
-	expected => vec![LogStreamItem {
+	expected => vec![LogStreamEvent::Logs(LogStreamItem {
 		from: 0xb,
 		to: 0x1006,
 		logs: vec![Log {
@@ -346,10 +493,31 @@ test_transport_stream! {
 			log_type: None,
 			removed: None,
 		}],
-	}],
+	})],
 	"eth_blockNumber" =>
 		req => json!([]),
 		res => json!("0x1010");
+	"eth_getBlockByNumber" =>
+		req => json!(["0xb", false]),
+		res => canonical_block(0xb);
+	"eth_getBlockByNumber" =>
+		req => json!(["0xff6", false]),
+		res => canonical_block(0xff6);
+	"eth_getBlockByNumber" =>
+		req => json!(["0xffe", false]),
+		res => canonical_block(0xffe);
+	"eth_getBlockByNumber" =>
+		req => json!(["0x1002", false]),
+		res => canonical_block(0x1002);
+	"eth_getBlockByNumber" =>
+		req => json!(["0x1004", false]),
+		res => canonical_block(0x1004);
+	"eth_getBlockByNumber" =>
+		req => json!(["0x1005", false]),
+		res => canonical_block(0x1005);
+	"eth_getBlockByNumber" =>
+		req => json!(["0x1006", false]),
+		res => canonical_block(0x1006);
 	"eth_getLogs" =>
 		req => json!([{
 			"address": null,
@@ -375,11 +543,14 @@ test_transport_stream! {
 			poll_interval: Duration::from_secs(0),
 			request_timeout: Duration::from_secs(5),
 			confirmations: 10,
+			max_block_range: None,
+			retry: Default::default(),
+			new_heads: None,
 		};
 
 		log_stream(transport, Default::default(), init).take(3)
 	},
-	expected => vec![LogStreamItem {
+	expected => vec![LogStreamEvent::Logs(LogStreamItem {
 		from: 0xb,
 		to: 0x1006,
 		logs: vec![Log {
@@ -395,11 +566,11 @@ test_transport_stream! {
 			log_type: None,
 			removed: None,
 		}],
-	}, LogStreamItem {
+	}), LogStreamEvent::Logs(LogStreamItem {
 		from: 0x1007,
 		to: 0x1007,
 		logs: vec![],
-	}, LogStreamItem {
+	}), LogStreamEvent::Logs(LogStreamItem {
 		from: 0x1008,
 		to: 0x1008,
 		logs: vec![Log {
@@ -427,10 +598,31 @@ test_transport_stream! {
 			log_type: None,
 			removed: None,
 		}],
-	}],
+	})],
 	"eth_blockNumber" =>
 		req => json!([]),
 		res => json!("0x1010");
+	"eth_getBlockByNumber" =>
+		req => json!(["0xb", false]),
+		res => canonical_block(0xb);
+	"eth_getBlockByNumber" =>
+		req => json!(["0xff6", false]),
+		res => canonical_block(0xff6);
+	"eth_getBlockByNumber" =>
+		req => json!(["0xffe", false]),
+		res => canonical_block(0xffe);
+	"eth_getBlockByNumber" =>
+		req => json!(["0x1002", false]),
+		res => canonical_block(0x1002);
+	"eth_getBlockByNumber" =>
+		req => json!(["0x1004", false]),
+		res => canonical_block(0x1004);
+	"eth_getBlockByNumber" =>
+		req => json!(["0x1005", false]),
+		res => canonical_block(0x1005);
+	"eth_getBlockByNumber" =>
+		req => json!(["0x1006", false]),
+		res => canonical_block(0x1006);
 	"eth_getLogs" =>
 		req => json!([{
 			"address": null,
@@ -448,6 +640,9 @@ test_transport_stream! {
 	"eth_blockNumber" =>
 		req => json!([]),
 		res => json!("0x1011");
+	"eth_getBlockByNumber" =>
+		req => json!(["0x1007", false]),
+		res => canonical_block(0x1007);
 	"eth_getLogs" =>
 		req => json!([{
 			"address": null,
@@ -460,6 +655,9 @@ test_transport_stream! {
 	"eth_blockNumber" =>
 		req => json!([]),
 		res => json!("0x1012");
+	"eth_getBlockByNumber" =>
+		req => json!(["0x1008", false]),
+		res => canonical_block(0x1008);
 	"eth_getLogs" =>
 		req => json!([{
 			"address": null,
@@ -483,3 +681,348 @@ test_transport_stream! {
 			}
 		]);
 }
+
+test_transport_stream! {
+	name => log_stream_reorg,
+	init => |transport| {
+		let init = LogStreamInit {
+			after: 10,
+			filter: FilterBuilder::default(),
+			poll_interval: Duration::from_secs(0),
+			request_timeout: Duration::from_secs(5),
+			confirmations: 0,
+			max_block_range: None,
+			retry: Default::default(),
+			new_heads: None,
+		};
+
+		log_stream(transport, Default::default(), init).take(3)
+	},
+	expected => vec![LogStreamEvent::Logs(LogStreamItem {
+		from: 0xb,
+		to: 0xc,
+		logs: vec![],
+	}), LogStreamEvent::Rewind(0xb), LogStreamEvent::Logs(LogStreamItem {
+		from: 0xc,
+		to: 0xd,
+		logs: vec![],
+	})],
+	"eth_blockNumber" =>
+		req => json!([]),
+		res => json!("0xc");
+	"eth_getBlockByNumber" =>
+		req => json!(["0xb", false]),
+		res => canonical_block(0xb);
+	"eth_getBlockByNumber" =>
+		req => json!(["0xc", false]),
+		res => canonical_block(0xc);
+	"eth_getLogs" =>
+		req => json!([{
+			"address": null,
+			"fromBlock": "0xb",
+			"limit": null,
+			"toBlock": "0xc",
+			"topics": null
+		}]),
+		res => json!([]);
+	// block 0xc has been replaced by 0x10c
+	"eth_blockNumber" =>
+		req => json!([]),
+		res => json!("0xd");
+	"eth_getBlockByNumber" =>
+		req => json!(["0xd", false]),
+		res => block(0xd, 0x10d, 0x10c);
+	// the first block of the range is checkpointed too, so only the replaced block is rescanned
+	"eth_getBlockByNumber" =>
+		req => json!(["0xc", false]),
+		res => block(0xc, 0x10c, 0xb);
+	"eth_getBlockByNumber" =>
+		req => json!(["0xc", false]),
+		res => block(0xc, 0x10c, 0xb);
+	"eth_getBlockByNumber" =>
+		req => json!(["0xd", false]),
+		res => block(0xd, 0x10d, 0x10c);
+	"eth_getLogs" =>
+		req => json!([{
+			"address": null,
+			"fromBlock": "0xc",
+			"limit": null,
+			"toBlock": "0xd",
+			"topics": null
+		}]),
+		res => json!([]);
+}
+
+test_transport_stream! {
+	name => log_stream_reorg_of_checkpointed_block_inside_range,
+	init => |transport| {
+		let init = LogStreamInit {
+			after: 10,
+			filter: FilterBuilder::default(),
+			poll_interval: Duration::from_secs(0),
+			request_timeout: Duration::from_secs(5),
+			confirmations: 0,
+			max_block_range: None,
+			retry: Default::default(),
+			new_heads: None,
+		};
+
+		log_stream(transport, Default::default(), init).take(3)
+	},
+	expected => vec![LogStreamEvent::Logs(LogStreamItem {
+		from: 0xb,
+		to: 0x13,
+		logs: vec![],
+	}), LogStreamEvent::Rewind(0x12), LogStreamEvent::Logs(LogStreamItem {
+		from: 0x13,
+		to: 0x14,
+		logs: vec![],
+	})],
+	"eth_blockNumber" =>
+		req => json!([]),
+		res => json!("0x13");
+	"eth_getBlockByNumber" =>
+		req => json!(["0xb", false]),
+		res => canonical_block(0xb);
+	"eth_getBlockByNumber" =>
+		req => json!(["0xf", false]),
+		res => canonical_block(0xf);
+	"eth_getBlockByNumber" =>
+		req => json!(["0x11", false]),
+		res => canonical_block(0x11);
+	"eth_getBlockByNumber" =>
+		req => json!(["0x12", false]),
+		res => canonical_block(0x12);
+	"eth_getBlockByNumber" =>
+		req => json!(["0x13", false]),
+		res => canonical_block(0x13);
+	"eth_getLogs" =>
+		req => json!([{
+			"address": null,
+			"fromBlock": "0xb",
+			"limit": null,
+			"toBlock": "0x13",
+			"topics": null
+		}]),
+		res => json!([]);
+	// block 0x13 has been replaced by 0x113, the range is rescanned from 0x12 instead of 0xa
+	"eth_blockNumber" =>
+		req => json!([]),
+		res => json!("0x14");
+	"eth_getBlockByNumber" =>
+		req => json!(["0x14", false]),
+		res => block(0x14, 0x114, 0x113);
+	"eth_getBlockByNumber" =>
+		req => json!(["0x13", false]),
+		res => block(0x13, 0x113, 0x12);
+	"eth_getBlockByNumber" =>
+		req => json!(["0x13", false]),
+		res => block(0x13, 0x113, 0x12);
+	"eth_getBlockByNumber" =>
+		req => json!(["0x14", false]),
+		res => block(0x14, 0x114, 0x113);
+	"eth_getLogs" =>
+		req => json!([{
+			"address": null,
+			"fromBlock": "0x13",
+			"limit": null,
+			"toBlock": "0x14",
+			"topics": null
+		}]),
+		res => json!([]);
+}
+
+#[test]
+fn log_stream_reorg_deeper_than_history() {
+	use futures::{Future, Stream};
+
+	let transport = tests::MockedTransport {
+		requests: Default::default(),
+		expected_requests: vec![
+			("eth_blockNumber", json!([])).into(),
+			("eth_getBlockByNumber", json!(["0xb", false])).into(),
+			("eth_getLogs", json!([{
+				"address": null,
+				"fromBlock": "0xb",
+				"limit": null,
+				"toBlock": "0xb",
+				"topics": null
+			}])).into(),
+			("eth_blockNumber", json!([])).into(),
+			("eth_getBlockByNumber", json!(["0xc", false])).into(),
+			("eth_getBlockByNumber", json!(["0xb", false])).into(),
+		],
+		mocked_responses: vec![
+			json!("0xb"),
+			canonical_block(0xb),
+			json!([]),
+			// both 0xa and 0xb have been replaced, the stream does not remember any older block
+			json!("0xc"),
+			block(0xc, 0x10c, 0x10b),
+			block(0xb, 0x10b, 0x10a),
+		],
+	};
+	let init = LogStreamInit {
+		after: 10,
+		filter: FilterBuilder::default(),
+		poll_interval: Duration::from_secs(0),
+		request_timeout: Duration::from_secs(5),
+		confirmations: 0,
+		max_block_range: None,
+		retry: Default::default(),
+		new_heads: None,
+	};
+
+	let (first, stream) = log_stream(&transport, Default::default(), init).into_future().wait().map_err(|(e, _)| e).unwrap();
+	assert_eq!(Some(LogStreamEvent::Logs(LogStreamItem {
+		from: 0xb,
+		to: 0xb,
+		logs: vec![],
+	})), first);
+
+	// the common ancestor is unknown, the stream stops instead of guessing how far to rewind
+	match stream.into_future().wait().map_err(|(e, _)| e) {
+		Err(Error(ErrorKind::ReorgTooDeep(0xa), _)) => {},
+		other => panic!("unexpected result {:?}", other.map(|(event, _)| event)),
+	}
+	assert_eq!(6, transport.requests.get());
+}
+
+test_transport_stream! {
+	name => log_stream_ignores_removed_logs,
+	init => |transport| {
+		let init = LogStreamInit {
+			after: 10,
+			filter: FilterBuilder::default(),
+			poll_interval: Duration::from_secs(0),
+			request_timeout: Duration::from_secs(5),
+			confirmations: 0,
+			max_block_range: None,
+			retry: Default::default(),
+			new_heads: None,
+		};
+
+		log_stream(transport, Default::default(), init).take(1)
+	},
+	expected => vec![LogStreamEvent::Logs(LogStreamItem {
+		from: 0xb,
+		to: 0xb,
+		logs: vec![Log {
+			address: "0000000000000000000000000000000000000001".into(),
+			topics: vec![],
+			data: vec![0x10].into(),
+			block_hash: None,
+			block_number: None,
+			transaction_hash: None,
+			transaction_index: None,
+			log_index: None,
+			transaction_log_index: None,
+			log_type: None,
+			removed: Some(false),
+		}],
+	})],
+	"eth_blockNumber" =>
+		req => json!([]),
+		res => json!("0xb");
+	"eth_getBlockByNumber" =>
+		req => json!(["0xb", false]),
+		res => canonical_block(0xb);
+	"eth_getLogs" =>
+		req => json!([{
+			"address": null,
+			"fromBlock": "0xb",
+			"limit": null,
+			"toBlock": "0xb",
+			"topics": null
+		}]),
+		res => json!([{
+			"address": "0x0000000000000000000000000000000000000001",
+			"topics": [],
+			"data": "0x10",
+			"type": "",
+			"removed": false
+		}, {
+			"address": "0x0000000000000000000000000000000000000001",
+			"topics": [],
+			"data": "0x20",
+			"type": "",
+			"removed": true
+		}]);
+}
+
+test_transport_stream! {
+	name => log_stream_max_block_range,
+	init => |transport| {
+		let init = LogStreamInit {
+			after: 10,
+			filter: FilterBuilder::default(),
+			poll_interval: Duration::from_secs(0),
+			request_timeout: Duration::from_secs(5),
+			confirmations: 0,
+			max_block_range: Some(2),
+			retry: Default::default(),
+			new_heads: None,
+		};
+
+		log_stream(transport, Default::default(), init).take(3)
+	},
+	expected => vec![LogStreamEvent::Logs(LogStreamItem {
+		from: 0xb,
+		to: 0xc,
+		logs: vec![],
+	}), LogStreamEvent::Logs(LogStreamItem {
+		from: 0xd,
+		to: 0xe,
+		logs: vec![],
+	}), LogStreamEvent::Logs(LogStreamItem {
+		from: 0xf,
+		to: 0xf,
+		logs: vec![],
+	})],
+	"eth_blockNumber" =>
+		req => json!([]),
+		res => json!("0xf");
+	"eth_getBlockByNumber" =>
+		req => json!(["0xb", false]),
+		res => canonical_block(0xb);
+	"eth_getBlockByNumber" =>
+		req => json!(["0xc", false]),
+		res => canonical_block(0xc);
+	"eth_getLogs" =>
+		req => json!([{
+			"address": null,
+			"fromBlock": "0xb",
+			"limit": null,
+			"toBlock": "0xc",
+			"topics": null
+		}]),
+		res => json!([]);
+	// next chunk is fetched without waiting for a new block
+	"eth_getBlockByNumber" =>
+		req => json!(["0xd", false]),
+		res => canonical_block(0xd);
+	"eth_getBlockByNumber" =>
+		req => json!(["0xe", false]),
+		res => canonical_block(0xe);
+	"eth_getLogs" =>
+		req => json!([{
+			"address": null,
+			"fromBlock": "0xd",
+			"limit": null,
+			"toBlock": "0xe",
+			"topics": null
+		}]),
+		res => json!([]);
+	"eth_getBlockByNumber" =>
+		req => json!(["0xf", false]),
+		res => canonical_block(0xf);
+	"eth_getLogs" =>
+		req => json!([{
+			"address": null,
+			"fromBlock": "0xf",
+			"limit": null,
+			"toBlock": "0xf",
+			"topics": null
+		}]),
+		res => json!([]);
+}
@@ -0,0 +1,78 @@
+extern crate futures;
+#[macro_use]
+extern crate serde_json;
+extern crate web3;
+extern crate bridge;
+#[macro_use]
+extern crate tests;
+extern crate ethcore;
+extern crate ethcore_transaction;
+
+use std::rc::Rc;
+use web3::types::H256;
+use ethcore_transaction::{Transaction, Action};
+use bridge::bridge::nonce::{send_transaction_with_nonce, SendRawTransaction};
+use bridge::signer::RpcSigner;
+use tests::MockedTransport;
+
+/// EIP-155 example transfer.
+fn transfer() -> Transaction {
+	Transaction {
+		nonce: 9.into(),
+		gas_price: 20_000_000_000u64.into(),
+		gas: 21000.into(),
+		action: Action::Call("3535353535353535353535353535353535353535".into()),
+		value: 1_000_000_000_000_000_000u64.into(),
+		data: vec![],
+	}
+}
+
+/// `transfer` signed with the chain id 1.
+const TRANSFER_RAW: &str = "0xf86c098504a817c800825208943535353535353535353535353535353535353535880de0b6b3a76400008025a028ef61340bd939bc2195fe537567866003e1a15d3c71ff63e1590620aa636276a067cbe9d8997f761aecb703304b3800ccf555c9f3dc64214b297fb1966a3b6d83";
+/// Keccak of `TRANSFER_RAW`.
+const TRANSFER_HASH: &str = "33469b22e9f636356c4160a87eb19df52b7412e8eac32a4a55ffe88ea8350788";
+
+/// Signer node returning `TRANSFER_RAW`.
+fn remote_signer() -> Rc<MockedTransport> {
+	Rc::new(MockedTransport {
+		requests: Default::default(),
+		expected_requests: vec![("eth_signTransaction", json!([{}])).into()],
+		mocked_responses: vec![json!({ "raw": TRANSFER_RAW, "tx": {} })],
+	})
+}
+
+test_app_stream! {
+	name => already_imported_transaction_signed_remotely,
+	database => Database::default(),
+	home =>
+		account => "0000000000000000000000000000000000000001",
+		confirmations => 12;
+	foreign =>
+		account => "0000000000000000000000000000000000000001",
+		confirmations => 12;
+	authorities =>
+		accounts => [
+			"0000000000000000000000000000000000000001",
+			"0000000000000000000000000000000000000002",
+		],
+		signatures => 1;
+	txs => Transactions::default(),
+	init => |app: Arc<App<&MockedTransport>>, _db| {
+		let signer = remote_signer();
+		*app.config.foreign.info.nonce.write().unwrap() = 9.into();
+		let send = send_transaction_with_nonce(app.connections.foreign, app.clone(), app.config.foreign.clone(), Arc::new(RpcSigner::node(signer.clone())), transfer(), 1, SendRawTransaction(app.connections.foreign));
+		send.then(move |result| {
+			assert_eq!(1, signer.requests.get(), "the transaction should be signed remotely");
+			// the hash of the signed transaction is reported, it is tracked until it gets mined
+			assert_eq!(vec![H256::from(TRANSFER_HASH)], app.config.foreign.info.pending_transactions.read().unwrap().iter().map(|tx| tx.hash).collect::<Vec<_>>());
+			result
+		}).into_stream()
+	},
+	expected => vec![H256::from(TRANSFER_HASH)],
+	home_transport => [],
+	foreign_transport => [
+		"eth_sendRawTransaction" =>
+			req => json!([TRANSFER_RAW]),
+			res => json!({ "error": { "code": -32010, "message": "Transaction with the same hash was already imported." } });
+	]
+}
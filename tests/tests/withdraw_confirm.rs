@@ -3,429 +3,211 @@
 extern crate futures;
 #[macro_use]
 extern crate serde_json;
+extern crate web3;
 extern crate bridge;
 #[macro_use]
 extern crate tests;
-extern crate ethabi;
-extern crate rustc_hex;
-extern crate ethereum_types;
 extern crate ethcore;
 
-use rustc_hex::{ToHex, FromHex};
-use bridge::bridge::create_withdraw_confirm;
-use bridge::message_to_mainnet::MessageToMainnet;
-use bridge::contracts;
-use ethabi::{encode, Token};
+use std::sync::RwLock;
+use futures::{future, Async};
+use bridge::bridge::{create_withdraw_confirm, AuthoritySet, GasPrice, GasLimit, BridgeEvent, BridgeChecked};
+use bridge::database::{TransactionRecord, TransactionKind};
+use tests::{MockedTransport, AUTHORITY, canonical_block};
 
 const WITHDRAW_TOPIC: &str = "0xf279e6a1f5e320cca91135676d9cb6e44ca8a08c0b88342bcdb1144f6511b568";
+const TOKEN_WITHDRAW_TOPIC: &str = "0xf6a7e66150be6cb9298dfde86d270dad2bb6cf1550db4bc04fabc754e002e10c";
 
-use std::sync::RwLock;
+/// Hash of the foreign transaction making the withdraw.
+const WITHDRAW_HASH: &str = "884edad9ce6fa2440d8a54cc123490eb96d2768479d49ff9c7366125a9424364";
 
-test_app_stream! {
-	name => withdraw_confirm_basic,
-	database => Database::default(),
-	home =>
-		account => "0000000000000000000000000000000000000001",
-		confirmations => 12;
-	foreign =>
-		account => "0000000000000000000000000000000000000001",
-		confirmations => 12;
-	authorities =>
-		accounts => [
-			"0000000000000000000000000000000000000001",
-			"0000000000000000000000000000000000000002",
-		],
-		signatures => 1;
-	txs => Transactions::default(),
-	init => |app, db| create_withdraw_confirm(app, db, Arc::new(RwLock::new(Some(99999999999u64.into()))), 17, Arc::new(RwLock::new(1))).take(2),
-	expected => vec![0x1005, 0x1006],
-	home_transport => [],
-	foreign_transport => [
-		"eth_blockNumber" =>
-			req => json!([]),
-			res => json!("0x1011");
-		"eth_getLogs" =>
-			req => json!([{
-				"address": ["0x0000000000000000000000000000000000000000"],
-				"fromBlock": "0x1",
-				"limit": null,
-				"toBlock": "0x1005",
-				"topics": [[WITHDRAW_TOPIC], null, null, null]
-			}]),
-			res => json!([]);
-		"eth_blockNumber" =>
-			req => json!([]),
-			res => json!("0x1012");
-		"eth_getLogs" =>
-			req => json!([{
-				"address": ["0x0000000000000000000000000000000000000000"],
-				"fromBlock": "0x1006",
-				"limit": null,
-				"toBlock": "0x1006",
-				"topics":[[WITHDRAW_TOPIC], null, null, null]
-			}]),
-			res => json!([]);
-	]
+/// Hash of the foreign transaction submitting the signature.
+const CONFIRM_HASH: &str = "1db8f385535c0d178b8f40016048f3a3cffee8f87e68c8b5d10aeff6d0f7c900";
+
+/// `Withdraw` of 0xf0 to 0xaff3454fce5edbc8cca8697c15331677e6ebcccc with home gas price of 1 gwei logged in block 6.
+fn withdraw_log() -> serde_json::Value {
+	json!([{
+		"address": "0x0000000000000000000000000000000000000000",
+		"topics": [WITHDRAW_TOPIC],
+		"data": "0x000000000000000000000000aff3454fce5edbc8cca8697c15331677e6ebcccc00000000000000000000000000000000000000000000000000000000000000f0000000000000000000000000000000000000000000000000000000003b9aca00",
+		"type": "",
+		"transactionHash": format!("0x{}", WITHDRAW_HASH),
+	}])
 }
 
-test_app_stream! {
-	name => withdraw_confirm_confirmations,
-	database => Database {
-		checked_withdraw_confirm: 0xf5,
-		..Database::default()
-	},
-	home =>
-		account => "0000000000000000000000000000000000000001",
-		confirmations => 1;
-	foreign =>
-		account => "0000000000000000000000000000000000000001",
-		confirmations => 12;
-	authorities =>
-		accounts => [
-			"0000000000000000000000000000000000000001",
-			"0000000000000000000000000000000000000002",
-		],
-		signatures => 1;
-	txs => Transactions::default(),
-	init => |app, db| create_withdraw_confirm(app, db, Arc::new(RwLock::new(Some(99999999999u64.into()))), 17, Arc::new(RwLock::new(1))).take(2),
-	expected => vec![0x1005, 0x1006],
-	home_transport => [],
-	foreign_transport => [
-		"eth_blockNumber" =>
-			req => json!([]),
-			res => json!("0x0100");
-		"eth_blockNumber" =>
-			req => json!([]),
-			res => json!("0x1011");
-		"eth_getLogs" =>
-			req => json!([{
-				"address":["0x0000000000000000000000000000000000000000"],
-				"fromBlock": "0xf6",
-				"limit": null, "toBlock": "0x1005",
-				"topics": [[WITHDRAW_TOPIC], null, null, null]
-			}]),
-			res => json!([]);
-		"eth_blockNumber" =>
-			req => json!([]),
-			res => json!("0x1012");
-		"eth_getLogs" =>
-			req => json!([{
-				"address":["0x0000000000000000000000000000000000000000"],
-				"fromBlock": "0x1006",
-				"limit": null,
-				"toBlock": "0x1006",
-				"topics":[[WITHDRAW_TOPIC], null, null, null]
-			}]),
-			res => json!([]);
+/// `ForeignBridge.isMessageSigned` call checking whether `AUTHORITY` has confirmed the withdraw of `withdraw_log`.
+fn is_message_signed_request() -> serde_json::Value {
+	json!([{
+		"data": "0xc7d29faf0000000000000000000000009d8a62f656a8d1615c1294fd71e9cfb3e4855a4f00000000000000000000000000000000000000000000000000000000000000400000000000000000000000000000000000000000000000000000000000000074aff3454fce5edbc8cca8697c15331677e6ebcccc00000000000000000000000000000000000000000000000000000000000000f0884edad9ce6fa2440d8a54cc123490eb96d2768479d49ff9c7366125a9424364000000000000000000000000000000000000000000000000000000003b9aca00000000000000000000000000",
+		"to": "0x0000000000000000000000000000000000000000",
+	}, "latest"])
+}
+
+const FALSE: &str = "0x0000000000000000000000000000000000000000000000000000000000000000";
+const TRUE: &str = "0x0000000000000000000000000000000000000000000000000000000000000001";
+
+fn confirmed() -> Vec<BridgeEvent> {
+	vec![
+		BridgeEvent::Sent(vec![TransactionRecord {
+			kind: TransactionKind::WithdrawConfirm,
+			source_transaction_hash: WITHDRAW_HASH.into(),
+			transaction_hash: CONFIRM_HASH.into(),
+			block: 6,
+		}]),
+		BridgeEvent::Checked(BridgeChecked::WithdrawConfirm(6)),
 	]
 }
 
 test_app_stream! {
-	name => withdraw_confirm_contract_address,
+	name => withdraw_confirm_single_log,
 	database => Database {
-		checked_withdraw_confirm: 0x00F5,
-		home_contract_address: "49edf201c1e139282643d5e7c6fb0c7219ad1db7".into(),
-		foreign_contract_address: "49edf201c1e139282643d5e7c6fb0c7219ad1db8".into(),
-		..Database::default()
+		checked_withdraw_confirm: 5,
+		..Default::default()
 	},
 	home =>
-		account => "0000000000000000000000000000000000000001",
+		account => AUTHORITY,
 		confirmations => 12;
 	foreign =>
-		account => "0000000000000000000000000000000000000001",
+		account => AUTHORITY,
 		confirmations => 12;
 	authorities =>
 		accounts => [
-			"0000000000000000000000000000000000000001",
+			AUTHORITY,
 			"0000000000000000000000000000000000000002",
 		],
 		signatures => 1;
 	txs => Transactions::default(),
-	init => |app, db| create_withdraw_confirm(app, db, Arc::new(RwLock::new(Some(99999999999u64.into()))), 17, Arc::new(RwLock::new(1))).take(2),
-	expected => vec![0x1005, 0x1006],
+	init => |app: Arc<App<&MockedTransport>>, db| create_withdraw_confirm(app.clone(), db, Arc::new(RwLock::new(Some(99999999999u64.into()))), 17,
+		GasPrice::new(&app.config.foreign), Arc::new(RwLock::new(None)), GasLimit::new(100_000)).take(2),
+	expected => confirmed(),
 	home_transport => [],
 	foreign_transport => [
 		"eth_blockNumber" =>
 			req => json!([]),
-			res => json!("0x0100");
-		"eth_blockNumber" =>
-			req => json!([]),
-			res => json!("0x1011");
+			res => json!("0x12");
+		"eth_getBlockByNumber" =>
+			req => json!(["0x6", false]),
+			res => canonical_block(6);
 		"eth_getLogs" =>
 			req => json!([{
-				"address":["0x49edf201c1e139282643d5e7c6fb0c7219ad1db8"],
-				"fromBlock": "0xf6",
+				"address": ["0x0000000000000000000000000000000000000000"],
+				"fromBlock": "0x6",
 				"limit": null,
-				"toBlock": "0x1005",
-				"topics":[[WITHDRAW_TOPIC],null,null,null]
+				"toBlock": "0x6",
+				"topics": [[WITHDRAW_TOPIC, TOKEN_WITHDRAW_TOPIC], null, null, null]
 			}]),
-			res => json!([]);
-		"eth_blockNumber" =>
+			res => withdraw_log();
+		"eth_call" =>
+			req => is_message_signed_request(),
+			res => json!(FALSE);
+		"eth_sendRawTransaction" =>
 			req => json!([]),
-			res => json!("0x1012");
-		"eth_getLogs" =>
-			req => json!([{
-				"address": ["0x49edf201c1e139282643d5e7c6fb0c7219ad1db8"],
-				"fromBlock": "0x1006",
-				"limit": null,
-				"toBlock": "0x1006",
-				"topics":[[WITHDRAW_TOPIC],null,null,null]
-			}]),
-			res => json!([]);
+			res => json!(format!("0x{}", CONFIRM_HASH));
 	]
 }
 
 test_app_stream! {
-	name => withdraw_confirm_payload_gas,
+	name => withdraw_confirm_skips_confirmed_withdraw,
 	database => Database {
-		checked_withdraw_confirm: 0x00F5,
-		home_contract_address: "49edf201c1e139282643d5e7c6fb0c7219ad1db7".into(),
-		foreign_contract_address: "49edf201c1e139282643d5e7c6fb0c7219ad1db8".into(),
-		..Database::default()
+		checked_withdraw_confirm: 5,
+		..Default::default()
 	},
 	home =>
-		account => "0000000000000000000000000000000000000001",
+		account => AUTHORITY,
 		confirmations => 12;
 	foreign =>
-		account => "0000000000000000000000000000000000000001",
+		account => AUTHORITY,
 		confirmations => 12;
 	authorities =>
 		accounts => [
-			"00000000000000000000000000000000000000F1",
-			"00000000000000000000000000000000000000F2",
+			AUTHORITY,
+			"0000000000000000000000000000000000000002",
 		],
 		signatures => 1;
-	txs => Transactions {
-		withdraw_confirm: TransactionConfig {
-			gas: 0xfe,
-			gas_price: 0xa1,
-			concurrency: 100,
-		},
-		..Default::default()
-	},
-	init => |app, db| create_withdraw_confirm(app, db, Arc::new(RwLock::new(Some(99999999999u64.into()))), 17, Arc::new(RwLock::new(1))).take(2),
-	expected => vec![0x1005, 0x1006],
+	txs => Transactions::default(),
+	init => |app: Arc<App<&MockedTransport>>, db| create_withdraw_confirm(app.clone(), db, Arc::new(RwLock::new(Some(99999999999u64.into()))), 17,
+		GasPrice::new(&app.config.foreign), Arc::new(RwLock::new(None)), GasLimit::new(100_000)).take(2),
+	expected => vec![
+		BridgeEvent::Sent(vec![]),
+		BridgeEvent::Checked(BridgeChecked::WithdrawConfirm(6)),
+	],
 	home_transport => [],
 	foreign_transport => [
 		"eth_blockNumber" =>
 			req => json!([]),
-			res => json!("0x0100");
-		"eth_blockNumber" =>
-			req => json!([]),
-			res => json!("0x1011");
+			res => json!("0x12");
+		"eth_getBlockByNumber" =>
+			req => json!(["0x6", false]),
+			res => canonical_block(6);
 		"eth_getLogs" =>
 			req => json!([{
-				"address": ["0x49edf201c1e139282643d5e7c6fb0c7219ad1db8"],
-				"fromBlock": "0xf6",
-				"limit": null,
-				"toBlock": "0x1005",
-				"topics": [[WITHDRAW_TOPIC], null, null, null]
-			}]),
-			res => json!([{
-				"address": "0x49edf201c1e139282643d5e7c6fb0c7219ad1db8",
-				"topics": [WITHDRAW_TOPIC],
-				"data": format!("0x{}", encode(&[
-					Token::Address([1u8; 20].into()),
-					Token::Uint(10000.into()),
-					Token::Uint(1000.into()),
-				]).to_hex()),
-				"type": "",
-				"transactionHash": "0x884edad9ce6fa2440d8a54cc123490eb96d2768479d49ff9c7366125a9424364"
-			}]);
-		"eth_sign" =>
-			req => json!([
-				"0x0000000000000000000000000000000000000001",
-				format!("0x{}", MessageToMainnet {
-					recipient: [1u8; 20].into(),
-					value: 10000.into(),
-					sidenet_transaction_hash: "0x884edad9ce6fa2440d8a54cc123490eb96d2768479d49ff9c7366125a9424364".into(),
-					mainnet_gas_price: 1000.into(),
-				}
-				.to_bytes()
-				.to_hex())
-			]),
-			res => json!("0x8697c15331677e6ebccccaff3454fce5edbc8cca8697c15331677aff3454fce5edbc8cca8697c15331677e6ebccccaff3454fce5edbc8cca8697c15331677e6ebc");
-		// `submitSignature`
-		"eth_sendTransaction" =>
-			req => json!([{
-				"data": format!("0x{}", contracts::foreign::ForeignBridge::default()
-					.functions()
-					.submit_signature()
-					.input(
-						"8697c15331677e6ebccccaff3454fce5edbc8cca8697c15331677aff3454fce5edbc8cca8697c15331677e6ebccccaff3454fce5edbc8cca8697c15331677e6ebc".from_hex().unwrap(),
-						MessageToMainnet {
-							recipient: [1u8; 20].into(),
-							value: 10000.into(),
-							sidenet_transaction_hash: "0x884edad9ce6fa2440d8a54cc123490eb96d2768479d49ff9c7366125a9424364".into(),
-							mainnet_gas_price: 1000.into(),
-						}.to_bytes()
-					)
-					.to_hex()),
-				"from": "0x0000000000000000000000000000000000000001",
-				"gas": "0xfe",
-				"gasPrice": "0xa1",
-				"to":"0x49edf201c1e139282643d5e7c6fb0c7219ad1db8"
-			}]),
-			res => json!("0x1db8f385535c0d178b8f40016048f3a3cffee8f94e68978ea4b277f57b638f0b");
-		"eth_blockNumber" =>
-			req => json!([]),
-			res => json!("0x1012");
-		"eth_getLogs" =>
-			req => json!([{
-				"address": ["0x49edf201c1e139282643d5e7c6fb0c7219ad1db8"],
-				"fromBlock": "0x1006",
+				"address": ["0x0000000000000000000000000000000000000000"],
+				"fromBlock": "0x6",
 				"limit": null,
-				"toBlock": "0x1006",
-				"topics":[[WITHDRAW_TOPIC], null, null, null]
+				"toBlock": "0x6",
+				"topics": [[WITHDRAW_TOPIC, TOKEN_WITHDRAW_TOPIC], null, null, null]
 			}]),
-			res => json!([]);
+			res => withdraw_log();
+		"eth_call" =>
+			req => is_message_signed_request(),
+			res => json!(TRUE);
 	]
 }
 
 test_app_stream! {
-	name => withdraw_confirm_payload_multiple,
+	name => withdraw_confirm_waits_until_authority_is_added,
 	database => Database {
-		home_contract_address: "49edf201c1e139282643d5e7c6fb0c7219ad1db7".into(),
-		foreign_contract_address: "49edf201c1e139282643d5e7c6fb0c7219ad1db8".into(),
-		..Database::default()
+		checked_withdraw_confirm: 5,
+		..Default::default()
 	},
 	home =>
-		account => "0000000000000000000000000000000000000001",
+		account => AUTHORITY,
 		confirmations => 12;
 	foreign =>
-		account => "0000000000000000000000000000000000000001",
+		account => AUTHORITY,
 		confirmations => 12;
 	authorities =>
 		accounts => [
-			"00000000000000000000000000000000000000F1",
-			"00000000000000000000000000000000000000F2",
+			AUTHORITY,
+			"0000000000000000000000000000000000000002",
 		],
 		signatures => 1;
-	txs => Transactions {
-		withdraw_confirm: TransactionConfig {
-			gas: 0xff,
-			gas_price: 0xaa,
-			concurrency: 100,
-		},
-		..Default::default()
+	txs => Transactions::default(),
+	init => |app: Arc<App<&MockedTransport>>, db| {
+		let authorities = Arc::new(RwLock::new(Some(AuthoritySet {
+			accounts: vec!["0000000000000000000000000000000000000002".into()],
+			required_signatures: 1,
+		})));
+		let mut confirm = create_withdraw_confirm(app.clone(), db, Arc::new(RwLock::new(Some(99999999999u64.into()))), 17,
+			GasPrice::new(&app.config.foreign), authorities.clone(), GasLimit::new(100_000));
+		// withdraws are not even fetched while the foreign contract does not accept signatures of `AUTHORITY`
+		assert_eq!(Async::NotReady, future::lazy(|| confirm.poll()).wait().unwrap());
+		authorities.write().unwrap().as_mut().unwrap().accounts.push(AUTHORITY.into());
+		confirm.take(2)
 	},
-	init => |app, db| create_withdraw_confirm(app, db, Arc::new(RwLock::new(Some(99999999999u64.into()))), 17, Arc::new(RwLock::new(1))).take(2),
-	expected => vec![0x2, 0x1006],
+	expected => confirmed(),
 	home_transport => [],
 	foreign_transport => [
 		"eth_blockNumber" =>
 			req => json!([]),
-			res => json!("0xe");
+			res => json!("0x12");
+		"eth_getBlockByNumber" =>
+			req => json!(["0x6", false]),
+			res => canonical_block(6);
 		"eth_getLogs" =>
 			req => json!([{
-				"address": ["0x49edf201c1e139282643d5e7c6fb0c7219ad1db8"],
-				"fromBlock": "0x1",
+				"address": ["0x0000000000000000000000000000000000000000"],
+				"fromBlock": "0x6",
 				"limit": null,
-				"toBlock": "0x2",
-				"topics": [[WITHDRAW_TOPIC], null, null, null]
-			}]),
-			res => json!([{
-				"address": "0x49edf201c1e139282643d5e7c6fb0c7219ad1db8",
-				"topics": [WITHDRAW_TOPIC],
-				"data": format!("0x{}", encode(&[
-					Token::Address([1u8; 20].into()),
-					Token::Uint(10000.into()),
-					Token::Uint(1000.into()),
-				]).to_hex()),
-				"type": "",
-				"transactionHash": "0x884edad9ce6fa2440d8a54cc123490eb96d2768479d49ff9c7366125a9424364"},
-			{
-				"address":"0x49edf201c1e139282643d5e7c6fb0c7219ad1db8",
-				"topics": [WITHDRAW_TOPIC],
-				"data": format!("0x{}", encode(&[
-					Token::Address([2u8; 20].into()),
-					Token::Uint(42.into()),
-					Token::Uint(100.into()),
-				]).to_hex()),
-				"type":"",
-				"transactionHash":"0xfffedad9ce6fa2440d8a54cc123490eb96d2768479d49ff9c7366125a9424364"
-			}]);
-		"eth_sign" =>
-			req => json!([
-				"0x0000000000000000000000000000000000000001",
-				format!("0x{}", MessageToMainnet {
-					recipient: [1u8; 20].into(),
-					value: 10000.into(),
-					sidenet_transaction_hash: "0x884edad9ce6fa2440d8a54cc123490eb96d2768479d49ff9c7366125a9424364".into(),
-					mainnet_gas_price: 1000.into(),
-				}
-				.to_bytes()
-				.to_hex())
-			]),
-			res => json!("0x8697c15331677e6ebccccaff3454fce5edbc8cca8697c15331677aff3454fce5edbc8cca8697c15331677e6ebccccaff3454fce5edbc8cca8697c15331677e6ebc");
-		"eth_sign" =>
-			req => json!([
-				"0x0000000000000000000000000000000000000001",
-				format!("0x{}", MessageToMainnet {
-					recipient: [2u8; 20].into(),
-					value: 42.into(),
-					sidenet_transaction_hash: "0xfffedad9ce6fa2440d8a54cc123490eb96d2768479d49ff9c7366125a9424364".into(),
-					mainnet_gas_price: 100.into(),
-				}
-				.to_bytes()
-				.to_hex())
-			]),
-			res => json!("0x8a3b24c56e46f6fc9fa7ed14795745348059b8ac84d6ee93323e83a429e760ae6e89510834ee4d65eefacd74cddca53df61b5eba1c3007ed88d2eebff2e0e2151b");
-		// `submitSignature`
-		"eth_sendTransaction" =>
-			req => json!([{
-				"data": format!("0x{}", contracts::foreign::ForeignBridge::default()
-					.functions()
-					.submit_signature()
-					.input(
-						"8697c15331677e6ebccccaff3454fce5edbc8cca8697c15331677aff3454fce5edbc8cca8697c15331677e6ebccccaff3454fce5edbc8cca8697c15331677e6ebc".from_hex().unwrap(),
-						MessageToMainnet {
-							recipient: [1u8; 20].into(),
-							value: 10000.into(),
-							sidenet_transaction_hash: "0x884edad9ce6fa2440d8a54cc123490eb96d2768479d49ff9c7366125a9424364".into(),
-							mainnet_gas_price: 1000.into(),
-						}.to_bytes()
-					)
-					.to_hex()),
-				"from": "0x0000000000000000000000000000000000000001",
-				"gas": "0xff",
-				"gasPrice": "0xaa",
-				"to":"0x49edf201c1e139282643d5e7c6fb0c7219ad1db8"
-			}]),
-			res => json!("0x1db8f385535c0d178b8f40016048f3a3cffee8f94e68978ea4b277f57b638f0b");
-		// `submitSignature`
-		"eth_sendTransaction" =>
-			req => json!([{
-				"data": format!("0x{}", contracts::foreign::ForeignBridge::default()
-					.functions()
-					.submit_signature()
-					.input(
-						"8a3b24c56e46f6fc9fa7ed14795745348059b8ac84d6ee93323e83a429e760ae6e89510834ee4d65eefacd74cddca53df61b5eba1c3007ed88d2eebff2e0e2151b".from_hex().unwrap(),
-						MessageToMainnet {
-							recipient: [2u8; 20].into(),
-							value: 42.into(),
-							sidenet_transaction_hash: "0xfffedad9ce6fa2440d8a54cc123490eb96d2768479d49ff9c7366125a9424364".into(),
-							mainnet_gas_price: 100.into(),
-						}.to_bytes()
-					)
-					.to_hex()),
-				"from": "0x0000000000000000000000000000000000000001",
-				"gas": "0xff",
-				"gasPrice": "0xaa",
-				"to":"0x49edf201c1e139282643d5e7c6fb0c7219ad1db8"
+				"toBlock": "0x6",
+				"topics": [[WITHDRAW_TOPIC, TOKEN_WITHDRAW_TOPIC], null, null, null]
 			}]),
-			res => json!("0x1db8f385535c0d178b8f40016048f3a3cffee8f94e68978ea4b277f57b638f0c");
-		"eth_blockNumber" =>
+			res => withdraw_log();
+		"eth_call" =>
+			req => is_message_signed_request(),
+			res => json!(FALSE);
+		"eth_sendRawTransaction" =>
 			req => json!([]),
-			res => json!("0x1012");
-		"eth_getLogs" =>
-			req => json!([{
-				"address": ["0x49edf201c1e139282643d5e7c6fb0c7219ad1db8"],
-				"fromBlock": "0x3",
-				"limit": null,
-				"toBlock": "0x1006",
-				"topics":[[WITHDRAW_TOPIC], null, null, null]
-			}]),
-			res => json!([]);
+			res => json!(format!("0x{}", CONFIRM_HASH));
 	]
 }
@@ -3,214 +3,380 @@
 extern crate futures;
 #[macro_use]
 extern crate serde_json;
+extern crate web3;
 extern crate bridge;
 #[macro_use]
 extern crate tests;
-extern crate ethabi;
-extern crate ethereum_types;
-extern crate rustc_hex;
 extern crate ethcore;
 
-use ethereum_types::{U256, H256};
-use rustc_hex::ToHex;
+use std::sync::RwLock;
+use bridge::bridge::{create_withdraw_relay, AuthoritySet, GasPrice, GasLimit, BridgeEvent, BridgeChecked};
+use bridge::database::{TransactionRecord, TransactionKind};
+use tests::{MockedTransport, AUTHORITY, canonical_block};
 
-use bridge::bridge::create_withdraw_relay;
-use bridge::message_to_mainnet::MessageToMainnet;
-use bridge::signature::Signature;
-use bridge::contracts;
+const COLLECTED_SIGNATURES_TOPIC: &str = "0x415557404d88a0c0b8e3b16967cafffc511213fd9c465c16832ee17ed57d7237";
+const COLLECTED_ADDITIONAL_SIGNATURE_TOPIC: &str = "0x3cbadf8969d23c84b110349c9b2c1e1a7f04b40e48986fa606a321e79c652083";
 
-use std::sync::RwLock;
+/// Withdraw of 0xf0 to 0xaff3454fce5edbc8cca8697c15331677e6ebcccc with home gas price of 1 gwei.
+const MESSAGE: &str = "aff3454fce5edbc8cca8697c15331677e6ebcccc00000000000000000000000000000000000000000000000000000000000000f0884edad9ce6fa2440d8a54cc123490eb96d2768479d49ff9c7366125a9424364000000000000000000000000000000000000000000000000000000003b9aca00";
+/// Keccak of `MESSAGE`.
+const MESSAGE_HASH: &str = "6bb35be3d74609e84e9fea63c32ed95a8ed180599064dc0386dea02b370ef962";
+/// Signature of `MESSAGE` collected on foreign.
+const SIGNATURE: &str = "11111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111b";
+
+/// Hash of the foreign transaction making the withdraw.
+const WITHDRAW_HASH: &str = "884edad9ce6fa2440d8a54cc123490eb96d2768479d49ff9c7366125a9424364";
+
+/// Hash of the home transaction relaying the withdraw.
+const RELAY_HASH: &str = "1db8f385535c0d178b8f40016048f3a3cffee8f87e68c8b5d10aeff6d0f7c900";
+
+const FALSE: &str = "0x0000000000000000000000000000000000000000000000000000000000000000";
+const TRUE: &str = "0x0000000000000000000000000000000000000000000000000000000000000001";
+
+/// `CollectedSignatures` or `CollectedAdditionalSignature` (given by `topic`) of `MESSAGE` logged in block 6.
+fn collected_signatures_log(topic: &str, authority: &str, collected: u64) -> serde_json::Value {
+	json!([{
+		"address": "0x0000000000000000000000000000000000000000",
+		"topics": [topic],
+		"data": format!("0x{:0>64}{}{:064x}", authority, MESSAGE_HASH, collected),
+		"type": "",
+		"transactionHash": "0x5d7b4ab9b1bc6d4e4b2c1b1cfe4b5cd1cb0b8e7b7a33f3c6e1b2a1c6b1e9a7d2",
+	}])
+}
+
+fn collected_signatures_request() -> serde_json::Value {
+	json!([{
+		"address": ["0x0000000000000000000000000000000000000000"],
+		"fromBlock": "0x6",
+		"limit": null,
+		"toBlock": "0x6",
+		"topics": [[COLLECTED_SIGNATURES_TOPIC, COLLECTED_ADDITIONAL_SIGNATURE_TOPIC], null, null, null]
+	}])
+}
+
+/// `ForeignBridge.message` call reading `MESSAGE`.
+fn message_request() -> serde_json::Value {
+	json!([{
+		"data": format!("0x490a32c6{}", MESSAGE_HASH),
+		"to": "0x0000000000000000000000000000000000000000",
+	}, "latest"])
+}
+
+fn message_response() -> serde_json::Value {
+	json!(format!("0x{:064x}{:064x}{:0<256}", 0x20, MESSAGE.len() / 2, MESSAGE))
+}
+
+/// `ForeignBridge.signature` call reading signature number `index` of `MESSAGE`.
+fn signature_request(index: u64) -> serde_json::Value {
+	json!([{
+		"data": format!("0x1812d996{}{:064x}", MESSAGE_HASH, index),
+		"to": "0x0000000000000000000000000000000000000000",
+	}, "latest"])
+}
+
+fn signature_response() -> serde_json::Value {
+	json!(format!("0x{:064x}{:064x}{:0<192}", 0x20, SIGNATURE.len() / 2, SIGNATURE))
+}
+
+/// `HomeBridge.withdraws` call checking whether the withdraw has been relayed.
+fn withdrawn_request() -> serde_json::Value {
+	json!([{
+		"data": format!("0xe09ab428{}", WITHDRAW_HASH),
+		"to": "0x0000000000000000000000000000000000000000",
+	}, "latest"])
+}
+
+fn relayed() -> Vec<BridgeEvent> {
+	vec![
+		BridgeEvent::Sent(vec![TransactionRecord {
+			kind: TransactionKind::WithdrawRelay,
+			source_transaction_hash: WITHDRAW_HASH.into(),
+			transaction_hash: RELAY_HASH.into(),
+			block: 6,
+		}]),
+		BridgeEvent::Checked(BridgeChecked::WithdrawRelay(6)),
+	]
+}
 
-const COLLECTED_SIGNATURES_TOPIC: &str = "0xeb043d149eedb81369bec43d4c3a3a53087debc88d2525f13bfaa3eecda28b5c";
+fn skipped() -> Vec<BridgeEvent> {
+	vec![
+		BridgeEvent::Sent(vec![]),
+		BridgeEvent::Checked(BridgeChecked::WithdrawRelay(6)),
+	]
+}
 
-// 1 signature required. relay polled twice.
-// no CollectedSignatures on ForeignBridge.
-// no relay.
+// all collected signatures are relayed while the home authorities are not known.
 test_app_stream! {
-	name => withdraw_relay_no_log_no_relay,
-	database => Database::default(),
+	name => withdraw_relay_single_log_relay,
+	database => Database {
+		checked_withdraw_relay: 5,
+		..Default::default()
+	},
+	home =>
+		account => AUTHORITY,
+		confirmations => 12;
+	foreign =>
+		account => AUTHORITY,
+		confirmations => 12;
+	authorities =>
+		accounts => [
+			AUTHORITY,
+			"0000000000000000000000000000000000000002",
+		],
+		signatures => 1;
+	txs => Transactions::default(),
+	init => |app: Arc<App<&MockedTransport>>, db| create_withdraw_relay(app.clone(), db, Arc::new(RwLock::new(Some(99999999999u64.into()))), 17,
+		GasPrice::new(&app.config.home), Arc::new(RwLock::new(None)), GasLimit::new(100_000)).take(2),
+	expected => relayed(),
+	home_transport => [
+		"eth_call" =>
+			req => withdrawn_request(),
+			res => json!(FALSE);
+		"eth_sendRawTransaction" =>
+			req => json!([]),
+			res => json!(format!("0x{}", RELAY_HASH));
+	],
+	foreign_transport => [
+		"eth_blockNumber" =>
+			req => json!([]),
+			res => json!("0x12");
+		"eth_getBlockByNumber" =>
+			req => json!(["0x6", false]),
+			res => canonical_block(6);
+		"eth_getLogs" =>
+			req => collected_signatures_request(),
+			res => collected_signatures_log(COLLECTED_SIGNATURES_TOPIC, AUTHORITY, 1);
+		"eth_call" =>
+			req => message_request(),
+			res => message_response();
+		"eth_call" =>
+			req => signature_request(0),
+			res => signature_response();
+	]
+}
+
+test_app_stream! {
+	name => withdraw_relay_single_log_authority_not_responsible_no_relay,
+	database => Database {
+		checked_withdraw_relay: 5,
+		..Default::default()
+	},
 	home =>
-		account => "0000000000000000000000000000000000000001",
+		account => AUTHORITY,
 		confirmations => 12;
 	foreign =>
-		account => "0000000000000000000000000000000000000001",
+		account => AUTHORITY,
 		confirmations => 12;
 	authorities =>
 		accounts => [
-			"0000000000000000000000000000000000000001",
+			AUTHORITY,
 			"0000000000000000000000000000000000000002",
 		],
 		signatures => 1;
 	txs => Transactions::default(),
-	init => |app, db| create_withdraw_relay(app, db, Arc::new(RwLock::new(Some(99999999999u64.into()))), 17, Arc::new(RwLock::new(1))).take(2),
-	expected => vec![0x1005, 0x1006],
+	init => |app: Arc<App<&MockedTransport>>, db| create_withdraw_relay(app.clone(), db, Arc::new(RwLock::new(Some(99999999999u64.into()))), 17,
+		GasPrice::new(&app.config.home), Arc::new(RwLock::new(None)), GasLimit::new(100_000)).take(2),
+	expected => skipped(),
 	home_transport => [],
 	foreign_transport => [
 		"eth_blockNumber" =>
 			req => json!([]),
-			res => json!("0x1011");
+			res => json!("0x12");
+		"eth_getBlockByNumber" =>
+			req => json!(["0x6", false]),
+			res => canonical_block(6);
 		"eth_getLogs" =>
-			req => json!([{
-				"address": ["0x0000000000000000000000000000000000000000"],
-				"fromBlock": "0x1",
-				"limit": null,
-				"toBlock": "0x1005",
-				"topics": [[COLLECTED_SIGNATURES_TOPIC], null, null, null]
-			}]),
-			res => json!([]);
+			req => collected_signatures_request(),
+			res => collected_signatures_log(COLLECTED_SIGNATURES_TOPIC, "0000000000000000000000000000000000000002", 1);
+	]
+}
+
+test_app_stream! {
+	name => withdraw_relay_skips_relayed_withdraw,
+	database => Database {
+		checked_withdraw_relay: 5,
+		..Default::default()
+	},
+	home =>
+		account => AUTHORITY,
+		confirmations => 12;
+	foreign =>
+		account => AUTHORITY,
+		confirmations => 12;
+	authorities =>
+		accounts => [
+			AUTHORITY,
+			"0000000000000000000000000000000000000002",
+		],
+		signatures => 1;
+	txs => Transactions::default(),
+	init => |app: Arc<App<&MockedTransport>>, db| create_withdraw_relay(app.clone(), db, Arc::new(RwLock::new(Some(99999999999u64.into()))), 17,
+		GasPrice::new(&app.config.home), Arc::new(RwLock::new(None)), GasLimit::new(100_000)).take(2),
+	expected => skipped(),
+	home_transport => [
+		"eth_call" =>
+			req => withdrawn_request(),
+			res => json!(TRUE);
+	],
+	foreign_transport => [
 		"eth_blockNumber" =>
 			req => json!([]),
-			res => json!("0x1012");
+			res => json!("0x12");
+		"eth_getBlockByNumber" =>
+			req => json!(["0x6", false]),
+			res => canonical_block(6);
 		"eth_getLogs" =>
-			req => json!([{
-				"address": ["0x0000000000000000000000000000000000000000"],
-				"fromBlock": "0x1006",
-				"limit": null,
-				"toBlock": "0x1006",
-				"topics": [[COLLECTED_SIGNATURES_TOPIC], null, null, null]
-			}]),
-			res => json!([]);
+			req => collected_signatures_request(),
+			res => collected_signatures_log(COLLECTED_SIGNATURES_TOPIC, AUTHORITY, 1);
+		"eth_call" =>
+			req => message_request(),
+			res => message_response();
+		"eth_call" =>
+			req => signature_request(0),
+			res => signature_response();
 	]
 }
 
-// 2 signatures required. relay polled twice.
-// single CollectedSignatures log present. message value covers relay cost.
-// authority not responsible.
-// message is ignored.
+// home requires more signatures than foreign has collected, the withdraw is relayed once more are collected.
 test_app_stream! {
-	name => withdraw_relay_single_log_authority_not_responsible_no_relay,
-	database => Database::default(),
+	name => withdraw_relay_waits_for_signatures_required_by_home,
+	database => Database {
+		checked_withdraw_relay: 5,
+		..Default::default()
+	},
 	home =>
-		account => "0000000000000000000000000000000000000001",
+		account => AUTHORITY,
 		confirmations => 12;
 	foreign =>
-		account => "0000000000000000000000000000000000000001",
+		account => AUTHORITY,
 		confirmations => 12;
 	authorities =>
 		accounts => [
-			"0000000000000000000000000000000000000001",
+			AUTHORITY,
 			"0000000000000000000000000000000000000002",
 		],
 		signatures => 1;
 	txs => Transactions::default(),
-	init => |app, db| create_withdraw_relay(app, db, Arc::new(RwLock::new(Some(99999999999u64.into()))), 17, Arc::new(RwLock::new(1))).take(1),
-	expected => vec![0x1005],
+	init => |app: Arc<App<&MockedTransport>>, db| {
+		let home_authorities = Arc::new(RwLock::new(Some(AuthoritySet {
+			accounts: vec![AUTHORITY.into(), "0000000000000000000000000000000000000002".into()],
+			required_signatures: 2,
+		})));
+		create_withdraw_relay(app.clone(), db, Arc::new(RwLock::new(Some(99999999999u64.into()))), 17,
+			GasPrice::new(&app.config.home), home_authorities, GasLimit::new(100_000)).take(2)
+	},
+	expected => skipped(),
 	home_transport => [],
 	foreign_transport => [
 		"eth_blockNumber" =>
 			req => json!([]),
-			res => json!("0x1011");
+			res => json!("0x12");
+		"eth_getBlockByNumber" =>
+			req => json!(["0x6", false]),
+			res => canonical_block(6);
 		"eth_getLogs" =>
-			req => json!([{
-				"address": ["0x0000000000000000000000000000000000000000"],
-				"fromBlock": "0x1",
-				"limit": null,
-				"toBlock": "0x1005",
-				"topics": [[COLLECTED_SIGNATURES_TOPIC], null, null, null]
-			}]),
-			res => json!([{
-				"address": "0x0000000000000000000000000000000000000000",
-				"topics": [COLLECTED_SIGNATURES_TOPIC],
-				"data": "0x000000000000000000000000aff3454fce5edbc8cca8697c15331677e6ebcccc00000000000000000000000000000000000000000000000000000000000000f0",
-				"type": "",
-				"transactionHash": "0x884edad9ce6fa2440d8a54cc123490eb96d2768479d49ff9c7366125a9424364"
-			}]);
+			req => collected_signatures_request(),
+			res => collected_signatures_log(COLLECTED_SIGNATURES_TOPIC, AUTHORITY, 1);
 	]
 }
 
-// 2 signatures required. relay polled twice.
-// single CollectedSignatures log present.
-// message gets relayed.
 test_app_stream! {
-	name => withdraw_relay_single_log_sufficient_value_relay,
+	name => withdraw_relay_additional_signature_completing_required_relay,
 	database => Database {
-		home_contract_address: "00000000000000000000000000000000000000dd".into(),
-		foreign_contract_address: "00000000000000000000000000000000000000ee".into(),
+		checked_withdraw_relay: 5,
 		..Default::default()
 	},
 	home =>
-		account => "0000000000000000000000000000000000000001",
+		account => AUTHORITY,
 		confirmations => 12;
 	foreign =>
-		account => "aff3454fce5edbc8cca8697c15331677e6ebcccc",
+		account => AUTHORITY,
 		confirmations => 12;
 	authorities =>
 		accounts => [
-			"0000000000000000000000000000000000000001",
+			AUTHORITY,
 			"0000000000000000000000000000000000000002",
 		],
-		signatures => 2;
+		signatures => 1;
 	txs => Transactions::default(),
-	init => |app, db| create_withdraw_relay(app, db, Arc::new(RwLock::new(Some(99999999999u64.into()))), 17, Arc::new(RwLock::new(1))).take(1),
-	expected => vec![0x1005],
+	init => |app: Arc<App<&MockedTransport>>, db| {
+		let home_authorities = Arc::new(RwLock::new(Some(AuthoritySet {
+			accounts: vec![AUTHORITY.into(), "0000000000000000000000000000000000000002".into()],
+			required_signatures: 2,
+		})));
+		create_withdraw_relay(app.clone(), db, Arc::new(RwLock::new(Some(99999999999u64.into()))), 17,
+			GasPrice::new(&app.config.home), home_authorities, GasLimit::new(100_000)).take(2)
+	},
+	expected => relayed(),
 	home_transport => [
-		// `HomeBridge.withdraw`
-		"eth_sendTransaction" =>
-			req => json!([{
-				"data": format!("0x{}", contracts::home::HomeBridge::default()
-					.functions()
-					.withdraw()
-					.input(
-						vec![U256::from(1), U256::from(4)],
-						vec![H256::from(2), H256::from(5)],
-						vec![H256::from(3), H256::from(6)],
-						MessageToMainnet {
-							recipient: [1u8; 20].into(),
-							value: 10000.into(),
-							sidenet_transaction_hash: "0x884edad9ce6fa2440d8a54cc123490eb96d2768479d49ff9c7366125a9424364".into(),
-							mainnet_gas_price: 1000.into(),
-						}.to_bytes()
-					).to_hex()),
-				"from": "0x0000000000000000000000000000000000000001",
-				"gas": "0x0",
-				"gasPrice": "0x3e8",
-				"to": "0x00000000000000000000000000000000000000dd"
-			}]),
-			res => json!("0x1db8f385535c0d178b8f40016048f3a3cffee8f94e68978ea4b277f57b638f0b");
+		"eth_call" =>
+			req => withdrawn_request(),
+			res => json!(FALSE);
+		"eth_sendRawTransaction" =>
+			req => json!([]),
+			res => json!(format!("0x{}", RELAY_HASH));
 	],
 	foreign_transport => [
 		"eth_blockNumber" =>
 			req => json!([]),
-			res => json!("0x1011");
+			res => json!("0x12");
+		"eth_getBlockByNumber" =>
+			req => json!(["0x6", false]),
+			res => canonical_block(6);
 		"eth_getLogs" =>
-			req => json!([{
-				"address": ["0x00000000000000000000000000000000000000ee"],
-				"fromBlock": "0x1",
-				"limit": null,
-				"toBlock": "0x1005",
-				"topics": [[COLLECTED_SIGNATURES_TOPIC], null, null, null]
-			}]),
-			res => json!([{
-				"address": "0x00000000000000000000000000000000000000ee",
-				"topics": [COLLECTED_SIGNATURES_TOPIC],
-				"data": "0x000000000000000000000000aff3454fce5edbc8cca8697c15331677e6ebcccc00000000000000000000000000000000000000000000000000000000000000f0",
-				"type": "",
-				"transactionHash": "0x884edad9ce6fa2440d8a54cc123490eb96d2768479d49ff9c7366125a9424364"
-			}]);
-		// call to `message`
+			req => collected_signatures_request(),
+			res => collected_signatures_log(COLLECTED_ADDITIONAL_SIGNATURE_TOPIC, AUTHORITY, 2);
 		"eth_call" =>
-			req => json!([{
-				"data": "0x490a32c600000000000000000000000000000000000000000000000000000000000000f0",
-				"to": "0x00000000000000000000000000000000000000ee"
-			}, "latest"]),
-			res => json!(format!("0x{}", MessageToMainnet {
-				recipient: [1u8; 20].into(),
-				value: 10000.into(),
-				sidenet_transaction_hash: "0x884edad9ce6fa2440d8a54cc123490eb96d2768479d49ff9c7366125a9424364".into(),
-				mainnet_gas_price: 1000.into(),
-			}.to_payload().to_hex()));
-		// calls to `signature`
+			req => message_request(),
+			res => message_response();
 		"eth_call" =>
-			req => json!([{
-				"data": "0x1812d99600000000000000000000000000000000000000000000000000000000000000f00000000000000000000000000000000000000000000000000000000000000000",
-				"to": "0x00000000000000000000000000000000000000ee"
-			},"latest"]),
-			res => json!(format!("0x{}", Signature { v: 1, r: 2.into(), s: 3.into() }.to_payload().to_hex()));
+			req => signature_request(0),
+			res => signature_response();
 		"eth_call" =>
-			req => json!([{
-				"data": "0x1812d99600000000000000000000000000000000000000000000000000000000000000f00000000000000000000000000000000000000000000000000000000000000001",
-				"to": "0x00000000000000000000000000000000000000ee"
-			},"latest"]),
-			res => json!(format!("0x{}", Signature { v: 4, r: 5.into(), s: 6.into() }.to_payload().to_hex()));
+			req => signature_request(1),
+			res => signature_response();
+	]
+}
+
+// the withdraw has been relayed with the signatures home required when `CollectedSignatures` was logged.
+test_app_stream! {
+	name => withdraw_relay_additional_signature_beyond_required_no_relay,
+	database => Database {
+		checked_withdraw_relay: 5,
+		..Default::default()
+	},
+	home =>
+		account => AUTHORITY,
+		confirmations => 12;
+	foreign =>
+		account => AUTHORITY,
+		confirmations => 12;
+	authorities =>
+		accounts => [
+			AUTHORITY,
+			"0000000000000000000000000000000000000002",
+		],
+		signatures => 1;
+	txs => Transactions::default(),
+	init => |app: Arc<App<&MockedTransport>>, db| {
+		let home_authorities = Arc::new(RwLock::new(Some(AuthoritySet {
+			accounts: vec![AUTHORITY.into(), "0000000000000000000000000000000000000002".into()],
+			required_signatures: 1,
+		})));
+		create_withdraw_relay(app.clone(), db, Arc::new(RwLock::new(Some(99999999999u64.into()))), 17,
+			GasPrice::new(&app.config.home), home_authorities, GasLimit::new(100_000)).take(2)
+	},
+	expected => skipped(),
+	home_transport => [],
+	foreign_transport => [
+		"eth_blockNumber" =>
+			req => json!([]),
+			res => json!("0x12");
+		"eth_getBlockByNumber" =>
+			req => json!(["0x6", false]),
+			res => canonical_block(6);
+		"eth_getLogs" =>
+			req => collected_signatures_request(),
+			res => collected_signatures_log(COLLECTED_ADDITIONAL_SIGNATURE_TOPIC, AUTHORITY, 2);
 	]
 }
@@ -18,7 +18,9 @@ contract('ForeignBridge', function(accounts) {
     }).then(function(result) {
       assert.equal(0, result, "initial supply should be 0");
 
-      return contract.deposit(owner, value, hash, {from: authorities[0]});
+      return helpers.signDeposit(authorities[0], owner, value, hash).then(function(signature) {
+        return contract.deposit(owner, value, hash, signature, { from: authorities[0] });
+      });
     }).then(function(result) {
 
       return contract.totalSupply();
@@ -50,7 +52,9 @@ contract('ForeignBridge', function(accounts) {
       contract = instance;
 
       // deposit something so we can transfer it
-      return contract.deposit(owner, web3.toWei(3, "ether"), hash, {from: authorities[0]});
+      return helpers.signDeposit(authorities[0], owner, web3.toWei(3, "ether"), hash).then(function(signature) {
+        return contract.deposit(owner, web3.toWei(3, "ether"), hash, signature, { from: authorities[0] });
+      });
     }).then(function(result) {
 
       return contract.allowance(owner, spender);
@@ -148,7 +152,9 @@ contract('ForeignBridge', function(accounts) {
     return ForeignBridge.new(requiredSignatures, authorities, estimatedGasCostOfWithdraw).then(function(instance) {
       meta = instance;
       // top up balance so we can transfer
-      return meta.deposit(userAccount, user1InitialValue, hash, { from: authorities[0] });
+      return helpers.signDeposit(authorities[0], userAccount, user1InitialValue, hash).then(function(signature) {
+        return meta.deposit(userAccount, user1InitialValue, hash, signature, { from: authorities[0] });
+      });
     }).then(function(result) {
       return meta.transfer(userAccount2, transferedValue, { from: userAccount });
     }).then(function(result) {
@@ -179,7 +185,9 @@ contract('ForeignBridge', function(accounts) {
     var hash = "0xe55bb43c36cdf79e23b4adc149cdded921f0d482e613c50c6540977c213bc408";
     return ForeignBridge.new(requiredSignatures, authorities, estimatedGasCostOfWithdraw).then(function(instance) {
       meta = instance;
-      return meta.deposit(userAccount, userValue, hash, { from: authorities[0] });
+      return helpers.signDeposit(authorities[0], userAccount, userValue, hash).then(function(signature) {
+        return meta.deposit(userAccount, userValue, hash, signature, { from: authorities[0] });
+      });
     }).then(function(result) {
       return meta.transfer(recipientAccount, transferedValue, { from: userAccount })
         .then(function() {
@@ -199,7 +207,9 @@ contract('ForeignBridge', function(accounts) {
     var hash = "0xe55bb43c36cdf79e23b4adc149cdded921f0d482e613c50c6540977c213bc408";
     return ForeignBridge.new(requiredSignatures, authorities, estimatedGasCostOfWithdraw).then(function(instance) {
       meta = instance;
-      return meta.deposit(userAccount, userValue, hash, { from: authorities[0] });
+      return helpers.signDeposit(authorities[0], userAccount, userValue, hash).then(function(signature) {
+        return meta.deposit(userAccount, userValue, hash, signature, { from: authorities[0] });
+      });
     }).then(function(result) {
       return meta.transfer(recipientAccount, 0, { from: userAccount });
     }).then(function(result) {
@@ -229,9 +239,13 @@ contract('ForeignBridge', function(accounts) {
     var hash = "0xe55bb43c36cdf79e23b4adc149cdded921f0d482e613c50c6540977c213bc408";
     return ForeignBridge.new(requiredSignatures, authorities, estimatedGasCostOfWithdraw).then(function(instance) {
       meta = instance;
-      return meta.deposit(recipientAccount, maxValue, hash, { from: authorities[0] });
+      return helpers.signDeposit(authorities[0], recipientAccount, maxValue, hash).then(function(signature) {
+        return meta.deposit(recipientAccount, maxValue, hash, signature, { from: authorities[0] });
+      });
     }).then(function(result) {
-      return meta.deposit(userAccount, 1, hash, { from: authorities[0] });
+      return helpers.signDeposit(authorities[0], userAccount, 1, hash).then(function(signature) {
+        return meta.deposit(userAccount, 1, hash, signature, { from: authorities[0] });
+      });
     }).then(function(result) {
       return meta.transfer(recipientAccount, 1, { from: userAccount })
         .then(function() {
@@ -252,9 +266,13 @@ contract('ForeignBridge', function(accounts) {
     var hash = "0xe55bb43c36cdf79e23b4adc149cdded921f0d482e613c50c6540977c213bc408";
     return ForeignBridge.new(requiredSignatures, authorities, estimatedGasCostOfWithdraw).then(function(instance) {
       meta = instance;
-      return meta.deposit(recipientAccount, maxValue, hash, { from: authorities[0] });
+      return helpers.signDeposit(authorities[0], recipientAccount, maxValue, hash).then(function(signature) {
+        return meta.deposit(recipientAccount, maxValue, hash, signature, { from: authorities[0] });
+      });
     }).then(function(result) {
-      return meta.deposit(userAccount, 1, hash, { from: authorities[0] });
+      return helpers.signDeposit(authorities[0], userAccount, 1, hash).then(function(signature) {
+        return meta.deposit(userAccount, 1, hash, signature, { from: authorities[0] });
+      });
     }).then(function(result) {
       return meta.approve(spenderAccount, 1, {from: userAccount});
     }).then(function(result) {
@@ -46,7 +46,9 @@ contract('ForeignBridge', function(accounts) {
 
     return ForeignBridge.new(requiredSignatures, authorities, estimatedGasCostOfWithdraw).then(function(instance) {
       meta = instance;
-      return meta.deposit(userAccount, value, hash, { from: authorities[0] });
+      return helpers.signDeposit(authorities[0], userAccount, value, hash).then(function(signature) {
+        return meta.deposit(userAccount, value, hash, signature, { from: authorities[0] });
+      });
     }).then(function(result) {
       assert.equal(2, result.logs.length)
 
@@ -76,13 +78,17 @@ contract('ForeignBridge', function(accounts) {
 
     return ForeignBridge.new(requiredSignatures, authorities, estimatedGasCostOfWithdraw).then(function(instance) {
       meta = instance;
-      return meta.deposit(userAccount, value, hash, { from: authorities[0] });
+      return helpers.signDeposit(authorities[0], userAccount, value, hash).then(function(signature) {
+        return meta.deposit(userAccount, value, hash, signature, { from: authorities[0] });
+      });
     }).then(function(result) {
       assert.equal(0, result.logs.length, "No event should be created");
       return meta.balances.call(userAccount);
     }).then(function(result) {
       assert.equal(web3.toWei(0, "ether"), result, "Contract balance should not change yet");
-      return meta.deposit(userAccount, value, hash, { from: authorities[1] });
+      return helpers.signDeposit(authorities[1], userAccount, value, hash).then(function(signature) {
+        return meta.deposit(userAccount, value, hash, signature, { from: authorities[1] });
+      });
     }).then(function(result) {
       assert.equal(2, result.logs.length)
 
@@ -111,12 +117,15 @@ contract('ForeignBridge', function(accounts) {
 
     return ForeignBridge.new(requiredSignatures, authorities, estimatedGasCostOfWithdraw).then(function(instance) {
       meta = instance;
-      return meta.deposit(userAccount, value, hash, { from: authorities[0] });
+      return helpers.signDeposit(authorities[0], userAccount, value, hash).then(function(signature) {
+        return meta.deposit(userAccount, value, hash, signature, { from: authorities[0] });
+      });
     }).then(function(_) {
-      return meta.deposit(userAccount, value, hash, { from: authorities[0] })
-        .then(function() {
-          assert(false, "doing same deposit twice from same authority should fail");
-        }, helpers.ignoreExpectedError)
+      return helpers.signDeposit(authorities[0], userAccount, value, hash).then(function(signature) {
+        return meta.deposit(userAccount, value, hash, signature, { from: authorities[0] });
+      }).then(function() {
+        assert(false, "doing same deposit twice from same authority should fail");
+      }, helpers.ignoreExpectedError)
     })
   })
 
@@ -131,10 +140,39 @@ contract('ForeignBridge', function(accounts) {
 
     return ForeignBridge.new(requiredSignatures, authorities, estimatedGasCostOfWithdraw).then(function(instance) {
       meta = instance;
-      return meta.deposit(userAccount, value, hash, { from: userAccount })
-        .then(function() {
-          assert(false, "should fail");
-        }, helpers.ignoreExpectedError)
+      return helpers.signDeposit(userAccount, userAccount, value, hash).then(function(signature) {
+        return meta.deposit(userAccount, value, hash, signature, { from: userAccount });
+      }).then(function() {
+        assert(false, "should fail");
+      }, helpers.ignoreExpectedError)
+    })
+  })
+
+  it("should count a deposit signed by an authority and sent by another account", function() {
+    var meta;
+    var requiredSignatures = 1;
+    var estimatedGasCostOfWithdraw = 0;
+    var authorities = [accounts[0], accounts[1]];
+    var relayerAccount = accounts[2];
+    var userAccount = accounts[3];
+    var value = web3.toWei(1, "ether");
+    var hash = "0xe55bb43c36cdf79e23b4adc149cdded921f0d482e613c50c6540977c213bc408";
+
+    return ForeignBridge.new(requiredSignatures, authorities, estimatedGasCostOfWithdraw).then(function(instance) {
+      meta = instance;
+      return helpers.signDeposit(authorities[0], userAccount, value, hash).then(function(signature) {
+        return meta.deposit(userAccount, value, hash, signature, { from: relayerAccount });
+      });
+    }).then(function(result) {
+      assert.equal("Deposit", result.logs[1].event);
+      assert.equal(userAccount, result.logs[1].args.recipient);
+      assert.equal(value, result.logs[1].args.value);
+      return Promise.all([
+        meta.isDepositSigned.call(authorities[0], userAccount, value, hash),
+        meta.isDepositSigned.call(relayerAccount, userAccount, value, hash),
+      ]);
+    }).then(function(result) {
+      assert.deepEqual([true, false], result, "the deposit should be counted for the authority which signed it");
     })
   })
 
@@ -150,13 +188,19 @@ contract('ForeignBridge', function(accounts) {
 
     return ForeignBridge.new(requiredSignatures, authorities, estimatedGasCostOfWithdraw).then(function(instance) {
       meta = instance;
-      return meta.deposit(userAccount, value, hash, { from: authorities[0] });
+      return helpers.signDeposit(authorities[0], userAccount, value, hash).then(function(signature) {
+        return meta.deposit(userAccount, value, hash, signature, { from: authorities[0] });
+      });
     }).then(function(result) {
       assert.equal(0, result.logs.length, "No event should be created yet");
-      return meta.deposit(userAccount, invalidValue, hash, { from: authorities[1] });
+      return helpers.signDeposit(authorities[1], userAccount, invalidValue, hash).then(function(signature) {
+        return meta.deposit(userAccount, invalidValue, hash, signature, { from: authorities[1] });
+      });
     }).then(function(result) {
       assert.equal(0, result.logs.length, "Misbehaving authority should be ignored");
-      return meta.deposit(userAccount, value, hash, { from: authorities[2] })
+      return helpers.signDeposit(authorities[2], userAccount, value, hash).then(function(signature) {
+        return meta.deposit(userAccount, value, hash, signature, { from: authorities[2] });
+      })
     }).then(function(result) {
       assert.equal(2, result.logs.length)
 
@@ -188,7 +232,9 @@ contract('ForeignBridge', function(accounts) {
     var hash = "0xe55bb43c36cdf79e23b4adc149cdded921f0d482e613c50c6540977c213bc408";
     return ForeignBridge.new(requiredSignatures, authorities, estimatedGasCostOfWithdraw).then(function(instance) {
       meta = instance;
-      return meta.deposit(userAccount, userValue, hash, { from: authorities[0] });
+      return helpers.signDeposit(authorities[0], userAccount, userValue, hash).then(function(signature) {
+        return meta.deposit(userAccount, userValue, hash, signature, { from: authorities[0] });
+      });
     }).then(function(result) {
       return meta.transferHomeViaRelay(recipientAccount, transferedValue, homeGasPrice, { from: userAccount })
         .then(function() {
@@ -210,7 +256,9 @@ contract('ForeignBridge', function(accounts) {
     var hash = "0xe55bb43c36cdf79e23b4adc149cdded921f0d482e613c50c6540977c213bc408";
     return ForeignBridge.new(requiredSignatures, authorities, estimatedGasCostOfWithdraw).then(function(instance) {
       meta = instance;
-      return meta.deposit(userAccount, userValue, hash, { from: authorities[0] });
+      return helpers.signDeposit(authorities[0], userAccount, userValue, hash).then(function(signature) {
+        return meta.deposit(userAccount, userValue, hash, signature, { from: authorities[0] });
+      });
     }).then(function(result) {
       return meta.transferHomeViaRelay(recipientAccount, transferedValue, homeGasPrice, { from: userAccount })
         .then(function() {
@@ -232,7 +280,9 @@ contract('ForeignBridge', function(accounts) {
     var hash = "0xe55bb43c36cdf79e23b4adc149cdded921f0d482e613c50c6540977c213bc408";
     return ForeignBridge.new(requiredSignatures, authorities, estimatedGasCostOfWithdraw).then(function(instance) {
       meta = instance;
-      return meta.deposit(userAccount, userValue, hash, { from: authorities[0] });
+      return helpers.signDeposit(authorities[0], userAccount, userValue, hash).then(function(signature) {
+        return meta.deposit(userAccount, userValue, hash, signature, { from: authorities[0] });
+      });
     }).then(function(result) {
       return meta.transferHomeViaRelay(recipientAccount, transferedValue, homeGasPrice, { from: userAccount })
         .then(function() {
@@ -254,7 +304,9 @@ contract('ForeignBridge', function(accounts) {
     var hash = "0xe55bb43c36cdf79e23b4adc149cdded921f0d482e613c50c6540977c213bc408";
     return ForeignBridge.new(requiredSignatures, authorities, estimatedGasCostOfWithdraw).then(function(instance) {
       meta = instance;
-      return meta.deposit(userAccount, userValue, hash, { from: authorities[0] });
+      return helpers.signDeposit(authorities[0], userAccount, userValue, hash).then(function(signature) {
+        return meta.deposit(userAccount, userValue, hash, signature, { from: authorities[0] });
+      });
     }).then(function(result) {
       return meta.transferHomeViaRelay(recipientAccount, transferedValue, homeGasPrice, { from: userAccount })
         .then(function() {
@@ -277,7 +329,9 @@ contract('ForeignBridge', function(accounts) {
     return ForeignBridge.new(requiredSignatures, authorities, estimatedGasCostOfWithdraw).then(function(instance) {
       meta = instance;
       // top up balance so we can transfer
-      return meta.deposit(userAccount, value, hash, { from: authorities[0] });
+      return helpers.signDeposit(authorities[0], userAccount, value, hash).then(function(signature) {
+        return meta.deposit(userAccount, value, hash, signature, { from: authorities[0] });
+      });
     }).then(function(result) {
       return meta.transferHomeViaRelay(userAccount2, transferedValue, homeGasPrice, { from: userAccount });
     }).then(function(result) {
@@ -322,7 +376,7 @@ contract('ForeignBridge', function(accounts) {
     }).then(function(result) {
       assert.equal(1, result.logs.length, "Exactly one event should be created");
       assert.equal("CollectedSignatures", result.logs[0].event, "Event name should be CollectedSignatures");
-      assert.equal(authorities[0], result.logs[0].args.authorityResponsibleForRelay, "Event authority should be equal to transaction sender");
+      assert.equal(authorities[0], result.logs[0].args.authorityResponsibleForRelay, "Event authority should be equal to the signer");
       return Promise.all([
         meta.signature.call(result.logs[0].args.messageHash, 0),
         meta.message(result.logs[0].args.messageHash),
@@ -387,7 +441,7 @@ contract('ForeignBridge', function(accounts) {
     }).then(function(result) {
       assert.equal(1, result.logs.length, "Exactly one event should be created");
       assert.equal("CollectedSignatures", result.logs[0].event, "Event name should be CollectedSignatures");
-      assert.equal(authorities[0], result.logs[0].args.authorityResponsibleForRelay, "Event authority should be equal to transaction sender");
+      assert.equal(authorities[0], result.logs[0].args.authorityResponsibleForRelay, "Event authority should be equal to the signer");
       return Promise.all([
         meta.signature.call(result.logs[0].args.messageHash, 0),
         meta.signature.call(result.logs[0].args.messageHash, 1),
@@ -401,7 +455,7 @@ contract('ForeignBridge', function(accounts) {
     }).then(function(result) {
       assert.equal(1, result.logs.length, "Exactly one event should be created");
       assert.equal("CollectedSignatures", result.logs[0].event, "Event name should be CollectedSignatures");
-      assert.equal(authorities[1], result.logs[0].args.authorityResponsibleForRelay, "Event authority should be equal to transaction sender");
+      assert.equal(authorities[1], result.logs[0].args.authorityResponsibleForRelay, "Event authority should be equal to the signer");
       return Promise.all([
         meta.signature.call(result.logs[0].args.messageHash, 0),
         meta.signature.call(result.logs[0].args.messageHash, 1),
@@ -414,6 +468,44 @@ contract('ForeignBridge', function(accounts) {
     })
   })
 
+  it("should announce signatures submitted after CollectedSignatures", function() {
+    var meta;
+    var signatures = [];
+    var requiredSignatures = 1;
+    var estimatedGasCostOfWithdraw = 0;
+    var authorities = [accounts[0], accounts[1]];
+    var recipientAccount = accounts[2];
+    var transactionHash = "0x1045bfe274b88120a6b1e5d01b5ec00ab5d01098346e90e7c7a3c9b8f0181c80";
+    var homeGasPrice = web3.toBigNumber(web3.toWei(3, "gwei"));
+    var message = helpers.createMessage(recipientAccount, web3.toBigNumber(1000), transactionHash, homeGasPrice);
+    return ForeignBridge.new(requiredSignatures, authorities, estimatedGasCostOfWithdraw).then(function(instance) {
+      meta = instance;
+      return Promise.all([
+        helpers.sign(authorities[0], message),
+        helpers.sign(authorities[1], message),
+      ]);
+    }).then(function(result) {
+      signatures = result;
+      return meta.submitSignature(signatures[0], message, { from: authorities[0] });
+    }).then(function(result) {
+      assert.equal(1, result.logs.length, "Exactly one event should be created");
+      assert.equal("CollectedSignatures", result.logs[0].event, "Event name should be CollectedSignatures");
+      return meta.submitSignature(signatures[1], message, { from: authorities[1] });
+    }).then(function(result) {
+      assert.equal(1, result.logs.length, "Exactly one event should be created");
+      assert.equal("CollectedAdditionalSignature", result.logs[0].event, "Event name should be CollectedAdditionalSignature");
+      assert.equal(authorities[1], result.logs[0].args.authorityResponsibleForRelay, "Event authority should be equal to the signer");
+      assert(web3.toBigNumber(2).equals(result.logs[0].args.NumberOfCollectedSignatures));
+      return Promise.all([
+        meta.signature.call(result.logs[0].args.messageHash, 0),
+        meta.signature.call(result.logs[0].args.messageHash, 1),
+      ])
+    }).then(function(result) {
+      assert.equal(signatures[0], result[0]);
+      assert.equal(signatures[1], result[1]);
+    })
+  })
+
   it("should not be possible to submit message that is too short", function() {
     var meta;
     var requiredSignatures = 1;
@@ -457,22 +549,45 @@ contract('ForeignBridge', function(accounts) {
     })
   })
 
-  it("should not be possible to submit signature signed by different authority", function() {
+  it("should accept signature of an authority submitted by another account", function() {
     var meta;
     var requiredSignatures = 1;
     var estimatedGasCostOfWithdraw = 0;
     var authorities = [accounts[0], accounts[1]];
     var recipientAccount = accounts[2];
+    var relayerAccount = accounts[3];
     var transactionHash = "0x1045bfe274b88120a6b1e5d01b5ec00ab5d01098346e90e7c7a3c9b8f0181c80";
     var homeGasPrice = web3.toBigNumber(web3.toWei(3, "gwei"));
-    var homeGasPrice2 = web3.toBigNumber(web3.toWei(2, "gwei"));
     var message = helpers.createMessage(recipientAccount, web3.toBigNumber(1000), transactionHash, homeGasPrice);
-    var message2 = helpers.createMessage(recipientAccount, web3.toBigNumber(1000), transactionHash, homeGasPrice2);
     return ForeignBridge.new(requiredSignatures, authorities, estimatedGasCostOfWithdraw).then(function(instance) {
       meta = instance;
       return helpers.sign(authorities[0], message);
     }).then(function(result) {
-      return meta.submitSignature(result, message, { from: authorities[1] })
+      return meta.submitSignature(result, message, { from: relayerAccount });
+    }).then(function(result) {
+      assert.equal(1, result.logs.length, "Exactly one event should be created");
+      assert.equal("CollectedSignatures", result.logs[0].event, "Event name should be CollectedSignatures");
+      assert.equal(authorities[0], result.logs[0].args.authorityResponsibleForRelay, "Event authority should be equal to the signer");
+      return meta.isMessageSigned.call(authorities[0], message);
+    }).then(function(result) {
+      assert(result, "Message should be signed by the authority");
+    })
+  })
+
+  it("should not be possible to submit signature of a non-authority", function() {
+    var meta;
+    var requiredSignatures = 1;
+    var estimatedGasCostOfWithdraw = 0;
+    var authorities = [accounts[0], accounts[1]];
+    var recipientAccount = accounts[2];
+    var transactionHash = "0x1045bfe274b88120a6b1e5d01b5ec00ab5d01098346e90e7c7a3c9b8f0181c80";
+    var homeGasPrice = web3.toBigNumber(web3.toWei(3, "gwei"));
+    var message = helpers.createMessage(recipientAccount, web3.toBigNumber(1000), transactionHash, homeGasPrice);
+    return ForeignBridge.new(requiredSignatures, authorities, estimatedGasCostOfWithdraw).then(function(instance) {
+      meta = instance;
+      return helpers.sign(accounts[3], message);
+    }).then(function(result) {
+      return meta.submitSignature(result, message, { from: authorities[0] })
         .then(function() {
           assert(false, "submitSignature should fail");
         }, helpers.ignoreExpectedError)
@@ -98,6 +98,23 @@ function createMessage(recipient, value, transactionHash, homeGasPrice) {
 }
 module.exports.createMessage = createMessage;
 
+// returns a Promise that resolves with the signature by `authority`
+// of a deposit accepted by `ForeignBridge.deposit`
+function signDeposit(authority, recipient, value, transactionHash) {
+  recipient = strip0x(recipient);
+  assert.equal(recipient.length, 20 * 2);
+
+  value = strip0x(bigNumberToPaddedBytes32(web3.toBigNumber(value)));
+  assert.equal(value.length, 64);
+
+  transactionHash = strip0x(transactionHash);
+  assert.equal(transactionHash.length, 32 * 2);
+
+  var hash = web3.sha3(recipient + value + transactionHash, { encoding: "hex" });
+  return sign(authority, hash);
+}
+module.exports.signDeposit = signDeposit;
+
 // returns array of integers progressing from `start` up to, but not including, `end`
 function range(start, end) {
   var result = [];
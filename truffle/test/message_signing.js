@@ -26,6 +26,25 @@ contract("MessageSigning", function(accounts) {
     })
   })
 
+  it("should recover address from signed hash", function() {
+    var hash = "0xe55bb43c36cdf79e23b4adc149cdded921f0d482e613c50c6540977c213bc408";
+    var meta;
+    var signature;
+
+    return MessageSigning.new().then(function(instance) {
+      meta = instance;
+      return helpers.sign(accounts[0], hash);
+    }).then(function(result) {
+      signature = result;
+      return meta.recoverAddressFromSignedHash.call(signature, hash);
+    }).then(function(result) {
+      assert.equal(accounts[0], result);
+      return meta.recoverAddressFromSignedMessage.call(signature, hash);
+    }).then(function(result) {
+      assert.equal(accounts[0], result, "a signed hash is a signed 32 bytes long message");
+    })
+  })
+
   it("should fail to recover address from signature that is too short", function() {
     var signature = "0x3c9158597e22fa43fcc6636399c560441808e1d8496de0108e401a2ad71022b15d1191cf3c96e06759601c8e00ce7f03f350c12b19d0a8ba3ab3c07a71063f2b";
     var message = "0x111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111";